@@ -118,6 +118,7 @@ struct ApiConfig {
 
     // Skip serialization (won't appear in JSON)
     #[serde(skip)]
+    #[allow(dead_code)]
     runtime_data: String,
 }
 
@@ -324,7 +325,7 @@ struct HyperParams {
 fn enum_config_example() -> Result<(), Box<dyn Error>> {
     println!("\n=== SECTION 7: Enum-based Configuration ===\n");
 
-    let configs = vec![
+    let configs = [
         ModelConfig {
             model_type: ModelType::LinearRegression,
             hyperparameters: HyperParams {
@@ -405,6 +406,7 @@ struct ValidatedConfig {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 enum ConfigError {
     Io(std::io::Error),
     Parse(serde_json::Error),
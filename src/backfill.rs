@@ -0,0 +1,261 @@
+//! Backfills: the pipeline run once per date partition, e.g. every month of
+//! 2023, each into a folder of its own. Which partitions succeeded is kept in
+//! a state file, so an interrupted or partly failed backfill resumes where it
+//! left off.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{Local, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::config::entry::EntrypointConfig;
+use crate::filter::{Bound, RowFilter};
+use crate::output::PARTITION_PLACEHOLDER;
+
+/// One partition: the rows whose date lies in `[start, end)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    /// `YYYY-MM` or `YYYY-MM-DD`, as given on the command line.
+    pub label: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl Partition {
+    /// Filters reading only this partition's rows of `column`.
+    pub fn filters(&self, column: &str) -> Vec<RowFilter> {
+        vec![
+            RowFilter::Range {
+                column: column.to_string(),
+                min: Some(Bound::Text(self.start.to_string())),
+                max: None,
+            },
+            RowFilter::Before {
+                column: column.to_string(),
+                value: Bound::Text(self.end.to_string()),
+            },
+        ]
+    }
+}
+
+/// Checks that partitions of `entrypoint` can run at once: each must write
+/// only files of its own.
+pub fn check(entrypoint: &EntrypointConfig) -> Result<(), String> {
+    if entrypoint.incremental.is_some() {
+        return Err(
+            "'incremental' can't be backfilled: partitions would overwrite each other's \
+             watermark; remove it for the backfill"
+                .to_string(),
+        );
+    }
+    if let Some(output) = &entrypoint.output
+        && !output.is_partitioned()
+    {
+        return Err(format!(
+            "'output.path' ({}) must contain {} to be backfilled, so partitions don't \
+             overwrite each other's files",
+            output.path, PARTITION_PLACEHOLDER
+        ));
+    }
+    Ok(())
+}
+
+/// The partitions from `from` to `to`, both inclusive: months when both are
+/// `YYYY-MM`, days when both are `YYYY-MM-DD`.
+pub fn partitions(from: &str, to: &str) -> Result<Vec<Partition>, String> {
+    let (first, monthly) = parse(from)?;
+    let (last, to_monthly) = parse(to)?;
+    if monthly != to_monthly {
+        return Err(format!(
+            "'{}' and '{}' must both be months (YYYY-MM) or both days (YYYY-MM-DD)",
+            from, to
+        ));
+    }
+    if last < first {
+        return Err(format!("'{}' is before '{}'", to, from));
+    }
+
+    let mut partitions = Vec::new();
+    let mut start = first;
+    while start <= last {
+        let end = if monthly {
+            start.checked_add_months(Months::new(1))
+        } else {
+            start.succ_opt()
+        }
+        .ok_or_else(|| format!("partition after {} is out of range", start))?;
+        let label = if monthly {
+            start.format("%Y-%m").to_string()
+        } else {
+            start.to_string()
+        };
+        partitions.push(Partition { label, start, end });
+        start = end;
+    }
+    Ok(partitions)
+}
+
+/// The first day of `value` and whether it names a month.
+fn parse(value: &str) -> Result<(NaiveDate, bool), String> {
+    if let Ok(day) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok((day, false));
+    }
+    NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d")
+        .map(|month| (month, true))
+        .map_err(|_| format!("'{}' is not a month (YYYY-MM) or day (YYYY-MM-DD)", value))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PartitionStatus {
+    Success,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartitionState {
+    pub status: PartitionStatus,
+    /// Input rows of the partition.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub finished_at: String,
+}
+
+/// Outcome of every partition run so far, by label.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BackfillState {
+    pub partitions: BTreeMap<String, PartitionState>,
+}
+
+impl BackfillState {
+    /// The state saved at `path`; empty when there is none yet.
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        // Written aside and renamed, so a crash never leaves half a state file.
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&partial, path)
+    }
+
+    pub fn succeeded(&self, label: &str) -> bool {
+        self.partitions
+            .get(label)
+            .is_some_and(|p| p.status == PartitionStatus::Success)
+    }
+
+    pub fn record(&mut self, label: &str, rows: Option<usize>, error: Option<String>) {
+        let status = match error {
+            Some(_) => PartitionStatus::Failed,
+            None => PartitionStatus::Success,
+        };
+        self.partitions.insert(
+            label.to_string(),
+            PartitionState {
+                status,
+                rows,
+                error,
+                finished_at: Local::now().to_rfc3339(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::*;
+
+    use super::*;
+    use crate::filter;
+    use crate::output::OutputConfig;
+
+    fn entrypoint(extra: &str) -> EntrypointConfig {
+        serde_yaml::from_str(&format!(
+            "data: tx.csv\nfeatures: features.yaml\nlabels: labels.yaml\n{}",
+            extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn months_and_days_are_half_open() {
+        let months = partitions("2023-11", "2024-01").unwrap();
+        let labels: Vec<&str> = months.iter().map(|p| p.label.as_str()).collect();
+        assert_eq!(labels, ["2023-11", "2023-12", "2024-01"]);
+        assert_eq!(months[2].end, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+
+        let days = partitions("2024-02-28", "2024-03-01").unwrap();
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[1].label, "2024-02-29");
+
+        assert!(partitions("2024-01", "2024-01-31").is_err());
+        assert!(partitions("2024-02", "2024-01").is_err());
+    }
+
+    #[test]
+    fn partitions_read_each_row_once() {
+        let days = Series::new(
+            "day".into(),
+            [
+                "2023-01-01",
+                "2023-01-31",
+                "2023-02-01",
+                "2023-02-28",
+                "2023-03-01",
+            ],
+        )
+        .cast(&DataType::Date)
+        .unwrap()
+        .into_column();
+        let data = DataFrame::new(vec![days]).unwrap();
+
+        let rows: Vec<usize> = partitions("2023-01", "2023-03")
+            .unwrap()
+            .iter()
+            .map(|p| {
+                filter::apply(data.clone().lazy(), &p.filters("day"))
+                    .unwrap()
+                    .collect()
+                    .unwrap()
+                    .height()
+            })
+            .collect();
+        assert_eq!(rows, [2, 2, 1]);
+    }
+
+    #[test]
+    fn partitions_write_to_folders_of_their_own() {
+        let output: OutputConfig =
+            serde_yaml::from_str("path: data/backfill/{partition}/latest").unwrap();
+        let paths: Vec<String> = partitions("2023-01", "2023-02")
+            .unwrap()
+            .iter()
+            .map(|p| output.for_partition(&p.label).path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "data/backfill/2023-01/latest",
+                "data/backfill/2023-02/latest"
+            ]
+        );
+    }
+
+    #[test]
+    fn check_rejects_shared_outputs_and_watermarks() {
+        assert!(check(&entrypoint("")).is_ok());
+        assert!(check(&entrypoint("output:\n  path: out/{partition}")).is_ok());
+        assert!(check(&entrypoint("output:\n  path: out/latest")).is_err());
+        assert!(check(&entrypoint("incremental:\n  column: day")).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use std::{fs, time::Instant};
+
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::PipelineStepError,
+    pipeline::features::{ExecutionStrategy, FeaturePipeline},
+};
+
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub iterations: usize,
+    pub sample_rows: Option<usize>,
+    pub seed: Option<u64>,
+    pub strategies: Vec<ExecutionStrategy>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            sample_rows: None,
+            seed: None,
+            strategies: ExecutionStrategy::ALL.to_vec(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Timing {
+    pub mean_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Timing {
+    fn from_samples(samples: &[f64]) -> Self {
+        let mean_ms = samples.iter().sum::<f64>() / samples.len().max(1) as f64;
+        let min_ms = samples.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_ms = samples.iter().copied().fold(0.0, f64::max);
+        Self {
+            mean_ms,
+            min_ms: if min_ms.is_finite() { min_ms } else { 0.0 },
+            max_ms,
+        }
+    }
+}
+
+/// One step's cost. `timing` is the step applied eagerly, which the
+/// sequential, rayon and threads strategies all do; `lazy` and `streaming`
+/// time the step's expression collected alone by that engine, for steps the
+/// lazy strategies fuse.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StepReport {
+    pub step: String,
+    pub timing: Timing,
+    pub rows_per_sec: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lazy: Option<Timing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streaming: Option<Timing>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StrategyReport {
+    pub strategy: ExecutionStrategy,
+    pub timing: Timing,
+    pub rows_per_sec: f64,
+    pub peak_memory_bytes: Option<u64>,
+}
+
+/// A strategy or step that got slower than in a baseline report.
+#[derive(Serialize, Debug)]
+pub struct Regression {
+    /// `strategy <name>`, or `step <name> (<engine>)` with engine `eager`,
+    /// `lazy` or `streaming`.
+    pub subject: String,
+    pub baseline_ms: f64,
+    pub current_ms: f64,
+    /// Relative slowdown, e.g. `0.3` for 30% slower.
+    pub change: f64,
+}
+
+impl std::fmt::Display for Regression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {:.2} ms -> {:.2} ms (+{:.0}%)",
+            self.subject,
+            self.baseline_ms,
+            self.current_ms,
+            self.change * 100.0
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BenchReport {
+    pub rows: usize,
+    pub iterations: usize,
+    pub steps: Vec<StepReport>,
+    pub strategies: Vec<StrategyReport>,
+}
+
+pub fn run(
+    pipeline: &FeaturePipeline,
+    data: &DataFrame,
+    options: &BenchOptions,
+) -> Result<BenchReport, PipelineStepError> {
+    let data = match options.sample_rows {
+        Some(n) if n < data.height() => data.sample_n_literal(n, false, true, options.seed)?,
+        _ => data.clone(),
+    };
+    let rows = data.height();
+    // Steps are timed on the frame the strategies hand them.
+    let data = pipeline.prepare(&data)?.into_owned();
+    let iterations = options.iterations.max(1);
+
+    let mut steps = Vec::with_capacity(pipeline.steps.len());
+    for (i, step) in pipeline.steps.iter().enumerate() {
+        let mut samples = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            let start = Instant::now();
+            step.apply_feature(&data)?;
+            samples.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+        let timing = Timing::from_samples(&samples);
+
+        let expr = step.feature_expr(&data)?;
+        let engine_timing = |strategy: ExecutionStrategy, engine: Engine| {
+            let Some(expr) = expr
+                .as_ref()
+                .filter(|_| options.strategies.contains(&strategy))
+            else {
+                return Ok(None);
+            };
+            let mut samples = Vec::with_capacity(iterations);
+            for _ in 0..iterations {
+                let start = Instant::now();
+                data.clone()
+                    .lazy()
+                    .with_column(expr.clone())
+                    .collect_with_engine(engine)?;
+                samples.push(start.elapsed().as_secs_f64() * 1000.0);
+            }
+            Ok::<_, PipelineStepError>(Some(Timing::from_samples(&samples)))
+        };
+        let lazy = engine_timing(ExecutionStrategy::Lazy, Engine::InMemory)?;
+        let streaming = engine_timing(ExecutionStrategy::Streaming, Engine::Streaming)?;
+
+        steps.push(StepReport {
+            step: step
+                .name()
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| format!("step_{}", i + 1)),
+            rows_per_sec: rows_per_sec(rows, timing.mean_ms),
+            timing,
+            lazy,
+            streaming,
+        });
+    }
+
+    let mut strategies = Vec::with_capacity(options.strategies.len());
+    for strategy in &options.strategies {
+        reset_peak_memory();
+        let mut samples = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            let start = Instant::now();
+            pipeline.apply_with(&data, *strategy)?;
+            samples.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+        let timing = Timing::from_samples(&samples);
+        strategies.push(StrategyReport {
+            strategy: *strategy,
+            rows_per_sec: rows_per_sec(rows, timing.mean_ms),
+            peak_memory_bytes: peak_memory_bytes(),
+            timing,
+        });
+    }
+
+    Ok(BenchReport {
+        rows,
+        iterations,
+        steps,
+        strategies,
+    })
+}
+
+impl BenchReport {
+    /// Strategies and steps whose fastest iteration, the least noisy measure,
+    /// is more than `max_regression` (a fraction) slower than in `baseline`.
+    /// Entries missing from either report are skipped.
+    pub fn regressions(&self, baseline: &BenchReport, max_regression: f64) -> Vec<Regression> {
+        let mut timings: Vec<(String, &Timing, &Timing)> = Vec::new();
+        for current in &self.strategies {
+            if let Some(before) = baseline
+                .strategies
+                .iter()
+                .find(|s| s.strategy == current.strategy)
+            {
+                let subject = format!("strategy {}", current.strategy.as_str());
+                timings.push((subject, &before.timing, &current.timing));
+            }
+        }
+        for current in &self.steps {
+            let Some(before) = baseline.steps.iter().find(|s| s.step == current.step) else {
+                continue;
+            };
+            let subject = format!("step {}", current.step);
+            for (engine, before, current) in [
+                ("eager", Some(&before.timing), Some(&current.timing)),
+                ("lazy", before.lazy.as_ref(), current.lazy.as_ref()),
+                (
+                    "streaming",
+                    before.streaming.as_ref(),
+                    current.streaming.as_ref(),
+                ),
+            ] {
+                if let (Some(before), Some(current)) = (before, current) {
+                    timings.push((format!("{} ({})", subject, engine), before, current));
+                }
+            }
+        }
+
+        timings
+            .into_iter()
+            .filter(|(_, before, _)| before.min_ms > 0.0)
+            .filter_map(|(subject, before, current)| {
+                let change = current.min_ms / before.min_ms - 1.0;
+                (change > max_regression).then_some(Regression {
+                    subject,
+                    baseline_ms: before.min_ms,
+                    current_ms: current.min_ms,
+                    change,
+                })
+            })
+            .collect()
+    }
+}
+
+fn rows_per_sec(rows: usize, mean_ms: f64) -> f64 {
+    if mean_ms > 0.0 {
+        rows as f64 / (mean_ms / 1000.0)
+    } else {
+        0.0
+    }
+}
+
+/// Peak resident set size of the current process, read from `/proc/self/status`.
+/// Returns `None` on platforms without procfs.
+pub fn peak_memory_bytes() -> Option<u64> {
+    proc_status_bytes("VmHWM:")
+}
+
+/// Current resident set size of the process; `None` without procfs.
+pub fn memory_bytes() -> Option<u64> {
+    proc_status_bytes("VmRSS:")
+}
+
+fn proc_status_bytes(field: &str) -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with(field))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Resets the kernel's peak RSS counter so the next reading covers only new work.
+/// Best-effort: silently does nothing where `/proc/self/clear_refs` is unavailable.
+pub fn reset_peak_memory() {
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
@@ -1,48 +1,419 @@
-use std::{fs::File, io::BufReader, path::Path};
+use std::{collections::BTreeMap, path::Path};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_yaml::from_reader;
 
+use crate::config::format::ConfigFormat;
+use crate::config::{overlay, remote};
+use crate::drift::DriftConfig;
 use crate::errors::ConfigError;
+use crate::filter::RowFilter;
+use crate::incremental::IncrementalConfig;
+use crate::metrics::MetricsConfig;
+use crate::output::OutputConfig;
+use crate::pipeline::correlation::CorrelationConfig;
+use crate::pipeline::leakage::LeakageConfig;
+use crate::pipeline::relevance::RelevanceConfig;
+use crate::pipeline::selection::SelectionConfig;
+use crate::pipeline::split::SplitConfig;
+use crate::profiling::ProfilingConfig;
+use crate::resample::ResampleConfig;
+use crate::retention::RetentionConfig;
+use crate::spill::MemoryConfig;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Dataset name used for the single-file `data:` shorthand.
+pub const DEFAULT_DATASET: &str = "default";
+
+fn default_prune_columns() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Csv,
+    Parquet,
+}
+
+impl DataFormat {
+    /// Detects the format from the file extension, falling back to CSV.
+    pub fn from_path(path: &str) -> Self {
+        Self::from_extension(Path::new(path)).unwrap_or(Self::Csv)
+    }
+
+    /// The format a `.csv` or `.parquet` extension names, if any.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Some(Self::Csv),
+            Some(ext) if ext.eq_ignore_ascii_case("parquet") => Some(Self::Parquet),
+            _ => None,
+        }
+    }
+}
+
+/// Reader options. Only apply to CSV inputs.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ReadOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<char>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_header: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub null_values: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub infer_schema_length: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DatasetConfig {
+    /// A file, a directory of `.csv`/`.parquet` files, or a glob pattern such
+    /// as `data/2024-*.csv`. Several files are stacked in path order.
+    pub path: String,
+
+    /// Detected from the file extension when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<DataFormat>,
+
+    #[serde(default)]
+    pub options: ReadOptions,
+
+    /// Files read at once when `path` names several; `max_threads` (by
+    /// default one per core) when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<usize>,
+
+    /// Writes features and labels per input file, under `features/` and
+    /// `labels/`, instead of one `features.csv` and `labels.csv`. Only applies
+    /// to the dataset the features pipeline reads.
+    #[serde(default)]
+    pub partition_outputs: bool,
+
+    /// Columns read even though no step uses them, e.g. IDs wanted in the
+    /// input profile. Only matters while `prune_columns` is on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub passthrough: Vec<String>,
+
+    /// Conditions rows must all meet to be read, e.g. a date range. Checked
+    /// by the reader, so other rows are never loaded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filter: Vec<RowFilter>,
+
+    /// List columns unnested into one row per element, e.g. a Parquet
+    /// `items` column; the row's other values are repeated. Columns listed
+    /// together are unnested side by side, so a row's lists must have the
+    /// same length. Empty and null lists give a single row with a null.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub explode: Vec<String>,
+
+    /// Truncates a timestamp column to its day, week or month, optionally
+    /// aggregating each entity's rows per period; rows are read as they are
+    /// when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resample: Option<ResampleConfig>,
+}
+
+impl DatasetConfig {
+    pub fn format(&self) -> DataFormat {
+        self.format.unwrap_or(DataFormat::from_path(&self.path))
+    }
+
+    pub fn is_glob(&self) -> bool {
+        self.path.contains(['*', '?', '['])
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct EntrypointConfig {
-    pub data: String,
+    /// Shorthand for a single CSV dataset named `default`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+
+    /// Named input datasets, addressable from pipelines via `dataset:`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub datasets: BTreeMap<String, DatasetConfig>,
+
+    /// Dataset used by pipelines that don't name one. Required when more than
+    /// one dataset is declared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary: Option<String>,
+
+    /// Shorthand for the primary dataset's `filter`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filter: Vec<RowFilter>,
+
     pub features: String,
     pub labels: String,
+
+    /// Overlays merged on top of the features config, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features_overlays: Vec<String>,
+
+    /// Overlays merged on top of the labels config, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels_overlays: Vec<String>,
+
+    /// Target leakage check run before outputs are written; off when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leakage: Option<LeakageConfig>,
+
+    /// Train/validation/test assignment of input rows; training takes 80% of
+    /// rows in order when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split: Option<SplitConfig>,
+
+    /// Input and output data profiles written to the run folder; off when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiling: Option<ProfilingConfig>,
+
+    /// Comparison with a baseline profile from an earlier run; off when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift: Option<DriftConfig>,
+
+    /// Feature correlation matrix written to the run folder; off when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation: Option<CorrelationConfig>,
+
+    /// Ranking of features by how much each tells about the training target,
+    /// written to the run folder; off when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relevance: Option<RelevanceConfig>,
+
+    /// Constant, near-constant and highly correlated features dropped before
+    /// outputs are written; every feature is kept when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub select_features: Option<SelectionConfig>,
+
+    /// Run and step metrics pushed to a monitoring sink; off when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<MetricsConfig>,
+
+    /// Seed for every random choice that doesn't set its own, e.g. `split`
+    /// shuffles and label `balance` sampling, so rebuilds give the same
+    /// dataset. Such choices are not reproducible when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    /// Reads only the input columns the pipelines use (plus each dataset's
+    /// `passthrough`), skipping the rest at the reader.
+    #[serde(default = "default_prune_columns")]
+    pub prune_columns: bool,
+
+    /// Threads polars and rayon may use; one per core when omitted. The
+    /// `--max-threads` flag takes precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_threads: Option<usize>,
+
+    /// Stable folder `features.csv` and `labels.csv` are also written to;
+    /// outputs go to the run folder only when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputConfig>,
+
+    /// Which run folders to keep; `clean` deletes the rest, as does every run
+    /// when it ends. Nothing is deleted when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<RetentionConfig>,
+
+    /// Reads only rows past the previous run's watermark and appends their
+    /// outputs; every run reads all rows when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incremental: Option<IncrementalConfig>,
+
+    /// Memory budget past which input datasets are spilled to disk once
+    /// features and labels are built; unlimited when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryConfig>,
 }
 
 impl EntrypointConfig {
     pub fn from_yaml(filepath: &str) -> Result<Self, ConfigError> {
-        let config_yaml = File::open(filepath)?;
-        let reader = BufReader::new(config_yaml);
-        let config: EntrypointConfig = from_reader(reader)?;
+        Self::from_file(filepath, Some(ConfigFormat::Yaml))
+    }
+
+    pub fn from_file(filepath: &str, format: Option<ConfigFormat>) -> Result<Self, ConfigError> {
+        Self::from_layers(filepath, &[], format)
+    }
+
+    /// Loads `filepath` with `overlays` merged on top; see [`overlay::merge`].
+    pub fn from_layers(
+        filepath: &str,
+        overlays: &[String],
+        format: Option<ConfigFormat>,
+    ) -> Result<Self, ConfigError> {
+        let mut config: EntrypointConfig = overlay::load(filepath, overlays, format)?;
+        config.normalize(filepath)?;
         config.validate()?;
         Ok(config)
     }
 
+    /// Name of the dataset pipelines read when they don't specify one.
+    pub fn primary_dataset(&self) -> &str {
+        self.primary.as_deref().unwrap_or(DEFAULT_DATASET)
+    }
+
+    /// Folds the `data:` shorthand into `datasets`, resolves `primary` and
+    /// moves the top-level `filter` onto it.
+    fn normalize(&mut self, filepath: &str) -> Result<(), ConfigError> {
+        let invalid = |error: &str| ConfigError::ParseError {
+            path: filepath.to_string(),
+            error: error.to_string(),
+        };
+
+        if let Some(data) = &self.data {
+            if self.datasets.contains_key(DEFAULT_DATASET) {
+                return Err(invalid(
+                    "'data' conflicts with a dataset named 'default'; use one or the other",
+                ));
+            }
+            self.datasets.insert(
+                DEFAULT_DATASET.to_string(),
+                DatasetConfig {
+                    path: data.clone(),
+                    format: None,
+                    options: ReadOptions::default(),
+                    concurrency: None,
+                    partition_outputs: false,
+                    passthrough: Vec::new(),
+                    filter: Vec::new(),
+                    explode: Vec::new(),
+                    resample: None,
+                },
+            );
+        }
+
+        if self.datasets.is_empty() {
+            return Err(invalid("either 'data' or 'datasets' must be set"));
+        }
+
+        if self.primary.is_none() {
+            if self.datasets.len() == 1 {
+                self.primary = self.datasets.keys().next().cloned();
+            } else if self.data.is_some() {
+                self.primary = Some(DEFAULT_DATASET.to_string());
+            } else {
+                return Err(invalid(
+                    "'primary' must name a dataset when several are declared",
+                ));
+            }
+        }
+
+        if self.max_threads == Some(0) {
+            return Err(invalid("'max_threads' must be at least 1"));
+        }
+
+        if let Some(selection) = &self.select_features {
+            selection.validate().map_err(|error| invalid(&error))?;
+        }
+
+        if let Some(relevance) = &self.relevance {
+            relevance.validate().map_err(|error| invalid(&error))?;
+        }
+
+        if let Some(split) = &mut self.split {
+            split.validate().map_err(|error| invalid(&error))?;
+            if let Some(seed) = self.seed {
+                split.default_seed(seed);
+            }
+        }
+
+        if let Some(name) = self
+            .datasets
+            .iter()
+            .find_map(|(name, d)| (d.concurrency == Some(0)).then_some(name))
+        {
+            return Err(invalid(&format!(
+                "dataset '{}': 'concurrency' must be at least 1",
+                name
+            )));
+        }
+
+        for (name, dataset) in &self.datasets {
+            if dataset.explode.iter().any(|c| c.trim().is_empty()) {
+                return Err(invalid(&format!(
+                    "dataset '{}': 'explode' column names must not be empty",
+                    name
+                )));
+            }
+            let Some(resample) = &dataset.resample else {
+                continue;
+            };
+            if let Some(error) = resample.invalid() {
+                return Err(invalid(&format!("dataset '{}': {}", name, error)));
+            }
+            // Aggregated rows no longer belong to a single input file.
+            if resample.aggregates() && dataset.partition_outputs {
+                return Err(invalid(&format!(
+                    "dataset '{}': 'partition_outputs' can't be combined with resample 'aggregations'",
+                    name
+                )));
+            }
+        }
+
+        if !self.datasets.contains_key(self.primary_dataset()) {
+            return Err(invalid(&format!(
+                "primary dataset '{}' is not declared in 'datasets'",
+                self.primary_dataset()
+            )));
+        }
+
+        if !self.filter.is_empty() {
+            let filter = std::mem::take(&mut self.filter);
+            let primary = self.primary_dataset().to_string();
+            if let Some(dataset) = self.datasets.get_mut(&primary) {
+                dataset.filter.extend(filter);
+            }
+        }
+
+        Ok(())
+    }
+
     fn validate(&self) -> Result<(), ConfigError> {
-        if !Path::new(&self.data).exists() {
-            return Err(ConfigError::FileNotFound {
-                path: self.data.clone(),
-                kind: "data".to_string(),
-            });
+        for (name, dataset) in &self.datasets {
+            // Glob patterns are expanded, and checked for matches, when loading.
+            if !dataset.is_glob() && !Path::new(&dataset.path).exists() {
+                return Err(ConfigError::FileNotFound {
+                    path: dataset.path.clone(),
+                    kind: format!("dataset '{}'", name),
+                });
+            }
         }
 
-        if !Path::new(&self.features).exists() {
+        if !config_exists(&self.features) {
             return Err(ConfigError::FileNotFound {
                 path: self.features.clone(),
                 kind: "features".to_string(),
             });
         }
 
-        if !Path::new(&self.labels).exists() {
+        if !config_exists(&self.labels) {
             return Err(ConfigError::FileNotFound {
                 path: self.labels.clone(),
                 kind: "labels".to_string(),
             });
         }
 
+        for (overlays, kind) in [
+            (&self.features_overlays, "features overlay"),
+            (&self.labels_overlays, "labels overlay"),
+        ] {
+            if let Some(missing) = overlays.iter().find(|p| !config_exists(p)) {
+                return Err(ConfigError::FileNotFound {
+                    path: missing.clone(),
+                    kind: kind.to_string(),
+                });
+            }
+        }
+
         Ok(())
     }
 }
+
+/// Local config paths must exist; URLs are checked when fetched.
+fn config_exists(path: &str) -> bool {
+    remote::is_url(path) || Path::new(path).exists()
+}
@@ -0,0 +1,169 @@
+use std::env;
+
+use serde_json::Value;
+
+use crate::errors::ConfigError;
+
+/// Expands `${VAR}` and `${VAR:-default}` placeholders in the string values
+/// of a parsed config.
+///
+/// Keys, comments and everything the parser has already consumed are left
+/// alone, and an expansion never adds structure to the config. A value that is
+/// exactly one placeholder takes the type its expansion reads as, so
+/// placeholders work for numbers and booleans too; anything else stays a
+/// string. `$${` produces a literal `${`.
+pub fn expand(filepath: &str, value: Value) -> Result<Value, ConfigError> {
+    Ok(match value {
+        Value::String(text) => {
+            let expanded = interpolate(filepath, &text)?;
+            if is_placeholder(&text) {
+                scalar(expanded)
+            } else {
+                Value::String(expanded)
+            }
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| expand(filepath, item))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| Ok((key, expand(filepath, value)?)))
+                .collect::<Result<_, ConfigError>>()?,
+        ),
+        other => other,
+    })
+}
+
+/// Expands the placeholders in one string.
+pub fn interpolate(filepath: &str, content: &str) -> Result<String, ConfigError> {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if let Some(after) = tail.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+            continue;
+        }
+
+        if !tail.starts_with("${") {
+            out.push('$');
+            rest = &tail[1..];
+            continue;
+        }
+
+        let end = tail.find('}').ok_or_else(|| ConfigError::ParseError {
+            path: filepath.to_string(),
+            error: format!(
+                "unterminated placeholder starting at '{}'",
+                first_line(tail)
+            ),
+        })?;
+        let expr = &tail[2..end];
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+
+        let value = match (env::var(name), default) {
+            (Ok(value), _) => value,
+            (Err(_), Some(default)) => default.to_string(),
+            (Err(_), None) => {
+                return Err(ConfigError::MissingEnvVar {
+                    name: name.to_string(),
+                    path: filepath.to_string(),
+                });
+            }
+        };
+        out.push_str(&value);
+        rest = &tail[end + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Whether `text` is a single `${...}` placeholder and nothing else.
+fn is_placeholder(text: &str) -> bool {
+    text.strip_prefix("${")
+        .and_then(|rest| rest.strip_suffix('}'))
+        .is_some_and(|expr| !expr.contains('}'))
+}
+
+/// `text` as a number or boolean when it reads as one, else as a string.
+fn scalar(text: String) -> Value {
+    match serde_yaml::from_str::<Value>(&text) {
+        Ok(value @ (Value::Number(_) | Value::Bool(_))) => value,
+        _ => Value::String(text),
+    }
+}
+
+fn first_line(s: &str) -> &str {
+    s.lines().next().unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const UNSET: &str = "FEATURES_PIPELINE_TEST_UNSET";
+
+    fn parse_and_expand(yaml: &str) -> Result<Value, ConfigError> {
+        expand("test.yaml", serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn placeholders_in_comments_and_keys_are_ignored() {
+        let yaml = format!("# needs ${{{}}}\n${{{}}}: 1\n", UNSET, UNSET);
+        assert_eq!(
+            parse_and_expand(&yaml).unwrap(),
+            json!({ format!("${{{}}}", UNSET): 1 })
+        );
+    }
+
+    #[test]
+    fn expansions_stay_in_their_value() {
+        let yaml = format!("data: \"${{{}:-a.csv\\nfeatures: [x] # y}}\"\n", UNSET);
+        assert_eq!(
+            parse_and_expand(&yaml).unwrap(),
+            json!({"data": "a.csv\nfeatures: [x] # y"})
+        );
+    }
+
+    #[test]
+    fn whole_placeholders_take_the_expanded_type() {
+        let yaml = format!(
+            "threads: ${{{u}:-4}}\nratio: ${{{u}:-0.5}}\nflag: ${{{u}:-true}}\nname: ${{{u}:-null}}\n\
+             path: ${{{u}:-4}}/x\nliteral: $${{{u}}}\n",
+            u = UNSET
+        );
+        assert_eq!(
+            parse_and_expand(&yaml).unwrap(),
+            json!({
+                "threads": 4,
+                "ratio": 0.5,
+                "flag": true,
+                "name": "null",
+                "path": "4/x",
+                "literal": format!("${{{}}}", UNSET),
+            })
+        );
+    }
+
+    #[test]
+    fn unset_variables_without_default_fail() {
+        let yaml = format!("data: ${{{}}}\n", UNSET);
+        assert!(matches!(
+            parse_and_expand(&yaml),
+            Err(ConfigError::MissingEnvVar { name, .. }) if name == UNSET
+        ));
+    }
+}
@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+use crate::config::entry::EntrypointConfig;
+use crate::config::remote;
+use crate::pipeline::features::FeaturePipeline;
+use crate::pipeline::labels::LabelsPipeline;
+
+/// The configs a run actually used, after overlays, env interpolation, `vars`
+/// and templates have been applied.
+#[derive(Serialize, Debug)]
+pub struct ResolvedConfig<'a> {
+    pub entrypoint: &'a EntrypointConfig,
+    pub features: &'a FeaturePipeline,
+    pub labels: &'a LabelsPipeline,
+}
+
+impl ResolvedConfig<'_> {
+    /// SHA-256 of the compact JSON form. Struct fields serialize in declaration
+    /// order and maps are sorted, so equal configs always hash the same,
+    /// regardless of source format, key order or comments.
+    pub fn fingerprint(&self) -> Result<String, serde_json::Error> {
+        Ok(remote::sha256_hex(&serde_json::to_vec(self)?))
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
@@ -0,0 +1,145 @@
+use std::{fmt::Display, fs, path::Path};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::config::{env, remote, suggest};
+use crate::errors::ConfigError;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Detects the format from the file extension, falling back to YAML.
+    pub fn from_path(filepath: &str) -> Self {
+        match Path::new(remote::strip_url_suffix(filepath))
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("json") => Self::Json,
+            Some("toml") => Self::Toml,
+            _ => Self::Yaml,
+        }
+    }
+}
+
+/// Reads a config file, or fetches it when `filepath` is an HTTP(S) URL.
+pub fn read(filepath: &str) -> Result<String, ConfigError> {
+    if remote::is_url(filepath) {
+        remote::fetch(filepath)
+    } else {
+        Ok(fs::read_to_string(filepath)?)
+    }
+}
+
+/// Reads and parses a config file, detecting the format from the extension
+/// unless `format` is given, and expands `${VAR}` placeholders in its values.
+pub fn read_value(
+    filepath: &str,
+    format: Option<ConfigFormat>,
+) -> Result<(Value, String), ConfigError> {
+    let content = read(filepath)?;
+    let value = parse(
+        filepath,
+        &content,
+        format.unwrap_or(ConfigFormat::from_path(filepath)),
+    )?;
+    Ok((env::expand(filepath, value)?, content))
+}
+
+/// Reads and deserializes a config file; see [`read_value`].
+pub fn load<T: DeserializeOwned>(
+    filepath: &str,
+    format: Option<ConfigFormat>,
+) -> Result<T, ConfigError> {
+    let (value, content) = read_value(filepath, format)?;
+    serde_json::from_value(value).map_err(|e| parse_error(filepath, &content, e))
+}
+
+pub fn parse<T: DeserializeOwned>(
+    filepath: &str,
+    content: &str,
+    format: ConfigFormat,
+) -> Result<T, ConfigError> {
+    match format {
+        ConfigFormat::Yaml => {
+            serde_yaml::from_str(content).map_err(|e| parse_error(filepath, content, e))
+        }
+        ConfigFormat::Json => {
+            serde_json::from_str(content).map_err(|e| parse_error(filepath, content, e))
+        }
+        ConfigFormat::Toml => {
+            toml::from_str(content).map_err(|e| parse_error(filepath, content, e))
+        }
+    }
+}
+
+/// Builds a `ParseError`, adding a "did you mean" hint and source line for
+/// unknown fields and variants.
+pub fn parse_error(filepath: &str, content: &str, error: impl Display) -> ConfigError {
+    ConfigError::ParseError {
+        path: filepath.to_string(),
+        error: suggest::enrich(content, &error.to_string()),
+    }
+}
+
+/// [`parse_error`] for a pipeline deserialized from a merged or expanded value,
+/// which has lost source positions. Names the first of `steps` that does not
+/// deserialize as `S` and points at the line of its `name`, if it has one.
+pub fn step_error<S: DeserializeOwned>(
+    filepath: &str,
+    content: &str,
+    steps: Option<&Value>,
+    error: impl Display,
+) -> ConfigError {
+    let failing = steps
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .find_map(|(i, step)| Some((i, step, serde_json::from_value::<S>(step.clone()).err()?)));
+    let Some((i, step, step_error)) = failing else {
+        return parse_error(filepath, content, error);
+    };
+
+    let name = step.get("name").and_then(Value::as_str);
+    let mut message = format!(
+        "step {} (`{}`",
+        i + 1,
+        step.get("function").and_then(Value::as_str).unwrap_or("?")
+    );
+    if let Some(name) = name {
+        message.push_str(&format!(", name=`{}`", name));
+    }
+    message.push_str("): ");
+    message.push_str(&suggest::enrich(content, &step_error.to_string()));
+    if !message.contains("line ")
+        && let Some(line) = name.and_then(|name| suggest::find_line(content, name))
+    {
+        message.push_str(&format!(" (line {})", line));
+    }
+    ConfigError::ParseError {
+        path: filepath.to_string(),
+        error: message,
+    }
+}
+
+/// Serializes a config value back into the given format.
+pub fn to_string<T: Serialize>(value: &T, format: ConfigFormat) -> Result<String, ConfigError> {
+    let serialize_error = |e: &dyn Display| ConfigError::ParseError {
+        path: "<output>".to_string(),
+        error: e.to_string(),
+    };
+    match format {
+        ConfigFormat::Yaml => serde_yaml::to_string(value).map_err(|e| serialize_error(&e)),
+        ConfigFormat::Json => serde_json::to_string_pretty(value).map_err(|e| serialize_error(&e)),
+        ConfigFormat::Toml => toml::to_string_pretty(value).map_err(|e| serialize_error(&e)),
+    }
+}
@@ -0,0 +1,61 @@
+use serde_json::{Map, Value};
+
+use crate::errors::ConfigError;
+
+/// Inlines named lookup tables into the steps that reference them.
+///
+/// Tables are declared once under the top-level `lookups:` mapping; a step's
+/// `lookup: <name>` is replaced by `mapping: <table>`. The `lookups` section
+/// itself is kept so it stays visible in the resolved config.
+pub fn resolve(filepath: &str, mut config: Value) -> Result<Value, ConfigError> {
+    let invalid = |error: String| ConfigError::ParseError {
+        path: filepath.to_string(),
+        error,
+    };
+
+    let lookups = match config.get("lookups") {
+        Some(Value::Object(lookups)) => lookups.clone(),
+        Some(Value::Null) | None => Map::new(),
+        Some(_) => {
+            return Err(invalid(
+                "'lookups' must be a mapping of names to tables".to_string(),
+            ));
+        }
+    };
+
+    let Some(Value::Array(steps)) = config.get_mut("steps") else {
+        return Ok(config);
+    };
+
+    for (i, step) in steps.iter_mut().enumerate() {
+        let Some(step) = step.as_object_mut() else {
+            continue;
+        };
+        let Some(reference) = step.remove("lookup") else {
+            continue;
+        };
+        let Value::String(name) = reference else {
+            return Err(invalid(format!(
+                "step {}: 'lookup' must name a table in 'lookups'",
+                i + 1
+            )));
+        };
+        if step.contains_key("mapping") {
+            return Err(invalid(format!(
+                "step {}: set either 'lookup' or 'mapping', not both",
+                i + 1
+            )));
+        }
+        let table = lookups.get(&name).cloned().ok_or_else(|| {
+            invalid(format!(
+                "step {}: lookup '{}' is not defined in 'lookups'. Available {:?}",
+                i + 1,
+                name,
+                lookups.keys().collect::<Vec<_>>()
+            ))
+        })?;
+        step.insert("mapping".to_string(), table);
+    }
+
+    Ok(config)
+}
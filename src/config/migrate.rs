@@ -0,0 +1,194 @@
+use serde_yaml::{Mapping, Value};
+
+use crate::config::schema::ConfigKind;
+use crate::errors::ConfigError;
+
+/// Schema version written by this build. Version history:
+///
+/// - 1: original, unversioned format.
+/// - 2: `comparator` is a strict `gt`/`lt` enum (v1 silently skipped other
+///   values); the ignored v1 `name` is dropped from `existing_target` label
+///   steps, so it can't be mistaken for the target name.
+pub const CURRENT_VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub struct Migration {
+    pub config: Value,
+    pub from: u32,
+    pub to: u32,
+    pub changes: Vec<String>,
+}
+
+/// Rejects configs written for a newer schema than this build understands.
+pub fn check_version(filepath: &str, version: Option<u32>) -> Result<(), ConfigError> {
+    match version {
+        Some(v) if v > CURRENT_VERSION => Err(ConfigError::MigrationError {
+            path: filepath.to_string(),
+            error: format!(
+                "config version {} is newer than supported version {}",
+                v, CURRENT_VERSION
+            ),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Guesses the config kind from its top-level keys and step functions.
+pub fn detect_kind(config: &Value) -> ConfigKind {
+    if config.get("data").is_some() || config.get("datasets").is_some() {
+        return ConfigKind::Entrypoint;
+    }
+    let is_labels = config
+        .get("steps")
+        .and_then(Value::as_sequence)
+        .is_some_and(|steps| {
+            steps
+                .iter()
+                .any(|s| s.get("function").and_then(Value::as_str) == Some("existing_target"))
+        });
+    if is_labels {
+        ConfigKind::Labels
+    } else {
+        ConfigKind::Features
+    }
+}
+
+/// Rewrites `config` from its declared version up to version `to`, recording
+/// a human-readable note for every change made.
+pub fn migrate(
+    filepath: &str,
+    kind: ConfigKind,
+    mut config: Value,
+    to: u32,
+) -> Result<Migration, ConfigError> {
+    let error = |error: String| ConfigError::MigrationError {
+        path: filepath.to_string(),
+        error,
+    };
+
+    let from = match config.get("version") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .map(|v| v as u32)
+            .ok_or_else(|| error(format!("invalid version {:?}", v)))?,
+    };
+    if to > CURRENT_VERSION {
+        return Err(error(format!(
+            "target version {} is newer than supported version {}",
+            to, CURRENT_VERSION
+        )));
+    }
+    if to < from {
+        return Err(error(format!(
+            "cannot downgrade from version {} to {}",
+            from, to
+        )));
+    }
+
+    let mut changes = Vec::new();
+    if kind == ConfigKind::Entrypoint {
+        return Ok(Migration {
+            config,
+            from,
+            to: from,
+            changes,
+        });
+    }
+
+    for version in from..to {
+        if version == 1 {
+            v1_to_v2(kind, &mut config, &mut changes).map_err(error)?;
+        }
+    }
+
+    if from != to {
+        config = with_version(config, to);
+        changes.push(format!("set version: {}", to));
+    }
+
+    Ok(Migration {
+        config,
+        from,
+        to,
+        changes,
+    })
+}
+
+fn v1_to_v2(kind: ConfigKind, config: &mut Value, changes: &mut Vec<String>) -> Result<(), String> {
+    let Some(steps) = config.get_mut("steps").and_then(Value::as_sequence_mut) else {
+        return Ok(());
+    };
+
+    for (i, step) in steps.iter_mut().enumerate() {
+        let Some(step) = step.as_mapping_mut() else {
+            continue;
+        };
+        let function = step
+            .get("function")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        match (kind, function.as_str()) {
+            (ConfigKind::Features, "threshold") => {
+                let Some(Value::String(comparator)) = step.get_mut("comparator") else {
+                    continue;
+                };
+                if comparator.starts_with('$') {
+                    continue;
+                }
+                let normalized = normalize_comparator(comparator).ok_or_else(|| {
+                    format!(
+                        "step {}: comparator '{}' has no v2 equivalent (expected gt or lt)",
+                        i + 1,
+                        comparator
+                    )
+                })?;
+                if comparator != normalized {
+                    changes.push(format!(
+                        "step {}: comparator '{}' -> '{}'",
+                        i + 1,
+                        comparator,
+                        normalized
+                    ));
+                    *comparator = normalized.to_string();
+                }
+            }
+            (ConfigKind::Labels, "existing_target") => {
+                let removed = step.shift_remove("name");
+                if removed.is_some() {
+                    changes.push(format!(
+                        "step {}: removed unused 'name' from existing_target",
+                        i + 1
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn normalize_comparator(comparator: &str) -> Option<&'static str> {
+    match comparator.trim().to_ascii_lowercase().as_str() {
+        "gt" | ">" | "greater" | "greater_than" | "above" => Some("gt"),
+        "lt" | "<" | "less" | "less_than" | "below" => Some("lt"),
+        _ => None,
+    }
+}
+
+fn with_version(config: Value, version: u32) -> Value {
+    let Value::Mapping(map) = config else {
+        return config;
+    };
+    let mut out = Mapping::with_capacity(map.len() + 1);
+    out.insert(Value::from("version"), Value::from(version));
+    for (k, v) in map {
+        if k.as_str() != Some("version") {
+            out.insert(k, v);
+        }
+    }
+    Value::Mapping(out)
+}
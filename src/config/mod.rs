@@ -1 +1,12 @@
 pub mod entry;
+pub mod env;
+pub mod fingerprint;
+pub mod format;
+pub mod lookups;
+pub mod migrate;
+pub mod overlay;
+pub mod remote;
+pub mod schema;
+pub mod suggest;
+pub mod template;
+pub mod vars;
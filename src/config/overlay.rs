@@ -0,0 +1,104 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::config::format::{self, ConfigFormat};
+use crate::errors::ConfigError;
+
+/// Loads `filepath` and merges each overlay on top, in order. Returns the merged
+/// value together with the base file's text, which error messages point into.
+pub fn load_layered(
+    filepath: &str,
+    overlays: &[String],
+    format: Option<ConfigFormat>,
+) -> Result<(Value, String), ConfigError> {
+    let (mut value, content) = format::read_value(filepath, format)?;
+
+    for overlay in overlays {
+        let (overlay_value, _) = format::read_value(overlay, format)?;
+        value = merge(value, overlay_value);
+    }
+
+    Ok((value, content))
+}
+
+/// [`load_layered`] followed by deserialization into `T`.
+pub fn load<T: DeserializeOwned>(
+    filepath: &str,
+    overlays: &[String],
+    format: Option<ConfigFormat>,
+) -> Result<T, ConfigError> {
+    let (value, content) = load_layered(filepath, overlays, format)?;
+    serde_json::from_value(value).map_err(|e| {
+        // The merged value has no positions; without overlays or placeholders
+        // the base file alone reproduces the error with its line and column.
+        let direct = (overlays.is_empty() && !content.contains("${")).then(|| {
+            format::parse::<T>(
+                filepath,
+                &content,
+                format.unwrap_or(ConfigFormat::from_path(filepath)),
+            )
+        });
+        match direct {
+            Some(Err(positioned)) => positioned,
+            _ => format::parse_error(filepath, &content, e),
+        }
+    })
+}
+
+/// Merges `overlay` into `base`:
+///
+/// - mappings are merged key by key, recursively; a `null` value removes the key;
+/// - `steps` lists are merged by step `name`: a named overlay step replaces the
+///   base step with the same name in place (or deletes it with `remove: true`),
+///   anything else is appended;
+/// - all other values, including other lists, are replaced by the overlay.
+pub fn merge(base: Value, overlay: Value) -> Value {
+    match (base, overlay) {
+        (Value::Object(mut base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                if value.is_null() {
+                    base.remove(&key);
+                    continue;
+                }
+                let merged = match (key.as_str(), base.remove(&key), value) {
+                    ("steps", Some(Value::Array(existing)), Value::Array(steps)) => {
+                        Value::Array(merge_steps(existing, steps))
+                    }
+                    (_, Some(existing), value) => merge(existing, value),
+                    (_, None, value) => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Object(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+fn merge_steps(mut base: Vec<Value>, overlay: Vec<Value>) -> Vec<Value> {
+    for mut step in overlay {
+        let remove = step
+            .as_object_mut()
+            .and_then(|s| s.remove("remove"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let name = step.get("name").and_then(Value::as_str).map(str::to_string);
+        let existing = name
+            .as_deref()
+            .and_then(|n| base.iter().position(|s| step_name(s) == Some(n)));
+
+        match (existing, remove) {
+            (Some(i), true) => {
+                base.remove(i);
+            }
+            (Some(i), false) => base[i] = step,
+            (None, true) => {}
+            (None, false) => base.push(step),
+        }
+    }
+    base
+}
+
+fn step_name(step: &Value) -> Option<&str> {
+    step.get("name").and_then(Value::as_str)
+}
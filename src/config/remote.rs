@@ -0,0 +1,64 @@
+use sha2::{Digest, Sha256};
+
+use crate::errors::ConfigError;
+
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// The URL without its `#fragment` and `?query`, for extension detection.
+pub fn strip_url_suffix(path: &str) -> &str {
+    let end = path.find(['#', '?']).unwrap_or(path.len());
+    &path[..end]
+}
+
+/// Fetches a config over HTTP(S). A `#sha256=<hex>` fragment pins the expected
+/// checksum of the response body; a mismatch is an error.
+pub fn fetch(url: &str) -> Result<String, ConfigError> {
+    let (location, expected) = match url.split_once('#') {
+        Some((location, fragment)) => (location, fragment.strip_prefix("sha256=")),
+        None => (url, None),
+    };
+
+    let remote_error = |error: String| ConfigError::RemoteError {
+        url: location.to_string(),
+        error,
+    };
+
+    let body = get(location).map_err(remote_error)?;
+
+    if let Some(expected) = expected {
+        let actual = sha256_hex(body.as_bytes());
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(ConfigError::ChecksumMismatch {
+                url: location.to_string(),
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+    }
+
+    Ok(body)
+}
+
+#[cfg(feature = "native")]
+fn get(location: &str) -> Result<String, String> {
+    ureq::get(location)
+        .call()
+        .map_err(|e| e.to_string())?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "native"))]
+fn get(_location: &str) -> Result<String, String> {
+    Err("fetching configs requires the `native` feature".to_string())
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -0,0 +1,25 @@
+use schemars::schema_for;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::entry::EntrypointConfig;
+use crate::pipeline::{features::FeaturePipeline, labels::LabelsPipeline};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigKind {
+    Entrypoint,
+    Features,
+    Labels,
+}
+
+/// JSON Schema for one of the config file kinds, derived from the Rust types.
+/// The same schema validates YAML, JSON and TOML configs.
+pub fn json_schema(kind: ConfigKind) -> Value {
+    let schema = match kind {
+        ConfigKind::Entrypoint => schema_for!(EntrypointConfig),
+        ConfigKind::Features => schema_for!(FeaturePipeline),
+        ConfigKind::Labels => schema_for!(LabelsPipeline),
+    };
+    schema.to_value()
+}
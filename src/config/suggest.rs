@@ -0,0 +1,74 @@
+/// Appends a "did you mean" hint and the source line to serde's
+/// `unknown field` / `unknown variant` errors. Other messages pass through unchanged.
+pub fn enrich(content: &str, message: &str) -> String {
+    if !message.contains("unknown field") && !message.contains("unknown variant") {
+        return message.to_string();
+    }
+
+    let tokens = backticked(message);
+    let Some((unknown, candidates)) = tokens.split_first() else {
+        return message.to_string();
+    };
+
+    let mut enriched = message.to_string();
+    if let Some(best) = closest(unknown, candidates) {
+        enriched.push_str(&format!(". Did you mean `{}`?", best));
+    }
+    if !message.contains("line ")
+        && let Some(line) = find_line(content, unknown)
+    {
+        enriched.push_str(&format!(" (line {})", line));
+    }
+    enriched
+}
+
+/// Closest candidate by edit distance, if it is close enough to be a plausible typo.
+pub fn closest<'a>(unknown: &str, candidates: &'a [String]) -> Option<&'a str> {
+    let max_distance = (unknown.len() / 3).max(2);
+    candidates
+        .iter()
+        .map(|c| (c, levenshtein(unknown, c)))
+        .filter(|(_, d)| *d <= max_distance)
+        .min_by_key(|(_, d)| *d)
+        .map(|(c, _)| c.as_str())
+}
+
+fn backticked(message: &str) -> Vec<String> {
+    message
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .map(|s| s.to_string())
+        .collect()
+}
+
+pub fn find_line(content: &str, token: &str) -> Option<usize> {
+    content
+        .lines()
+        .position(|line| {
+            line.match_indices(token).any(|(i, _)| {
+                let before = line[..i].chars().next_back();
+                let after = line[i + token.len()..].chars().next();
+                !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+            })
+        })
+        .map(|i| i + 1)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
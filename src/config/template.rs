@@ -0,0 +1,115 @@
+use serde_json::{Map, Value};
+
+use crate::errors::ConfigError;
+
+/// Expands `function: template` steps into concrete steps.
+///
+/// A template lists `functions` and optionally `columns` and `group_by_sets`;
+/// one step is generated per combination, in that nesting order. `name` is a
+/// pattern with `{function}`, `{column}` and `{group_by}` placeholders
+/// (`group_by` columns joined by `_`). Any other keys are copied into every
+/// generated step.
+pub fn expand(filepath: &str, mut config: Value) -> Result<Value, ConfigError> {
+    let Some(Value::Array(steps)) = config.get_mut("steps") else {
+        return Ok(config);
+    };
+
+    let mut expanded = Vec::with_capacity(steps.len());
+    for (i, step) in steps.drain(..).enumerate() {
+        match step {
+            Value::Object(template)
+                if template.get("function").and_then(Value::as_str) == Some("template") =>
+            {
+                expanded.extend(expand_template(template).map_err(|error| {
+                    ConfigError::ParseError {
+                        path: filepath.to_string(),
+                        error: format!("step {}: {}", i + 1, error),
+                    }
+                })?);
+            }
+            other => expanded.push(other),
+        }
+    }
+
+    *steps = expanded;
+    Ok(config)
+}
+
+fn expand_template(mut template: Map<String, Value>) -> Result<Vec<Value>, String> {
+    template.remove("function");
+
+    let functions = strings(template.remove("functions"), "functions")?
+        .ok_or("template requires a 'functions' list")?;
+    let columns = strings(template.remove("columns"), "columns")?;
+    let group_by_sets = match template.remove("group_by_sets") {
+        None => None,
+        Some(Value::Array(sets)) => Some(
+            sets.into_iter()
+                .map(|set| strings(Some(set), "group_by_sets").map(Option::unwrap_or_default))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Some(_) => return Err("'group_by_sets' must be a list of column lists".to_string()),
+    };
+
+    let pattern = match template.remove("name") {
+        Some(Value::String(pattern)) => pattern,
+        Some(_) => return Err("template 'name' must be a string".to_string()),
+        None => {
+            let mut pattern = "{function}".to_string();
+            if columns.is_some() {
+                pattern.push_str("_{column}");
+            }
+            if group_by_sets.is_some() {
+                pattern.push_str("_by_{group_by}");
+            }
+            pattern
+        }
+    };
+
+    let columns: Vec<Option<String>> = match columns {
+        Some(columns) => columns.into_iter().map(Some).collect(),
+        None => vec![None],
+    };
+    let group_by_sets: Vec<Option<Vec<String>>> = match group_by_sets {
+        Some(sets) => sets.into_iter().map(Some).collect(),
+        None => vec![None],
+    };
+
+    let mut steps = Vec::new();
+    for function in &functions {
+        for column in &columns {
+            for group_by in &group_by_sets {
+                let mut step = template.clone();
+                step.insert("function".to_string(), Value::from(function.as_str()));
+                let mut name = pattern.replace("{function}", function);
+                if let Some(column) = column {
+                    step.insert("column".to_string(), Value::from(column.as_str()));
+                    name = name.replace("{column}", column);
+                }
+                if let Some(group_by) = group_by {
+                    step.insert("group_by".to_string(), Value::from(group_by.clone()));
+                    name = name.replace("{group_by}", &group_by.join("_"));
+                }
+                step.insert("name".to_string(), Value::from(name));
+                steps.push(Value::Object(step));
+            }
+        }
+    }
+
+    Ok(steps)
+}
+
+fn strings(value: Option<Value>, key: &str) -> Result<Option<Vec<String>>, String> {
+    match value {
+        None => Ok(None),
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s),
+                other => Err(format!("'{}' entries must be strings, got {}", key, other)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        Some(other) => Err(format!("'{}' must be a list, got {}", key, other)),
+    }
+}
@@ -0,0 +1,79 @@
+use serde_json::{Map, Value};
+
+use crate::errors::ConfigError;
+
+/// Resolves `$name` references in `steps` against the top-level `vars:` map.
+///
+/// A string value equal to `$name` is replaced by the variable's value, whatever
+/// its type. Inside a list, a reference to a list variable is spliced in, so
+/// `group_by: [$base_groups, gender]` extends a shared list. `$$` escapes a
+/// literal leading `$`, and strings starting `$.` or `$[`, JSON paths, are
+/// never references.
+pub fn resolve(filepath: &str, mut config: Value) -> Result<Value, ConfigError> {
+    let vars = match config.get("vars") {
+        Some(Value::Object(vars)) => vars.clone(),
+        Some(Value::Null) | None => Map::new(),
+        Some(_) => {
+            return Err(ConfigError::ParseError {
+                path: filepath.to_string(),
+                error: "'vars' must be a mapping of names to values".to_string(),
+            });
+        }
+    };
+
+    if let Some(steps) = config.get_mut("steps") {
+        *steps = substitute(filepath, &vars, steps.take())?;
+    }
+
+    Ok(config)
+}
+
+fn substitute(
+    filepath: &str,
+    vars: &Map<String, Value>,
+    value: Value,
+) -> Result<Value, ConfigError> {
+    match value {
+        Value::String(s) => lookup(filepath, vars, s),
+        Value::Array(items) => {
+            let mut out = Vec::with_capacity(items.len());
+            for item in items {
+                let is_ref = matches!(&item, Value::String(s) if is_reference(s));
+                match substitute(filepath, vars, item)? {
+                    Value::Array(spliced) if is_ref => out.extend(spliced),
+                    other => out.push(other),
+                }
+            }
+            Ok(Value::Array(out))
+        }
+        Value::Object(map) => {
+            let mut out = Map::with_capacity(map.len());
+            for (k, v) in map {
+                out.insert(k, substitute(filepath, vars, v)?);
+            }
+            Ok(Value::Object(out))
+        }
+        other => Ok(other),
+    }
+}
+
+fn is_reference(s: &str) -> bool {
+    s.starts_with('$') && !["$$", "$.", "$["].iter().any(|p| s.starts_with(p))
+}
+
+fn lookup(filepath: &str, vars: &Map<String, Value>, s: String) -> Result<Value, ConfigError> {
+    if let Some(escaped) = s.strip_prefix("$$") {
+        return Ok(Value::String(format!("${escaped}")));
+    }
+    if !is_reference(&s) {
+        return Ok(Value::String(s));
+    }
+    let name = &s[1..];
+    vars.get(name)
+        .cloned()
+        .ok_or_else(|| ConfigError::UnknownVariable {
+            name: name.to_string(),
+            path: filepath.to_string(),
+            available: vars.keys().cloned().collect(),
+        })
+}
@@ -0,0 +1,454 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use polars::prelude::*;
+use serde::Serialize;
+use tracing::{debug, info};
+
+use crate::config::entry::{DataFormat, DatasetConfig, EntrypointConfig};
+use crate::errors::PipelineStepError;
+use crate::filter;
+use crate::spill::{Held, SpillStore};
+
+/// Rows read from one input file of a dataset.
+#[derive(Serialize, Debug, Clone)]
+pub struct SourceFile {
+    pub path: String,
+    pub rows: usize,
+}
+
+/// Files a dataset `path` stands for: the data files of a directory or the
+/// matches of a glob pattern, sorted, or else `path` itself.
+pub fn input_files(config: &DatasetConfig) -> Result<Vec<PathBuf>, PipelineStepError> {
+    let path = Path::new(&config.path);
+    let mut files: Vec<PathBuf> = if path.is_dir() {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            let format = DataFormat::from_extension(&file);
+            if file.is_file() && format.is_some() && config.format.is_none_or(|f| format == Some(f))
+            {
+                files.push(file);
+            }
+        }
+        files
+    } else if config.is_glob() {
+        glob::glob(&config.path)
+            .map_err(|e| polars_err!(ComputeError: "invalid glob '{}': {}", config.path, e))?
+            .filter_map(Result::ok)
+            .filter(|file| file.is_file())
+            .collect()
+    } else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+
+    if files.is_empty() {
+        return Err(polars_err!(ComputeError: "no data files match '{}'", config.path).into());
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Lazily scans one input file of a dataset according to its format and
+/// reader options.
+#[cfg(feature = "native")]
+pub fn scan_file(config: &DatasetConfig, file: &Path) -> Result<LazyFrame, PipelineStepError> {
+    let path = PlPath::new(&file.to_string_lossy());
+    let format = config
+        .format
+        .or(DataFormat::from_extension(file))
+        .unwrap_or(DataFormat::Csv);
+    match format {
+        DataFormat::Csv => {
+            let options = &config.options;
+            let mut reader = LazyCsvReader::new(path)
+                .with_glob(false)
+                .with_has_header(options.has_header.unwrap_or(true))
+                .map_parse_options(|p| {
+                    let mut p = p;
+                    if let Some(separator) = options.separator {
+                        p = p.with_separator(separator as u8);
+                    }
+                    if !options.null_values.is_empty() {
+                        p = p.with_null_values(Some(NullValues::AllColumns(
+                            options.null_values.iter().map(|v| v.into()).collect(),
+                        )));
+                    }
+                    p
+                });
+            if let Some(length) = options.infer_schema_length {
+                reader = reader.with_infer_schema_length(Some(length));
+            }
+            Ok(reader.finish()?)
+        }
+        DataFormat::Parquet => {
+            let args = ScanArgsParquet {
+                glob: false,
+                ..Default::default()
+            };
+            Ok(LazyFrame::scan_parquet(path, args)?)
+        }
+    }
+}
+
+/// Without the `native` feature no file format can be read.
+#[cfg(not(feature = "native"))]
+pub fn scan_file(_config: &DatasetConfig, file: &Path) -> Result<LazyFrame, PipelineStepError> {
+    Err(polars_err!(
+        ComputeError: "cannot read '{}': reading files requires the `native` feature",
+        file.display()
+    )
+    .into())
+}
+
+/// Columns each dataset is read with when the entrypoint prunes columns:
+/// those `readers` reference, given as a dataset name (the primary one when
+/// `None`) and the columns it reads, plus the dataset's `passthrough`, the
+/// columns it explodes and those its `resample` reads.
+/// Datasets no reader names are read whole, as is every dataset when
+/// `prune_columns` is off.
+pub fn required_columns<'a>(
+    config: &EntrypointConfig,
+    readers: impl IntoIterator<Item = (Option<&'a str>, BTreeSet<&'a str>)>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut required: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    if !config.prune_columns {
+        return required;
+    }
+    for (dataset, columns) in readers {
+        let name = dataset.unwrap_or(config.primary_dataset());
+        let Some(dataset) = config.datasets.get(name) else {
+            continue;
+        };
+        required
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                let resampled = dataset.resample.iter().flat_map(|r| r.input_columns());
+                dataset
+                    .passthrough
+                    .iter()
+                    .chain(&dataset.explode)
+                    .cloned()
+                    .chain(resampled.map(str::to_string))
+                    .collect()
+            })
+            .extend(columns.into_iter().map(str::to_string));
+    }
+    required
+}
+
+/// Column tagging each row with the index of the file it was read from, so
+/// files read in one plan can still be counted apart.
+const SOURCE_COLUMN: &str = "__source_file__";
+
+/// The plan reading one input file: only the rows meeting the dataset's
+/// `filter` and, with `columns`, only those columns, with its `explode`
+/// columns unnested. Files are exploded one by one so each file's row count
+/// covers its unnested rows.
+fn scan_input(
+    config: &DatasetConfig,
+    file: &Path,
+    columns: Option<&BTreeSet<String>>,
+) -> Result<LazyFrame, PipelineStepError> {
+    let frame = filter::apply(scan_file(config, file)?, &config.filter)?;
+    let frame = project(frame, columns, config, file)?;
+    Ok(match config.explode.as_slice() {
+        [] => frame,
+        explode => frame.explode(by_name(explode.iter().cloned(), true)),
+    })
+}
+
+/// Keeps the columns of `frame` listed in `columns`, so only those are read.
+/// Fails when the file lacks any listed column, other than those an
+/// aggregating `resample` produces, rather than read the rest without it.
+fn project(
+    frame: LazyFrame,
+    columns: Option<&BTreeSet<String>>,
+    config: &DatasetConfig,
+    file: &Path,
+) -> Result<LazyFrame, PipelineStepError> {
+    let Some(columns) = columns else {
+        return Ok(frame);
+    };
+    let mut frame = frame;
+    let schema = frame.collect_schema()?;
+    let resampled: BTreeSet<&str> = config
+        .resample
+        .iter()
+        .filter(|r| r.aggregates())
+        .flat_map(|r| r.output_columns())
+        .collect();
+    let missing: Vec<String> = columns
+        .iter()
+        .filter(|c| !schema.contains(c.as_str()) && !resampled.contains(c.as_str()))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        return Err(PipelineStepError::MissingColumns {
+            path: file.display().to_string(),
+            missing,
+            available: schema.iter_names().map(|c| c.to_string()).collect(),
+        });
+    }
+    let keep: Vec<Expr> = schema
+        .iter_names()
+        .filter(|name| columns.contains(name.as_str()))
+        .map(|name| col(name.clone()))
+        .collect();
+    debug!(
+        "Reading {} of {} columns from {}",
+        keep.len(),
+        schema.len(),
+        file.display()
+    );
+    Ok(frame.select(keep))
+}
+
+/// Reads every input file of a dataset, up to `concurrency` at a time, and
+/// stacks them in file order. Column types that differ between files are
+/// widened to a common type. With `columns`, only those are read. The stacked
+/// rows are then resampled when the dataset sets `resample`; sources keep the
+/// rows read from each file.
+pub fn load_files(
+    config: &DatasetConfig,
+    columns: Option<&BTreeSet<String>>,
+) -> Result<(DataFrame, Vec<SourceFile>), PipelineStepError> {
+    let (frame, sources) = read_files(config, columns)?;
+    let frame = match &config.resample {
+        Some(resample) => {
+            let read = frame.height();
+            let frame = resample.apply(frame)?;
+            info!(
+                "Resampled '{}' by {} into {} rows ({} read)",
+                config.path,
+                resample.column,
+                frame.height(),
+                read
+            );
+            frame
+        }
+        None => frame,
+    };
+    Ok((frame, sources))
+}
+
+/// Reads the input files of a dataset as one plan, collected once: each
+/// file's scan, filter, projection and explode, stacked in file order.
+fn read_files(
+    config: &DatasetConfig,
+    columns: Option<&BTreeSet<String>>,
+) -> Result<(DataFrame, Vec<SourceFile>), PipelineStepError> {
+    let files = input_files(config)?;
+    if let [file] = files.as_slice() {
+        let frame = scan_input(config, file, columns)?.collect()?;
+        let source = SourceFile {
+            path: config.path.clone(),
+            rows: frame.height(),
+        };
+        return Ok((frame, vec![source]));
+    }
+
+    let scans = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| {
+            Ok(scan_input(config, file, columns)?
+                .with_column(lit(i as u32).cast(DataType::UInt32).alias(SOURCE_COLUMN)))
+        })
+        .collect::<Result<Vec<LazyFrame>, PipelineStepError>>()?;
+    // Polars reads the inputs of a parallel union at once and those of a
+    // sequential one in turn, so `concurrency` parallel groups of files,
+    // each read in turn, keep that many files open.
+    let concurrency = config
+        .concurrency
+        .unwrap_or_else(rayon::current_num_threads)
+        .min(files.len());
+    let union = |parallel| UnionArgs {
+        parallel,
+        to_supertypes: true,
+        ..Default::default()
+    };
+    let groups = scans
+        .chunks(files.len().div_ceil(concurrency))
+        .map(|group| concat(group, union(false)))
+        .collect::<PolarsResult<Vec<LazyFrame>>>()?;
+    let mut frame = concat(groups, union(true))?.collect()?;
+    info!(
+        "Read {} files for '{}' ({} at a time)",
+        files.len(),
+        config.path,
+        concurrency
+    );
+
+    let mut rows = vec![0; files.len()];
+    for i in frame.column(SOURCE_COLUMN)?.u32()?.into_no_null_iter() {
+        rows[i as usize] += 1;
+    }
+    let _ = frame.drop_in_place(SOURCE_COLUMN)?;
+    let sources = files
+        .iter()
+        .zip(rows)
+        .map(|(file, rows)| SourceFile {
+            path: file.display().to_string(),
+            rows,
+        })
+        .collect();
+    Ok((frame, sources))
+}
+
+/// Reads one dataset, stacking its files when `path` names several.
+pub fn load_dataset(config: &DatasetConfig) -> Result<DataFrame, PipelineStepError> {
+    Ok(load_files(config, None)?.0)
+}
+
+/// All datasets declared in the entrypoint, loaded and addressable by name.
+#[derive(Debug)]
+pub struct Datasets {
+    frames: BTreeMap<String, Held>,
+    sources: BTreeMap<String, Vec<SourceFile>>,
+    primary: String,
+}
+
+impl Datasets {
+    /// Loads every dataset, reading only `columns[name]` where given; see
+    /// [`required_columns`].
+    pub fn load(
+        config: &EntrypointConfig,
+        columns: &BTreeMap<String, BTreeSet<String>>,
+    ) -> Result<Self, PipelineStepError> {
+        let mut frames = BTreeMap::new();
+        let mut sources = BTreeMap::new();
+        for (name, dataset) in &config.datasets {
+            for condition in &dataset.filter {
+                info!("Dataset '{}' filter: {}", name, condition);
+            }
+            let (frame, files) = load_files(dataset, columns.get(name))?;
+            frames.insert(name.clone(), Held::Memory(frame));
+            sources.insert(name.clone(), files);
+        }
+        Ok(Self {
+            frames,
+            sources,
+            primary: config.primary_dataset().to_string(),
+        })
+    }
+
+    /// The named dataset, read back from disk if it was spilled.
+    pub fn get(&self, name: &str) -> Result<Cow<'_, DataFrame>, PipelineStepError> {
+        self.frames
+            .get(name)
+            .ok_or_else(|| PipelineStepError::DatasetNotFound {
+                name: name.to_string(),
+                available: self.frames.keys().cloned().collect(),
+            })?
+            .frame()
+    }
+
+    /// The named dataset, or the primary one when `name` is `None`.
+    pub fn select(&self, name: Option<&str>) -> Result<Cow<'_, DataFrame>, PipelineStepError> {
+        self.get(name.unwrap_or(&self.primary))
+    }
+
+    /// Moves the datasets that don't fit in what is left of `store`'s budget
+    /// to disk, in name order. Returns the names of those spilled.
+    pub fn spill(&mut self, store: &mut SpillStore) -> Result<Vec<String>, PipelineStepError> {
+        let mut spilled = Vec::new();
+        for (name, held) in &mut self.frames {
+            if let Held::Memory(frame) = held {
+                *held = store.hold(name, std::mem::take(frame))?;
+                if held.is_spilled() {
+                    spilled.push(name.clone());
+                }
+            }
+        }
+        Ok(spilled)
+    }
+
+    /// Files the named (or primary) dataset was read from, in row order.
+    pub fn sources(&self, name: Option<&str>) -> &[SourceFile] {
+        self.sources
+            .get(name.unwrap_or(&self.primary))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn primary(&self) -> Result<Cow<'_, DataFrame>, PipelineStepError> {
+        self.get(&self.primary)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.frames.keys().map(|k| k.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> LazyFrame {
+        df!("user" => ["a", "b"], "day" => ["2023-01-01", "2023-01-02"], "amount" => [1.0, 2.0])
+            .unwrap()
+            .lazy()
+    }
+
+    fn columns(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn dataset(yaml: &str) -> DatasetConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn project_keeps_only_read_columns() {
+        let read = columns(&["amount", "user"]);
+        let projected = project(
+            frame(),
+            Some(&read),
+            &dataset("path: tx.csv"),
+            Path::new("tx.csv"),
+        )
+        .unwrap()
+        .collect()
+        .unwrap();
+        assert_eq!(projected.get_column_names(), ["user", "amount"]);
+    }
+
+    #[test]
+    fn project_names_missing_columns() {
+        let read = columns(&["amount", "price", "user", "zip"]);
+        let error = project(
+            frame(),
+            Some(&read),
+            &dataset("path: tx.csv"),
+            Path::new("tx.csv"),
+        )
+        .err()
+        .unwrap();
+        match error {
+            PipelineStepError::MissingColumns {
+                path,
+                missing,
+                available,
+            } => {
+                assert_eq!(path, "tx.csv");
+                assert_eq!(missing, ["price", "zip"]);
+                assert_eq!(available, ["user", "day", "amount"]);
+            }
+            other => panic!("expected MissingColumns, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn project_allows_resample_outputs() {
+        let config = dataset(
+            "path: tx.csv\nresample:\n  column: day\n  every: month\n  group_by: [user]\n  \
+             aggregations:\n    - {column: amount, agg: sum, name: total}",
+        );
+        let read = columns(&["amount", "day", "total", "user"]);
+        assert!(project(frame(), Some(&read), &config, Path::new("tx.csv")).is_ok());
+    }
+}
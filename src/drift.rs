@@ -0,0 +1,300 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ConfigError, PipelineStepError};
+use crate::profiling::{self, ColumnProfile, FrameProfile};
+
+/// Floor for bin shares in PSI, so empty bins don't make it infinite.
+const MIN_SHARE: f64 = 1e-4;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftAction {
+    /// Log the drifted columns and carry on.
+    #[default]
+    Warn,
+    /// Fail the run before outputs are written.
+    Fail,
+}
+
+/// Which frame is compared with the baseline.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftFrame {
+    #[default]
+    Input,
+    Features,
+}
+
+impl DriftFrame {
+    /// Name of the matching frame in `profile.json`.
+    pub fn profile_name(&self) -> &'static str {
+        match self {
+            Self::Input => "input",
+            Self::Features => "features",
+        }
+    }
+}
+
+fn default_max_psi() -> f64 {
+    0.2
+}
+
+fn default_max_ks() -> f64 {
+    0.2
+}
+
+/// Compares the current data with a baseline `profile.json` from an earlier run.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DriftConfig {
+    /// `profile.json` written by a run with `profiling` enabled.
+    pub baseline: String,
+
+    #[serde(default)]
+    pub frame: DriftFrame,
+
+    /// Columns whose population stability index exceeds this value drift.
+    #[serde(default = "default_max_psi")]
+    pub max_psi: f64,
+
+    /// Numeric columns whose Kolmogorov-Smirnov statistic, computed over the
+    /// baseline histogram bins, exceeds this value drift.
+    #[serde(default = "default_max_ks")]
+    pub max_ks: f64,
+
+    #[serde(default)]
+    pub action: DriftAction,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum SchemaChange {
+    Added {
+        column: String,
+        dtype: String,
+    },
+    Removed {
+        column: String,
+        dtype: String,
+    },
+    TypeChanged {
+        column: String,
+        baseline: String,
+        current: String,
+    },
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ColumnDrift {
+    pub column: String,
+    pub psi: f64,
+    /// Only computed for numeric columns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ks: Option<f64>,
+    /// Only reported when the baseline profile lists every category.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub new_categories: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_categories: Vec<String>,
+    pub drifted: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DriftReport {
+    pub baseline: String,
+    pub frame: DriftFrame,
+    pub schema_changes: Vec<SchemaChange>,
+    pub columns: Vec<ColumnDrift>,
+}
+
+impl DriftReport {
+    /// One line per schema change and drifted column.
+    pub fn issues(&self) -> Vec<String> {
+        let schema = self.schema_changes.iter().map(|change| match change {
+            SchemaChange::Added { column, dtype } => format!("'{}' ({}) is new", column, dtype),
+            SchemaChange::Removed { column, dtype } => {
+                format!("'{}' ({}) is missing", column, dtype)
+            }
+            SchemaChange::TypeChanged {
+                column,
+                baseline,
+                current,
+            } => format!("'{}' changed type from {} to {}", column, baseline, current),
+        });
+        let columns = self.columns.iter().filter(|c| c.drifted).map(|c| {
+            let mut message = format!("'{}' drifted: psi {:.3}", c.column, c.psi);
+            if let Some(ks) = c.ks {
+                message.push_str(&format!(", ks {:.3}", ks));
+            }
+            if !c.new_categories.is_empty() {
+                message.push_str(&format!(", new categories {:?}", c.new_categories));
+            }
+            if !c.missing_categories.is_empty() {
+                message.push_str(&format!(", missing categories {:?}", c.missing_categories));
+            }
+            message
+        });
+        schema.chain(columns).collect()
+    }
+}
+
+impl DriftConfig {
+    /// Reads the baseline profile of [`DriftConfig::frame`].
+    pub fn load_baseline(&self) -> Result<FrameProfile, ConfigError> {
+        if !Path::new(&self.baseline).exists() {
+            return Err(ConfigError::FileNotFound {
+                path: self.baseline.clone(),
+                kind: "Drift baseline".to_string(),
+            });
+        }
+        let invalid = |error: String| ConfigError::ParseError {
+            path: self.baseline.clone(),
+            error,
+        };
+        let profiles: Vec<FrameProfile> =
+            serde_json::from_str(&fs::read_to_string(&self.baseline)?)
+                .map_err(|e| invalid(e.to_string()))?;
+        let name = self.frame.profile_name();
+        profiles
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| invalid(format!("no '{}' profile", name)))
+    }
+
+    /// Compares every column of `data` present in `baseline`.
+    pub fn compare(
+        &self,
+        baseline: &FrameProfile,
+        data: &DataFrame,
+    ) -> Result<DriftReport, PipelineStepError> {
+        let mut schema_changes = Vec::new();
+        let mut columns = Vec::new();
+
+        for expected in &baseline.columns {
+            let Ok(column) = data.column(&expected.name) else {
+                schema_changes.push(SchemaChange::Removed {
+                    column: expected.name.clone(),
+                    dtype: expected.dtype.clone(),
+                });
+                continue;
+            };
+            let dtype = column.dtype().to_string();
+            if dtype != expected.dtype {
+                schema_changes.push(SchemaChange::TypeChanged {
+                    column: expected.name.clone(),
+                    baseline: expected.dtype.clone(),
+                    current: dtype,
+                });
+                continue;
+            }
+            columns.push(self.compare_column(expected, column)?);
+        }
+
+        for column in data.get_columns() {
+            if !baseline
+                .columns
+                .iter()
+                .any(|c| c.name == column.name().as_str())
+            {
+                schema_changes.push(SchemaChange::Added {
+                    column: column.name().to_string(),
+                    dtype: column.dtype().to_string(),
+                });
+            }
+        }
+
+        Ok(DriftReport {
+            baseline: self.baseline.clone(),
+            frame: self.frame,
+            schema_changes,
+            columns,
+        })
+    }
+
+    fn compare_column(
+        &self,
+        expected: &ColumnProfile,
+        column: &Column,
+    ) -> Result<ColumnDrift, PipelineStepError> {
+        let mut drift = ColumnDrift {
+            column: expected.name.clone(),
+            psi: 0.0,
+            ks: None,
+            new_categories: Vec::new(),
+            missing_categories: Vec::new(),
+            drifted: false,
+        };
+
+        if let Some(histogram) = &expected.histogram {
+            let values = column.cast(&DataType::Float64)?;
+            let current = profiling::bin_counts(values.f64()?, &histogram.edges);
+            drift.psi = psi(&histogram.counts, &current);
+            drift.ks = Some(ks(&histogram.counts, &current));
+        } else if !expected.top_values.is_empty() {
+            let mut counts = profiling::value_counts(column)?;
+            let mut baseline: Vec<usize> = Vec::new();
+            let mut current: Vec<usize> = Vec::new();
+            for value in &expected.top_values {
+                baseline.push(value.count);
+                current.push(counts.remove(&value.value).unwrap_or(0));
+                if current.last() == Some(&0) {
+                    drift.missing_categories.push(value.value.clone());
+                }
+            }
+
+            let listed: usize = expected.top_values.iter().map(|v| v.count).sum();
+            let baseline_other = (expected.count - expected.null_count).saturating_sub(listed);
+            if baseline_other == 0 {
+                drift.new_categories = counts
+                    .keys()
+                    .cloned()
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect();
+            }
+            baseline.push(baseline_other);
+            current.push(counts.values().sum());
+            drift.psi = psi(&baseline, &current);
+        }
+
+        drift.drifted = drift.psi > self.max_psi
+            || drift.ks.is_some_and(|ks| ks > self.max_ks)
+            || !drift.new_categories.is_empty()
+            || !drift.missing_categories.is_empty();
+        Ok(drift)
+    }
+}
+
+fn shares(counts: &[usize]) -> Vec<f64> {
+    let total = counts.iter().sum::<usize>().max(1) as f64;
+    counts.iter().map(|&c| c as f64 / total).collect()
+}
+
+/// Population stability index between two binned distributions.
+fn psi(baseline: &[usize], current: &[usize]) -> f64 {
+    shares(baseline)
+        .into_iter()
+        .zip(shares(current))
+        .map(|(b, c)| {
+            let (b, c) = (b.max(MIN_SHARE), c.max(MIN_SHARE));
+            (c - b) * (c / b).ln()
+        })
+        .sum()
+}
+
+/// Largest gap between the two cumulative distributions, over shared bins.
+fn ks(baseline: &[usize], current: &[usize]) -> f64 {
+    let (mut cdf_baseline, mut cdf_current, mut max_gap) = (0.0, 0.0, 0.0_f64);
+    for (b, c) in shares(baseline).into_iter().zip(shares(current)) {
+        cdf_baseline += b;
+        cdf_current += c;
+        max_gap = max_gap.max((cdf_baseline - cdf_current).abs());
+    }
+    max_gap
+}
@@ -3,7 +3,7 @@ use std::{
     fmt::{self},
 };
 
-use polars::error::PolarsError;
+use polars::{error::PolarsError, frame::DataFrame};
 
 #[derive(Debug)]
 pub enum PipelineStepError {
@@ -14,9 +14,31 @@ pub enum PipelineStepError {
     EmptyGroupby {
         feature_name: String,
     },
+    DatasetNotFound {
+        name: String,
+        available: Vec<String>,
+    },
+    ExpectationFailed {
+        failures: Vec<String>,
+    },
+    /// Columns the config reads that an input file doesn't have.
+    MissingColumns {
+        path: String,
+        missing: Vec<String>,
+        available: Vec<String>,
+    },
+    /// Any of the above, raised while applying a pipeline step.
+    Step {
+        /// 1-based position in `steps`.
+        step: usize,
+        function: &'static str,
+        name: Option<String>,
+        source: Box<PipelineStepError>,
+    },
     DataframeError(PolarsError),
     IoError(std::io::Error),
     SerdeError(serde_yaml::Error),
+    ConfigError(ConfigError),
 }
 
 impl fmt::Display for PipelineStepError {
@@ -25,7 +47,7 @@ impl fmt::Display for PipelineStepError {
             PipelineStepError::ColumnNotFound { found, available } => {
                 write!(
                     f,
-                    "Column '{}' not found. Available [{:?}]",
+                    "Column '{}' not found; available: {:?}",
                     found, available
                 )
             }
@@ -36,15 +58,83 @@ impl fmt::Display for PipelineStepError {
                     feature_name
                 )
             }
+            PipelineStepError::DatasetNotFound { name, available } => {
+                write!(
+                    f,
+                    "Dataset '{}' not declared in entrypoint. Available [{:?}]",
+                    name, available
+                )
+            }
+            PipelineStepError::ExpectationFailed { failures } => {
+                write!(f, "Expectations failed: {}", failures.join("; "))
+            }
+            PipelineStepError::MissingColumns {
+                path,
+                missing,
+                available,
+            } => {
+                write!(
+                    f,
+                    "Columns {:?} read by the config not found in {}; available: {:?}",
+                    missing, path, available
+                )
+            }
+            PipelineStepError::Step {
+                step,
+                function,
+                name,
+                source,
+            } => {
+                write!(f, "step {} (`{}`", step, function)?;
+                if let Some(name) = name {
+                    write!(f, ", name=`{}`", name)?;
+                }
+                write!(f, "): {}", source)
+            }
             PipelineStepError::DataframeError(err) => write!(f, "Polars error: {}", err),
             PipelineStepError::IoError(e) => write!(f, "IO error: {}", e),
             PipelineStepError::SerdeError(e) => write!(f, "Serde error: {}", e),
+            PipelineStepError::ConfigError(e) => write!(f, "Config error: {}", e),
         }
     }
 }
 
 impl Error for PipelineStepError {}
 
+impl PipelineStepError {
+    /// Attributes `error` to the step at `index` that received `input`. Polars'
+    /// plain `"name" not found` errors are given the columns `input` had.
+    pub fn in_step(
+        index: usize,
+        function: &'static str,
+        name: Option<&str>,
+        input: &DataFrame,
+        error: PipelineStepError,
+    ) -> Self {
+        let source = match error {
+            PipelineStepError::DataframeError(PolarsError::ColumnNotFound(ref message))
+                if let Some(found) = message.strip_suffix(" not found") =>
+            {
+                PipelineStepError::ColumnNotFound {
+                    found: found.trim_matches('"').to_string(),
+                    available: input
+                        .get_column_names()
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect(),
+                }
+            }
+            error => error,
+        };
+        PipelineStepError::Step {
+            step: index + 1,
+            function,
+            name: name.map(str::to_string),
+            source: Box::new(source),
+        }
+    }
+}
+
 impl From<PolarsError> for PipelineStepError {
     fn from(value: PolarsError) -> Self {
         PipelineStepError::DataframeError(value)
@@ -63,12 +153,52 @@ impl From<serde_yaml::Error> for PipelineStepError {
     }
 }
 
+impl From<ConfigError> for PipelineStepError {
+    fn from(value: ConfigError) -> Self {
+        PipelineStepError::ConfigError(value)
+    }
+}
+
 #[derive(Debug)]
 pub enum ConfigError {
-    FileNotFound { path: String, kind: String },
+    FileNotFound {
+        path: String,
+        kind: String,
+    },
     IoError(std::io::Error),
-    ParseError { path: String, error: String },
+    ParseError {
+        path: String,
+        error: String,
+    },
     SerdeError(serde_yaml::Error),
+    MissingEnvVar {
+        path: String,
+        name: String,
+    },
+    UnknownVariable {
+        path: String,
+        name: String,
+        available: Vec<String>,
+    },
+    MigrationError {
+        path: String,
+        error: String,
+    },
+    RemoteError {
+        url: String,
+        error: String,
+    },
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+    /// A step built in code with invalid parameters; see
+    /// [`FeatureConfig`](crate::pipeline::features::FeatureConfig)'s constructors.
+    InvalidStep {
+        function: &'static str,
+        error: String,
+    },
 }
 
 impl fmt::Display for ConfigError {
@@ -82,6 +212,44 @@ impl fmt::Display for ConfigError {
             }
             ConfigError::IoError(e) => write!(f, "IO error: {}", e),
             ConfigError::SerdeError(e) => write!(f, "Serde error: {}", e),
+            ConfigError::MissingEnvVar { path, name } => {
+                write!(
+                    f,
+                    "Environment variable '{}' referenced in {} is not set and has no default",
+                    name, path
+                )
+            }
+            ConfigError::UnknownVariable {
+                path,
+                name,
+                available,
+            } => {
+                write!(
+                    f,
+                    "Variable '${}' in {} is not defined in 'vars'. Available [{:?}]",
+                    name, path, available
+                )
+            }
+            ConfigError::MigrationError { path, error } => {
+                write!(f, "Failed to migrate {}: {}", path, error)
+            }
+            ConfigError::RemoteError { url, error } => {
+                write!(f, "Failed to fetch {}: {}", url, error)
+            }
+            ConfigError::ChecksumMismatch {
+                url,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "Checksum mismatch for {}: expected sha256 {}, got {}",
+                    url, expected, actual
+                )
+            }
+            ConfigError::InvalidStep { function, error } => {
+                write!(f, "Invalid `{}` step: {}", function, error)
+            }
         }
     }
 }
@@ -0,0 +1,286 @@
+//! Feast definitions for the features a config produces, so the columns a run
+//! writes can be registered in a feature store without translating the
+//! config by hand.
+//!
+//! Each set of `group_by` columns becomes a feature view keyed by one entity
+//! per column, holding the aggregations grouped that way. Row-level steps
+//! (`ratio`, `threshold`, `map`) go in a `rows` view keyed by the pipeline's
+//! `keys`, or with no entities when it has none. `ohe` columns depend on the
+//! data and `expect` steps produce none; both are left out.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde::Serialize;
+
+use crate::pipeline::calendar::CalendarOutput;
+use crate::pipeline::email::EmailOutput;
+use crate::pipeline::features::{FeatureConfig, FeaturePipeline, RollingAgg};
+
+/// Name of the view holding the row-level features.
+const ROW_VIEW: &str = "rows";
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FeastFormat {
+    /// A Python module for a Feast feature repository.
+    Python,
+    /// The same definitions as YAML, for tooling that generates its own.
+    Yaml,
+}
+
+/// Where the feature views read from: the features file of a run, which must
+/// carry the entity columns and an event timestamp.
+#[derive(Serialize, Debug, Clone)]
+pub struct FeastSource {
+    pub name: String,
+    pub path: String,
+    pub timestamp_field: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FeastEntity {
+    pub name: String,
+    pub join_keys: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FeastField {
+    pub name: String,
+    /// A `feast.types` primitive: `Float64`, `Int64` or `Bool`.
+    pub dtype: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FeastFeatureView {
+    pub name: String,
+    /// Names of the entities the view is keyed by.
+    pub entities: Vec<String>,
+    pub schema: Vec<FeastField>,
+}
+
+/// Feast definitions of a features config; see the module docs.
+#[derive(Serialize, Debug, Clone)]
+pub struct FeastRepo {
+    pub project: String,
+    pub source: FeastSource,
+    pub entities: Vec<FeastEntity>,
+    pub feature_views: Vec<FeastFeatureView>,
+    /// Steps with no Feast field, as `<function>` or `<function> '<name>'`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+impl FeastRepo {
+    pub fn new(pipeline: &FeaturePipeline, project: &str, source: FeastSource) -> Self {
+        let row_keys: Vec<String> = pipeline
+            .keys
+            .as_ref()
+            .map(|k| k.columns.clone())
+            .unwrap_or_default();
+        let mut views: BTreeMap<Vec<String>, Vec<FeastField>> = BTreeMap::new();
+        let mut skipped = Vec::new();
+        for step in &pipeline.steps {
+            let Some((name, dtype)) = step.name().zip(dtype(step)) else {
+                skipped.push(match step.name() {
+                    Some(name) => format!("{} '{}'", step.function(), name),
+                    None => step.function().to_string(),
+                });
+                continue;
+            };
+            let keys = match step.group_by() {
+                Some(group_by) if !group_by.is_empty() => group_by.to_vec(),
+                _ => row_keys.clone(),
+            };
+            let metadata = step.metadata();
+            let mut tags: BTreeMap<String, String> = metadata
+                .tags
+                .iter()
+                .map(|tag| (tag.clone(), "true".to_string()))
+                .collect();
+            if let Some(owner) = metadata.owner {
+                tags.insert("owner".to_string(), owner.to_string());
+            }
+            views.entry(keys).or_default().push(FeastField {
+                name: format!("feature_{}", name),
+                dtype,
+                description: metadata.description.map(str::to_string),
+                tags,
+            });
+        }
+
+        let mut entities: Vec<FeastEntity> = views
+            .keys()
+            .flatten()
+            .map(|key| FeastEntity {
+                name: key.clone(),
+                join_keys: vec![key.clone()],
+            })
+            .collect();
+        entities.sort_by(|a, b| a.name.cmp(&b.name));
+        entities.dedup_by(|a, b| a.name == b.name);
+        let feature_views = views
+            .into_iter()
+            .map(|(keys, schema)| FeastFeatureView {
+                name: if keys == row_keys {
+                    ROW_VIEW.to_string()
+                } else {
+                    format!("by_{}", keys.join("_"))
+                },
+                entities: keys,
+                schema,
+            })
+            .collect();
+        Self {
+            project: project.to_string(),
+            source,
+            entities,
+            feature_views,
+            skipped,
+        }
+    }
+
+    pub fn render(&self, format: FeastFormat) -> Result<String, serde_yaml::Error> {
+        match format {
+            FeastFormat::Python => Ok(self.to_python()),
+            FeastFormat::Yaml => serde_yaml::to_string(self),
+        }
+    }
+
+    /// A module defining the entities, the file source and one `FeatureView`
+    /// per view, for `feast apply`.
+    pub fn to_python(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# Generated by features-pipeline for project '{}'; do not edit.",
+            self.project
+        );
+        for step in &self.skipped {
+            let _ = writeln!(out, "# Not exported: {}", step);
+        }
+        out.push_str("from feast import Entity, FeatureView, Field, FileSource\n");
+        out.push_str("from feast.types import Bool, Float64, Int64\n\n");
+
+        for entity in &self.entities {
+            let _ = writeln!(
+                out,
+                "{} = Entity(name={}, join_keys=[{}])",
+                identifier("entity", &entity.name),
+                quote(&entity.name),
+                entity
+                    .join_keys
+                    .iter()
+                    .map(|k| quote(k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        let _ = writeln!(
+            out,
+            "\nsource = FileSource(\n    name={},\n    path={},\n    timestamp_field={},\n)",
+            quote(&self.source.name),
+            quote(&self.source.path),
+            quote(&self.source.timestamp_field)
+        );
+
+        for view in &self.feature_views {
+            let entities: Vec<String> = view
+                .entities
+                .iter()
+                .map(|e| identifier("entity", e))
+                .collect();
+            let _ = writeln!(
+                out,
+                "\n{} = FeatureView(\n    name={},\n    entities=[{}],\n    schema=[",
+                identifier("view", &view.name),
+                quote(&view.name),
+                entities.join(", ")
+            );
+            for field in &view.schema {
+                let mut args = format!("name={}, dtype={}", quote(&field.name), field.dtype);
+                if let Some(description) = &field.description {
+                    let _ = write!(args, ", description={}", quote(description));
+                }
+                if !field.tags.is_empty() {
+                    let tags: Vec<String> = field
+                        .tags
+                        .iter()
+                        .map(|(k, v)| format!("{}: {}", quote(k), quote(v)))
+                        .collect();
+                    let _ = write!(args, ", tags={{{}}}", tags.join(", "));
+                }
+                let _ = writeln!(out, "        Field({}),", args);
+            }
+            out.push_str("    ],\n    source=source,\n)\n");
+        }
+        out
+    }
+}
+
+/// The Feast type of a step's feature; `None` for steps without one.
+fn dtype(step: &FeatureConfig) -> Option<&'static str> {
+    match step {
+        FeatureConfig::Count { .. }
+        | FeatureConfig::CountNonNull { .. }
+        | FeatureConfig::CountDistinct { .. } => Some("Int64"),
+        FeatureConfig::RollingByTime { agg, .. } => Some(match agg {
+            RollingAgg::Count => "Int64",
+            RollingAgg::Sum | RollingAgg::Mean => "Float64",
+        }),
+        FeatureConfig::Calendar { output, .. } => Some(match output {
+            CalendarOutput::DaysToNextHoliday => "Int64",
+            CalendarOutput::IsHoliday | CalendarOutput::IsBusinessDay => "Bool",
+        }),
+        FeatureConfig::Threshold { .. } | FeatureConfig::ListContains { .. } => Some("Bool"),
+        FeatureConfig::ListLen { .. } => Some("Int64"),
+        FeatureConfig::IpFeatures { output, .. } => (!output.is_prefix()).then_some("Bool"),
+        FeatureConfig::UrlParse { output, .. } => (!output.is_key()).then_some("Int64"),
+        FeatureConfig::EmailDomain { output, .. } => {
+            (*output == EmailOutput::IsFreeMail).then_some("Bool")
+        }
+        FeatureConfig::Mean { .. }
+        | FeatureConfig::LooTargetEncode { .. }
+        | FeatureConfig::Sum { .. }
+        | FeatureConfig::Max { .. }
+        | FeatureConfig::Min { .. }
+        | FeatureConfig::PercentRank { .. }
+        | FeatureConfig::Ratio { .. }
+        | FeatureConfig::ListAgg { .. }
+        | FeatureConfig::AnomalyScore { .. }
+        | FeatureConfig::WoeEncode { .. }
+        | FeatureConfig::QuantileTransform { .. }
+        | FeatureConfig::PowerTransform { .. }
+        | FeatureConfig::RobustScale { .. }
+        | FeatureConfig::RandomFeature { .. }
+        | FeatureConfig::Map { .. } => Some("Float64"),
+        FeatureConfig::JsonExtract { .. }
+        | FeatureConfig::ParseNumber { .. }
+        | FeatureConfig::CleanText { .. }
+        | FeatureConfig::CollapseRare { .. }
+        | FeatureConfig::BlockingKey { .. }
+        | FeatureConfig::Geohash { .. }
+        | FeatureConfig::Expect { .. }
+        | FeatureConfig::Ohe { .. }
+        | FeatureConfig::MultiHot { .. } => None,
+    }
+}
+
+/// A Python variable name for `name`, e.g. `entity_hours_per_week` for the
+/// column `hours-per-week`.
+fn identifier(prefix: &str, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{}", prefix, name)
+}
+
+/// A Python string literal; JSON string escapes are valid Python.
+fn quote(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
@@ -0,0 +1,149 @@
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+
+/// A range bound: an integer, kept exact past 2^53, another number, or a
+/// string such as `2024-01-01` that is cast to the column's type, so dates
+/// compare as dates.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Bound {
+    Int(i64),
+    Number(f64),
+    Text(String),
+}
+
+impl Bound {
+    fn to_expr(&self, dtype: &DataType) -> Expr {
+        match self {
+            Self::Int(value) => lit(*value),
+            Self::Number(value) => lit(*value),
+            Self::Text(value) if dtype.is_string() => lit(value.as_str()),
+            Self::Text(value) => lit(value.as_str()).strict_cast(dtype.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for Bound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Number(value) => write!(f, "{}", value),
+            Self::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// A condition input rows must meet to be read. Rows where the column is null
+/// fail every condition.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum RowFilter {
+    /// `column` lies within `min` and `max`, both inclusive; either may be
+    /// omitted.
+    Range {
+        column: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<Bound>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<Bound>,
+    },
+    /// `column` is greater than `value`, e.g. past an incremental watermark.
+    After { column: String, value: Bound },
+    /// `column` is less than `value`.
+    Before { column: String, value: Bound },
+    /// `column` is one of `values`, compared as strings.
+    ValuesIn { column: String, values: Vec<String> },
+    /// `column` is not null.
+    NotNull { column: String },
+}
+
+impl RowFilter {
+    pub fn column(&self) -> &str {
+        match self {
+            Self::Range { column, .. }
+            | Self::After { column, .. }
+            | Self::Before { column, .. }
+            | Self::ValuesIn { column, .. }
+            | Self::NotNull { column } => column,
+        }
+    }
+
+    /// The predicate for a frame with `schema`.
+    fn to_expr(&self, schema: &Schema) -> Result<Expr, PipelineStepError> {
+        let Some(dtype) = schema.get(self.column()) else {
+            return Err(PipelineStepError::ColumnNotFound {
+                found: self.column().to_string(),
+                available: schema.iter_names().map(|name| name.to_string()).collect(),
+            });
+        };
+        let column = col(self.column());
+        let predicate = match self {
+            Self::Range { min, max, .. } => {
+                let mut predicate = column.clone().is_not_null();
+                if let Some(min) = min {
+                    predicate = predicate.and(column.clone().gt_eq(min.to_expr(dtype)));
+                }
+                if let Some(max) = max {
+                    predicate = predicate.and(column.lt_eq(max.to_expr(dtype)));
+                }
+                predicate
+            }
+            Self::After { value, .. } => column
+                .clone()
+                .gt(value.to_expr(dtype))
+                .fill_null(lit(false)),
+            Self::Before { value, .. } => column
+                .clone()
+                .lt(value.to_expr(dtype))
+                .fill_null(lit(false)),
+            Self::ValuesIn { values, .. } => {
+                let column = column.cast(DataType::String);
+                values
+                    .iter()
+                    .map(|value| column.clone().eq(lit(value.as_str())))
+                    .reduce(Expr::or)
+                    .unwrap_or(lit(false))
+                    .fill_null(lit(false))
+            }
+            Self::NotNull { .. } => column.is_not_null(),
+        };
+        Ok(predicate)
+    }
+}
+
+impl std::fmt::Display for RowFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Range { column, min, max } => {
+                let min = min.as_ref().map_or("..".to_string(), |m| m.to_string());
+                let max = max.as_ref().map_or("..".to_string(), |m| m.to_string());
+                write!(f, "range({}, {}, {})", column, min, max)
+            }
+            Self::After { column, value } => write!(f, "after({}, {})", column, value),
+            Self::Before { column, value } => write!(f, "before({}, {})", column, value),
+            Self::ValuesIn { column, values } => {
+                write!(f, "values_in({}, {:?})", column, values)
+            }
+            Self::NotNull { column } => write!(f, "not_null({})", column),
+        }
+    }
+}
+
+/// Keeps the rows of `frame` meeting every filter. Applied to a scan, the
+/// predicate is pushed into the reader, which skips non-matching rows (and
+/// Parquet row groups whose statistics rule them out) instead of loading them.
+pub fn apply(frame: LazyFrame, filters: &[RowFilter]) -> Result<LazyFrame, PipelineStepError> {
+    if filters.is_empty() {
+        return Ok(frame);
+    }
+    let mut frame = frame;
+    let schema = frame.collect_schema()?;
+    let mut predicate = lit(true);
+    for filter in filters {
+        predicate = predicate.and(filter.to_expr(&schema)?);
+    }
+    Ok(frame.filter(predicate))
+}
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+use arrow_array::RecordBatch;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::utils::flight_data_to_batches;
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaResult, Ticket,
+};
+use arrow_schema::SchemaRef;
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use polars::prelude::*;
+use serde::Serialize;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::info;
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::fitted::FittedPipeline;
+
+/// Serves the features of named input frames over Arrow Flight:
+///
+/// - `DoGet` with a dataset name as the ticket streams that dataset's
+///   features, computed by the fitted pipeline on request.
+/// - `DoPut` with a descriptor path `[name]` stores the uploaded frame as an
+///   input under `name`, replacing any dataset of that name, and answers with
+///   its row count; `DoGet` on `name` then returns its features.
+/// - `ListFlights` and `GetFlightInfo` describe the features of each input.
+///
+/// Frames are held in memory for the life of the server.
+pub struct FeatureFlightService {
+    fitted: Arc<FittedPipeline>,
+    inputs: RwLock<BTreeMap<String, Arc<DataFrame>>>,
+}
+
+#[derive(Serialize, Debug)]
+struct PutMetadata<'a> {
+    dataset: &'a str,
+    rows: usize,
+}
+
+impl FeatureFlightService {
+    /// `inputs` are the frames available to `DoGet` before any upload.
+    pub fn new(fitted: FittedPipeline, inputs: BTreeMap<String, DataFrame>) -> Self {
+        Self {
+            fitted: Arc::new(fitted),
+            inputs: RwLock::new(
+                inputs
+                    .into_iter()
+                    .map(|(name, frame)| (name, Arc::new(frame)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn input(&self, name: &str) -> Result<Arc<DataFrame>, Status> {
+        let inputs = self.inputs.read().expect("inputs lock poisoned");
+        inputs.get(name).cloned().ok_or_else(|| {
+            Status::not_found(format!(
+                "no dataset '{}'; available: {:?}",
+                name,
+                inputs.keys().collect::<Vec<_>>()
+            ))
+        })
+    }
+
+    /// The features of the input `name`, computed off the async workers.
+    async fn features(&self, name: &str) -> Result<DataFrame, Status> {
+        let input = self.input(name)?;
+        let fitted = Arc::clone(&self.fitted);
+        tokio::task::spawn_blocking(move || fitted.transform(&input))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(pipeline_status)
+    }
+
+    /// Describes the features of `name` from a transform of none of its rows.
+    fn flight_info(&self, name: &str) -> Result<FlightInfo, Status> {
+        let input = self.input(name)?;
+        let mut empty = self
+            .fitted
+            .transform(&input.head(Some(0)))
+            .map_err(pipeline_status)?;
+        let (schema, _) = to_arrow(&mut empty)?;
+        FlightInfo::new()
+            .try_with_schema(&schema)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(|info| {
+                info.with_descriptor(FlightDescriptor::new_path(vec![name.to_string()]))
+                    .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(name.to_string())))
+                    .with_total_records(input.height() as i64)
+            })
+    }
+}
+
+/// Serves `service` on `addr` until the process is stopped.
+pub async fn serve(
+    addr: SocketAddr,
+    service: FeatureFlightService,
+) -> Result<(), tonic::transport::Error> {
+    info!("Serving Arrow Flight on grpc://{}", addr);
+    tonic::transport::Server::builder()
+        .add_service(FlightServiceServer::new(service))
+        .serve(addr)
+        .await
+}
+
+#[tonic::async_trait]
+impl FlightService for FeatureFlightService {
+    type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+    type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+    type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+    type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+    type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+    type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("handshake is not supported"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        let names: Vec<String> = self
+            .inputs
+            .read()
+            .expect("inputs lock poisoned")
+            .keys()
+            .cloned()
+            .collect();
+        let infos = names
+            .iter()
+            .map(|name| self.flight_info(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Response::new(
+            stream::iter(infos.into_iter().map(Ok)).boxed(),
+        ))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        let name = descriptor_name(&request.into_inner())?;
+        Ok(Response::new(self.flight_info(&name)?))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented("poll_flight_info is not supported"))
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        Err(Status::unimplemented(
+            "get_schema is not supported; use get_flight_info",
+        ))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let ticket = request.into_inner().ticket;
+        let name = std::str::from_utf8(&ticket)
+            .map_err(|_| Status::invalid_argument("ticket must be a UTF-8 dataset name"))?;
+        let mut features = self.features(name).await?;
+        info!(dataset = name, rows = features.height(), "DoGet");
+
+        let (_, batches) = to_arrow(&mut features)?;
+        let stream = FlightDataEncoderBuilder::new()
+            .build(stream::iter(batches.into_iter().map(Ok)))
+            .map_err(Status::from);
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn do_put(
+        &self,
+        request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        let messages: Vec<FlightData> = request.into_inner().try_collect().await?;
+        let descriptor = messages
+            .first()
+            .and_then(|m| m.flight_descriptor.as_ref())
+            .ok_or_else(|| Status::invalid_argument("DoPut needs a flight descriptor"))?;
+        let name = descriptor_name(descriptor)?;
+        let batches = flight_data_to_batches(&messages)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let frame = from_arrow(&batches)?;
+        let rows = frame.height();
+        info!(dataset = name.as_str(), rows, "DoPut");
+
+        self.inputs
+            .write()
+            .expect("inputs lock poisoned")
+            .insert(name.clone(), Arc::new(frame));
+        let metadata = serde_json::to_vec(&PutMetadata {
+            dataset: &name,
+            rows,
+        })
+        .map_err(|e| Status::internal(e.to_string()))?;
+        let result = PutResult {
+            app_metadata: metadata.into(),
+        };
+        Ok(Response::new(stream::once(async { Ok(result) }).boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("do_exchange is not supported"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("do_action is not supported"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(stream::empty().boxed()))
+    }
+}
+
+fn descriptor_name(descriptor: &FlightDescriptor) -> Result<String, Status> {
+    match descriptor.path.as_slice() {
+        [name] => Ok(name.clone()),
+        _ => Err(Status::invalid_argument(
+            "descriptor path must be a single dataset name",
+        )),
+    }
+}
+
+fn pipeline_status(error: PipelineStepError) -> Status {
+    match error {
+        PipelineStepError::ColumnNotFound { .. } | PipelineStepError::Step { .. } => {
+            Status::invalid_argument(error.to_string())
+        }
+        error => Status::internal(error.to_string()),
+    }
+}
+
+/// Polars and arrow-rs don't share array types, so frames cross over as an
+/// in-memory IPC stream. Strings are written as `LargeUtf8`, which older
+/// clients read too.
+fn to_arrow(frame: &mut DataFrame) -> Result<(SchemaRef, Vec<RecordBatch>), Status> {
+    let mut buffer = Vec::new();
+    IpcStreamWriter::new(&mut buffer)
+        .with_compat_level(CompatLevel::oldest())
+        .finish(frame)
+        .map_err(|e| Status::internal(e.to_string()))?;
+    let reader = arrow_ipc::reader::StreamReader::try_new(Cursor::new(buffer), None)
+        .map_err(|e| Status::internal(e.to_string()))?;
+    let schema = reader.schema();
+    let batches = reader
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Status::internal(e.to_string()))?;
+    Ok((schema, batches))
+}
+
+fn from_arrow(batches: &[RecordBatch]) -> Result<DataFrame, Status> {
+    let schema = batches
+        .first()
+        .map(|b| b.schema())
+        .ok_or_else(|| Status::invalid_argument("DoPut uploaded no record batches"))?;
+    let mut buffer = Vec::new();
+    let mut writer = arrow_ipc::writer::StreamWriter::try_new(&mut buffer, &schema)
+        .map_err(|e| Status::internal(e.to_string()))?;
+    for batch in batches {
+        writer
+            .write(batch)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+    }
+    writer
+        .finish()
+        .map_err(|e| Status::internal(e.to_string()))?;
+    drop(writer);
+    IpcStreamReader::new(Cursor::new(buffer))
+        .finish()
+        .map_err(|e| Status::invalid_argument(e.to_string()))
+}
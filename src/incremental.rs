@@ -0,0 +1,255 @@
+//! Incremental runs: only input rows past the watermark of the previous run
+//! are read, and their features and labels are appended to a standing output
+//! folder. The watermark is the largest value of a column, e.g. an order
+//! date, kept in a state file between runs.
+//!
+//! Aggregates are computed over the new rows only, so a `group_by` feature
+//! sees one run's rows, not the full history.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::Local;
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+use crate::filter::{Bound, RowFilter};
+use crate::output::append_csv;
+
+fn default_state() -> String {
+    "data/state/incremental.json".to_string()
+}
+
+fn default_output() -> String {
+    "data/output/incremental".to_string()
+}
+
+/// Reads only rows past the last run's watermark and appends their outputs.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct IncrementalConfig {
+    /// Column of the features dataset that only grows as rows arrive, e.g. a
+    /// date or sequence number. Rows where it is null are never read.
+    pub column: String,
+
+    /// File holding the watermark between runs; deleting it reprocesses
+    /// everything.
+    #[serde(default = "default_state")]
+    pub state: String,
+
+    /// Folder whose `features.csv` and `labels.csv` new rows are appended to.
+    #[serde(default = "default_output")]
+    pub output: String,
+}
+
+/// State saved after each incremental run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Watermark {
+    pub column: String,
+    /// Largest value of `column` processed so far.
+    pub value: Bound,
+    /// Rows appended by the run that set it.
+    pub rows: usize,
+    pub updated_at: String,
+}
+
+impl IncrementalConfig {
+    /// The saved watermark; `None` before the first run, or when the state
+    /// file tracks another column.
+    pub fn load(&self) -> Result<Option<Watermark>, PipelineStepError> {
+        if !Path::new(&self.state).exists() {
+            return Ok(None);
+        }
+        let watermark: Watermark =
+            serde_json::from_str(&fs::read_to_string(&self.state)?).map_err(io::Error::from)?;
+        Ok((watermark.column == self.column).then_some(watermark))
+    }
+
+    /// The filter reading only rows past `watermark`.
+    pub fn filter(&self, watermark: &Watermark) -> RowFilter {
+        RowFilter::After {
+            column: self.column.clone(),
+            value: watermark.value.clone(),
+        }
+    }
+
+    /// The watermark after processing `data`; `None` when it has no values.
+    /// Integers are kept exact and dates and times as text the column's type
+    /// parses back; a maximum that can't be kept exactly, or a NaN, is an
+    /// error rather than a watermark that would skip or repeat rows.
+    pub fn watermark(
+        &self,
+        data: &DataFrame,
+        rows: usize,
+    ) -> Result<Option<Watermark>, PipelineStepError> {
+        let max = data
+            .column(&self.column)?
+            .as_materialized_series()
+            .max_reduce()?;
+        if max.is_null() {
+            return Ok(None);
+        }
+        let unusable = |value: &dyn std::fmt::Display| {
+            polars_err!(
+                ComputeError: "can't use {} as the watermark of '{}'",
+                value,
+                self.column
+            )
+        };
+        let value = if max.dtype().is_integer() {
+            let value = max.value();
+            Bound::Int(value.extract::<i64>().ok_or_else(|| unusable(&value))?)
+        } else if max.dtype().is_primitive_numeric() {
+            let value = max.value();
+            match value.extract::<f64>() {
+                Some(number) if number.is_finite() => Bound::Number(number),
+                _ => return Err(unusable(&value).into()),
+            }
+        } else {
+            let max = max.into_series("max".into());
+            // Datetimes in the ISO 8601 form with a `T`, which is the one
+            // casting text back to a datetime accepts, with every digit.
+            let text = match max.dtype() {
+                DataType::Datetime(..) => max
+                    .datetime()?
+                    .to_string("%Y-%m-%dT%H:%M:%S%.f")?
+                    .into_series(),
+                _ => max.cast(&DataType::String)?,
+            };
+            match text.str()?.get(0) {
+                Some(text) => Bound::Text(text.to_string()),
+                None => return Ok(None),
+            }
+        };
+        Ok(Some(Watermark {
+            column: self.column.clone(),
+            value,
+            rows,
+            updated_at: Local::now().to_rfc3339(),
+        }))
+    }
+
+    pub fn save(&self, watermark: &Watermark) -> Result<(), PipelineStepError> {
+        if let Some(parent) = Path::new(&self.state).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Written aside and renamed, so a crash never leaves half a state file.
+        let partial = format!("{}.partial", self.state);
+        fs::write(
+            &partial,
+            serde_json::to_string_pretty(watermark).map_err(io::Error::from)?,
+        )?;
+        fs::rename(&partial, &self.state)?;
+        Ok(())
+    }
+
+    /// Appends `features` and `labels` to the output folder's CSV files,
+    /// writing headers only to new files.
+    pub fn append(
+        &self,
+        features: &DataFrame,
+        labels: &DataFrame,
+    ) -> Result<(), PipelineStepError> {
+        fs::create_dir_all(&self.output)?;
+        for (name, frame) in [("features", features), ("labels", labels)] {
+            append_csv(
+                &Path::new(&self.output).join(format!("{}.csv", name)),
+                frame,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter;
+
+    fn config(column: &str, test: &str) -> IncrementalConfig {
+        let dir = std::env::temp_dir().join(format!("incremental-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        IncrementalConfig {
+            column: column.to_string(),
+            state: dir.join("state.json").to_string_lossy().into_owned(),
+            output: dir.join("output").to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Saves the watermark of `seen`, loads it back and returns it with the
+    /// rows of `next` past it.
+    fn round_trip(
+        config: &IncrementalConfig,
+        seen: &DataFrame,
+        next: &DataFrame,
+    ) -> (Bound, DataFrame) {
+        let watermark = config.watermark(seen, seen.height()).unwrap().unwrap();
+        config.save(&watermark).unwrap();
+        let loaded = config.load().unwrap().unwrap();
+        let _ = fs::remove_dir_all(Path::new(&config.state).parent().unwrap());
+        assert_eq!(loaded.value, watermark.value);
+        let after = filter::apply(next.clone().lazy(), &[config.filter(&loaded)])
+            .unwrap()
+            .collect()
+            .unwrap();
+        (loaded.value, after)
+    }
+
+    #[test]
+    fn integer_watermark_is_exact_past_2_pow_53() {
+        let config = config("id", "integer");
+        let big = (1i64 << 53) + 1;
+        let seen = df!("id" => [1i64, big, big - 1]).unwrap();
+        let next = df!("id" => [big - 1, big, big + 1]).unwrap();
+
+        let (watermark, after) = round_trip(&config, &seen, &next);
+
+        assert_eq!(watermark, Bound::Int(big));
+        assert_eq!(
+            after.column("id").unwrap().i64().unwrap().get(0),
+            Some(big + 1)
+        );
+        assert_eq!(after.height(), 1);
+    }
+
+    #[test]
+    fn datetime_watermark_keeps_sub_second_digits() {
+        let config = config("at", "datetime");
+        let micros = |values: &[i64]| {
+            Series::new("at".into(), values)
+                .cast(&DataType::Datetime(TimeUnit::Microseconds, None))
+                .unwrap()
+                .into_column()
+        };
+        let start = 1_700_000_000_000_000i64;
+        let seen = DataFrame::new(vec![micros(&[start, start + 1])]).unwrap();
+        let next = DataFrame::new(vec![micros(&[start + 1, start + 2])]).unwrap();
+
+        let (_, after) = round_trip(&config, &seen, &next);
+
+        assert_eq!(after.height(), 1);
+        assert_eq!(
+            after.column("at").unwrap().datetime().unwrap().phys.get(0),
+            Some(start + 2)
+        );
+    }
+
+    #[test]
+    fn nan_is_not_a_watermark() {
+        let config = config("score", "nan");
+        let seen = df!("score" => [f64::NAN, f64::NAN]).unwrap();
+
+        assert!(config.watermark(&seen, seen.height()).is_err());
+    }
+
+    #[test]
+    fn empty_column_has_no_watermark() {
+        let config = config("id", "empty");
+        let seen = df!("id" => [None::<i64>, None]).unwrap();
+
+        assert!(config.watermark(&seen, seen.height()).unwrap().is_none());
+    }
+}
@@ -1,3 +1,31 @@
+pub mod backfill;
+pub mod bench;
 pub mod config;
+pub mod data;
+pub mod drift;
 pub mod errors;
+pub mod feast;
+pub mod filter;
+#[cfg(feature = "flight")]
+pub mod flight;
+pub mod incremental;
+pub mod manifest;
+pub mod metrics;
+pub mod mlflow;
+pub mod output;
 pub mod pipeline;
+pub mod profiling;
+#[cfg(feature = "native")]
+pub mod registry;
+pub mod report;
+pub mod resample;
+pub mod retention;
+pub mod run_result;
+#[cfg(feature = "native")]
+pub mod serve;
+pub mod spill;
+#[cfg(feature = "native")]
+pub mod telemetry;
+pub mod threads;
+#[cfg(feature = "wasm")]
+pub mod wasm;
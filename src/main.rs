@@ -1,23 +1,668 @@
 use chrono::Local;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use features_pipeline::backfill::{self, BackfillState, Partition};
+use features_pipeline::bench::{self, BenchOptions, BenchReport};
+use features_pipeline::drift::{DriftAction, DriftFrame};
+use features_pipeline::feast::{FeastFormat, FeastRepo, FeastSource};
+use features_pipeline::manifest::{self, Manifest};
+use features_pipeline::metrics::MetricsConfig;
+use features_pipeline::mlflow::MlflowRun;
+use features_pipeline::pipeline::expect;
+use features_pipeline::pipeline::fitted::FittedPipeline;
+use features_pipeline::pipeline::joins::{JoinReport, JoinStatus};
+use features_pipeline::pipeline::keys::DuplicateKeyAction;
 use features_pipeline::pipeline::labels::LabelsPipeline;
+use features_pipeline::pipeline::leakage::LeakageAction;
+use features_pipeline::pipeline::lint::Severity;
+use features_pipeline::pipeline::python;
+use features_pipeline::pipeline::quality;
+use features_pipeline::pipeline::split;
+use features_pipeline::pipeline::sql::{SqlDialect, SqlQuery};
+use features_pipeline::profiling;
+use features_pipeline::registry::{self, RunQuery, RunRecord, RunRegistry};
+use features_pipeline::report::{self, RunReport};
+use features_pipeline::retention::{self, RUN_FOLDER_FORMAT, RetentionConfig};
+use features_pipeline::run_result::{FailureKind, RunResult, RunStatus, StageFailed};
+use features_pipeline::serve;
+use features_pipeline::spill::SpillStore;
+use features_pipeline::telemetry;
+use features_pipeline::threads;
 use linfa::DatasetBase;
 use linfa::metrics::ToConfusionMatrix;
 use linfa::prelude::*;
-use linfa_logistic::LogisticRegression;
+use linfa_logistic::{
+    FittedLogisticRegression, LogisticRegression, MultiFittedLogisticRegression,
+    MultiLogisticRegression,
+};
+use ndarray::{Array1, Array2, Axis};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::SdkTracerProvider;
 use polars::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::fs;
 use std::fs::File;
-use std::path::PathBuf;
-use tracing::info;
-use tracing_subscriber;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::{Level, error, field, info, info_span, warn};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
 
 use features_pipeline::config::entry::EntrypointConfig;
-use features_pipeline::pipeline::features::FeaturePipeline;
+use features_pipeline::config::fingerprint::ResolvedConfig;
+use features_pipeline::config::format::{self, ConfigFormat};
+use features_pipeline::config::migrate;
+use features_pipeline::config::remote;
+use features_pipeline::config::schema::{self, ConfigKind};
+use features_pipeline::data::{self, Datasets, SourceFile};
+use features_pipeline::errors::{ConfigError, PipelineStepError};
+use features_pipeline::pipeline::features::{ExecutionStrategy, FeaturePipeline};
 
-fn create_run_folder() -> Result<PathBuf, std::io::Error> {
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-    let run_dir = PathBuf::from("data/output").join(timestamp);
+/// Parent of the timestamped run folders.
+const OUTPUT_DIR: &str = "data/output";
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Strategy {
+    Sequential,
+    Rayon,
+    Threads,
+    Lazy,
+    Streaming,
+}
+
+impl From<Strategy> for ExecutionStrategy {
+    fn from(value: Strategy) -> Self {
+        match value {
+            Strategy::Sequential => ExecutionStrategy::Sequential,
+            Strategy::Rayon => ExecutionStrategy::Rayon,
+            Strategy::Threads => ExecutionStrategy::Threads,
+            Strategy::Lazy => ExecutionStrategy::Lazy,
+            Strategy::Streaming => ExecutionStrategy::Streaming,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ConfigFileFormat {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl From<ConfigFileFormat> for ConfigFormat {
+    fn from(value: ConfigFileFormat) -> Self {
+        match value {
+            ConfigFileFormat::Yaml => ConfigFormat::Yaml,
+            ConfigFileFormat::Json => ConfigFormat::Json,
+            ConfigFileFormat::Toml => ConfigFormat::Toml,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum FeastFileFormat {
+    Python,
+    Yaml,
+}
+
+impl From<FeastFileFormat> for FeastFormat {
+    fn from(value: FeastFileFormat) -> Self {
+        match value {
+            FeastFileFormat::Python => FeastFormat::Python,
+            FeastFileFormat::Yaml => FeastFormat::Yaml,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum CompileTarget {
+    Sql,
+    Python,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Dialect {
+    Postgres,
+    Bigquery,
+}
+
+impl From<Dialect> for SqlDialect {
+    fn from(value: Dialect) -> Self {
+        match value {
+            Dialect::Postgres => SqlDialect::Postgres,
+            Dialect::Bigquery => SqlDialect::BigQuery,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SchemaKind {
+    Entrypoint,
+    Features,
+    Labels,
+}
+
+impl From<SchemaKind> for ConfigKind {
+    fn from(value: SchemaKind) -> Self {
+        match value {
+            SchemaKind::Entrypoint => ConfigKind::Entrypoint,
+            SchemaKind::Features => ConfigKind::Features,
+            SchemaKind::Labels => ConfigKind::Labels,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Status {
+    Success,
+    Failed,
+}
+
+impl From<Status> for RunStatus {
+    fn from(value: Status) -> Self {
+        match value {
+            Status::Success => RunStatus::Success,
+            Status::Failed => RunStatus::Failed,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum RunsCommand {
+    /// List registered runs, most recent first
+    List {
+        /// Only runs that ended with this status
+        #[arg(long, value_enum)]
+        status: Option<Status>,
+
+        /// Only runs with this `--run-name`
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Only runs tagged `key=value`; repeatable, all must match
+        #[arg(long = "with-tag", value_parser = parse_tag)]
+        with_tags: Vec<(String, String)>,
+
+        /// Only runs whose config fingerprint starts with this
+        #[arg(long)]
+        config_fingerprint: Option<String>,
+
+        /// Only runs whose input fingerprint starts with this
+        #[arg(long)]
+        input_fingerprint: Option<String>,
+
+        /// Most runs listed
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Show a run, by ID, name or a file it wrote
+    Show {
+        /// Run ID or name, or a path inside a run folder, e.g. its features.csv
+        run: String,
+    },
+}
+
+/// Where to load configs from: the entrypoint, its overlays and the format
+/// override, which applies to the entrypoint only; the features and labels
+/// files it references are detected from their own extensions.
+struct ConfigSource {
+    path: String,
+    overlays: Vec<String>,
+    format: Option<ConfigFormat>,
+    /// The entrypoint, or why it failed to load, from its first use on.
+    entrypoint: OnceLock<Result<EntrypointConfig, Arc<ConfigError>>>,
+}
+
+impl ConfigSource {
+    /// The entrypoint, loaded once however often it is asked for, so a
+    /// remote one is fetched once and a failure is reported by whoever uses
+    /// it.
+    fn entrypoint(&self) -> Result<EntrypointConfig, Arc<ConfigError>> {
+        self.entrypoint
+            .get_or_init(|| {
+                EntrypointConfig::from_layers(&self.path, &self.overlays, self.format)
+                    .map_err(Arc::new)
+            })
+            .clone()
+    }
+
+    fn features(
+        &self,
+        entrypoint: &EntrypointConfig,
+    ) -> Result<FeaturePipeline, PipelineStepError> {
+        let mut features = FeaturePipeline::from_layers(
+            &entrypoint.features,
+            &entrypoint.features_overlays,
+            None,
+        )?;
+        if let Some(seed) = entrypoint.seed {
+            features.default_seed(seed);
+        }
+        Ok(features)
+    }
+
+    /// The labels config, its `balance` seeded by the entrypoint's `seed`
+    /// unless it sets its own.
+    fn labels(&self, entrypoint: &EntrypointConfig) -> Result<LabelsPipeline, PipelineStepError> {
+        let mut labels = LabelsPipeline::from_layers(
+            &entrypoint.labels,
+            &entrypoint.labels_overlays,
+            None,
+        )?;
+        if let (Some(seed), Some(balance)) = (entrypoint.seed, &mut labels.balance) {
+            balance.seed.get_or_insert(seed);
+        }
+        Ok(labels)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Build features and labels, then train and evaluate the model (default)
+    Run,
+
+    /// Run the pipeline once per date partition, resuming past partitions that
+    /// already succeeded
+    Backfill {
+        /// First partition: a month (`2023-01`) or a day (`2023-01-15`)
+        #[arg(long)]
+        from: String,
+
+        /// Last partition, inclusive, in the same form as `--from`
+        #[arg(long)]
+        to: String,
+
+        /// Date column of the features dataset partitions are cut on
+        #[arg(long)]
+        column: String,
+
+        /// Partitions run at once
+        #[arg(short, long, default_value_t = 1)]
+        jobs: usize,
+
+        /// Folder holding a run folder per partition and the backfill state
+        #[arg(short, long, default_value = "data/output/backfill")]
+        output: PathBuf,
+
+        /// Run partitions that already succeeded again
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Measure feature pipeline performance
+    Bench {
+        /// Number of timed iterations per step and strategy
+        #[arg(short = 'n', long, default_value_t = 5)]
+        iterations: usize,
+
+        /// Benchmark on a random sample of this many rows
+        #[arg(long)]
+        sample: Option<usize>,
+
+        /// Seed for row sampling; the config's `seed` when omitted
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Execution strategies to compare (defaults to all)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        strategy: Vec<Strategy>,
+
+        /// Write the report as JSON to this path, for use as a later baseline
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Report from an earlier `bench --output` to compare against
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+
+        /// Slowdown against the baseline that fails the bench, as a fraction
+        #[arg(long, default_value_t = 0.2, requires = "baseline")]
+        max_regression: f64,
+    },
+
+    /// Check the features config for issues without running the pipeline
+    Lint,
+
+    /// Fit the features pipeline on the input data, then serve it over HTTP
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: SocketAddr,
+    },
+
+    /// Fit the features pipeline on the input data, then serve features over
+    /// Arrow Flight
+    #[cfg(feature = "flight")]
+    Flight {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8815")]
+        addr: SocketAddr,
+    },
+
+    /// Rewrite a config file into a newer schema version
+    MigrateConfig {
+        /// Config file to migrate
+        path: String,
+
+        /// Target schema version, e.g. `v2`
+        #[arg(long, value_parser = parse_version)]
+        to: Option<u32>,
+
+        /// Config kind; detected from the content when omitted
+        #[arg(long, value_enum)]
+        kind: Option<SchemaKind>,
+
+        /// Write the migrated config here instead of stdout
+        #[arg(short, long, conflicts_with = "in_place")]
+        output: Option<PathBuf>,
+
+        /// Overwrite the input file
+        #[arg(long)]
+        in_place: bool,
+    },
+
+    /// Print the JSON Schema for a config file kind
+    Schema {
+        #[arg(value_enum)]
+        kind: SchemaKind,
+
+        /// Write the schema to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Translate the features config into a query for another engine
+    Compile {
+        /// What to compile to
+        #[arg(long, value_enum)]
+        target: CompileTarget,
+
+        /// SQL dialect
+        #[arg(long, value_enum, default_value_t = Dialect::Postgres)]
+        dialect: Dialect,
+
+        /// Table holding the input rows, optionally qualified, e.g. `raw.adult`
+        #[arg(long, default_value = "input")]
+        table: String,
+
+        /// Write the query or script to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Delete run folders the retention policy no longer keeps
+    Clean {
+        /// Keep the most recent this many runs; overrides `retention` in the
+        /// entrypoint, as does `--keep-days`
+        #[arg(long)]
+        keep_last: Option<usize>,
+
+        /// Keep runs started less than this many days ago
+        #[arg(long)]
+        keep_days: Option<u64>,
+
+        /// List the folders that would be deleted without deleting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Find past runs in the run registry
+    Runs {
+        #[command(subcommand)]
+        command: RunsCommand,
+    },
+
+    /// Export the features config as Feast entity and feature view definitions
+    ExportFeast {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = FeastFileFormat::Python)]
+        format: FeastFileFormat,
+
+        /// Feast project the definitions are written for
+        #[arg(long, default_value = "features_pipeline")]
+        project: String,
+
+        /// Features file the views read, e.g. a run's features converted to Parquet
+        #[arg(long, default_value = "features.parquet")]
+        source_path: String,
+
+        /// Event timestamp column of the features file
+        #[arg(long, default_value = "event_timestamp")]
+        timestamp_field: String,
+
+        /// Write the definitions to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl Command {
+    /// Whether the command reads the entrypoint; `migrate-config`, `schema`
+    /// and `runs` don't, so they work without one.
+    fn reads_entrypoint(&self) -> bool {
+        !matches!(
+            self,
+            Command::MigrateConfig { .. } | Command::Schema { .. } | Command::Runs { .. }
+        )
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "Config-driven feature engineering and training pipeline"
+)]
+struct Cli {
+    /// Increase log verbosity (-v debug, -vv trace)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Decrease log verbosity (-q warn, -qq error)
+    #[arg(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+    quiet: u8,
+
+    /// Log output format
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    log_format: LogFormat,
+
+    /// Print the final run result as JSON to stdout (logs go to stderr)
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Also write the final run result JSON to this path
+    #[arg(long, global = true)]
+    result_file: Option<PathBuf>,
+
+    /// Threads polars and rayon may use; overrides `max_threads` in the entrypoint
+    #[arg(long, global = true, value_parser = clap::value_parser!(u64).range(1..))]
+    max_threads: Option<u64>,
+
+    /// Path to the entrypoint config
+    #[arg(long, default_value = "config/entrypoint_adult.yaml", global = true)]
+    config: String,
+
+    /// Overlay merged on top of the entrypoint config; repeatable, applied in order
+    #[arg(long, global = true)]
+    overlay: Vec<String>,
+
+    /// Entrypoint config format; detected from the file extension when
+    /// omitted, and always for the files it references
+    #[arg(long, value_enum, global = true)]
+    config_format: Option<ConfigFileFormat>,
+
+    /// Export spans to this OTLP/HTTP collector, e.g. http://otel-collector:4318
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT", global = true)]
+    otlp_endpoint: Option<String>,
+
+    /// Orchestrator run ID, attached to the run span and result
+    #[arg(long, env = "PIPELINE_RUN_ID", global = true)]
+    run_id: Option<String>,
+
+    /// Name for the run, appended to its folder's name, e.g. `march_backfill`
+    #[arg(long, env = "PIPELINE_RUN_NAME", value_parser = parse_run_name, global = true)]
+    run_name: Option<String>,
+
+    /// Tag the run as `key=value`; repeatable
+    #[arg(long = "tag", value_parser = parse_tag, global = true)]
+    tags: Vec<(String, String)>,
+
+    /// SQLite registry runs are recorded in and `runs` reads
+    #[arg(long, env = "PIPELINE_REGISTRY", default_value = registry::DEFAULT_REGISTRY, global = true)]
+    registry: PathBuf,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+impl Cli {
+    fn log_level(&self) -> Level {
+        match (self.verbose, self.quiet) {
+            (0, 0) => Level::INFO,
+            (1, _) => Level::DEBUG,
+            (_, 0) => Level::TRACE,
+            (_, 1) => Level::WARN,
+            _ => Level::ERROR,
+        }
+    }
+}
+
+/// Logs to stderr, keeps warnings for the run report and, with an OTLP endpoint,
+/// exports spans too. The returned provider must be shut down before exiting so
+/// buffered spans are flushed.
+fn init_logging(cli: &Cli) -> Result<Option<SdkTracerProvider>, Box<dyn Error>> {
+    let fmt = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    let fmt = match cli.log_format {
+        LogFormat::Text => fmt.boxed(),
+        LogFormat::Json => fmt.json().boxed(),
+    };
+
+    let provider = cli
+        .otlp_endpoint
+        .as_deref()
+        .map(telemetry::tracer_provider)
+        .transpose()?;
+    let otel = provider
+        .as_ref()
+        .map(|p| tracing_opentelemetry::layer().with_tracer(p.tracer(telemetry::SERVICE_NAME)));
+
+    let level = LevelFilter::from_level(cli.log_level());
+    tracing_subscriber::registry()
+        .with(fmt.with_filter(level))
+        .with(otel.with_filter(level))
+        .with(telemetry::WarningCollector.with_filter(LevelFilter::WARN))
+        .init();
+    Ok(provider)
+}
+
+/// Logistic regression fitted for binary targets, or multinomial when the
+/// labels have more than two classes (e.g. `binned_target`).
+enum Model {
+    Binary(FittedLogisticRegression<f64, i32>),
+    Multi(MultiFittedLogisticRegression<f64, i32>),
+}
+
+impl Model {
+    fn predict(&self, records: &Array2<f64>) -> Array1<i32> {
+        match self {
+            Self::Binary(model) => model.predict(records),
+            Self::Multi(model) => model.predict(records),
+        }
+    }
+}
+
+/// Writes `features.csv` and `labels.csv`, or per-file outputs when `rows`
+/// holds each row's source file; `suffix` goes at the end of file names.
+fn write_outputs(
+    run_dir: &Path,
+    sources: &[SourceFile],
+    rows: &Option<Series>,
+    features: &DataFrame,
+    labels: &DataFrame,
+    suffix: &str,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(rows) = rows {
+        return write_partitions(run_dir, sources, rows, features, labels, suffix);
+    }
+    for (name, frame) in [("features", features), ("labels", labels)] {
+        let file = File::create_new(run_dir.join(format!("{}{}.csv", name, suffix)))?;
+        CsvWriter::new(&file)
+            .include_header(true)
+            .with_separator(b';')
+            .finish(&mut frame.clone())?;
+    }
+    Ok(())
+}
+
+fn write_partitions(
+    run_dir: &Path,
+    sources: &[SourceFile],
+    rows: &Series,
+    features: &DataFrame,
+    labels: &DataFrame,
+    suffix: &str,
+) -> Result<(), Box<dyn Error>> {
+    for (i, source) in sources.iter().enumerate() {
+        let mask = rows.equal(i as u32)?;
+        let stem = Path::new(&source.path)
+            .file_stem()
+            .map_or(format!("part_{}", i), |s| s.to_string_lossy().to_string());
+        for (dir, frame) in [("features", features), ("labels", labels)] {
+            fs::create_dir_all(run_dir.join(dir))?;
+            let file = File::create_new(run_dir.join(dir).join(format!("{}{}.csv", stem, suffix)))?;
+            CsvWriter::new(&file)
+                .include_header(true)
+                .with_separator(b';')
+                .finish(&mut frame.filter(&mask)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Named evaluation metrics; undefined ones, e.g. precision without positive
+/// predictions, are left out.
+fn evaluation<const N: usize>(metrics: [(&str, f32); N]) -> BTreeMap<String, f64> {
+    metrics
+        .into_iter()
+        .filter(|(_, value)| value.is_finite())
+        .map(|(name, value)| (name.to_string(), f64::from(value)))
+        .collect()
+}
+
+/// The rows of `dataset` whose entry in `rows` is `split`.
+fn split_dataset(
+    dataset: &DatasetBase<Array2<f64>, Array1<i32>>,
+    rows: &Series,
+    split: &str,
+) -> Result<DatasetBase<Array2<f64>, Array1<i32>>, PolarsError> {
+    let indices: Vec<usize> = rows
+        .equal(split)?
+        .iter()
+        .enumerate()
+        .filter_map(|(i, picked)| picked.unwrap_or(false).then_some(i))
+        .collect();
+    Ok(DatasetBase::new(
+        dataset.records().select(Axis(0), &indices),
+        dataset.targets().select(Axis(0), &indices),
+    ))
+}
+
+fn create_run_folder(name: Option<&str>) -> Result<PathBuf, std::io::Error> {
+    let mut folder = Local::now().format(RUN_FOLDER_FORMAT).to_string();
+    if let Some(name) = name {
+        folder = format!("{}_{}", folder, name);
+    }
+    let run_dir = PathBuf::from(OUTPUT_DIR).join(folder);
 
     fs::create_dir_all(&run_dir)?;
 
@@ -25,101 +670,1557 @@ fn create_run_folder() -> Result<PathBuf, std::io::Error> {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    tracing_subscriber::fmt::init();
-    // tracing_subscriber::fmt().json().init();
+    let cli = Cli::parse();
+
+    let config_format = cli.config_format.map(ConfigFormat::from);
+    let source = ConfigSource {
+        path: cli.config.clone(),
+        overlays: cli.overlay.clone(),
+        format: config_format,
+        entrypoint: OnceLock::new(),
+    };
+
+    // Polars and rayon size their pools on first use, so the limit is applied
+    // before anything else starts threads. The entrypoint loaded here is the
+    // one the command uses, and reports when it failed to load.
+    let max_threads = match cli.max_threads {
+        Some(n) => Some(n as usize),
+        None if cli.command.as_ref().is_none_or(Command::reads_entrypoint) => {
+            source.entrypoint().ok().and_then(|e| e.max_threads)
+        }
+        None => None,
+    };
+    if let Some(max_threads) = max_threads {
+        threads::limit(max_threads)?;
+    }
+
+    let tracer_provider = init_logging(&cli)?;
+    if let Some(max_threads) = max_threads {
+        info!("Limited polars and rayon to {} threads", max_threads);
+    }
+
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => {
+            let mut result = RunResult::new();
+            result.run_id = cli.run_id;
+            result.run_name = cli.run_name;
+            result.tags = cli.tags.into_iter().collect();
+            let mut metrics = None;
+            let mut retention = None;
+
+            let span = info_span!("run", run_id = field::Empty, run_name = field::Empty);
+            if let Some(run_id) = &result.run_id {
+                span.record("run_id", run_id.as_str());
+            }
+            if let Some(run_name) = &result.run_name {
+                span.record("run_name", run_name.as_str());
+            }
+            telemetry::adopt_parent_from_env(&span);
+            span.in_scope(|| {
+                if run(&source, &mut result, &mut metrics, &mut retention, None).is_ok() {
+                    result.finish();
+                }
+            });
+            drop(span);
+
+            report_result(&result, cli.json, cli.result_file.as_deref())?;
+            let registry = RunRegistry::open(&cli.registry);
+            register(&registry, &result);
+            if let Some(retention) = retention
+                && let Err(e) = clean(&retention, &registry, false)
+            {
+                warn!("Failed to clean up old runs: {}", e);
+            }
+            if let Some(metrics) = metrics
+                && let Err(e) = metrics.push(&result)
+            {
+                warn!("Failed to export metrics: {}", e);
+            }
+            if let Some(mlflow) = MlflowRun::from_env() {
+                match mlflow.log(&result) {
+                    Ok(()) => info!("Logged run to MLflow run {}", mlflow.run_id),
+                    Err(e) => warn!("Failed to log to MLflow: {}", e),
+                }
+            }
+            if let Some(provider) = tracer_provider
+                && let Err(e) = provider.shutdown()
+            {
+                warn!("Failed to flush traces: {}", e);
+            }
+            process::exit(result.exit_code());
+        }
+        Command::Backfill {
+            from,
+            to,
+            column,
+            jobs,
+            output,
+            force,
+        } => {
+            let partitions = backfill::partitions(&from, &to)?;
+            let options = BackfillOptions {
+                column: &column,
+                jobs: jobs.max(1),
+                output: &output,
+                force,
+                run_id: cli.run_id.as_deref(),
+                run_name: cli.run_name.as_deref(),
+                tags: cli.tags.into_iter().collect(),
+                registry: RunRegistry::open(&cli.registry),
+            };
+            run_backfill(&source, &partitions, &options)
+        }
+        Command::Bench {
+            iterations,
+            sample,
+            seed,
+            strategy,
+            output,
+            baseline,
+            max_regression,
+        } => {
+            let mut options = BenchOptions {
+                iterations,
+                sample_rows: sample,
+                seed,
+                ..Default::default()
+            };
+            if !strategy.is_empty() {
+                options.strategies = strategy.into_iter().map(Into::into).collect();
+            }
+            let compare = baseline.map(|path| (path, max_regression));
+            run_bench(&source, &options, output.as_deref(), compare, cli.json)
+        }
+        Command::Lint => run_lint(&source, cli.json),
+        Command::Serve { addr } => run_serve(&source, addr),
+        #[cfg(feature = "flight")]
+        Command::Flight { addr } => run_flight(&source, addr),
+        Command::MigrateConfig {
+            path,
+            to,
+            kind,
+            output,
+            in_place,
+        } => {
+            let format = config_format.unwrap_or(ConfigFormat::from_path(&path));
+            let raw: serde_yaml::Value = format::parse(&path, &fs::read_to_string(&path)?, format)?;
+            let kind = kind
+                .map(ConfigKind::from)
+                .unwrap_or(migrate::detect_kind(&raw));
+            let migration =
+                migrate::migrate(&path, kind, raw, to.unwrap_or(migrate::CURRENT_VERSION))?;
+
+            for change in &migration.changes {
+                info!("{}", change);
+            }
+            info!(
+                "Migrated {:?} config {} from v{} to v{} ({} changes)",
+                kind,
+                path,
+                migration.from,
+                migration.to,
+                migration.changes.len()
+            );
+
+            let migrated = format::to_string(&migration.config, format)?;
+            match (output, in_place) {
+                (Some(out), _) => fs::write(out, migrated)?,
+                (None, true) => fs::write(&path, migrated)?,
+                (None, false) => print!("{}", migrated),
+            }
+            Ok(())
+        }
+        Command::Schema { kind, output } => {
+            let schema = serde_json::to_string_pretty(&schema::json_schema(kind.into()))?;
+            match output {
+                Some(path) => fs::write(path, schema)?,
+                None => println!("{}", schema),
+            }
+            Ok(())
+        }
+        Command::Compile {
+            target,
+            dialect,
+            table,
+            output,
+        } => {
+            let entrypoint = source.entrypoint()?;
+            let pipeline = source.features(&entrypoint)?;
+            let code = match target {
+                CompileTarget::Sql => {
+                    let query = SqlQuery::compile(&pipeline, &table, dialect.into());
+                    for step in &query.skipped {
+                        warn!("Not compiled to SQL: {}", step);
+                    }
+                    info!(
+                        "Compiled {} features to {:?} SQL",
+                        query.features.len(),
+                        query.dialect
+                    );
+                    query.sql
+                }
+                CompileTarget::Python => {
+                    info!("Compiled {} steps to Python", pipeline.steps.len());
+                    python::compile(&pipeline, &entrypoint.features)
+                }
+            };
+            match output {
+                Some(path) => fs::write(path, code)?,
+                None => print!("{}", code),
+            }
+            Ok(())
+        }
+        Command::Clean {
+            keep_last,
+            keep_days,
+            dry_run,
+        } => {
+            let retention = if keep_last.is_some() || keep_days.is_some() {
+                RetentionConfig {
+                    keep_last,
+                    keep_days,
+                }
+            } else {
+                source.entrypoint()?.retention.ok_or(
+                    "no retention policy: pass --keep-last or --keep-days, or add a \
+                     `retention` section to the entrypoint",
+                )?
+            };
+            clean(&retention, &RunRegistry::open(&cli.registry), dry_run)
+        }
+        Command::Runs { command } => run_runs(&RunRegistry::open(&cli.registry), command, cli.json),
+        Command::ExportFeast {
+            format,
+            project,
+            source_path,
+            timestamp_field,
+            output,
+        } => {
+            let entrypoint = source.entrypoint()?;
+            let pipeline = source.features(&entrypoint)?;
+            let feast_source = FeastSource {
+                name: "features".to_string(),
+                path: source_path,
+                timestamp_field,
+            };
+            let repo = FeastRepo::new(&pipeline, &project, feast_source);
+            for step in &repo.skipped {
+                warn!("Not exported to Feast: {}", step);
+            }
+            info!(
+                "Exported {} feature views over {} entities",
+                repo.feature_views.len(),
+                repo.entities.len()
+            );
+            let definitions = repo.render(format.into())?;
+            match output {
+                Some(path) => fs::write(path, definitions)?,
+                None => print!("{}", definitions),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Run names become part of folder names, so only letters, digits, `-`, `_`
+/// and `.` are allowed.
+fn parse_run_name(value: &str) -> Result<String, String> {
+    if value.is_empty()
+        || value.starts_with('.')
+        || !value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+    {
+        return Err(format!(
+            "invalid run name '{}': use letters, digits, '-', '_' and '.'",
+            value
+        ));
+    }
+    Ok(value.to_string())
+}
+
+fn parse_tag(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("invalid tag '{}', expected key=value", value)),
+    }
+}
+
+fn parse_version(value: &str) -> Result<u32, String> {
+    value
+        .trim_start_matches(['v', 'V'])
+        .parse()
+        .map_err(|_| format!("invalid version '{}', expected e.g. v2", value))
+}
+
+fn report_result(
+    result: &RunResult,
+    json: bool,
+    result_file: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(error) = &result.error {
+        error!(
+            "Run failed at stage '{}' ({:?}): {}",
+            error.stage, error.kind, error.message
+        );
+    }
+
+    if let Some(run_dir) = &result.run_dir {
+        result.write_json(&Path::new(run_dir).join("result.json"))?;
+    }
+    if let Some(path) = result_file {
+        result.write_json(path)?;
+    }
+    if json {
+        println!("{}", result.to_json()?);
+    }
+
+    Ok(())
+}
+
+/// Deletes the run folders `retention` doesn't keep, and their registry
+/// records.
+fn clean(
+    retention: &RetentionConfig,
+    registry: &RunRegistry,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let expired = retention.expired(Path::new(OUTPUT_DIR), Local::now().naive_local())?;
+    if dry_run {
+        for folder in &expired {
+            info!("Would delete {}", folder.path.display());
+        }
+        info!("{} run folder(s) would be deleted", expired.len());
+        return Ok(());
+    }
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    let freed = retention::delete(&expired)?;
+    let dirs: Vec<PathBuf> = expired.iter().map(|f| f.path.clone()).collect();
+    let forgotten = registry.forget(&dirs)?;
+    info!(
+        "Deleted {} run folder(s), freeing {:.1} MiB; removed {} registry record(s)",
+        expired.len(),
+        freed as f64 / (1024.0 * 1024.0),
+        forgotten
+    );
+    Ok(())
+}
+
+/// Records `result` in `registry`, warning when that fails.
+fn register(registry: &RunRegistry, result: &RunResult) {
+    if let Err(e) = registry.register(&RunRecord::from_result(result)) {
+        warn!(
+            "Failed to register the run in {}: {}",
+            registry.path().display(),
+            e
+        );
+    }
+}
+
+fn run_runs(
+    registry: &RunRegistry,
+    command: RunsCommand,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    match command {
+        RunsCommand::List {
+            status,
+            name,
+            with_tags,
+            config_fingerprint,
+            input_fingerprint,
+            limit,
+        } => {
+            let runs = registry.query(&RunQuery {
+                status: status.map(Into::into),
+                name,
+                tags: with_tags.into_iter().collect(),
+                config_fingerprint,
+                input_fingerprint,
+                limit: Some(limit),
+            })?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&runs)?);
+                return Ok(());
+            }
+            let short = |hash: &Option<String>| {
+                hash.as_deref()
+                    .map_or("-".to_string(), |h| h.chars().take(12).collect())
+            };
+            println!(
+                "{:<24} {:<25} {:<8} {:<12} {:<12} {:>8}  RUN_DIR",
+                "ID", "FINISHED", "STATUS", "CONFIG", "INPUT", "ROWS"
+            );
+            for run in &runs {
+                println!(
+                    "{:<24} {:<25} {:<8} {:<12} {:<12} {:>8}  {}",
+                    run.id,
+                    run.finished_at.get(..19).unwrap_or(&run.finished_at),
+                    format!("{:?}", run.status).to_lowercase(),
+                    short(&run.config_fingerprint),
+                    short(&run.input_fingerprint),
+                    run.rows.features.map_or("-".to_string(), |r| r.to_string()),
+                    run.run_dir.as_deref().unwrap_or("-")
+                );
+            }
+            Ok(())
+        }
+        RunsCommand::Show { run } => {
+            let found = match registry.get(&run)? {
+                Some(record) => Some(record),
+                None if Path::new(&run).exists() => registry.producing(Path::new(&run))?,
+                None => None,
+            };
+            let Some(record) = found else {
+                return Err(format!("no run '{}' in {}", run, registry.path().display()).into());
+            };
+            println!("{}", serde_json::to_string_pretty(&record)?);
+            Ok(())
+        }
+    }
+}
+
+fn run_lint(source: &ConfigSource, json: bool) -> Result<(), Box<dyn Error>> {
+    let entrypoint_config = source.entrypoint()?;
+    let features_pipeline = source.features(&entrypoint_config)?;
+
+    let issues = features_pipeline.lint();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+    }
+    for issue in &issues {
+        let name = issue.name.as_deref().unwrap_or("-");
+        match issue.severity {
+            Severity::Error => error!("step {} ({}): {}", issue.step, name, issue.message),
+            Severity::Warning => warn!("step {} ({}): {}", issue.step, name, issue.message),
+        }
+    }
+
+    let errors = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .count();
+    info!(
+        "{}: {} errors, {} warnings",
+        entrypoint_config.features,
+        errors,
+        issues.len() - errors
+    );
+
+    if errors > 0 {
+        process::exit(FailureKind::Config.exit_code());
+    }
+    Ok(())
+}
+
+/// Fits the features pipeline on its dataset and serves it on `addr` until
+/// the process is stopped.
+fn run_serve(source: &ConfigSource, addr: SocketAddr) -> Result<(), Box<dyn Error>> {
+    let entrypoint_config = source.entrypoint()?;
+    let features_pipeline = source.features(&entrypoint_config)?;
+
+    let columns = data::required_columns(
+        &entrypoint_config,
+        [(
+            features_pipeline.dataset.as_deref(),
+            features_pipeline.input_columns(),
+        )],
+    );
+    let datasets = Datasets::load(&entrypoint_config, &columns)?;
+    let df = datasets.select(features_pipeline.dataset.as_deref())?;
+
+    let fitted = FittedPipeline::fit(&features_pipeline, &df)?;
+    info!(
+        "Fitted {} steps on {} rows",
+        features_pipeline.steps.len(),
+        df.height()
+    );
+    drop(df);
+    drop(datasets);
+
+    tokio::runtime::Runtime::new()?.block_on(serve::serve(addr, fitted))?;
+    Ok(())
+}
+
+/// Fits the features pipeline on its dataset and serves the features of
+/// every entrypoint dataset, and of uploaded ones, over Arrow Flight on `addr`.
+#[cfg(feature = "flight")]
+fn run_flight(source: &ConfigSource, addr: SocketAddr) -> Result<(), Box<dyn Error>> {
+    use features_pipeline::flight::{self, FeatureFlightService};
+
+    let entrypoint_config = source.entrypoint()?;
+    let features_pipeline = source.features(&entrypoint_config)?;
+
+    let datasets = Datasets::load(&entrypoint_config, &BTreeMap::new())?;
+    let fitted = FittedPipeline::fit(
+        &features_pipeline,
+        &*datasets.select(features_pipeline.dataset.as_deref())?,
+    )?;
+    let inputs = datasets
+        .names()
+        .map(|name| Ok((name.to_string(), datasets.get(name)?.into_owned())))
+        .collect::<Result<BTreeMap<_, _>, PipelineStepError>>()?;
+    info!(
+        "Fitted {} steps; serving datasets {:?}",
+        features_pipeline.steps.len(),
+        inputs.keys().collect::<Vec<_>>()
+    );
+    drop(datasets);
+
+    tokio::runtime::Runtime::new()?.block_on(flight::serve(
+        addr,
+        FeatureFlightService::new(fitted, inputs),
+    ))?;
+    Ok(())
+}
+
+struct BackfillOptions<'a> {
+    column: &'a str,
+    jobs: usize,
+    output: &'a Path,
+    force: bool,
+    run_id: Option<&'a str>,
+    run_name: Option<&'a str>,
+    tags: BTreeMap<String, String>,
+    registry: RunRegistry,
+}
+
+fn run_backfill(
+    source: &ConfigSource,
+    partitions: &[Partition],
+    options: &BackfillOptions,
+) -> Result<(), Box<dyn Error>> {
+    backfill::check(&source.entrypoint()?)?;
+    fs::create_dir_all(options.output)?;
+    let state_path = options.output.join("backfill.json");
+    let state = BackfillState::load(&state_path)?;
+
+    let pending: Vec<&Partition> = partitions
+        .iter()
+        .filter(|p| options.force || !state.succeeded(&p.label))
+        .collect();
+    info!(
+        "Backfilling {} of {} partitions (skipping {} that already succeeded), {} at a time",
+        pending.len(),
+        partitions.len(),
+        partitions.len() - pending.len(),
+        options.jobs
+    );
+
+    let state = Mutex::new(state);
+    let next = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..options.jobs.min(pending.len()) {
+            scope.spawn(|| {
+                while let Some(partition) = pending.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let mut result = RunResult::new();
+                    // Suffixed, so the registry tells the partitions apart.
+                    result.run_id = options
+                        .run_id
+                        .map(|id| format!("{}_{}", id, partition.label));
+                    result.run_name = options.run_name.map(str::to_string);
+                    result.tags = options.tags.clone();
+                    let run_partition = RunPartition {
+                        partition,
+                        column: options.column,
+                        dir: options.output.join(&partition.label),
+                    };
+                    info_span!("partition", partition = partition.label.as_str()).in_scope(|| {
+                        if run(
+                            source,
+                            &mut result,
+                            &mut None,
+                            &mut None,
+                            Some(&run_partition),
+                        )
+                        .is_ok()
+                        {
+                            result.finish();
+                        }
+                        if let Err(e) = report_result(&result, false, None) {
+                            warn!("Failed to write the run result: {}", e);
+                        }
+                        register(&options.registry, &result);
+                    });
+
+                    let error = result
+                        .error
+                        .as_ref()
+                        .map(|e| format!("stage '{}' ({:?}): {}", e.stage, e.kind, e.message));
+                    if error.is_some() {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                    }
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    state.record(&partition.label, result.rows.input, error);
+                    if let Err(e) = state.save(&state_path) {
+                        warn!("Failed to save {}: {}", state_path.display(), e);
+                    }
+                }
+            });
+        }
+    });
+
+    let failed = failed.into_inner();
+    info!(
+        "Backfill finished: {} succeeded, {} failed; state in {}",
+        pending.len() - failed,
+        failed,
+        state_path.display()
+    );
+    if failed > 0 {
+        return Err(format!(
+            "{} partition(s) failed; rerun the same command to retry them",
+            failed
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Runs the benchmark, then writes the report to `output` and checks it
+/// against `baseline`, a report path and the slowdown it tolerates.
+fn run_bench(
+    source: &ConfigSource,
+    options: &BenchOptions,
+    output: Option<&Path>,
+    baseline: Option<(PathBuf, f64)>,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let entrypoint_config = source.entrypoint()?;
+    let features_pipeline = source.features(&entrypoint_config)?;
+
+    let columns = data::required_columns(
+        &entrypoint_config,
+        [(
+            features_pipeline.dataset.as_deref(),
+            features_pipeline.input_columns(),
+        )],
+    );
+    let datasets = Datasets::load(&entrypoint_config, &columns)?;
+    let df = datasets.select(features_pipeline.dataset.as_deref())?;
+    let options = &BenchOptions {
+        seed: options.seed.or(entrypoint_config.seed),
+        ..options.clone()
+    };
+
+    info!(
+        "Benchmarking {} steps, {} iterations",
+        features_pipeline.steps.len(),
+        options.iterations
+    );
+
+    let report = bench::run(&features_pipeline, &df, options)?;
+
+    info!("=== Per-step timings ({} rows) ===", report.rows);
+    for step in &report.steps {
+        let engines: String = [("lazy", &step.lazy), ("streaming", &step.streaming)]
+            .into_iter()
+            .filter_map(|(engine, timing)| {
+                timing
+                    .as_ref()
+                    .map(|t| format!("  {} {:>9.2} ms", engine, t.mean_ms))
+            })
+            .collect();
+        info!(
+            "{:<40} mean {:>9.2} ms  min {:>9.2} ms  max {:>9.2} ms  {:>12.0} rows/s{}",
+            step.step,
+            step.timing.mean_ms,
+            step.timing.min_ms,
+            step.timing.max_ms,
+            step.rows_per_sec,
+            engines
+        );
+    }
+
+    info!("=== Execution strategies ===");
+    for strategy in &report.strategies {
+        let peak_mb = strategy
+            .peak_memory_bytes
+            .map(|b| format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)))
+            .unwrap_or_else(|| "n/a".to_string());
+        info!(
+            "{:<12} mean {:>9.2} ms  min {:>9.2} ms  max {:>9.2} ms  {:>12.0} rows/s  peak {}",
+            strategy.strategy.as_str(),
+            strategy.timing.mean_ms,
+            strategy.timing.min_ms,
+            strategy.timing.max_ms,
+            strategy.rows_per_sec,
+            peak_mb
+        );
+    }
+
+    if let Some(path) = output {
+        fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        info!("Wrote bench report to {}", path.display());
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
 
-    let entrypoint_config = EntrypointConfig::from_yaml("config/entrypoint_adult.yaml")?;
+    if let Some((path, max_regression)) = baseline {
+        let baseline: BenchReport = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        let regressions = report.regressions(&baseline, max_regression);
+        info!(
+            "=== Against {} ({} rows): {} regression(s) over {:.0}% ===",
+            path.display(),
+            baseline.rows,
+            regressions.len(),
+            max_regression * 100.0
+        );
+        for regression in &regressions {
+            warn!("{}", regression);
+        }
+        if !regressions.is_empty() {
+            process::exit(FailureKind::Pipeline.exit_code());
+        }
+    }
 
-    let features_pipeline = FeaturePipeline::from_yaml(&entrypoint_config.features)?;
+    Ok(())
+}
 
-    let labels_pipeline = LabelsPipeline::from_yaml(&entrypoint_config.labels)?;
+/// A backfill partition to run the pipeline on, instead of all input rows.
+struct RunPartition<'a> {
+    partition: &'a Partition,
+    /// Date column of the features dataset the partition is cut on.
+    column: &'a str,
+    /// Run folder, replacing the timestamped one.
+    dir: PathBuf,
+}
 
-    let run_dir = create_run_folder()?;
+/// `metrics` is set as soon as the config is loaded, so that failed runs are
+/// reported too.
+fn run(
+    source: &ConfigSource,
+    result: &mut RunResult,
+    metrics: &mut Option<MetricsConfig>,
+    retention: &mut Option<RetentionConfig>,
+    partition: Option<&RunPartition>,
+) -> Result<(), StageFailed> {
+    let (entrypoint_config, features_pipeline, labels_pipeline) =
+        result.stage("load_config", FailureKind::Config, || {
+            let entrypoint_config = source.entrypoint()?;
+            let features_pipeline = source.features(&entrypoint_config)?;
+            let labels_pipeline = source.labels(&entrypoint_config)?;
+            labels_pipeline.check_names_against(&entrypoint_config.labels, &features_pipeline)?;
+            if let Some(output) = &entrypoint_config.output
+                && output.is_partitioned()
+                && partition.is_none()
+            {
+                return Err(format!(
+                    "'output.path' ({}) has a partition placeholder, which only backfill fills in",
+                    output.path
+                )
+                .into());
+            }
+            Ok::<_, Box<dyn Error>>((entrypoint_config, features_pipeline, labels_pipeline))
+        })?;
+    metrics.clone_from(&entrypoint_config.metrics);
+    retention.clone_from(&entrypoint_config.retention);
+
+    let resolved = ResolvedConfig {
+        entrypoint: &entrypoint_config,
+        features: &features_pipeline,
+        labels: &labels_pipeline,
+    };
+    let fingerprint = result.stage("fingerprint_config", FailureKind::Config, || {
+        resolved.fingerprint()
+    })?;
+    result.config_fingerprint = Some(fingerprint.clone());
+
+    info!("Config fingerprint: {}", fingerprint);
+
+    let run_name = result.run_name.clone();
+    let run_dir = result.stage("create_run_dir", FailureKind::Output, || {
+        let run_dir = match partition {
+            Some(partition) => {
+                // Left by an earlier, failed attempt at the partition.
+                if partition.dir.exists() {
+                    fs::remove_dir_all(&partition.dir)?;
+                }
+                fs::create_dir_all(&partition.dir)?;
+                partition.dir.clone()
+            }
+            None => create_run_folder(run_name.as_deref())?,
+        };
+        fs::write(run_dir.join("config.resolved.json"), resolved.to_json()?)?;
+        Ok::<_, Box<dyn Error>>(run_dir)
+    })?;
+    result.run_dir = Some(run_dir.display().to_string());
 
     info!("Created run folder: {}", run_dir.display());
 
     info!(
-        "Loaded {} labels steps from config/features.yaml\n",
-        labels_pipeline.steps.len()
+        "Loaded {} labels steps from {}",
+        labels_pipeline.steps.len(),
+        entrypoint_config.labels
     );
 
     for (i, feature) in features_pipeline.steps.iter().enumerate() {
         info!("Feature {}: {:?}", i + 1, feature);
     }
 
-    let csv_file = std::fs::File::open(&entrypoint_config.data)?;
-    let df = CsvReader::new(csv_file).finish()?;
+    let watermark = match &entrypoint_config.incremental {
+        Some(incremental) => {
+            result.stage("load_watermark", FailureKind::Config, || incremental.load())?
+        }
+        None => None,
+    };
+    if let Some(watermark) = &watermark {
+        info!(
+            "Reading rows with {} after {}",
+            watermark.column, watermark.value
+        );
+    }
+
+    let mut datasets = result.stage("load_data", FailureKind::Data, || {
+        let mut feature_columns = features_pipeline.input_columns();
+        feature_columns.extend(entrypoint_config.split.as_ref().and_then(|s| s.column()));
+        feature_columns.extend(
+            entrypoint_config
+                .incremental
+                .as_ref()
+                .map(|i| i.column.as_str()),
+        );
+        let mut columns = data::required_columns(
+            &entrypoint_config,
+            [
+                (features_pipeline.dataset.as_deref(), feature_columns),
+                (
+                    labels_pipeline.dataset.as_deref(),
+                    labels_pipeline.input_columns(),
+                ),
+            ],
+        );
+        // Input drift compares every column against the baseline.
+        if entrypoint_config
+            .drift
+            .as_ref()
+            .is_some_and(|drift| drift.frame == DriftFrame::Input)
+        {
+            columns.remove(
+                features_pipeline
+                    .dataset
+                    .as_deref()
+                    .unwrap_or(entrypoint_config.primary_dataset()),
+            );
+        }
+        let mut filters = Vec::new();
+        if let (Some(incremental), Some(watermark)) = (&entrypoint_config.incremental, &watermark) {
+            filters.push(incremental.filter(watermark));
+        }
+        if let Some(partition) = partition {
+            filters.extend(partition.partition.filters(partition.column));
+        }
+        let mut read_config = Cow::Borrowed(&entrypoint_config);
+        if !filters.is_empty() {
+            let name = features_pipeline
+                .dataset
+                .as_deref()
+                .unwrap_or(entrypoint_config.primary_dataset())
+                .to_string();
+            if let Some(dataset) = read_config.to_mut().datasets.get_mut(&name) {
+                dataset.filter.extend(filters);
+            }
+        }
+        Datasets::load(&read_config, &columns)
+    })?;
+
+    result.input_fingerprint = manifest::inputs(&entrypoint_config, &datasets)
+        .ok()
+        .and_then(|inputs| serde_json::to_vec(&inputs).ok())
+        .map(|bytes| remote::sha256_hex(&bytes));
+
+    for name in datasets.names() {
+        if let Ok(df) = datasets.get(name) {
+            info!("Dataset '{}' shape: {:?}", name, df.shape());
+            info!("Dataset '{}' columns: {:?}", name, df.get_column_names());
+        }
+    }
+
+    let (features_df, labels_df) = result.stage("select_data", FailureKind::Config, || {
+        Ok::<_, Box<dyn Error>>((
+            datasets.select(features_pipeline.dataset.as_deref())?,
+            datasets.select(labels_pipeline.dataset.as_deref())?,
+        ))
+    })?;
+    result.rows.input = Some(features_df.height());
 
-    info!("Raw data shape: {:?}", df.shape());
-    info!("Columns: {:?}", df.get_column_names());
+    if let Some(partition) = partition
+        && features_df.height() == 0
+    {
+        info!("Partition {} has no rows", partition.partition.label);
+        return Ok(());
+    }
+
+    let next_watermark = match &entrypoint_config.incremental {
+        Some(incremental) => {
+            if features_df.height() == 0 {
+                info!("No new rows since the last run");
+                return Ok(());
+            }
+            result.stage("watermark", FailureKind::Data, || {
+                incremental.watermark(&features_df, features_df.height())
+            })?
+        }
+        None => None,
+    };
+
+    // Each input row's file, filtered and resampled along with the outputs so
+    // they can be written per file.
+    let sources = datasets
+        .sources(features_pipeline.dataset.as_deref())
+        .to_vec();
+    let per_file_outputs = entrypoint_config
+        .datasets
+        .get(
+            features_pipeline
+                .dataset
+                .as_deref()
+                .unwrap_or(entrypoint_config.primary_dataset()),
+        )
+        .is_some_and(|d| d.partition_outputs);
+    let mut source_rows = per_file_outputs.then(|| {
+        let rows: Vec<u32> = sources
+            .iter()
+            .enumerate()
+            .flat_map(|(i, source)| std::iter::repeat_n(i as u32, source.rows))
+            .collect();
+        Series::new("source".into(), rows)
+    });
+
+    // Each input row's key, kept aligned with the outputs like `source_rows`
+    // when they are sorted by it.
+    let mut key_rows = None;
+    if let Some(keys) = &features_pipeline.keys {
+        key_rows = result.stage("key_check", FailureKind::Data, || {
+            if let Some(report) = keys.check(&features_df)? {
+                fs::write(
+                    run_dir.join("duplicate_keys.json"),
+                    serde_json::to_string_pretty(&report)?,
+                )?;
+                warn!(
+                    "{} key(s) of {:?} occur more than once ({} extra rows); most repeated: {:?}",
+                    report.duplicated_keys,
+                    report.columns,
+                    report.extra_rows,
+                    report.examples.first().map(|e| &e.key)
+                );
+                if keys.action == DuplicateKeyAction::Fail {
+                    return Err(format!(
+                        "{} duplicated key(s) in {:?}",
+                        report.duplicated_keys, report.columns
+                    )
+                    .into());
+                }
+            }
+            Ok::<_, Box<dyn Error>>(
+                keys.sort_outputs
+                    .then(|| features_df.select(&keys.columns))
+                    .transpose()?,
+            )
+        })?;
+    }
 
-    // let mut features = features_pipeline.apply(&df)?;
-    let mut features = features_pipeline.apply_parallel(&df)?;
+    // Each input row's split, kept aligned with the outputs like `source_rows`.
+    let mut split_rows = match &entrypoint_config.split {
+        Some(split) => {
+            Some(result.stage("split", FailureKind::Data, || split.assign(&features_df))?)
+        }
+        None => None,
+    };
 
-    info!("Features before fill_null: {:?}", features.shape());
+    result.stage("expectations", FailureKind::Data, || {
+        let expectations = features_pipeline.expectations(&features_df)?;
+        if expectations.is_empty() {
+            return Ok(());
+        }
+        // Written before enforcing, so a failed run still leaves the report.
+        fs::write(
+            run_dir.join("expectations.json"),
+            serde_json::to_string_pretty(&expectations)?,
+        )?;
+        for failed in expectations.iter().filter(|e| !e.passed) {
+            warn!(
+                "Expectation failed (step {}): {} (observed {})",
+                failed.step, failed.expectation, failed.observed
+            );
+        }
+        Ok::<_, Box<dyn Error>>(expect::enforce(&expectations)?)
+    })?;
 
-    features = features.fill_null(FillNullStrategy::Zero)?;
+    let (features, quality, feature_joins, step_stats) =
+        result.stage("features", FailureKind::Pipeline, || {
+            // let features = features_pipeline.apply(features_df)?;
+            let (features, step_stats) = features_pipeline.apply_lazy(&features_df)?;
+
+            info!("Features before fill_null: {:?}", features.shape());
+
+            let quality = quality::assess(&features)?;
+            let joins = features_pipeline.join_reports(&features_df)?;
+
+            Ok::<_, Box<dyn Error>>((
+                features.fill_null(FillNullStrategy::Zero)?,
+                quality,
+                joins,
+                step_stats,
+            ))
+        })?;
+    result.rows.features = Some(features.height());
+    result.steps = step_stats.clone();
+
+    for feature in &quality {
+        if let Some(degenerate) = feature.degenerate {
+            warn!("Degenerate feature '{}': {:?}", feature.column, degenerate);
+        }
+    }
 
     info!("Features after fill_null: {:?}", features.shape());
+
+    let (features, dropped_features) = match &entrypoint_config.select_features {
+        Some(selection) => result.stage("select_features", FailureKind::Pipeline, || {
+            let dropped = selection.select(&features)?;
+            for feature in &dropped {
+                info!("Dropped feature '{}': {:?}", feature.column, feature.reason);
+            }
+            let features = features.drop_many(dropped.iter().map(|f| f.column.as_str()));
+            Ok::<_, PipelineStepError>((features, dropped))
+        })?,
+        None => (features, Vec::new()),
+    };
     info!("Feature columns: {:?}", features.get_column_names());
 
-    let mut labels = labels_pipeline.apply(&df)?;
+    if let Some(drift) = &entrypoint_config.drift {
+        result.stage("drift", FailureKind::Data, || {
+            let baseline = drift.load_baseline()?;
+            let current = match drift.frame {
+                DriftFrame::Input => &*features_df,
+                DriftFrame::Features => &features,
+            };
+            let report = drift.compare(&baseline, current)?;
+            fs::write(
+                run_dir.join("drift.json"),
+                serde_json::to_string_pretty(&report)?,
+            )?;
+
+            let issues = report.issues();
+            for issue in &issues {
+                warn!("Drift from baseline: {}", issue);
+            }
+            if drift.action == DriftAction::Fail && !issues.is_empty() {
+                return Err(
+                    format!("{} drift issue(s) against {}", issues.len(), drift.baseline).into(),
+                );
+            }
+            Ok::<_, Box<dyn Error>>(())
+        })?;
+    }
+
+    let (labels, label_joins, label_stats) =
+        result.stage("labels", FailureKind::Pipeline, || {
+            let (labels, stats) = labels_pipeline.apply(&labels_df)?;
+            let joins = labels_pipeline.join_reports(&labels_df, &labels)?;
+            Ok::<_, PipelineStepError>((labels, joins, stats))
+        })?;
+
+    // Inputs are read again only for reports, so past the memory budget they
+    // wait on disk while the outputs are finished and the model trains.
+    let _spill_store = match &entrypoint_config.memory {
+        Some(memory) => Some(result.stage("spill", FailureKind::Data, || {
+            let mut store = SpillStore::new(memory)?;
+            store.reserve(&features);
+            store.reserve(&labels);
+            let spilled = datasets.spill(&mut store)?;
+            if !spilled.is_empty() {
+                info!("Spilled input datasets {:?} to disk", spilled);
+            }
+            Ok::<_, PipelineStepError>(store)
+        })?),
+        None => None,
+    };
+
+    let joins: Vec<JoinReport> = feature_joins.into_iter().chain(label_joins).collect();
+    for join in &joins {
+        if join.unmatched > 0 {
+            let log = format!(
+                "Step '{}' ({}): {} of {} rows unmatched ({:.1}% coverage); top unmatched keys {:?}",
+                join.step,
+                join.function,
+                join.unmatched,
+                join.rows,
+                join.coverage * 100.0,
+                join.top_unmatched
+                    .iter()
+                    .map(|k| k.key.as_str())
+                    .collect::<Vec<_>>()
+            );
+            match join.status {
+                JoinStatus::Ok => info!("{}", log),
+                _ => warn!("{}", log),
+            }
+        }
+    }
+
+    // Rows without a training target, e.g. `future_event` rows whose horizon
+    // isn't fully observed, are dropped from both sides to keep them aligned.
+    let (mut features, mut labels) =
+        result.stage("drop_unlabeled", FailureKind::Pipeline, || {
+            let labeled = labels
+                .column(&labels_pipeline.train_column())?
+                .is_not_null();
+            let dropped = labeled.len() - labeled.sum().unwrap_or(0) as usize;
+            if dropped == 0 {
+                return Ok::<_, Box<dyn Error>>((features, labels));
+            }
+            info!("Dropping {} rows without a training target", dropped);
+            for rows in [&mut source_rows, &mut split_rows].into_iter().flatten() {
+                *rows = rows.filter(&labeled)?;
+            }
+            if let Some(keys) = &mut key_rows {
+                *keys = keys.filter(&labeled)?;
+            }
+            Ok((features.filter(&labeled)?, labels.filter(&labeled)?))
+        })?;
+
+    if let Some(balance) = &labels_pipeline.balance {
+        (features, labels) = result.stage("balance", FailureKind::Pipeline, || {
+            let picked = balance.sample(&labels, &labels_pipeline.train_column())?;
+            for rows in [&mut source_rows, &mut split_rows].into_iter().flatten() {
+                *rows = rows.take(&picked)?;
+            }
+            if let Some(keys) = &mut key_rows {
+                *keys = keys.take(&picked)?;
+            }
+            Ok::<_, PipelineStepError>((features.take(&picked)?, labels.take(&picked)?))
+        })?;
+        info!(
+            "Balanced classes ({:?}): {} rows",
+            balance.strategy,
+            labels.height()
+        );
+    }
+    if let (Some(keys), Some(key_rows)) = (&features_pipeline.keys, &key_rows) {
+        (features, labels) = result.stage("sort_outputs", FailureKind::Output, || {
+            let order = keys.sort_order(key_rows)?;
+            for rows in [&mut source_rows, &mut split_rows].into_iter().flatten() {
+                *rows = rows.take(&order)?;
+            }
+            Ok::<_, PipelineStepError>((features.take(&order)?, labels.take(&order)?))
+        })?;
+    }
+    result.rows.features = Some(features.height());
+    result.rows.labels = Some(labels.height());
 
     info!("Labels shape: {:?}", labels.shape());
 
-    let features_filename = File::create_new(run_dir.join("features.csv"))?;
-    let labels_filename = File::create_new(run_dir.join("labels.csv"))?;
+    if let Some(leakage) = &entrypoint_config.leakage {
+        result.stage("leakage_check", FailureKind::Pipeline, || {
+            let issues = leakage.check(&features_pipeline, &labels_pipeline, &features, &labels)?;
+            for issue in &issues {
+                warn!(
+                    "Possible target leakage ({:?}): {}",
+                    issue.kind, issue.message
+                );
+            }
+            if leakage.action == LeakageAction::Fail && !issues.is_empty() {
+                return Err(format!(
+                    "{} feature(s) may leak the target: {}",
+                    issues.len(),
+                    issues
+                        .iter()
+                        .map(|i| i.column.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+                .into());
+            }
+            Ok::<_, Box<dyn Error>>(())
+        })?;
+    }
+
+    if let Some(rows) = &split_rows {
+        let counts: Vec<String> = split::SPLITS
+            .iter()
+            .map(|name| {
+                Ok(format!(
+                    "{} {}",
+                    rows.equal(*name)?.sum().unwrap_or(0),
+                    name
+                ))
+            })
+            .collect::<PolarsResult<_>>()
+            .unwrap_or_default();
+        info!("Split rows: {}", counts.join(", "));
+    }
+
+    let encodings = result.stage("write_outputs", FailureKind::Output, || {
+        let output = entrypoint_config.split.as_ref().map(|s| s.output());
+        match (&split_rows, output) {
+            (Some(rows), Some(output)) => {
+                if output.column() {
+                    let mut features = features.clone();
+                    let mut labels = labels.clone();
+                    features.with_column(rows.clone())?;
+                    labels.with_column(rows.clone())?;
+                    write_outputs(&run_dir, &sources, &source_rows, &features, &labels, "")?;
+                }
+                if output.files() {
+                    for name in split::SPLITS {
+                        let mask = rows.equal(name)?;
+                        let source_rows =
+                            source_rows.as_ref().map(|r| r.filter(&mask)).transpose()?;
+                        write_outputs(
+                            &run_dir,
+                            &sources,
+                            &source_rows,
+                            &features.filter(&mask)?,
+                            &labels.filter(&mask)?,
+                            &format!("_{}", name),
+                        )?;
+                    }
+                }
+            }
+            _ => write_outputs(&run_dir, &sources, &source_rows, &features, &labels, "")?,
+        }
+
+        let columns: Vec<String> = features
+            .get_column_names()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        fs::write(
+            run_dir.join("lineage.json"),
+            serde_json::to_string_pretty(&features_pipeline.lineage(&columns))?,
+        )?;
+
+        fs::write(
+            run_dir.join("feature_quality.json"),
+            serde_json::to_string_pretty(&quality)?,
+        )?;
+
+        if !joins.is_empty() {
+            fs::write(
+                run_dir.join("join_report.json"),
+                serde_json::to_string_pretty(&joins)?,
+            )?;
+        }
+
+        let features_df = datasets.select(features_pipeline.dataset.as_deref())?;
+        let woe_tables = features_pipeline.woe_tables(&features_df)?;
+        if !woe_tables.is_empty() {
+            fs::write(
+                run_dir.join("woe_tables.json"),
+                serde_json::to_string_pretty(&woe_tables)?,
+            )?;
+        }
+
+        let quantile_maps = features_pipeline.quantile_maps(&features_df)?;
+        if !quantile_maps.is_empty() {
+            fs::write(
+                run_dir.join("quantile_transforms.json"),
+                serde_json::to_string_pretty(&quantile_maps)?,
+            )?;
+        }
+
+        let ohe_categories = features_pipeline.ohe_categories(&features_df)?;
+        if !ohe_categories.is_empty() {
+            fs::write(
+                run_dir.join("ohe_categories.json"),
+                serde_json::to_string_pretty(&ohe_categories)?,
+            )?;
+        }
+
+        let kept_categories = features_pipeline.kept_categories(&features_df)?;
+        if !kept_categories.is_empty() {
+            fs::write(
+                run_dir.join("rare_categories.json"),
+                serde_json::to_string_pretty(&kept_categories)?,
+            )?;
+        }
+
+        let power_fits = features_pipeline.power_fits(&features_df)?;
+        if !power_fits.is_empty() {
+            fs::write(
+                run_dir.join("power_transforms.json"),
+                serde_json::to_string_pretty(&power_fits)?,
+            )?;
+        }
+
+        let labels_df = datasets.select(labels_pipeline.dataset.as_deref())?;
+        let encodings = labels_pipeline.encodings(&labels_df)?;
+        if !encodings.is_empty() {
+            fs::write(
+                run_dir.join("label_encodings.json"),
+                serde_json::to_string_pretty(&encodings)?,
+            )?;
+        }
+
+        let transforms = labels_pipeline.transforms(&labels_df)?;
+        if !transforms.is_empty() {
+            fs::write(
+                run_dir.join("target_transforms.json"),
+                serde_json::to_string_pretty(&transforms)?,
+            )?;
+        }
+
+        let bin_edges = labels_pipeline.bin_edges(&labels_df)?;
+        if !bin_edges.is_empty() {
+            fs::write(
+                run_dir.join("bin_edges.json"),
+                serde_json::to_string_pretty(&bin_edges)?,
+            )?;
+        }
+
+        Ok::<_, Box<dyn Error>>(encodings)
+    })?;
+
+    if let Some(output) = &entrypoint_config.output {
+        let output = match partition {
+            Some(partition) => output.for_partition(&partition.partition.label),
+            None => output.clone(),
+        };
+        result.stage("write_target", FailureKind::Output, || {
+            output.write(&features, &labels)?;
+            info!("Wrote outputs to {}", output.path);
+            Ok::<_, PipelineStepError>(())
+        })?;
+    }
+
+    if let Some(incremental) = &entrypoint_config.incremental {
+        result.stage("append_outputs", FailureKind::Output, || {
+            incremental.append(&features, &labels)?;
+            // Saved only once the rows are appended, so a failed run reads them again.
+            if let Some(watermark) = &next_watermark {
+                incremental.save(watermark)?;
+                info!(
+                    "Appended {} rows to {}; watermark now {}",
+                    features.height(),
+                    incremental.output,
+                    watermark.value
+                );
+            }
+            Ok::<_, PipelineStepError>(())
+        })?;
+    }
+
+    if let Some(correlation) = &entrypoint_config.correlation {
+        result.stage("correlation", FailureKind::Output, || {
+            let label = labels.column(&labels_pipeline.train_column())?;
+            let matrix = correlation.compute(&features, Some(label))?;
+            for (a, b, r) in matrix.redundant_pairs(correlation.redundant_above) {
+                warn!(
+                    "Possibly redundant features '{}' and '{}' (r = {:.3})",
+                    a, b, r
+                );
+            }
+
+            let file = File::create_new(run_dir.join("correlation.csv"))?;
+            CsvWriter::new(&file)
+                .include_header(true)
+                .with_separator(b';')
+                .finish(&mut matrix.to_dataframe()?)?;
+            fs::write(run_dir.join("correlation.html"), matrix.to_html())?;
+            Ok::<_, Box<dyn Error>>(())
+        })?;
+    }
 
-    CsvWriter::new(&features_filename)
-        .include_header(true)
-        .with_separator(b';')
-        .finish(&mut features)?;
+    if let Some(relevance) = &entrypoint_config.relevance {
+        result.stage("relevance", FailureKind::Output, || {
+            let target = labels.column(&labels_pipeline.train_column())?;
+            let ranked = relevance.rank(&features, target)?;
+            let least: Vec<&str> = ranked
+                .iter()
+                .rev()
+                .take(5)
+                .map(|f| f.column.as_str())
+                .collect();
+            info!("Least relevant features: {:?}", least);
+            fs::write(
+                run_dir.join("relevance.json"),
+                serde_json::to_string_pretty(&ranked)?,
+            )?;
+            Ok::<_, Box<dyn Error>>(())
+        })?;
+    }
 
-    CsvWriter::new(&labels_filename)
-        .include_header(true)
-        .with_separator(b';')
-        .finish(&mut labels)?;
+    if let Some(profiling) = &entrypoint_config.profiling {
+        result.stage("profile", FailureKind::Output, || {
+            let input = datasets.select(features_pipeline.dataset.as_deref())?;
+            let profiles = vec![
+                profiling.profile("input", &input)?,
+                profiling.profile("features", &features)?,
+                profiling.profile("labels", &labels)?,
+            ];
+            fs::write(
+                run_dir.join("profile.json"),
+                serde_json::to_string_pretty(&profiles)?,
+            )?;
+            fs::write(run_dir.join("profile.html"), profiling::to_html(&profiles))?;
+            Ok::<_, Box<dyn Error>>(())
+        })?;
+    }
 
     info!("Saved to: {}", run_dir.display());
 
-    let features_array = features.to_ndarray::<Float64Type>(IndexOrder::C)?;
-    let targets_array = labels
-        .to_ndarray::<Int32Type>(IndexOrder::C)?
-        .column(0)
-        .to_owned();
+    let (train, test, model) = result.stage("train", FailureKind::Training, || {
+        let features_array = features.to_ndarray::<Float64Type>(IndexOrder::C)?;
+        let train_column = labels_pipeline.train_column();
+        info!("Training on target: {}", train_column);
+        let targets_array = labels
+            .select([train_column])?
+            .to_ndarray::<Int32Type>(IndexOrder::C)?
+            .column(0)
+            .to_owned();
 
-    info!("Features array shape: {:?}", features_array.shape());
-    info!("Targets array shape: {:?}", targets_array.shape());
+        info!("Features array shape: {:?}", features_array.shape());
+        info!("Targets array shape: {:?}", targets_array.shape());
 
-    let training_dataset = DatasetBase::new(features_array, targets_array);
-    let (train, test) = training_dataset.split_with_ratio(0.8);
+        let training_dataset = DatasetBase::new(features_array, targets_array);
+        let (train, test) = match &split_rows {
+            Some(rows) => (
+                split_dataset(&training_dataset, rows, split::TRAIN)?,
+                split_dataset(&training_dataset, rows, split::TEST)?,
+            ),
+            None => training_dataset.split_with_ratio(0.8),
+        };
 
-    info!(
-        "Train size: {}, Test size: {}",
-        train.nsamples(),
-        test.nsamples()
+        info!(
+            "Train size: {}, Test size: {}",
+            train.nsamples(),
+            test.nsamples()
+        );
+
+        let classes: BTreeSet<i32> = train.targets().iter().copied().collect();
+        let model = if classes.len() > 2 {
+            info!("Training multinomial model on {} classes", classes.len());
+            Model::Multi(
+                MultiLogisticRegression::default()
+                    .max_iterations(300)
+                    .gradient_tolerance(0.0001)
+                    .fit(&train)?,
+            )
+        } else {
+            Model::Binary(
+                LogisticRegression::default()
+                    .max_iterations(300)
+                    .gradient_tolerance(0.0001)
+                    .fit(&train)?,
+            )
+        };
+        info!("Model trained successfully");
+
+        Ok::<_, Box<dyn Error>>((train, test, model))
+    })?;
+    result.rows.train = Some(train.nsamples());
+    result.rows.test = Some(test.nsamples());
+
+    result.evaluation = result.stage("evaluate", FailureKind::Training, || {
+        info!("=== Making Predictions ===");
+        let predictions = model.predict(test.records());
+        info!("Predictions shape: {:?}", predictions.shape());
+
+        if let Some(encoding) = encodings.get(labels_pipeline.train_target()) {
+            let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+            for &code in &predictions {
+                *counts
+                    .entry(encoding.decode(code).unwrap_or("<unknown>"))
+                    .or_default() += 1;
+            }
+            info!("Predicted classes: {:?}", counts);
+        }
+
+        if let Model::Multi(_) = model {
+            let confusion = predictions
+                .mapv(|x| x as usize)
+                .confusion_matrix(test.targets().mapv(|x| x as usize))?;
+
+            info!("=== Model Evaluation ===");
+            info!("{:?}", confusion);
+            info!("Accuracy:  {:.4}", confusion.accuracy());
+            return Ok(evaluation([("accuracy", confusion.accuracy())]));
+        }
+
+        let pred_bool = predictions.mapv(|x| x == 1);
+        let target_bool = test.targets().mapv(|x| x == 1);
+        let confusion = pred_bool.confusion_matrix(&target_bool)?;
+
+        info!("=== Model Evaluation ===");
+        info!("{:?}", confusion);
+        info!("Accuracy:  {:.4}", confusion.accuracy());
+        info!("Precision: {:.4}", confusion.precision());
+        info!("Recall:    {:.4}", confusion.recall());
+        info!("F1 Score:  {:.4}", confusion.f1_score());
+
+        Ok::<_, Box<dyn Error>>(evaluation([
+            ("accuracy", confusion.accuracy()),
+            ("precision", confusion.precision()),
+            ("recall", confusion.recall()),
+            ("f1", confusion.f1_score()),
+        ]))
+    })?;
+
+    let stages = result.stages.clone();
+    let (run_id, run_name, tags) = (
+        result.run_id.clone(),
+        result.run_name.clone(),
+        result.tags.clone(),
     );
+    result.stage("manifest", FailureKind::Output, || {
+        let columns: Vec<String> = features
+            .get_column_names()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let manifest = Manifest {
+            run_id: run_id.as_deref(),
+            run_name: run_name.as_deref(),
+            tags: &tags,
+            crate_version: env!("CARGO_PKG_VERSION"),
+            git_commit: manifest::git_commit(),
+            seed: entrypoint_config.seed,
+            config_fingerprint: &fingerprint,
+            config: &resolved,
+            inputs: manifest::inputs(&entrypoint_config, &datasets)?,
+            stages: &stages,
+            peak_memory_bytes: bench::peak_memory_bytes(),
+            steps: manifest::steps(step_stats, &features_pipeline.lineage(&columns)),
+            label_steps: &label_stats,
+            dropped_features: &dropped_features,
+            features: manifest::schema(&features),
+            labels: manifest::schema(&labels),
+        };
+        fs::write(
+            run_dir.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+        Ok::<_, Box<dyn Error>>(())
+    })?;
 
-    let model = LogisticRegression::default()
-        .max_iterations(300)
-        .gradient_tolerance(0.0001)
-        .fit(&train)?;
-    info!("Model trained successfully");
-
-    info!("\n=== Making Predictions ===");
-    let predictions = model.predict(&test);
-    info!("Predictions shape: {:?}", predictions.shape());
-
-    let pred_bool = predictions.mapv(|x| x == 1);
-    let target_bool = test.targets().mapv(|x| x == 1);
-    let confusion = pred_bool.confusion_matrix(&target_bool)?;
-
-    info!("\n=== Model Evaluation ===");
-    info!("{:?}", confusion);
-    info!("Accuracy:  {:.4}", confusion.accuracy());
-    info!("Precision: {:.4}", confusion.precision());
-    info!("Recall:    {:.4}", confusion.recall());
-    info!("F1 Score:  {:.4}", confusion.f1_score());
+    let rows = result.rows.clone();
+    let stages = result.stages.clone();
+    let steps = result.steps.clone();
+    result.stage("report", FailureKind::Output, || {
+        let report = RunReport {
+            run_id: run_id.as_deref(),
+            run_name: run_name.as_deref(),
+            tags: &tags,
+            fingerprint: &fingerprint,
+            config: &resolved,
+            inputs: &manifest::inputs(&entrypoint_config, &datasets)?,
+            rows: &rows,
+            stages: &stages,
+            steps: &steps,
+            label_steps: &label_stats,
+            quality: &quality,
+            labels: &report::label_distributions(&labels, &encodings)?,
+            warnings: &telemetry::warnings(),
+        };
+        fs::write(run_dir.join("report.html"), report.to_html()?)?;
+        Ok::<_, Box<dyn Error>>(())
+    })?;
 
     Ok(())
 }
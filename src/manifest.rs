@@ -0,0 +1,138 @@
+use std::{collections::BTreeMap, fs, process::Command};
+
+use polars::prelude::*;
+use serde::Serialize;
+
+use crate::config::entry::EntrypointConfig;
+use crate::config::fingerprint::ResolvedConfig;
+use crate::data::{Datasets, SourceFile};
+use crate::errors::PipelineStepError;
+use crate::pipeline::lineage::LineageEntry;
+use crate::pipeline::selection::DroppedFeature;
+use crate::pipeline::stats::StepStats;
+use crate::run_result::StageResult;
+
+#[derive(Serialize, Debug)]
+pub struct InputFile {
+    pub dataset: String,
+    pub path: String,
+    /// `None` when the path is not a local file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    pub rows: usize,
+    /// Path and rows of each file when `path` named several.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<SourceFile>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub dtype: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StepManifest {
+    #[serde(flatten)]
+    pub stats: StepStats,
+    pub output_columns: Vec<String>,
+}
+
+/// Everything needed to tell how a run folder was produced, written as
+/// `manifest.json`.
+#[derive(Serialize, Debug)]
+pub struct Manifest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: &'a BTreeMap<String, String>,
+    pub crate_version: &'static str,
+    /// `HEAD` of the git checkout the run was started from, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    /// The entrypoint's `seed`, if set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    pub config_fingerprint: &'a str,
+    pub config: &'a ResolvedConfig<'a>,
+    pub inputs: Vec<InputFile>,
+    pub stages: &'a [StageResult],
+    /// Highest resident set size of the process over the run, where procfs is
+    /// available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_memory_bytes: Option<u64>,
+    pub steps: Vec<StepManifest>,
+    pub label_steps: &'a [StepStats],
+    /// Features `select_features` dropped, and why.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub dropped_features: &'a [DroppedFeature],
+    pub features: Vec<ColumnSchema>,
+    pub labels: Vec<ColumnSchema>,
+}
+
+/// Path, size and row count of every dataset the entrypoint loaded.
+pub fn inputs(
+    config: &EntrypointConfig,
+    datasets: &Datasets,
+) -> Result<Vec<InputFile>, PipelineStepError> {
+    config
+        .datasets
+        .iter()
+        .map(|(name, dataset)| {
+            let files = datasets.sources(Some(name));
+            let size_bytes = files
+                .iter()
+                .map(|file| fs::metadata(&file.path).ok().map(|m| m.len()))
+                .sum();
+            Ok(InputFile {
+                dataset: name.clone(),
+                path: dataset.path.clone(),
+                size_bytes,
+                rows: files.iter().map(|file| file.rows).sum(),
+                files: if files.len() > 1 {
+                    files.to_vec()
+                } else {
+                    Vec::new()
+                },
+            })
+        })
+        .collect()
+}
+
+/// Pairs each step's stats with the output columns `lineage` attributes to it.
+pub fn steps(stats: Vec<StepStats>, lineage: &[LineageEntry]) -> Vec<StepManifest> {
+    stats
+        .into_iter()
+        .map(|stats| StepManifest {
+            output_columns: lineage
+                .iter()
+                .filter(|entry| entry.step == stats.step)
+                .map(|entry| entry.column.clone())
+                .collect(),
+            stats,
+        })
+        .collect()
+}
+
+pub fn schema(data: &DataFrame) -> Vec<ColumnSchema> {
+    data.get_columns()
+        .iter()
+        .map(|c| ColumnSchema {
+            name: c.name().to_string(),
+            dtype: c.dtype().to_string(),
+        })
+        .collect()
+}
+
+pub fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
@@ -0,0 +1,270 @@
+use std::{collections::BTreeMap, fmt::Write, io, net::UdpSocket};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::run_result::{RunResult, RunStatus, StageStatus};
+
+fn default_prefix() -> String {
+    "features_pipeline".to_string()
+}
+
+fn default_job() -> String {
+    "features_pipeline".to_string()
+}
+
+/// Prometheus Pushgateway; each run replaces the metrics of its job group.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PushgatewayConfig {
+    /// Base URL, e.g. `http://pushgateway:9091`.
+    pub url: String,
+
+    #[serde(default = "default_job")]
+    pub job: String,
+}
+
+/// StatsD daemon reached over UDP; label values become name segments.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct StatsdConfig {
+    /// `host:port`, e.g. `127.0.0.1:8125`.
+    pub address: String,
+}
+
+/// Run-level and per-step metrics sent once a run ends, whether it succeeded
+/// or failed.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pushgateway: Option<PushgatewayConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statsd: Option<StatsdConfig>,
+
+    /// Prepended to every metric name.
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+
+    /// Constant labels added to every metric, e.g. `env: prod`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// One gauge value; `name` excludes the configured prefix.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub name: &'static str,
+    pub labels: Vec<(&'static str, String)>,
+    pub value: f64,
+}
+
+impl Sample {
+    fn new(name: &'static str, value: f64) -> Self {
+        Self {
+            name,
+            labels: Vec::new(),
+            value,
+        }
+    }
+
+    fn label(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.labels.push((key, value.into()));
+        self
+    }
+}
+
+/// Durations, row counts and failures recorded in `result`.
+pub fn samples(result: &RunResult) -> Vec<Sample> {
+    let duration_ms: f64 = result.stages.iter().map(|s| s.duration_ms).sum();
+    let mut samples = vec![
+        Sample::new("run_duration_seconds", duration_ms / 1000.0),
+        Sample::new(
+            "run_success",
+            if result.status == RunStatus::Success {
+                1.0
+            } else {
+                0.0
+            },
+        ),
+    ];
+
+    if let Some(error) = &result.error {
+        samples.push(
+            Sample::new("run_failure", 1.0)
+                .label("kind", format!("{:?}", error.kind).to_lowercase())
+                .label("stage", error.stage.as_str()),
+        );
+    }
+
+    let rows = &result.rows;
+    for (frame, count) in [
+        ("input", rows.input),
+        ("features", rows.features),
+        ("labels", rows.labels),
+        ("train", rows.train),
+        ("test", rows.test),
+    ] {
+        if let Some(count) = count {
+            samples.push(Sample::new("rows", count as f64).label("frame", frame));
+        }
+    }
+
+    for stage in &result.stages {
+        samples.push(
+            Sample::new("stage_duration_seconds", stage.duration_ms / 1000.0)
+                .label("stage", stage.name.as_str()),
+        );
+        samples.push(
+            Sample::new(
+                "stage_failed",
+                if stage.status == StageStatus::Failed {
+                    1.0
+                } else {
+                    0.0
+                },
+            )
+            .label("stage", stage.name.as_str()),
+        );
+    }
+
+    for step in &result.steps {
+        let name = step
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("step_{}", step.step));
+        let labelled = |metric, value| {
+            Sample::new(metric, value)
+                .label("step", name.as_str())
+                .label("function", step.function)
+        };
+        if let Some(duration_ms) = step.duration_ms {
+            samples.push(labelled("step_duration_seconds", duration_ms / 1000.0));
+        }
+        samples.push(labelled("step_rows_in", step.rows_in as f64));
+        samples.push(labelled("step_rows_out", step.rows_out as f64));
+    }
+
+    samples
+}
+
+impl MetricsConfig {
+    /// Sends `result` to every configured sink, attempting all of them before
+    /// returning the first error.
+    pub fn push(&self, result: &RunResult) -> Result<(), io::Error> {
+        let samples = samples(result);
+        let pushed = self
+            .pushgateway
+            .as_ref()
+            .map_or(Ok(()), |sink| self.push_gateway(sink, &samples));
+        let sent = self
+            .statsd
+            .as_ref()
+            .map_or(Ok(()), |sink| self.send_statsd(sink, &samples));
+        pushed.and(sent)
+    }
+
+    /// Prometheus text exposition format, samples grouped by metric name as
+    /// the format requires.
+    pub fn to_prometheus(&self, samples: &[Sample]) -> String {
+        let mut samples: Vec<&Sample> = samples.iter().collect();
+        samples.sort_by_key(|s| s.name);
+
+        let mut text = String::new();
+        let mut previous = None;
+        for sample in samples {
+            let name = format!("{}_{}", self.prefix, sample.name);
+            if previous != Some(sample.name) {
+                previous = Some(sample.name);
+                let _ = writeln!(text, "# TYPE {} gauge", name);
+            }
+            let labels: Vec<String> = self
+                .labels
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .chain(sample.labels.iter().map(|(k, v)| (*k, v.as_str())))
+                .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                .collect();
+            if labels.is_empty() {
+                let _ = writeln!(text, "{} {}", name, sample.value);
+            } else {
+                let _ = writeln!(text, "{}{{{}}} {}", name, labels.join(","), sample.value);
+            }
+        }
+        text
+    }
+
+    /// One `name:value|g` line per sample, label values appended as segments.
+    pub fn to_statsd(&self, samples: &[Sample]) -> Vec<String> {
+        samples
+            .iter()
+            .map(|sample| {
+                let segments: Vec<String> = std::iter::once(self.prefix.as_str())
+                    .chain(self.labels.values().map(String::as_str))
+                    .chain(std::iter::once(sample.name))
+                    .chain(sample.labels.iter().map(|(_, v)| v.as_str()))
+                    .map(sanitize_segment)
+                    .collect();
+                format!("{}:{}|g", segments.join("."), sample.value)
+            })
+            .collect()
+    }
+
+    fn push_gateway(&self, sink: &PushgatewayConfig, samples: &[Sample]) -> Result<(), io::Error> {
+        let url = format!(
+            "{}/metrics/job/{}",
+            sink.url.trim_end_matches('/'),
+            sink.job
+        );
+        put(&url, self.to_prometheus(samples))
+    }
+
+    fn send_statsd(&self, sink: &StatsdConfig, samples: &[Sample]) -> Result<(), io::Error> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(&sink.address)?;
+        for line in self.to_statsd(samples) {
+            socket.send(line.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "native")]
+fn put(url: &str, body: String) -> Result<(), io::Error> {
+    ureq::put(url)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .send(body)
+        .map_err(io::Error::other)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "native"))]
+fn put(url: &str, _body: String) -> Result<(), io::Error> {
+    Err(io::Error::other(format!(
+        "cannot push to {}: requires the `native` feature",
+        url
+    )))
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// StatsD uses `.` as separator and `:|@` as syntax, so anything beyond
+/// alphanumerics, `_` and `-` becomes `_`.
+fn sanitize_segment(segment: &str) -> String {
+    segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
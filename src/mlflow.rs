@@ -0,0 +1,303 @@
+//! Logs a finished run to an existing MLflow run over the tracking server's
+//! REST API, so a dataset build shows up next to the training runs that
+//! consume it. The run is named by environment variables:
+//!
+//! - `MLFLOW_TRACKING_URI`: the tracking server, e.g. `http://mlflow:5000`.
+//! - `MLFLOW_RUN_ID`: the run to log to.
+//! - `MLFLOW_TRACKING_TOKEN`: bearer token, when the server requires one.
+//!
+//! Everything is logged under a `features_pipeline` prefix so it doesn't
+//! collide with what the training code logs to the same run: the config
+//! fingerprint and run ID as params, the run status as a tag, the
+//! [`metrics::samples`] as metrics and the files of the run folder (resolved
+//! config, manifest, profiles, outputs) as artifacts.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::metrics::{self, Sample};
+use crate::run_result::RunResult;
+
+/// Prefix of every key and of the artifact directory.
+const PREFIX: &str = "features_pipeline";
+
+/// Most metrics `log-batch` takes in one request.
+const MAX_BATCH_METRICS: usize = 1000;
+
+/// Scheme of artifact stores the tracking server proxies; only these can be
+/// written over the REST API.
+const PROXIED_SCHEME: &str = "mlflow-artifacts:";
+
+#[derive(Serialize, Debug, Clone)]
+struct Param {
+    key: String,
+    value: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct Metric {
+    key: String,
+    value: f64,
+    timestamp: u128,
+    step: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct LogBatch<'a> {
+    run_id: &'a str,
+    metrics: &'a [Metric],
+    params: &'a [Param],
+    tags: &'a [Param],
+}
+
+/// An MLflow run to log to; see the module docs.
+#[derive(Debug, Clone)]
+pub struct MlflowRun {
+    pub tracking_uri: String,
+    pub run_id: String,
+    pub token: Option<String>,
+}
+
+impl MlflowRun {
+    /// The run named by `MLFLOW_TRACKING_URI` and `MLFLOW_RUN_ID`; `None`
+    /// unless both are set.
+    pub fn from_env() -> Option<Self> {
+        let var = |name| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Some(Self {
+            tracking_uri: var("MLFLOW_TRACKING_URI")?
+                .trim_end_matches('/')
+                .to_string(),
+            run_id: var("MLFLOW_RUN_ID")?,
+            token: var("MLFLOW_TRACKING_TOKEN"),
+        })
+    }
+
+    /// Logs params, tags and metrics of `result`, then uploads its run
+    /// folder, attempting both before returning the first error.
+    pub fn log(&self, result: &RunResult) -> Result<(), io::Error> {
+        let logged = self.log_batch(result);
+        let uploaded = result
+            .run_dir
+            .as_deref()
+            .map_or(Ok(()), |dir| self.log_artifacts(Path::new(dir)));
+        logged.and(uploaded)
+    }
+
+    fn log_batch(&self, result: &RunResult) -> Result<(), io::Error> {
+        let mut params = vec![Param {
+            key: key("crate_version"),
+            value: env!("CARGO_PKG_VERSION").to_string(),
+        }];
+        for (name, value) in [
+            ("run_name", &result.run_name),
+            ("config_fingerprint", &result.config_fingerprint),
+            ("input_fingerprint", &result.input_fingerprint),
+            ("run_id", &result.run_id),
+        ] {
+            if let Some(value) = value {
+                params.push(Param {
+                    key: key(name),
+                    value: value.clone(),
+                });
+            }
+        }
+        let mut tags = vec![Param {
+            key: key("status"),
+            value: format!("{:?}", result.status).to_lowercase(),
+        }];
+        tags.extend(result.tags.iter().map(|(name, value)| Param {
+            key: key(&format!("tag.{}", name)),
+            value: value.clone(),
+        }));
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+        let metrics: Vec<Metric> = metrics::samples(result)
+            .iter()
+            .map(|sample| Metric {
+                key: metric_key(sample),
+                value: sample.value,
+                timestamp,
+                step: 0,
+            })
+            .collect();
+
+        let url = format!("{}/api/2.0/mlflow/runs/log-batch", self.tracking_uri);
+        // Params and tags go with the first batch; later ones carry metrics only.
+        let mut chunks = metrics.chunks(MAX_BATCH_METRICS);
+        let first = LogBatch {
+            run_id: &self.run_id,
+            metrics: chunks.next().unwrap_or_default(),
+            params: &params,
+            tags: &tags,
+        };
+        post(&url, self.token.as_deref(), &serde_json::to_string(&first)?)?;
+        for chunk in chunks {
+            let batch = LogBatch {
+                metrics: chunk,
+                params: &[],
+                tags: &[],
+                ..first
+            };
+            post(&url, self.token.as_deref(), &serde_json::to_string(&batch)?)?;
+        }
+        Ok(())
+    }
+
+    /// Uploads every file under `run_dir` to `features_pipeline/` in the
+    /// run's artifacts, keeping the folder layout.
+    fn log_artifacts(&self, run_dir: &Path) -> Result<(), io::Error> {
+        let url = format!(
+            "{}/api/2.0/mlflow/runs/get?run_id={}",
+            self.tracking_uri, self.run_id
+        );
+        let run: Value = serde_json::from_str(&get(&url, self.token.as_deref())?)?;
+        let artifact_uri = run["run"]["info"]["artifact_uri"]
+            .as_str()
+            .ok_or_else(|| io::Error::other(format!("run {} has no artifact_uri", self.run_id)))?;
+        let root = proxied_path(artifact_uri).ok_or_else(|| {
+            io::Error::other(format!(
+                "artifact store '{}' is not proxied by the tracking server; \
+                 start it with --serve-artifacts",
+                artifact_uri
+            ))
+        })?;
+
+        for file in files(run_dir)? {
+            let relative = file
+                .strip_prefix(run_dir)
+                .map_err(io::Error::other)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let url = format!(
+                "{}/api/2.0/mlflow-artifacts/artifacts/{}/{}/{}",
+                self.tracking_uri, root, PREFIX, relative
+            );
+            put(&url, self.token.as_deref(), fs::read(&file)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn key(name: &str) -> String {
+    format!("{}.{}", PREFIX, name)
+}
+
+/// `features_pipeline.<name>` followed by the label values as path segments,
+/// e.g. `features_pipeline.step_duration_seconds/avg_age/mean`. MLflow keys
+/// allow alphanumerics and `_-. /`; anything else becomes `_`.
+fn metric_key(sample: &Sample) -> String {
+    std::iter::once(key(sample.name))
+        .chain(sample.labels.iter().map(|(_, v)| v.clone()))
+        .map(|segment| {
+            segment
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || "_-. ".contains(c) {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The path of a proxied artifact location below the server's artifact
+/// root, e.g. `1/abc/artifacts` for `mlflow-artifacts:/1/abc/artifacts` or
+/// `mlflow-artifacts://host:5000/1/abc/artifacts`.
+fn proxied_path(artifact_uri: &str) -> Option<String> {
+    let path = artifact_uri.strip_prefix(PROXIED_SCHEME)?;
+    let path = match path.strip_prefix("//") {
+        Some(authority) => &authority[authority.find('/')?..],
+        None => path,
+    };
+    Some(path.trim_matches('/').to_string())
+}
+
+/// Files under `dir`, recursively, in name order.
+fn files(dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    let mut found = Vec::new();
+    for entry in entries {
+        if entry.is_dir() {
+            found.extend(files(&entry)?);
+        } else {
+            found.push(entry);
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(feature = "native")]
+fn authorized<B>(request: ureq::RequestBuilder<B>, token: Option<&str>) -> ureq::RequestBuilder<B> {
+    match token {
+        Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+        None => request,
+    }
+}
+
+#[cfg(feature = "native")]
+fn get(url: &str, token: Option<&str>) -> Result<String, io::Error> {
+    authorized(ureq::get(url), token)
+        .call()
+        .map_err(io::Error::other)?
+        .body_mut()
+        .read_to_string()
+        .map_err(io::Error::other)
+}
+
+#[cfg(feature = "native")]
+fn post(url: &str, token: Option<&str>, body: &str) -> Result<(), io::Error> {
+    authorized(ureq::post(url), token)
+        .header("Content-Type", "application/json")
+        .send(body)
+        .map_err(io::Error::other)?;
+    Ok(())
+}
+
+#[cfg(feature = "native")]
+fn put(url: &str, token: Option<&str>, body: Vec<u8>) -> Result<(), io::Error> {
+    authorized(ureq::put(url), token)
+        .header("Content-Type", "application/octet-stream")
+        .send(&body[..])
+        .map_err(io::Error::other)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "native"))]
+fn get(url: &str, _token: Option<&str>) -> Result<String, io::Error> {
+    unsupported(url)
+}
+
+#[cfg(not(feature = "native"))]
+fn post(url: &str, _token: Option<&str>, _body: &str) -> Result<(), io::Error> {
+    unsupported(url)
+}
+
+#[cfg(not(feature = "native"))]
+fn put(url: &str, _token: Option<&str>, _body: Vec<u8>) -> Result<(), io::Error> {
+    unsupported(url)
+}
+
+#[cfg(not(feature = "native"))]
+fn unsupported<T>(url: &str) -> Result<T, io::Error> {
+    Err(io::Error::other(format!(
+        "cannot reach {}: requires the `native` feature",
+        url
+    )))
+}
@@ -0,0 +1,133 @@
+//! Outputs written to a stable folder, e.g. one a downstream job reads, on top
+//! of the timestamped run folder, which always gets its own copy.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+
+/// What to do when the target folder already holds outputs.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum IfExists {
+    /// Replace them with this run's outputs.
+    #[default]
+    Overwrite,
+    /// Fail the run, leaving them as they are.
+    Error,
+    /// Append this run's rows; the columns must match.
+    Append,
+}
+
+/// Placeholder in [`OutputConfig::path`] a backfill replaces with each
+/// partition's label.
+pub const PARTITION_PLACEHOLDER: &str = "{partition}";
+
+/// Folder `features.csv` and `labels.csv` are also written to.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    /// May contain `{partition}`, e.g. `data/backfill/{partition}`, which
+    /// `backfill` requires so partitions write to folders of their own.
+    pub path: String,
+
+    #[serde(default)]
+    pub if_exists: IfExists,
+}
+
+impl OutputConfig {
+    /// Whether `path` names one folder per backfill partition.
+    pub fn is_partitioned(&self) -> bool {
+        self.path.contains(PARTITION_PLACEHOLDER)
+    }
+
+    /// This target with `{partition}` replaced by `label`.
+    pub fn for_partition(&self, label: &str) -> Self {
+        Self {
+            path: self.path.replace(PARTITION_PLACEHOLDER, label),
+            if_exists: self.if_exists,
+        }
+    }
+
+    /// Writes `features` and `labels` to the target folder.
+    pub fn write(&self, features: &DataFrame, labels: &DataFrame) -> Result<(), PipelineStepError> {
+        let dir = Path::new(&self.path);
+        fs::create_dir_all(dir)?;
+        let files = [("features", features), ("labels", labels)]
+            .map(|(name, frame)| (dir.join(format!("{}.csv", name)), frame));
+
+        match self.if_exists {
+            IfExists::Overwrite => {
+                for (path, frame) in &files {
+                    // Written aside and renamed, so readers never see half a file.
+                    let partial = path.with_extension("csv.partial");
+                    write_csv(&File::create(&partial)?, frame)?;
+                    fs::rename(&partial, path)?;
+                }
+            }
+            IfExists::Error => {
+                // Checked for both files first, so neither is written when one exists.
+                if let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+                    return Err(polars_err!(
+                        ComputeError: "output {} already exists; set 'output.if_exists' to overwrite or append",
+                        path.display()
+                    )
+                    .into());
+                }
+                for (path, frame) in &files {
+                    write_csv(&File::create_new(path)?, frame)?;
+                }
+            }
+            IfExists::Append => {
+                for (path, frame) in &files {
+                    append_csv(path, frame)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_csv(file: &File, frame: &DataFrame) -> Result<(), PipelineStepError> {
+    CsvWriter::new(file)
+        .include_header(true)
+        .with_separator(b';')
+        .finish(&mut frame.clone())?;
+    Ok(())
+}
+
+/// Appends `frame` to the `;`-separated CSV at `path`, whose header must
+/// list the same columns, e.g. not gain a one-hot column a new category added.
+pub(crate) fn append_csv(path: &Path, frame: &DataFrame) -> Result<(), PipelineStepError> {
+    let header = frame
+        .get_column_names()
+        .iter()
+        .map(|c| c.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let exists = path.exists();
+    if exists {
+        let mut existing = String::new();
+        BufReader::new(File::open(path)?).read_line(&mut existing)?;
+        if existing.trim_end() != header {
+            return Err(polars_err!(
+                ComputeError: "cannot append to {}: its columns [{}] differ from this run's [{}]",
+                path.display(),
+                existing.trim_end(),
+                header
+            )
+            .into());
+        }
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    CsvWriter::new(&file)
+        .include_header(!exists)
+        .with_separator(b';')
+        .finish(&mut frame.clone())?;
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! Mahalanobis distances for `anomaly_score` steps: how far a row lies from
+//! the fit data's mean, in standard deviations along the data's own axes, so
+//! that correlated columns aren't counted twice.
+
+use polars::prelude::*;
+
+/// Pivots smaller than this, relative to the correlation matrix's unit
+/// diagonal, mean the columns are linearly dependent.
+const SINGULAR: f64 = 1e-10;
+
+/// The mean and inverse covariance of the fit data's complete rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mahalanobis {
+    mean: Vec<f64>,
+    inverse: Vec<Vec<f64>>,
+}
+
+impl Mahalanobis {
+    /// Fitted on the rows of `data` where every one of `columns` is finite.
+    pub fn fit(data: &DataFrame, columns: &[String]) -> PolarsResult<Self> {
+        let rows: Vec<Vec<f64>> = rows(data, columns)?.into_iter().flatten().collect();
+        let (n, k) = (rows.len(), columns.len());
+        if n <= k {
+            polars_bail!(
+                ComputeError: "needs more complete rows than columns ({}), got {}", k, n
+            );
+        }
+
+        let mean: Vec<f64> = (0..k)
+            .map(|j| rows.iter().map(|r| r[j]).sum::<f64>() / n as f64)
+            .collect();
+        let mut covariance = vec![vec![0.0; k]; k];
+        for row in &rows {
+            for i in 0..k {
+                for j in 0..k {
+                    covariance[i][j] += (row[i] - mean[i]) * (row[j] - mean[j]);
+                }
+            }
+        }
+        for row in &mut covariance {
+            for c in row.iter_mut() {
+                *c /= (n - 1) as f64;
+            }
+        }
+
+        // Inverting the correlation matrix keeps the singularity check
+        // independent of the columns' scales.
+        let scale: Vec<f64> = (0..k).map(|i| covariance[i][i].sqrt()).collect();
+        if let Some(i) = scale.iter().position(|s| *s <= 0.0 || !s.is_finite()) {
+            polars_bail!(ComputeError: "column '{}' is constant on complete rows", columns[i]);
+        }
+        let correlation: Vec<Vec<f64>> = (0..k)
+            .map(|i| {
+                (0..k)
+                    .map(|j| covariance[i][j] / (scale[i] * scale[j]))
+                    .collect()
+            })
+            .collect();
+        let Some(inverse) = invert(correlation) else {
+            polars_bail!(
+                ComputeError: "columns {:?} are linearly dependent; drop one of them", columns
+            );
+        };
+        let inverse = (0..k)
+            .map(|i| {
+                (0..k)
+                    .map(|j| inverse[i][j] / (scale[i] * scale[j]))
+                    .collect()
+            })
+            .collect();
+        Ok(Self { mean, inverse })
+    }
+
+    /// The distance of `x`, in the fitted columns' order, from the mean.
+    pub fn score(&self, x: &[f64]) -> f64 {
+        let d: Vec<f64> = x.iter().zip(&self.mean).map(|(x, m)| x - m).collect();
+        let squared: f64 = self
+            .inverse
+            .iter()
+            .zip(&d)
+            .map(|(row, di)| di * row.iter().zip(&d).map(|(a, dj)| a * dj).sum::<f64>())
+            .sum();
+        squared.max(0.0).sqrt()
+    }
+
+    /// The scores of the rows of `data` as the column `name`; null for rows
+    /// where any of `columns` is null or not finite.
+    pub fn column(&self, data: &DataFrame, columns: &[String], name: &str) -> PolarsResult<Column> {
+        let scores: Float64Chunked = rows(data, columns)?
+            .iter()
+            .map(|row| row.as_deref().map(|x| self.score(x)))
+            .collect();
+        Ok(scores.with_name(name.into()).into_column())
+    }
+}
+
+/// The values of `columns` per row of `data`; `None` for rows where any is
+/// null or not finite.
+fn rows(data: &DataFrame, columns: &[String]) -> PolarsResult<Vec<Option<Vec<f64>>>> {
+    let values = columns
+        .iter()
+        .map(|c| data.column(c)?.cast(&DataType::Float64))
+        .collect::<PolarsResult<Vec<_>>>()?;
+    let values = values
+        .iter()
+        .map(|c| c.f64().cloned())
+        .collect::<PolarsResult<Vec<_>>>()?;
+    Ok((0..data.height())
+        .map(|row| {
+            values
+                .iter()
+                .map(|c| c.get(row).filter(|x| x.is_finite()))
+                .collect()
+        })
+        .collect())
+}
+
+/// The inverse of the square `matrix` by Gauss-Jordan elimination with
+/// partial pivoting; `None` when it is singular.
+fn invert(mut matrix: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let k = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..k)
+        .map(|i| (0..k).map(|j| f64::from(u8::from(i == j))).collect())
+        .collect();
+    for c in 0..k {
+        let pivot = (c..k).max_by(|&a, &b| matrix[a][c].abs().total_cmp(&matrix[b][c].abs()))?;
+        if matrix[pivot][c].abs() < SINGULAR {
+            return None;
+        }
+        matrix.swap(c, pivot);
+        inverse.swap(c, pivot);
+        let p = matrix[c][c];
+        for j in 0..k {
+            matrix[c][j] /= p;
+            inverse[c][j] /= p;
+        }
+        for r in (0..k).filter(|&r| r != c) {
+            let factor = matrix[r][c];
+            if factor == 0.0 {
+                continue;
+            }
+            for j in 0..k {
+                matrix[r][j] -= factor * matrix[c][j];
+                inverse[r][j] -= factor * inverse[c][j];
+            }
+        }
+    }
+    Some(inverse)
+}
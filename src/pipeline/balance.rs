@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceStrategy {
+    /// Randomly drop rows of larger classes down to the smallest class size.
+    Undersample,
+    /// Keep every row and resample smaller classes, with replacement, up to the
+    /// largest class size.
+    Oversample,
+}
+
+/// Equalizes class sizes of the training target after labels are computed.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BalanceConfig {
+    pub strategy: BalanceStrategy,
+
+    /// Seed for row sampling; runs are not reproducible when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl BalanceConfig {
+    /// Resamples rows by the classes in `labels[column]`, taking the same rows
+    /// from `features` so both frames stay aligned. The result is shuffled so
+    /// classes aren't laid out in blocks.
+    pub fn apply(
+        &self,
+        features: &DataFrame,
+        labels: &DataFrame,
+        column: &str,
+    ) -> Result<(DataFrame, DataFrame), PipelineStepError> {
+        let picked = self.sample(labels, column)?;
+        Ok((features.take(&picked)?, labels.take(&picked)?))
+    }
+
+    /// Row indices [`apply`](Self::apply) takes, for keeping other row-aligned
+    /// data in step.
+    pub fn sample(&self, labels: &DataFrame, column: &str) -> Result<IdxCa, PipelineStepError> {
+        let classes = labels.column(column)?.cast(&DataType::String)?;
+        let mut rows_by_class: BTreeMap<Option<&str>, Vec<IdxSize>> = BTreeMap::new();
+        for (row, class) in classes.str()?.iter().enumerate() {
+            rows_by_class.entry(class).or_default().push(row as IdxSize);
+        }
+
+        let sizes = rows_by_class.values().map(Vec::len);
+        let (Some(smallest), Some(largest)) = (sizes.clone().min(), sizes.max()) else {
+            return Ok(IdxCa::from_vec("rows".into(), Vec::new()));
+        };
+
+        let mut picked: Vec<IdxSize> = Vec::new();
+        for (i, rows) in rows_by_class.into_values().enumerate() {
+            // A distinct seed per class, so equal-sized classes aren't sampled alike.
+            let seed = self.seed.map(|s| s.wrapping_add(i as u64));
+            let rows = IdxCa::from_vec("rows".into(), rows);
+            match self.strategy {
+                BalanceStrategy::Undersample => {
+                    picked.extend(
+                        rows.sample_n(smallest, false, false, seed)?
+                            .into_no_null_iter(),
+                    );
+                }
+                BalanceStrategy::Oversample => {
+                    let extra = rows.sample_n(largest - rows.len(), true, false, seed)?;
+                    picked.extend(rows.into_no_null_iter());
+                    picked.extend(extra.into_no_null_iter());
+                }
+            }
+        }
+
+        let picked = IdxCa::from_vec("rows".into(), picked);
+        Ok(picked.sample_n(picked.len(), false, true, self.seed)?)
+    }
+}
@@ -0,0 +1,89 @@
+//! Match keys for `blocking_key` steps: names and addresses reduced so that
+//! spellings of the same record, e.g. `O'Brien, J.` and `obrien j`, share a
+//! key that approximate joins and duplicate counts can group on.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How a `blocking_key` step writes its key.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyEncoding {
+    /// Lowercased words without punctuation, separated by single spaces.
+    #[default]
+    Normalized,
+    /// The American Soundex code of each normalized word, e.g. `R163` for
+    /// both `Robert` and `Rupert`; words not starting with a letter, such
+    /// as house numbers, are kept as they are.
+    Soundex,
+}
+
+/// The key of a record whose columns hold `values`: the non-null values
+/// joined by spaces, normalized and encoded. `None` when nothing is left.
+pub fn key(values: &[Option<&str>], encoding: KeyEncoding) -> Option<String> {
+    let text: Vec<&str> = values.iter().flatten().copied().collect();
+    let normalized = normalize(&text.join(" "));
+    if normalized.is_empty() {
+        return None;
+    }
+    Some(match encoding {
+        KeyEncoding::Normalized => normalized,
+        KeyEncoding::Soundex => normalized
+            .split(' ')
+            .map(soundex)
+            .collect::<Vec<_>>()
+            .join(" "),
+    })
+}
+
+/// `text` lowercased, without characters other than letters, digits and
+/// whitespace, and with whitespace runs as single spaces.
+fn normalize(text: &str) -> String {
+    let kept: String = text
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect();
+    kept.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The Soundex digit of `c`; `None` for vowels and other characters.
+fn digit(c: char) -> Option<char> {
+    match c {
+        'b' | 'f' | 'p' | 'v' => Some('1'),
+        'c' | 'g' | 'j' | 'k' | 'q' | 's' | 'x' | 'z' => Some('2'),
+        'd' | 't' => Some('3'),
+        'l' => Some('4'),
+        'm' | 'n' => Some('5'),
+        'r' => Some('6'),
+        _ => None,
+    }
+}
+
+/// The Soundex code of a lowercase `word`: its first letter and the digits
+/// of the following consonants, repeats collapsed, padded to four.
+fn soundex(word: &str) -> String {
+    let mut chars = word.chars();
+    let Some(first) = chars.next().filter(char::is_ascii_alphabetic) else {
+        return word.to_string();
+    };
+    let mut code = String::from(first.to_ascii_uppercase());
+    let mut last = digit(first);
+    for c in chars {
+        // `h` and `w` don't separate consonants with the same digit.
+        if c == 'h' || c == 'w' {
+            continue;
+        }
+        let d = digit(c);
+        if let Some(d) = d
+            && Some(d) != last
+        {
+            code.push(d);
+            if code.len() == 4 {
+                break;
+            }
+        }
+        last = d;
+    }
+    format!("{:0<4}", code)
+}
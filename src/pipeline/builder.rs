@@ -0,0 +1,1112 @@
+use std::collections::BTreeMap;
+
+use crate::errors::{ConfigError, PipelineStepError};
+use crate::pipeline::blocking::KeyEncoding;
+use crate::pipeline::calendar::{CalendarOutput, Country};
+use crate::pipeline::email::EmailOutput;
+use crate::pipeline::expect::{Expectation, OnFailure};
+use crate::pipeline::features::{
+    Comparator, FeatureConfig, FeaturePipeline, ListAgg, NullPolicy, RollingAgg, UnknownCategory,
+};
+use crate::pipeline::ip::IpOutput;
+use crate::pipeline::json::JsonField;
+use crate::pipeline::keys::KeysConfig;
+use crate::pipeline::noise::Distribution;
+use crate::pipeline::power::PowerMethod;
+use crate::pipeline::text::TextOperation;
+use crate::pipeline::url::UrlOutput;
+
+/// Names a built pipeline in validation errors, where a loaded one has its path.
+const SOURCE: &str = "<PipelineBuilder>";
+
+/// Builds a [`FeaturePipeline`] in code rather than from a config file, e.g.
+/// `PipelineBuilder::new().mean("Units Sold", ["Country"], "avg_units").build()`.
+/// Steps run in the order they are added. [`build`](Self::build) validates
+/// the pipeline as loading a config file does. Steps needing options without
+/// a builder method can be passed whole with [`step`](Self::step). A step
+/// method given invalid parameters makes `build` fail with that error.
+#[derive(Debug, Default)]
+pub struct PipelineBuilder {
+    dataset: Option<String>,
+    keys: Option<KeysConfig>,
+    steps: Vec<FeatureConfig>,
+    null_policy: NullPolicy,
+    categorical_keys: Option<bool>,
+    description: Option<String>,
+    /// First error from a step method; reported by `build`.
+    error: Option<ConfigError>,
+}
+
+impl PipelineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Entrypoint dataset the pipeline reads; the primary one when not set.
+    pub fn dataset(mut self, name: impl Into<String>) -> Self {
+        self.dataset = Some(name.into());
+        self
+    }
+
+    /// Columns expected to identify a row; see [`KeysConfig`].
+    pub fn keys(mut self, keys: KeysConfig) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    /// Null handling for aggregation steps that don't set their own.
+    pub fn null_policy(mut self, policy: NullPolicy) -> Self {
+        self.null_policy = policy;
+        self
+    }
+
+    /// Whether string `group_by` columns are cast to `Categorical`; on by default.
+    pub fn categorical_keys(mut self, enabled: bool) -> Self {
+        self.categorical_keys = Some(enabled);
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds a fully specified step.
+    pub fn step(mut self, step: FeatureConfig) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Adds `step`, or keeps its error for `build` to report.
+    fn try_step(mut self, step: Result<FeatureConfig, ConfigError>) -> Self {
+        match step {
+            Ok(step) => self.steps.push(step),
+            Err(error) => {
+                self.error.get_or_insert(error);
+            }
+        }
+        self
+    }
+
+    pub fn mean(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::mean(column, group_by, name))
+    }
+
+    /// The mean of `column` over the rest of each row's group, times
+    /// Gaussian `noise` when set.
+    pub fn loo_target_encode(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        noise: Option<f64>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::loo_target_encode(
+            column, group_by, noise, name,
+        ))
+    }
+
+    pub fn sum(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::sum(column, group_by, name))
+    }
+
+    pub fn max(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::max(column, group_by, name))
+    }
+
+    pub fn min(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::min(column, group_by, name))
+    }
+
+    /// Number of rows in the group, nulls included.
+    pub fn count(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::count(column, group_by, name))
+    }
+
+    /// Number of non-null values of `column` in the group.
+    pub fn count_non_null(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::count_non_null(column, group_by, name))
+    }
+
+    /// Percentile rank of `column` within the group, from 0 to 1.
+    pub fn percent_rank(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::percent_rank(column, group_by, name))
+    }
+
+    /// `agg` of `column` over the trailing `window` (e.g. `"7d"`) of
+    /// `time_column`, within the group.
+    pub fn rolling_by_time(
+        self,
+        column: impl Into<String>,
+        time_column: impl Into<String>,
+        window: impl Into<String>,
+        agg: RollingAgg,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::rolling_by_time(
+            column,
+            time_column,
+            window,
+            agg,
+            group_by,
+            name,
+        ))
+    }
+
+    pub fn count_distinct(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::count_distinct(column, group_by, name))
+    }
+
+    pub fn threshold(
+        self,
+        column: impl Into<String>,
+        comparator: Comparator,
+        threshold: f64,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::threshold(
+            column, comparator, threshold, name,
+        ))
+    }
+
+    pub fn ratio(
+        self,
+        numerator: impl Into<String>,
+        denominator: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::ratio(numerator, denominator, name))
+    }
+
+    pub fn list_len(self, column: impl Into<String>, name: impl Into<String>) -> Self {
+        self.try_step(FeatureConfig::list_len(column, name))
+    }
+
+    pub fn list_contains(
+        self,
+        column: impl Into<String>,
+        value: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::list_contains(column, value, name))
+    }
+
+    pub fn list_agg(
+        self,
+        column: impl Into<String>,
+        agg: ListAgg,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::list_agg(column, agg, name))
+    }
+
+    /// Noise from `distribution`, as a feature importance baseline.
+    pub fn random_feature(
+        self,
+        distribution: Distribution,
+        seed: Option<u64>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::random_feature(distribution, seed, name))
+    }
+
+    /// The Mahalanobis distance of each row's `columns` from their mean.
+    pub fn anomaly_score(
+        self,
+        columns: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::anomaly_score(columns, name))
+    }
+
+    /// The numeric `column` through the `method` power transform whose
+    /// lambda makes it most normal-like.
+    pub fn power_transform(
+        self,
+        column: impl Into<String>,
+        method: PowerMethod,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::power_transform(column, method, name))
+    }
+
+    /// The numeric `column` less its median, over its interquartile range,
+    /// within each `group_by` group or over all rows when it is empty.
+    pub fn robust_scale(
+        self,
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::robust_scale(column, group_by, name))
+    }
+
+    /// The numeric `column` mapped through its empirical CDF onto
+    /// `distribution`.
+    pub fn quantile_transform(
+        self,
+        column: impl Into<String>,
+        distribution: Distribution,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::quantile_transform(
+            column,
+            distribution,
+            name,
+        ))
+    }
+
+    /// The weight of evidence of `column`'s bin, of at most `bins`, against
+    /// the binary `target`, whose larger value is the event.
+    pub fn woe_encode(
+        self,
+        column: impl Into<String>,
+        target: impl Into<String>,
+        bins: usize,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::woe_encode(column, target, bins, name))
+    }
+
+    /// `output` of the date in `column` by `country`'s nationwide holidays.
+    pub fn calendar(
+        self,
+        column: impl Into<String>,
+        country: Country,
+        output: CalendarOutput,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::calendar(column, country, output, name))
+    }
+
+    /// `output` of the IP address in `column`; prefixes become the key
+    /// column `name` rather than a feature.
+    pub fn ip_features(
+        self,
+        column: impl Into<String>,
+        output: IpOutput,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::ip_features(column, output, name))
+    }
+
+    /// The `fields` of the JSON documents in `column`, as columns later
+    /// steps read.
+    pub fn json_extract(
+        self,
+        column: impl Into<String>,
+        fields: impl IntoIterator<Item = JsonField>,
+    ) -> Self {
+        self.try_step(FeatureConfig::json_extract(column, fields))
+    }
+
+    /// `output` of the email address in `column`; the domain becomes the
+    /// key column `name` rather than a feature. Free mail is flagged by the
+    /// built-in provider list.
+    /// A match key of the name or address in `columns` as the key column
+    /// `name`, e.g. to count records that differ only in spelling.
+    pub fn blocking_key(
+        self,
+        columns: impl IntoIterator<Item = impl Into<String>>,
+        encoding: KeyEncoding,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::blocking_key(columns, encoding, name))
+    }
+
+    /// The text in `column` cleaned by `operations` as the key column
+    /// `name`, e.g. to one-hot encode `Germany ` and `germany` as one
+    /// category.
+    pub fn clean_text(
+        self,
+        column: impl Into<String>,
+        operations: impl IntoIterator<Item = TextOperation>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::clean_text(column, operations, name))
+    }
+
+    /// The categories of `column` with fewer than `min_count` rows replaced
+    /// by `other`, as the key column `name`.
+    pub fn collapse_rare(
+        self,
+        column: impl Into<String>,
+        min_count: usize,
+        other: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::collapse_rare(column, min_count, other, name))
+    }
+
+    /// The numbers in the string column `column` as the float column `name`,
+    /// e.g. `','`, `Some('.')` and `["€"]` for `1.234,56 €`.
+    pub fn parse_number(
+        self,
+        column: impl Into<String>,
+        decimal: char,
+        thousands: Option<char>,
+        currency: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::parse_number(
+            column, decimal, thousands, currency, name,
+        ))
+    }
+
+    pub fn email_domain(
+        self,
+        column: impl Into<String>,
+        output: EmailOutput,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::email_domain(column, output, name))
+    }
+
+    /// `output` of the URL in `column`; the domain and TLD become the key
+    /// column `name` rather than a feature.
+    pub fn url_parse(
+        self,
+        column: impl Into<String>,
+        output: UrlOutput,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::url_parse(column, output, name))
+    }
+
+    /// Geohash of `latitude` and `longitude` as the key column `name`, for
+    /// later steps to group by or encode.
+    pub fn geohash(
+        self,
+        latitude: impl Into<String>,
+        longitude: impl Into<String>,
+        precision: usize,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::geohash(latitude, longitude, precision, name))
+    }
+
+    /// Maps each value of `column` through `mapping`; missing values become null.
+    pub fn map<K: Into<String>>(
+        self,
+        column: impl Into<String>,
+        mapping: impl IntoIterator<Item = (K, f64)>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.try_step(FeatureConfig::map(column, mapping, name))
+    }
+
+    /// One dummy column per category, nulls included, none dropped.
+    pub fn ohe(self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.try_step(FeatureConfig::ohe(columns))
+    }
+
+    /// One 0/1 column per tag of `column`, split on `separator`, every tag
+    /// kept.
+    pub fn multi_hot(self, column: impl Into<String>, separator: impl Into<String>) -> Self {
+        self.try_step(FeatureConfig::multi_hot(column, separator))
+    }
+
+    /// Assertions on the input that fail the run when they don't hold.
+    pub fn expect(self, checks: impl IntoIterator<Item = Expectation>) -> Self {
+        self.try_step(FeatureConfig::expect(checks))
+    }
+
+    /// The pipeline, validated as a loaded config would be.
+    pub fn build(self) -> Result<FeaturePipeline, PipelineStepError> {
+        if let Some(error) = self.error {
+            return Err(error.into());
+        }
+        let mut pipeline = FeaturePipeline {
+            version: None,
+            dataset: self.dataset,
+            keys: self.keys,
+            steps: self.steps,
+            vars: BTreeMap::new(),
+            lookups: BTreeMap::new(),
+            null_policy: self.null_policy,
+            categorical_keys: self.categorical_keys.unwrap_or(true),
+            description: self.description,
+        };
+        pipeline.finish(SOURCE)?;
+        Ok(pipeline)
+    }
+}
+
+/// Constructors checking parameters up front, so a step built in code fails
+/// here rather than being skipped or failing when the pipeline runs. Optional
+/// fields start unset and can be filled in on the returned value.
+impl FeatureConfig {
+    /// Fails when `group_by` is empty, as for every aggregation.
+    pub fn mean(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Mean {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            null_policy: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `group_by` is empty or `noise` isn't positive.
+    pub fn loo_target_encode(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        noise: Option<f64>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::LooTargetEncode {
+            column: column.into(),
+            group_by: strings(group_by),
+            noise,
+            seed: None,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn sum(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Sum {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            null_policy: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn max(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Max {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            null_policy: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn min(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Min {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            null_policy: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn count(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Count {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn count_non_null(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::CountNonNull {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            null_policy: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn percent_rank(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::PercentRank {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            null_policy: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn rolling_by_time(
+        column: impl Into<String>,
+        time_column: impl Into<String>,
+        window: impl Into<String>,
+        agg: RollingAgg,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::RollingByTime {
+            column: column.into(),
+            time_column: time_column.into(),
+            window: window.into(),
+            agg,
+            group_by: strings(group_by),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn count_distinct(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::CountDistinct {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            null_policy: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `threshold` is NaN or infinite.
+    pub fn threshold(
+        column: impl Into<String>,
+        comparator: Comparator,
+        threshold: f64,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Threshold {
+            column: column.into(),
+            threshold,
+            comparator,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn ratio(
+        numerator: impl Into<String>,
+        denominator: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Ratio {
+            numerator: numerator.into(),
+            denominator: denominator.into(),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn list_len(
+        column: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::ListLen {
+            column: column.into(),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn list_contains(
+        column: impl Into<String>,
+        value: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::ListContains {
+            column: column.into(),
+            value: value.into(),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn list_agg(
+        column: impl Into<String>,
+        agg: ListAgg,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::ListAgg {
+            column: column.into(),
+            agg,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn random_feature(
+        distribution: Distribution,
+        seed: Option<u64>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::RandomFeature {
+            distribution,
+            seed,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `columns` is empty or lists a column twice.
+    pub fn anomaly_score(
+        columns: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::AnomalyScore {
+            columns: columns.into_iter().map(Into::into).collect(),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn power_transform(
+        column: impl Into<String>,
+        method: PowerMethod,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::PowerTransform {
+            column: column.into(),
+            method,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn robust_scale(
+        column: impl Into<String>,
+        group_by: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::RobustScale {
+            column: column.into(),
+            group_by: strings(group_by),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Keeps up to 1000 quantiles, the default of a config step.
+    pub fn quantile_transform(
+        column: impl Into<String>,
+        distribution: Distribution,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::QuantileTransform {
+            column: column.into(),
+            distribution,
+            quantiles: 1000,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `bins` is below 2 or `target` is `column`.
+    pub fn woe_encode(
+        column: impl Into<String>,
+        target: impl Into<String>,
+        bins: usize,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::WoeEncode {
+            column: column.into(),
+            target: target.into(),
+            bins,
+            positive: None,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn calendar(
+        column: impl Into<String>,
+        country: Country,
+        output: CalendarOutput,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Calendar {
+            column: column.into(),
+            country,
+            region: None,
+            output,
+            holidays: Vec::new(),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn ip_features(
+        column: impl Into<String>,
+        output: IpOutput,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::IpFeatures {
+            column: column.into(),
+            output,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `fields` is empty or a path is malformed.
+    pub fn json_extract(
+        column: impl Into<String>,
+        fields: impl IntoIterator<Item = JsonField>,
+    ) -> Result<Self, ConfigError> {
+        Self::JsonExtract {
+            column: column.into(),
+            fields: fields.into_iter().collect(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Collapses by count only, without a `min_fraction`; fails when
+    /// `min_count` is 0.
+    pub fn collapse_rare(
+        column: impl Into<String>,
+        min_count: usize,
+        other: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::CollapseRare {
+            column: column.into(),
+            min_count: Some(min_count),
+            min_fraction: None,
+            other: other.into(),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `operations` is empty.
+    pub fn clean_text(
+        column: impl Into<String>,
+        operations: impl IntoIterator<Item = TextOperation>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::CleanText {
+            column: column.into(),
+            operations: operations.into_iter().collect(),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `columns` is empty.
+    pub fn blocking_key(
+        columns: impl IntoIterator<Item = impl Into<String>>,
+        encoding: KeyEncoding,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::BlockingKey {
+            columns: columns.into_iter().map(Into::into).collect(),
+            encoding,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `decimal` and `thousands` are equal, digits or whitespace,
+    /// or `name` is `column`.
+    pub fn parse_number(
+        column: impl Into<String>,
+        decimal: char,
+        thousands: Option<char>,
+        currency: impl IntoIterator<Item = impl Into<String>>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::ParseNumber {
+            column: column.into(),
+            decimal,
+            thousands,
+            currency: currency.into_iter().map(Into::into).collect(),
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn email_domain(
+        column: impl Into<String>,
+        output: EmailOutput,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::EmailDomain {
+            column: column.into(),
+            output,
+            free_mail: None,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    pub fn url_parse(
+        column: impl Into<String>,
+        output: UrlOutput,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::UrlParse {
+            column: column.into(),
+            output,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `precision` is not between 1 and 12.
+    pub fn geohash(
+        latitude: impl Into<String>,
+        longitude: impl Into<String>,
+        precision: usize,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Geohash {
+            latitude: latitude.into(),
+            longitude: longitude.into(),
+            precision,
+            name: name.into(),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `mapping` is empty.
+    pub fn map<K: Into<String>>(
+        column: impl Into<String>,
+        mapping: impl IntoIterator<Item = (K, f64)>,
+        name: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::Map {
+            column: column.into(),
+            mapping: mapping
+                .into_iter()
+                .map(|(k, v)| (k.into(), v))
+                .collect::<BTreeMap<_, _>>(),
+            default: None,
+            name: name.into(),
+            min_coverage: None,
+            warn_coverage: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `columns` is empty.
+    pub fn ohe(columns: impl IntoIterator<Item = impl Into<String>>) -> Result<Self, ConfigError> {
+        Self::Ohe {
+            columns: strings(columns),
+            drop_first: false,
+            drop_nulls: false,
+            max_categories: None,
+            min_frequency: None,
+            handle_unknown: UnknownCategory::Ignore,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `separator` is empty.
+    pub fn multi_hot(
+        column: impl Into<String>,
+        separator: impl Into<String>,
+    ) -> Result<Self, ConfigError> {
+        Self::MultiHot {
+            column: column.into(),
+            separator: separator.into(),
+            max_tags: None,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Fails when `checks` is empty.
+    pub fn expect(checks: impl IntoIterator<Item = Expectation>) -> Result<Self, ConfigError> {
+        Self::Expect {
+            name: None,
+            checks: checks.into_iter().collect(),
+            on_failure: OnFailure::Fail,
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        }
+        .checked()
+    }
+
+    /// Also rejects what [`lint`](FeaturePipeline::lint) only flags in a
+    /// loaded config: steps that would produce nothing.
+    fn checked(self) -> Result<Self, ConfigError> {
+        let empty = match &self {
+            _ if self.group_by().is_some_and(|g| g.is_empty()) => Some("group_by"),
+            Self::Ohe { columns, .. } if columns.is_empty() => Some("columns"),
+            Self::Expect { checks, .. } if checks.is_empty() => Some("checks"),
+            _ => None,
+        };
+        let invalid = empty
+            .map(|field| format!("'{}' must not be empty", field))
+            .or_else(|| self.invalid());
+        match invalid {
+            Some(error) => Err(ConfigError::InvalidStep {
+                function: self.function(),
+                error,
+            }),
+            None => Ok(self),
+        }
+    }
+}
+
+fn strings(items: impl IntoIterator<Item = impl Into<String>>) -> Vec<String> {
+    items.into_iter().map(Into::into).collect()
+}
@@ -0,0 +1,414 @@
+//! Holiday calendars for `calendar` steps: the public holidays of a country,
+//! or of one of its regions, computed from rules rather than listed, so any
+//! year can be evaluated. One-off holidays, e.g. a royal jubilee, are not
+//! included; steps add them through `holidays`.
+
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Country {
+    /// US federal holidays.
+    US,
+    /// UK bank holidays; regions `ENG`, `WLS`, `SCT` and `NIR`.
+    GB,
+    /// German public holidays; regions are the state codes, e.g. `BY`.
+    DE,
+    /// French public holidays, without the Alsace-Moselle extras.
+    FR,
+}
+
+/// What a `calendar` step computes for each date.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarOutput {
+    IsHoliday,
+    /// Neither a weekend nor a holiday.
+    IsBusinessDay,
+    /// Days until the next holiday, 0 on a holiday.
+    DaysToNextHoliday,
+}
+
+/// How a holiday falling on a weekend is given a weekday off as well; the
+/// date itself stays a holiday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shift {
+    None,
+    /// Saturdays to the Friday before, Sundays to the Monday after.
+    Observed,
+    /// To the next weekday that isn't already a holiday.
+    Substitute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    Fixed {
+        month: u32,
+        day: u32,
+        shift: Shift,
+    },
+    /// The `n`th `weekday` of the month; `-1` is the last.
+    Nth {
+        month: u32,
+        weekday: Weekday,
+        n: i8,
+    },
+    /// Days after Easter Sunday, e.g. `-2` for Good Friday.
+    Easter {
+        offset: i64,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Holiday {
+    pub rule: Rule,
+    /// First year it is kept.
+    pub since: Option<i32>,
+    /// Regions keeping it; every region when empty.
+    regions: &'static [&'static str],
+}
+
+impl Holiday {
+    const fn new(rule: Rule) -> Self {
+        Self {
+            rule,
+            since: None,
+            regions: &[],
+        }
+    }
+
+    const fn since(self, year: i32) -> Self {
+        Self {
+            since: Some(year),
+            ..self
+        }
+    }
+
+    const fn only(self, regions: &'static [&'static str]) -> Self {
+        Self { regions, ..self }
+    }
+
+    /// Its date in `year`, before any shift.
+    fn date(&self, year: i32) -> Option<NaiveDate> {
+        if self.since.is_some_and(|since| year < since) {
+            return None;
+        }
+        match self.rule {
+            Rule::Fixed { month, day, .. } => NaiveDate::from_ymd_opt(year, month, day),
+            Rule::Nth { month, weekday, n } if n > 0 => {
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+            }
+            Rule::Nth { month, weekday, n } => {
+                let next = if month == 12 {
+                    NaiveDate::from_ymd_opt(year + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(year, month + 1, 1)
+                }?;
+                let mut day = next.pred_opt()?;
+                while day.weekday() != weekday {
+                    day = day.pred_opt()?;
+                }
+                day.checked_sub_days(Days::new(7 * u64::from(n.unsigned_abs() - 1)))
+            }
+            Rule::Easter { offset } => {
+                let easter = easter(year)?;
+                if offset < 0 {
+                    easter.checked_sub_days(Days::new(offset.unsigned_abs()))
+                } else {
+                    easter.checked_add_days(Days::new(offset.unsigned_abs()))
+                }
+            }
+        }
+    }
+}
+
+const fn fixed(month: u32, day: u32) -> Holiday {
+    Holiday::new(Rule::Fixed {
+        month,
+        day,
+        shift: Shift::None,
+    })
+}
+
+const fn observed(month: u32, day: u32) -> Holiday {
+    Holiday::new(Rule::Fixed {
+        month,
+        day,
+        shift: Shift::Observed,
+    })
+}
+
+const fn substitute(month: u32, day: u32) -> Holiday {
+    Holiday::new(Rule::Fixed {
+        month,
+        day,
+        shift: Shift::Substitute,
+    })
+}
+
+const fn nth(month: u32, weekday: Weekday, n: i8) -> Holiday {
+    Holiday::new(Rule::Nth { month, weekday, n })
+}
+
+const fn easter_offset(offset: i64) -> Holiday {
+    Holiday::new(Rule::Easter { offset })
+}
+
+const US: &[Holiday] = &[
+    observed(1, 1),
+    nth(1, Weekday::Mon, 3),
+    nth(2, Weekday::Mon, 3),
+    nth(5, Weekday::Mon, -1),
+    observed(6, 19).since(2021),
+    observed(7, 4),
+    nth(9, Weekday::Mon, 1),
+    nth(10, Weekday::Mon, 2),
+    observed(11, 11),
+    nth(11, Weekday::Thu, 4),
+    observed(12, 25),
+];
+
+const GB: &[Holiday] = &[
+    substitute(1, 1),
+    substitute(1, 2).only(&["SCT"]),
+    substitute(3, 17).only(&["NIR"]),
+    easter_offset(-2),
+    easter_offset(1).only(&["ENG", "WLS", "NIR"]),
+    nth(5, Weekday::Mon, 1),
+    nth(5, Weekday::Mon, -1),
+    substitute(7, 12).only(&["NIR"]),
+    nth(8, Weekday::Mon, 1).only(&["SCT"]),
+    nth(8, Weekday::Mon, -1).only(&["ENG", "WLS", "NIR"]),
+    substitute(11, 30).only(&["SCT"]),
+    substitute(12, 25),
+    substitute(12, 26),
+];
+
+const DE: &[Holiday] = &[
+    fixed(1, 1),
+    fixed(1, 6).only(&["BW", "BY", "ST"]),
+    fixed(3, 8).only(&["BE"]).since(2019),
+    easter_offset(-2),
+    easter_offset(1),
+    fixed(5, 1),
+    easter_offset(39),
+    easter_offset(50),
+    easter_offset(60).only(&["BW", "BY", "HE", "NW", "RP", "SL"]),
+    fixed(8, 15).only(&["SL"]),
+    fixed(9, 20).only(&["TH"]).since(2019),
+    fixed(10, 3),
+    fixed(10, 31).only(&["BB", "MV", "SN", "ST", "TH"]),
+    fixed(10, 31).only(&["HB", "HH", "NI", "SH"]).since(2018),
+    fixed(11, 1).only(&["BW", "BY", "NW", "RP", "SL"]),
+    fixed(12, 25),
+    fixed(12, 26),
+];
+
+const FR: &[Holiday] = &[
+    fixed(1, 1),
+    easter_offset(1),
+    fixed(5, 1),
+    fixed(5, 8),
+    easter_offset(39),
+    easter_offset(50),
+    fixed(7, 14),
+    fixed(8, 15),
+    fixed(11, 1),
+    fixed(11, 11),
+    fixed(12, 25),
+];
+
+impl Country {
+    pub fn regions(self) -> &'static [&'static str] {
+        match self {
+            Self::US | Self::FR => &[],
+            Self::GB => &["ENG", "WLS", "SCT", "NIR"],
+            Self::DE => &[
+                "BW", "BY", "BE", "BB", "HB", "HH", "HE", "MV", "NI", "NW", "RP", "SL", "SN", "ST",
+                "SH", "TH",
+            ],
+        }
+    }
+
+    /// The holidays kept in `region`, or in every region when `None`.
+    pub fn holidays(self, region: Option<&str>) -> impl Iterator<Item = &'static Holiday> {
+        let table = match self {
+            Self::US => US,
+            Self::GB => GB,
+            Self::DE => DE,
+            Self::FR => FR,
+        };
+        table.iter().filter(move |holiday| {
+            holiday.regions.is_empty() || region.is_some_and(|r| holiday.regions.contains(&r))
+        })
+    }
+}
+
+/// Easter Sunday of `year` in the Gregorian calendar.
+fn easter(year: i32) -> Option<NaiveDate> {
+    let (a, b, c) = (year % 19, year / 100, year % 100);
+    let (d, e) = (b / 4, b % 4);
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let (i, k) = (c / 4, c % 4);
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let n = h + l - 7 * m + 114;
+    NaiveDate::from_ymd_opt(year, (n / 31) as u32, (n % 31 + 1) as u32)
+}
+
+fn is_weekend(day: NaiveDate) -> bool {
+    matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// The holidays of a country or region over a range of years.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    days: BTreeSet<NaiveDate>,
+}
+
+impl Calendar {
+    /// Holidays in `years`, plus `extra` ones. Weekdays given off for a
+    /// weekend holiday may fall in the year before, e.g. a Saturday New
+    /// Year's Day observed on December 31.
+    pub fn new(
+        country: Country,
+        region: Option<&str>,
+        extra: &[NaiveDate],
+        years: RangeInclusive<i32>,
+    ) -> Self {
+        let mut days: BTreeSet<NaiveDate> = extra.iter().copied().collect();
+        for year in years {
+            let dates: Vec<(NaiveDate, Shift)> = country
+                .holidays(region)
+                .filter_map(|holiday| {
+                    let shift = match holiday.rule {
+                        Rule::Fixed { shift, .. } => shift,
+                        _ => Shift::None,
+                    };
+                    Some((holiday.date(year)?, shift))
+                })
+                .collect();
+            days.extend(dates.iter().map(|(day, _)| *day));
+            // Substitutes skip the year's other holidays, so a Christmas on
+            // Sunday moves past a Boxing Day on Monday.
+            for (day, shift) in dates {
+                if !is_weekend(day) {
+                    continue;
+                }
+                let moved = match (shift, day.weekday()) {
+                    (Shift::None, _) => continue,
+                    (Shift::Observed, Weekday::Sat) => day.pred_opt(),
+                    (Shift::Observed, _) => day.succ_opt(),
+                    (Shift::Substitute, _) => {
+                        let mut next = day.succ_opt();
+                        while let Some(candidate) = next
+                            && (is_weekend(candidate) || days.contains(&candidate))
+                        {
+                            next = candidate.succ_opt();
+                        }
+                        next
+                    }
+                };
+                days.extend(moved);
+            }
+        }
+        Self { days }
+    }
+
+    /// The calendar covering `dates`, and the year after them for
+    /// [`CalendarOutput::DaysToNextHoliday`].
+    pub fn covering(
+        country: Country,
+        region: Option<&str>,
+        extra: &[NaiveDate],
+        dates: &[Option<NaiveDate>],
+    ) -> Self {
+        let years = dates.iter().flatten().map(|day| day.year());
+        let (Some(first), Some(last)) = (years.clone().min(), years.max()) else {
+            return Self {
+                days: extra.iter().copied().collect(),
+            };
+        };
+        Self::new(country, region, extra, first..=last + 1)
+    }
+
+    pub fn is_holiday(&self, day: NaiveDate) -> bool {
+        self.days.contains(&day)
+    }
+
+    pub fn is_business_day(&self, day: NaiveDate) -> bool {
+        !is_weekend(day) && !self.is_holiday(day)
+    }
+
+    /// `None` past the calendar's last holiday.
+    pub fn days_to_next_holiday(&self, day: NaiveDate) -> Option<u32> {
+        let next = self.days.range(day..).next()?;
+        u32::try_from((*next - day).num_days()).ok()
+    }
+
+    /// The `output` column for `dates`, null where the date is null.
+    pub fn column(
+        &self,
+        name: &str,
+        output: CalendarOutput,
+        dates: &[Option<NaiveDate>],
+    ) -> Column {
+        match output {
+            CalendarOutput::IsHoliday => dates
+                .iter()
+                .map(|day| day.map(|d| self.is_holiday(d)))
+                .collect::<BooleanChunked>()
+                .with_name(name.into())
+                .into_column(),
+            CalendarOutput::IsBusinessDay => dates
+                .iter()
+                .map(|day| day.map(|d| self.is_business_day(d)))
+                .collect::<BooleanChunked>()
+                .with_name(name.into())
+                .into_column(),
+            CalendarOutput::DaysToNextHoliday => dates
+                .iter()
+                .map(|day| day.and_then(|d| self.days_to_next_holiday(d)))
+                .collect::<UInt32Chunked>()
+                .with_name(name.into())
+                .into_column(),
+        }
+    }
+}
+
+/// The days of a date, datetime or ISO 8601 string column.
+pub fn dates(column: &Column) -> Result<Vec<Option<NaiveDate>>, PipelineStepError> {
+    let name = column.name().clone();
+    let day = match column.dtype() {
+        DataType::String => col(name.clone()).str().to_datetime(
+            Some(TimeUnit::Milliseconds),
+            None,
+            StrptimeOptions::default(),
+            lit("raise"),
+        ),
+        _ => col(name.clone()),
+    }
+    .cast(DataType::Date);
+    let frame = DataFrame::new(vec![column.clone()])?
+        .lazy()
+        .select([day])
+        .collect()?;
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default();
+    Ok(frame
+        .column(name.as_str())?
+        .date()?
+        .physical()
+        .iter()
+        .map(|days| days.and_then(|d| epoch.checked_add_signed(chrono::Duration::days(d.into()))))
+        .collect())
+}
@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::leakage::pearson;
+use crate::profiling::escape;
+
+fn default_redundant_above() -> f64 {
+    0.95
+}
+
+/// Pairwise Pearson correlations between numeric features, written as
+/// `correlation.csv` and a `correlation.html` heatmap.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CorrelationConfig {
+    /// Adds the training target as the last row and column.
+    #[serde(default)]
+    pub include_label: bool,
+
+    /// Feature pairs whose absolute correlation reaches this value are reported
+    /// as redundant.
+    #[serde(default = "default_redundant_above")]
+    pub redundant_above: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CorrelationMatrix {
+    pub columns: Vec<String>,
+    /// `values[i][j]`; `None` when either column is constant over the shared rows.
+    pub values: Vec<Vec<Option<f64>>>,
+}
+
+impl CorrelationConfig {
+    /// Correlates the numeric columns of `features`, plus `label` when
+    /// `include_label` is set.
+    pub fn compute(
+        &self,
+        features: &DataFrame,
+        label: Option<&Column>,
+    ) -> Result<CorrelationMatrix, PipelineStepError> {
+        let mut columns: Vec<&Column> = features
+            .get_columns()
+            .iter()
+            .filter(|c| c.dtype().is_primitive_numeric() || c.dtype().is_bool())
+            .collect();
+        if self.include_label
+            && let Some(label) = label
+        {
+            columns.push(label);
+        }
+
+        let values: Vec<Float64Chunked> = columns
+            .iter()
+            .map(|c| Ok(c.cast(&DataType::Float64)?.f64()?.clone()))
+            .collect::<Result<_, PipelineStepError>>()?;
+
+        let mut matrix = vec![vec![None; values.len()]; values.len()];
+        for i in 0..values.len() {
+            for j in i..values.len() {
+                let pairs: Vec<(f64, f64)> = values[i]
+                    .iter()
+                    .zip(values[j].iter())
+                    .filter_map(|(x, y)| Some((x?, y?)))
+                    .collect();
+                let r = pearson(&pairs);
+                matrix[i][j] = r;
+                matrix[j][i] = r;
+            }
+        }
+
+        Ok(CorrelationMatrix {
+            columns: columns.iter().map(|c| c.name().to_string()).collect(),
+            values: matrix,
+        })
+    }
+}
+
+impl CorrelationMatrix {
+    /// Pairs of distinct columns with `|r| >= threshold`, strongest first.
+    pub fn redundant_pairs(&self, threshold: f64) -> Vec<(&str, &str, f64)> {
+        let mut pairs = Vec::new();
+        for i in 0..self.columns.len() {
+            for j in i + 1..self.columns.len() {
+                if let Some(r) = self.values[i][j]
+                    && r.abs() >= threshold
+                {
+                    pairs.push((self.columns[i].as_str(), self.columns[j].as_str(), r));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.2.abs().total_cmp(&a.2.abs()));
+        pairs
+    }
+
+    /// The matrix as a frame with a leading `feature` column naming each row.
+    pub fn to_dataframe(&self) -> Result<DataFrame, PipelineStepError> {
+        let mut columns = vec![Column::new("feature".into(), &self.columns)];
+        for (j, name) in self.columns.iter().enumerate() {
+            let values: Vec<Option<f64>> = self.values.iter().map(|row| row[j]).collect();
+            columns.push(Column::new(name.into(), values));
+        }
+        Ok(DataFrame::new(columns)?)
+    }
+
+    /// Self-contained HTML heatmap; red for positive, blue for negative.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Feature correlation</title>\n<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; }\n\
+             th, td { border: 1px solid #ddd; padding: 4px; font-size: 12px; text-align: center; }\n\
+             th.col { writing-mode: vertical-rl; transform: rotate(180deg); }\n\
+             </style>\n</head>\n<body>\n<h1>Feature correlation</h1>\n<table>\n<tr><th></th>",
+        );
+        for name in &self.columns {
+            let _ = write!(html, "<th class=\"col\">{}</th>", escape(name));
+        }
+        html.push_str("</tr>\n");
+
+        for (i, name) in self.columns.iter().enumerate() {
+            let _ = write!(html, "<tr><th>{}</th>", escape(name));
+            for value in &self.values[i] {
+                match value {
+                    Some(r) => {
+                        let shade = (255.0 * (1.0 - r.abs())) as u8;
+                        let color = if *r >= 0.0 {
+                            format!("255,{},{}", shade, shade)
+                        } else {
+                            format!("{},{},255", shade, shade)
+                        };
+                        let _ = write!(
+                            html,
+                            "<td style=\"background: rgb({})\">{:.2}</td>",
+                            color, r
+                        );
+                    }
+                    None => html.push_str("<td>-</td>"),
+                }
+            }
+            html.push_str("</tr>\n");
+        }
+
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
@@ -0,0 +1,64 @@
+//! Email domain extraction for `email_domain` steps.
+
+use std::collections::BTreeSet;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Free-mail providers flagged when a step lists none of its own.
+pub const FREE_MAIL: &[&str] = &[
+    "aol.com",
+    "gmail.com",
+    "gmx.com",
+    "gmx.de",
+    "googlemail.com",
+    "hotmail.com",
+    "icloud.com",
+    "live.com",
+    "mac.com",
+    "mail.com",
+    "mail.ru",
+    "me.com",
+    "msn.com",
+    "outlook.com",
+    "proton.me",
+    "protonmail.com",
+    "web.de",
+    "yahoo.com",
+    "yandex.ru",
+    "zoho.com",
+];
+
+/// What an `email_domain` step computes for each address.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailOutput {
+    /// The lowercased domain, as a key column.
+    Domain,
+    /// Whether the domain is a free-mail provider's.
+    IsFreeMail,
+}
+
+/// The domain of the address in `text`: what follows its last `@`,
+/// lowercased and without a trailing dot. `None` when there is no `@`,
+/// nothing before it, or the domain is empty or holds whitespace.
+pub fn domain(text: &str) -> Option<String> {
+    let (local, domain) = text.trim().rsplit_once('@')?;
+    let domain = domain.trim_end_matches('.');
+    if local.is_empty() || domain.is_empty() || domain.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(domain.to_lowercase())
+}
+
+/// The free-mail domains a step flags, normalized like [`domain`];
+/// [`FREE_MAIL`] when it lists none.
+pub fn free_mail(providers: Option<&[String]>) -> BTreeSet<String> {
+    match providers {
+        Some(providers) => providers
+            .iter()
+            .map(|d| d.trim().trim_end_matches('.').to_lowercase())
+            .collect(),
+        None => FREE_MAIL.iter().map(|d| d.to_string()).collect(),
+    }
+}
@@ -0,0 +1,191 @@
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::features::{FeatureConfig, FeaturePipeline};
+
+/// What a failed `expect` step does to the run.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OnFailure {
+    #[default]
+    Fail,
+    Warn,
+}
+
+/// One assertion about the input frame.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Expectation {
+    /// Share of nulls in `column` is at most `max`.
+    NullRate { column: String, max: f64 },
+    /// No two rows share the same values in `columns`.
+    Unique { columns: Vec<String> },
+    /// Smallest value of `column` is at least `at_least`.
+    Min { column: String, at_least: f64 },
+    /// Largest value of `column` is at most `at_most`.
+    Max { column: String, at_most: f64 },
+    /// Every non-null value of `column` is one of `values`, compared as strings.
+    ValuesIn { column: String, values: Vec<String> },
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ExpectationResult {
+    /// 1-based position of the `expect` step in the config.
+    pub step: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub expectation: String,
+    pub passed: bool,
+    pub observed: String,
+    pub on_failure: OnFailure,
+}
+
+impl Expectation {
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            Self::NullRate { column, .. }
+            | Self::Min { column, .. }
+            | Self::Max { column, .. }
+            | Self::ValuesIn { column, .. } => vec![column],
+            Self::Unique { columns } => columns.iter().map(|c| c.as_str()).collect(),
+        }
+    }
+
+    /// Evaluates against `data`, returning whether it holds and what was seen.
+    pub fn evaluate(&self, data: &DataFrame) -> Result<(bool, String), PipelineStepError> {
+        for column in self.columns() {
+            if !data.get_column_names().iter().any(|c| *c == column) {
+                return Err(PipelineStepError::ColumnNotFound {
+                    found: column.to_string(),
+                    available: data
+                        .get_column_names()
+                        .iter()
+                        .map(|s| s.to_string())
+                        .collect(),
+                });
+            }
+        }
+
+        match self {
+            Self::NullRate { column, max } => {
+                let values = data.column(column)?;
+                let rate = if values.is_empty() {
+                    0.0
+                } else {
+                    values.null_count() as f64 / values.len() as f64
+                };
+                Ok((rate <= *max, format!("{:.4}", rate)))
+            }
+            Self::Unique { columns } => {
+                let duplicated = data.select(columns)?.is_duplicated()?.sum().unwrap_or(0);
+                Ok((duplicated == 0, format!("{} duplicated rows", duplicated)))
+            }
+            Self::Min { column, at_least } => {
+                let min = data.column(column)?.cast(&DataType::Float64)?.f64()?.min();
+                Ok((
+                    min.is_none_or(|m| m >= *at_least),
+                    min.map_or("none".to_string(), |m| m.to_string()),
+                ))
+            }
+            Self::Max { column, at_most } => {
+                let max = data.column(column)?.cast(&DataType::Float64)?.f64()?.max();
+                Ok((
+                    max.is_none_or(|m| m <= *at_most),
+                    max.map_or("none".to_string(), |m| m.to_string()),
+                ))
+            }
+            Self::ValuesIn { column, values } => {
+                let unique = data.column(column)?.unique()?.cast(&DataType::String)?;
+                let unexpected: Vec<&str> = unique
+                    .str()?
+                    .into_iter()
+                    .flatten()
+                    .filter(|v| !values.iter().any(|allowed| allowed == v))
+                    .collect();
+                let observed = if unexpected.is_empty() {
+                    "all values allowed".to_string()
+                } else {
+                    format!("unexpected values {:?}", unexpected)
+                };
+                Ok((unexpected.is_empty(), observed))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Expectation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NullRate { column, max } => write!(f, "null_rate({}) <= {}", column, max),
+            Self::Unique { columns } => write!(f, "unique({})", columns.join(", ")),
+            Self::Min { column, at_least } => write!(f, "min({}) >= {}", column, at_least),
+            Self::Max { column, at_most } => write!(f, "max({}) <= {}", column, at_most),
+            Self::ValuesIn { column, values } => {
+                write!(f, "values_in({}, {:?})", column, values)
+            }
+        }
+    }
+}
+
+/// Evaluates every expectation of an `expect` step at position `step`.
+pub fn evaluate(
+    step: usize,
+    name: Option<&str>,
+    checks: &[Expectation],
+    on_failure: OnFailure,
+    data: &DataFrame,
+) -> Result<Vec<ExpectationResult>, PipelineStepError> {
+    checks
+        .iter()
+        .map(|check| {
+            let (passed, observed) = check.evaluate(data)?;
+            Ok(ExpectationResult {
+                step,
+                name: name.map(str::to_string),
+                expectation: check.to_string(),
+                passed,
+                observed,
+                on_failure,
+            })
+        })
+        .collect()
+}
+
+/// Fails with every unmet expectation listed when any of `results` failed.
+pub fn enforce(results: &[ExpectationResult]) -> Result<(), PipelineStepError> {
+    let failures: Vec<String> = results
+        .iter()
+        .filter(|r| !r.passed && r.on_failure == OnFailure::Fail)
+        .map(|r| format!("{} (observed {})", r.expectation, r.observed))
+        .collect();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(PipelineStepError::ExpectationFailed { failures })
+    }
+}
+
+impl FeaturePipeline {
+    /// Results of all `expect` steps against `data`, for the run report.
+    /// Failures don't error here; see [`enforce`].
+    pub fn expectations(
+        &self,
+        data: &DataFrame,
+    ) -> Result<Vec<ExpectationResult>, PipelineStepError> {
+        let mut results = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if let FeatureConfig::Expect {
+                name,
+                checks,
+                on_failure,
+                ..
+            } = step
+            {
+                results.extend(evaluate(i + 1, name.as_deref(), checks, *on_failure, data)?);
+            }
+        }
+        Ok(results)
+    }
+}
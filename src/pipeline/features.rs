@@ -1,20 +1,82 @@
-use std::{fs::File, io::BufReader, thread};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    thread,
+    time::Instant,
+};
 
 use polars::prelude::*;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_yaml::from_reader;
+use tracing::{Span, info, info_span};
 
-use crate::errors::PipelineStepError;
+use crate::config::format::{self, ConfigFormat};
+use crate::config::{lookups, migrate, overlay, template, vars};
+use crate::errors::{ConfigError, PipelineStepError};
+use crate::pipeline::anomaly::Mahalanobis;
+use crate::pipeline::blocking::{self, KeyEncoding};
+use crate::pipeline::calendar::{self, Calendar, CalendarOutput, Country};
+use crate::pipeline::email::{self, EmailOutput};
+use crate::pipeline::expect::{self, Expectation, OnFailure};
+use crate::pipeline::geohash;
+use crate::pipeline::ip::{self, IpOutput};
+use crate::pipeline::joins::{self, JoinReport};
+use crate::pipeline::json::{self, JsonField, JsonType};
+use crate::pipeline::keys::KeysConfig;
+use crate::pipeline::multi_hot;
+use crate::pipeline::noise::{self, Distribution};
+use crate::pipeline::number;
+use crate::pipeline::power::{PowerFit, PowerMethod};
+use crate::pipeline::quantile::QuantileMap;
+use crate::pipeline::rare::KeptCategories;
+use crate::pipeline::stats::StepStats;
+use crate::pipeline::text::{self, TextOperation};
+use crate::pipeline::url::{self, UrlOutput};
+use crate::pipeline::woe::WoeTable;
+use crate::profiling;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(tag = "function")]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(tag = "function", deny_unknown_fields)]
 pub enum FeatureConfig {
     #[serde(rename = "mean")]
     Mean {
         column: String,
         group_by: Vec<String>,
         name: String,
+        /// Overrides the pipeline's `null_policy` for this step.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        null_policy: Option<NullPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The mean of the numeric `column`, typically the label, over the
+    /// other rows of the row's `group_by` group: leave-one-out target
+    /// encoding, which unlike `mean` doesn't see the row's own target. Rows
+    /// alone in their group get the mean over all rows.
+    #[serde(rename = "loo_target_encode")]
+    LooTargetEncode {
+        column: String,
+        group_by: Vec<String>,
+        /// Standard deviation of the Gaussian factor `1 + noise * z` each
+        /// encoding is multiplied by, e.g. 0.05; off when omitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        noise: Option<f64>,
+        /// Seeds the noise; the entrypoint's `seed` when omitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
 
     #[serde(rename = "sum")]
@@ -22,6 +84,15 @@ pub enum FeatureConfig {
         column: String,
         group_by: Vec<String>,
         name: String,
+        /// Overrides the pipeline's `null_policy` for this step.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        null_policy: Option<NullPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
 
     #[serde(rename = "max")]
@@ -29,6 +100,15 @@ pub enum FeatureConfig {
         column: String,
         group_by: Vec<String>,
         name: String,
+        /// Overrides the pipeline's `null_policy` for this step.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        null_policy: Option<NullPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
 
     #[serde(rename = "min")]
@@ -36,14 +116,29 @@ pub enum FeatureConfig {
         column: String,
         group_by: Vec<String>,
         name: String,
+        /// Overrides the pipeline's `null_policy` for this step.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        null_policy: Option<NullPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
 
     #[serde(rename = "threshold")]
     Threshold {
         column: String,
         threshold: f64,
-        comparator: String,
+        comparator: Comparator,
         name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
 
     #[serde(rename = "ratio")]
@@ -51,6 +146,12 @@ pub enum FeatureConfig {
         numerator: String,
         denominator: String,
         name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
 
     #[serde(rename = "count_distinct")]
@@ -58,13 +159,489 @@ pub enum FeatureConfig {
         column: String,
         group_by: Vec<String>,
         name: String,
+        /// Overrides the pipeline's `null_policy` for this step.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        null_policy: Option<NullPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
 
+    /// Number of rows in the group, nulls included.
     #[serde(rename = "count")]
     Count {
         column: String,
         group_by: Vec<String>,
         name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Number of non-null values of `column` in the group.
+    #[serde(rename = "count_non_null")]
+    CountNonNull {
+        column: String,
+        group_by: Vec<String>,
+        name: String,
+        /// Overrides the pipeline's `null_policy` for this step.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        null_policy: Option<NullPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Percentile rank of the row's `column` value within its group, from 0
+    /// for the group's smallest value to 1 for its largest: the share of the
+    /// group's other values below it, ties sharing the lowest rank. Rows where
+    /// `column` is null get null.
+    #[serde(rename = "percent_rank")]
+    PercentRank {
+        column: String,
+        group_by: Vec<String>,
+        name: String,
+        /// Overrides the pipeline's `null_policy` for this step.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        null_policy: Option<NullPolicy>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// `agg` of `column` over a trailing time `window` per group, e.g. a
+    /// customer's spend in the last 7 days: each row sees its group's rows
+    /// whose `time_column` lies in `(time - window, time]`, its own included.
+    #[serde(rename = "rolling_by_time")]
+    RollingByTime {
+        column: String,
+        /// Date or datetime column, or ISO 8601 strings; must not be null.
+        time_column: String,
+        /// Polars duration, e.g. `7d`, `12h` or `1mo`.
+        window: String,
+        agg: RollingAgg,
+        group_by: Vec<String>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Holiday flag, business-day flag or days to the next holiday of the
+    /// date in `column`, by a country's public holiday calendar.
+    #[serde(rename = "calendar")]
+    Calendar {
+        /// Date or datetime column, or ISO 8601 strings.
+        column: String,
+        country: Country,
+        /// Region whose own holidays count too, e.g. `BY` for Bavaria; only
+        /// nationwide holidays when omitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        region: Option<String>,
+        output: CalendarOutput,
+        /// Extra holidays as `YYYY-MM-DD`, e.g. company closing days.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        holidays: Vec<String>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Geohash of the `latitude` and `longitude` columns, written to the
+    /// column `name` rather than as a feature: a string key later steps can
+    /// `group_by` or one-hot encode. Key columns are added before any step
+    /// runs. Rows with a null or out-of-range coordinate get null.
+    #[serde(rename = "geohash")]
+    Geohash {
+        latitude: String,
+        longitude: String,
+        /// Characters of the hash, from 1 (about 5000km) to 12; 6 (about
+        /// 1.2km by 0.6km) when omitted.
+        #[serde(default = "default_geohash_precision")]
+        precision: usize,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// A flag or network prefix of the IP address in `column`. Flags are
+    /// features; a prefix is written to the column `name`, like a `geohash`,
+    /// as a key later steps can `group_by` or one-hot encode. Values that
+    /// aren't addresses get null.
+    #[serde(rename = "ip_features")]
+    IpFeatures {
+        /// IPv4 or IPv6 addresses as text.
+        column: String,
+        output: IpOutput,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// A part or count of the URL in `column`. Counts are features; the
+    /// domain and TLD are written to the column `name`, like an
+    /// `ip_features` prefix. Values without a host get null.
+    #[serde(rename = "url_parse")]
+    UrlParse {
+        column: String,
+        output: UrlOutput,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The domain of the email address in `column`, written to the column
+    /// `name` as a key like a `url_parse` domain, or whether it is a
+    /// free-mail provider's as a feature. Values that aren't addresses get
+    /// null.
+    #[serde(rename = "email_domain")]
+    EmailDomain {
+        column: String,
+        output: EmailOutput,
+        /// Free-mail domains for `is_free_mail`; a built-in list of common
+        /// providers when omitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        free_mail: Option<Vec<String>>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Values at JSON paths of the documents in `column`, each written to the
+    /// column its field names, typed, for later steps to read like input
+    /// columns. Documents that don't parse give null.
+    #[serde(rename = "json_extract")]
+    JsonExtract {
+        column: String,
+        fields: Vec<JsonField>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// A match key of the name or address in `columns`, written to the
+    /// column `name` like a `geohash`, so records spelled differently can
+    /// be grouped or looked up together. Rows whose columns are all null or
+    /// punctuation get null.
+    #[serde(rename = "blocking_key")]
+    BlockingKey {
+        /// Joined by spaces in this order, nulls skipped.
+        columns: Vec<String>,
+        #[serde(default)]
+        encoding: KeyEncoding,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The numbers written in the string column `column`, e.g. `1.234,56 €`,
+    /// as floats in the column `name` for later steps to read like input
+    /// columns. Values that don't parse give null; numeric columns are
+    /// only cast.
+    #[serde(rename = "parse_number")]
+    ParseNumber {
+        column: String,
+        /// Decimal separator; `.` when omitted.
+        #[serde(default = "default_decimal")]
+        decimal: char,
+        /// Thousands separator removed before parsing, e.g. `.` or `'`;
+        /// whitespace is always removed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thousands: Option<char>,
+        /// Currency symbols or codes removed before parsing, e.g. `[€, EUR]`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        currency: Vec<String>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The text in `column` cleaned by `operations`, in order, and written
+    /// to the column `name` like a `geohash`, so spellings such as
+    /// `Germany ` and `germany` are one category for later steps to
+    /// `group_by` or one-hot encode. Values cleaned to nothing get null.
+    #[serde(rename = "clean_text")]
+    CleanText {
+        column: String,
+        operations: Vec<TextOperation>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The categories of `column` with fewer rows than `min_count` or a
+    /// smaller share than `min_fraction` replaced by `other`, written to the
+    /// column `name` like a `geohash`, so later `ohe` and `group_by` steps
+    /// see a bounded set of categories. The kept categories are fitted on
+    /// the data; nulls stay null.
+    #[serde(rename = "collapse_rare")]
+    CollapseRare {
+        column: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_count: Option<usize>,
+        /// Share of all rows, nulls included, e.g. 0.01.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_fraction: Option<f64>,
+        #[serde(default = "default_other")]
+        other: String,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Elements in each list of the list column `column`, nulls included.
+    #[serde(rename = "list_len")]
+    ListLen {
+        column: String,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Whether each list of the list column `column` holds `value`, elements
+    /// compared as strings like `map` keys.
+    #[serde(rename = "list_contains")]
+    ListContains {
+        column: String,
+        value: String,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The mean or sum of each list of the numeric list column `column`;
+    /// null elements are skipped, and an empty list sums to 0.
+    #[serde(rename = "list_agg")]
+    ListAgg {
+        column: String,
+        agg: ListAgg,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// How unusual each row's `columns` are together: the Mahalanobis
+    /// distance from their mean, fitted on the data's rows where all are
+    /// non-null. Rows with a null column get null.
+    #[serde(rename = "anomaly_score")]
+    AnomalyScore {
+        /// Numeric columns, at least one.
+        columns: Vec<String>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The weight of evidence of `column`'s bin against the binary `target`,
+    /// fitted on the data's rows with a target: `ln` of the bin's share of
+    /// non-events over its share of events, so riskier bins are negative.
+    /// Numeric columns are cut into equal-frequency bins, other columns
+    /// binned by value; bins the fit didn't see get null.
+    #[serde(rename = "woe_encode")]
+    WoeEncode {
+        column: String,
+        /// A column of the data holding the label, with two values.
+        target: String,
+        /// Equal-frequency bins of a numeric `column`, at most.
+        #[serde(default = "default_woe_bins")]
+        bins: usize,
+        /// The `target` value counted as an event, compared as text; by
+        /// default the larger of a numeric or boolean target's two values.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        positive: Option<String>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The numeric `column` mapped through its empirical CDF, fitted on the
+    /// data, onto a uniform on [0, 1] or a standard normal (rank-gauss):
+    /// skewed columns and outliers become evenly spread. Nulls stay null.
+    #[serde(rename = "quantile_transform")]
+    QuantileTransform {
+        column: String,
+        distribution: Distribution,
+        /// Quantiles of the fit data kept as the mapping, at most.
+        #[serde(default = "default_quantiles")]
+        quantiles: usize,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The numeric `column` through a Box-Cox or Yeo-Johnson power
+    /// transform, its lambda fitted on the data by maximum likelihood: skewed
+    /// columns come out closer to normal while keeping their order and
+    /// relative distances. Nulls stay null.
+    #[serde(rename = "power_transform")]
+    PowerTransform {
+        column: String,
+        #[serde(default)]
+        method: PowerMethod,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// The numeric `column` centered on its median and divided by its
+    /// interquartile range, over all rows or per `group_by` group: a scaling
+    /// that, unlike the mean and standard deviation, a few extreme values
+    /// don't move. A constant IQR of 0 leaves the values unscaled.
+    #[serde(rename = "robust_scale")]
+    RobustScale {
+        column: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        group_by: Vec<String>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Noise that reads no column, as a baseline for feature importances:
+    /// a feature the model ranks no higher than this one carries no signal.
+    #[serde(rename = "random_feature")]
+    RandomFeature {
+        distribution: Distribution,
+        /// The entrypoint's `seed` when omitted; values differ between runs
+        /// when neither is set.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Maps each value of `column` through a lookup table, e.g. country to
+    /// region code. Values missing from the table become `default`, or null.
+    #[serde(rename = "map")]
+    Map {
+        column: String,
+        /// Inline table; a step may instead name one from the pipeline's
+        /// `lookups` with `lookup: <name>`.
+        mapping: BTreeMap<String, f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<f64>,
+        name: String,
+        /// Fails the step when fewer rows than this share find their value in
+        /// the table.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_coverage: Option<f64>,
+        /// Reports the step as a warning below this share.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        warn_coverage: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+
+    /// Data assertions on the input frame; produces no feature column.
+    #[serde(rename = "expect")]
+    Expect {
+        /// Label for the expectations report.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        checks: Vec<Expectation>,
+        #[serde(default)]
+        on_failure: OnFailure,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
 
     #[serde(rename = "ohe")]
@@ -72,37 +649,825 @@ pub enum FeatureConfig {
         columns: Vec<String>,
         drop_first: bool,
         drop_nulls: bool,
+        /// Dummies for at most this many of each column's most frequent
+        /// categories, ties broken by value; the rest fold into the column's
+        /// `other` dummy.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_categories: Option<usize>,
+        /// Categories on a smaller share of rows than this, nulls included,
+        /// e.g. 0.01, fold into the column's `other` dummy.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_frequency: Option<f64>,
+        /// What a fitted pipeline does with categories absent from the fit
+        /// data.
+        #[serde(default)]
+        handle_unknown: UnknownCategory,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
     },
+
+    /// One 0/1 column per tag of a delimited string column, e.g. `a|c`,
+    /// named like `ohe` dummies.
+    #[serde(rename = "multi_hot")]
+    MultiHot {
+        column: String,
+        /// Between tags; `|` by default.
+        #[serde(default = "default_tag_separator")]
+        separator: String,
+        /// Columns for at most this many of the tags on the most rows, ties
+        /// broken by tag.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_tags: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<String>,
+    },
+}
+
+/// Aggregation of a `rolling_by_time` window; nulls are skipped.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RollingAgg {
+    Sum,
+    Mean,
+    /// Non-null values in the window.
+    Count,
+}
+
+/// Aggregation of a `list_agg` step's lists.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListAgg {
+    Mean,
+    Sum,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparator {
+    Gt,
+    Lt,
+}
+
+/// What a fitted `ohe` step does with a category absent from the fit data.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownCategory {
+    /// All of the column's dummies 0, or its `other` dummy when
+    /// `max_categories` or `min_frequency` fold categories into it.
+    #[default]
+    Ignore,
+    /// The column's `other` dummy, which is then always added.
+    Other,
+    /// Fail the batch.
+    Error,
+}
+
+/// How aggregations treat nulls in the aggregated column.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NullPolicy {
+    /// Aggregate the non-null values only.
+    #[default]
+    Ignore,
+    /// Null result for any group containing a null.
+    Propagate,
+    /// Fail the step if the column has any null.
+    Error,
+}
+
+impl NullPolicy {
+    /// Windows `agg`, an aggregation of `column`, over `group_by` under this policy.
+    fn over(
+        &self,
+        data: &DataFrame,
+        step: &str,
+        column: &str,
+        agg: Expr,
+        group_by: Vec<Expr>,
+    ) -> Result<Expr, PipelineStepError> {
+        match self {
+            Self::Ignore => Ok(agg.over(group_by)),
+            Self::Propagate => {
+                let has_nulls = col(column).null_count().over(group_by.clone()).gt(lit(0));
+                Ok(when(has_nulls)
+                    .then(lit(NULL))
+                    .otherwise(agg.over(group_by)))
+            }
+            Self::Error => {
+                let nulls = data.column(column)?.null_count();
+                if nulls > 0 {
+                    return Err(polars_err!(
+                        ComputeError: "step '{}': column '{}' has {} null(s) and null_policy is 'error'",
+                        step, column, nulls
+                    )
+                    .into());
+                }
+                Ok(agg.over(group_by))
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStrategy {
+    Sequential,
+    Rayon,
+    Threads,
+    Lazy,
+    Streaming,
+}
+
+impl ExecutionStrategy {
+    pub const ALL: [ExecutionStrategy; 5] = [
+        Self::Sequential,
+        Self::Rayon,
+        Self::Threads,
+        Self::Lazy,
+        Self::Streaming,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sequential => "sequential",
+            Self::Rayon => "rayon",
+            Self::Threads => "threads",
+            Self::Lazy => "lazy",
+            Self::Streaming => "streaming",
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+fn default_geohash_precision() -> usize {
+    6
+}
+
+fn default_woe_bins() -> usize {
+    10
+}
+
+fn default_quantiles() -> usize {
+    1000
+}
+
+fn default_tag_separator() -> String {
+    "|".to_string()
+}
+
+fn default_other() -> String {
+    OHE_OTHER.to_string()
+}
+
+/// The category rare values of a limited `ohe` step fold into, and the
+/// default `other` of a `collapse_rare` step.
+pub(crate) const OHE_OTHER: &str = "other";
+
+/// Whether `name` is an `ohe` dummy or a `multi_hot` tag column, whose names
+/// come from the data rather than the step.
+fn is_encoded_column(name: &str) -> bool {
+    name.contains("__ohe__") || name.contains(multi_hot::SEPARATOR)
+}
+
+/// The median and scale a `robust_scale` step applies to `column`, as
+/// aggregations: the IQR by linear interpolation, or 1 when it is 0.
+pub(crate) fn robust_stats(column: &str) -> [Expr; 2] {
+    let x = col(column).cast(DataType::Float64);
+    let quartile = |q: f64| x.clone().quantile(lit(q), QuantileMethod::Linear);
+    let iqr = quartile(0.75) - quartile(0.25);
+    [
+        x.clone().median(),
+        when(iqr.clone().eq(lit(0.0))).then(lit(1.0)).otherwise(iqr),
+    ]
+}
+
+fn default_decimal() -> char {
+    '.'
+}
+
+fn default_categorical_keys() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct FeaturePipeline {
+    /// Schema version; see [`crate::config::migrate`]. Absent means version 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+
+    /// Entrypoint dataset this pipeline reads; the primary dataset when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
+
+    /// Columns expected to identify a row; duplicates are reported before
+    /// features are computed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keys: Option<KeysConfig>,
+
     pub steps: Vec<FeatureConfig>,
 
+    /// Shared values referenced from steps as `$name`; resolved at load time.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, serde_json::Value>,
+
+    /// Named lookup tables for `map` steps, referenced as `lookup: <name>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub lookups: BTreeMap<String, BTreeMap<String, f64>>,
+
+    /// Null handling for aggregation steps that don't set their own.
+    #[serde(default)]
+    pub null_policy: NullPolicy,
+
+    /// Casts string `group_by` columns to `Categorical` before the steps run,
+    /// so window aggregations partition on integer codes instead of hashing
+    /// strings on every `.over()`.
+    #[serde(default = "default_categorical_keys")]
+    pub categorical_keys: bool,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 }
 
 impl FeaturePipeline {
     pub fn from_yaml(filepath: &str) -> Result<Self, PipelineStepError> {
-        let config_yaml = File::open(filepath)?;
-        let reader = BufReader::new(config_yaml);
-        let config: FeaturePipeline = from_reader(reader)?;
+        Self::from_file(filepath, Some(ConfigFormat::Yaml))
+    }
+
+    pub fn from_file(
+        filepath: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<Self, PipelineStepError> {
+        Self::from_layers(filepath, &[], format)
+    }
+
+    /// Loads `filepath` with `overlays` merged on top; see [`overlay::merge`].
+    pub fn from_layers(
+        filepath: &str,
+        overlays: &[String],
+        format: Option<ConfigFormat>,
+    ) -> Result<Self, PipelineStepError> {
+        let (raw, content) = overlay::load_layered(filepath, overlays, format)?;
+        Self::from_value(filepath, raw, &content)
+    }
+
+    /// Parses a config held in memory, e.g. from an editor. `source` names it
+    /// in errors. `${VAR}` placeholders are left as they are.
+    pub fn from_content(
+        source: &str,
+        content: &str,
+        format: ConfigFormat,
+    ) -> Result<Self, PipelineStepError> {
+        let raw = format::parse(source, content, format)?;
+        Self::from_value(source, raw, content)
+    }
+
+    /// Resolves `vars`, templates and lookups in `raw`, then deserializes and
+    /// validates it. `content` is the source text error messages point into.
+    fn from_value(
+        filepath: &str,
+        raw: serde_json::Value,
+        content: &str,
+    ) -> Result<Self, PipelineStepError> {
+        let resolved = lookups::resolve(
+            filepath,
+            template::expand(filepath, vars::resolve(filepath, raw)?)?,
+        )?;
+        let steps = resolved.get("steps").cloned();
+        let mut config: FeaturePipeline = serde_json::from_value(resolved).map_err(|e| {
+            format::step_error::<FeatureConfig>(filepath, content, steps.as_ref(), e)
+        })?;
+        migrate::check_version(filepath, config.version)?;
+        config.finish(filepath)?;
         Ok(config)
     }
 
-    pub fn apply_parallel(&self, data: &DataFrame) -> Result<DataFrame, PipelineStepError> {
-        let results: Vec<(DataFrame, Vec<String>)> = self
+    /// Gives steps without a `null_policy` the pipeline's, then validates.
+    /// `source` names the config in errors. Shared by file loading and
+    /// [`PipelineBuilder`](crate::pipeline::builder::PipelineBuilder).
+    pub(crate) fn finish(&mut self, source: &str) -> Result<(), ConfigError> {
+        for step in &mut self.steps {
+            if let Some(policy @ None) = step.null_policy_mut() {
+                *policy = Some(self.null_policy);
+            }
+        }
+        self.validate(source)
+    }
+
+    /// Seeds `random_feature` and `loo_target_encode` steps with `seed`
+    /// unless they set their own.
+    pub fn default_seed(&mut self, seed: u64) {
+        for step in &mut self.steps {
+            if let FeatureConfig::RandomFeature { seed: own, .. }
+            | FeatureConfig::LooTargetEncode { seed: own, .. } = step
+            {
+                own.get_or_insert(seed);
+            }
+        }
+    }
+
+    /// Match statistics of every `map` step against the input `data`.
+    pub fn join_reports(&self, data: &DataFrame) -> Result<Vec<JoinReport>, PipelineStepError> {
+        let mut reports = Vec::new();
+        for step in &self.steps {
+            reports.extend(step.join_report(data)?);
+        }
+        Ok(reports)
+    }
+
+    /// Fitted bins and weights of `woe_encode` steps on the input `data`,
+    /// keyed by feature name. Saved with run outputs so encodings can be
+    /// reproduced and audited.
+    pub fn woe_tables(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, WoeTable>, PipelineStepError> {
+        let data = self.prepare(data)?;
+        let mut tables = BTreeMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if let FeatureConfig::WoeEncode { name, .. } = step {
+                let table = step.woe_table(&data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), &data, e)
+                })?;
+                tables.insert(name.clone(), table);
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Fitted quantiles of `quantile_transform` steps on the input `data`,
+    /// keyed by feature name. Saved with run outputs so new values can be
+    /// mapped the same way.
+    pub fn quantile_maps(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, QuantileMap>, PipelineStepError> {
+        let data = self.prepare(data)?;
+        let mut maps = BTreeMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if let FeatureConfig::QuantileTransform { name, .. } = step {
+                let map = step.quantile_map(&data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), &data, e)
+                })?;
+                maps.insert(name.clone(), map);
+            }
+        }
+        Ok(maps)
+    }
+
+    /// Fitted lambdas of `power_transform` steps on the input `data`, keyed
+    /// by feature name. Saved with run outputs so new values can be
+    /// transformed the same way.
+    pub fn power_fits(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, PowerFit>, PipelineStepError> {
+        let data = self.prepare(data)?;
+        let mut fits = BTreeMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if let FeatureConfig::PowerTransform { name, .. } = step {
+                let fit = step.power_fit(&data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), &data, e)
+                })?;
+                fits.insert(name.clone(), fit);
+            }
+        }
+        Ok(fits)
+    }
+
+    /// Categories of the columns `ohe` steps encode, learned from the input
+    /// `data` and keyed by column. Saved with run outputs so serving can
+    /// check new rows for categories the dummies don't cover.
+    pub fn ohe_categories(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, BTreeSet<String>>, PipelineStepError> {
+        let data = self.prepare(data)?;
+        let mut sets = BTreeMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let categories = step.ohe_categories(&data).map_err(|e| {
+                PipelineStepError::in_step(i, step.function(), step.name(), &data, e)
+            })?;
+            sets.extend(categories);
+        }
+        Ok(sets)
+    }
+
+    /// Kept categories of `collapse_rare` steps on the input `data`, keyed
+    /// by key column. Saved with run outputs so new rows are collapsed the
+    /// same way.
+    pub fn kept_categories(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, KeptCategories>, PipelineStepError> {
+        let data = self.prepare(data)?;
+        let mut sets = BTreeMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if let FeatureConfig::CollapseRare { name, .. } = step {
+                let kept = step.kept_categories(&data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), &data, e)
+                })?;
+                sets.insert(name.clone(), kept);
+            }
+        }
+        Ok(sets)
+    }
+
+    /// `data` as the steps see it: with the key columns steps such as
+    /// `geohash` write added, then [`with_categorical_keys`](Self::with_categorical_keys).
+    pub fn prepare<'a>(
+        &self,
+        data: &'a DataFrame,
+    ) -> Result<Cow<'a, DataFrame>, PipelineStepError> {
+        match self.with_key_columns(data)? {
+            Cow::Borrowed(data) => self.with_categorical_keys(data),
+            Cow::Owned(data) => Ok(Cow::Owned(self.with_categorical_keys(&data)?.into_owned())),
+        }
+    }
+
+    /// `data` with the key columns steps write added, so steps reading
+    /// them work under every execution strategy, not only sequentially.
+    pub fn with_key_columns<'a>(
+        &self,
+        data: &'a DataFrame,
+    ) -> Result<Cow<'a, DataFrame>, PipelineStepError> {
+        let mut result = Cow::Borrowed(data);
+        for (i, step) in self.steps.iter().enumerate() {
+            if !step.key_columns().is_empty() {
+                let output = step.apply_feature(&result).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), &result, e)
+                })?;
+                result = Cow::Owned(output);
+            }
+        }
+        Ok(result)
+    }
+
+    /// `data` with the string `group_by` columns cast to `Categorical` when
+    /// `categorical_keys` is on. Columns an `ohe` step encodes keep their
+    /// type, so dummy names don't depend on the setting.
+    pub fn with_categorical_keys<'a>(
+        &self,
+        data: &'a DataFrame,
+    ) -> Result<Cow<'a, DataFrame>, PipelineStepError> {
+        if !self.categorical_keys {
+            return Ok(Cow::Borrowed(data));
+        }
+        let encoded: BTreeSet<&str> = self
             .steps
+            .iter()
+            .filter(|step| matches!(step, FeatureConfig::Ohe { .. }))
+            .flat_map(|step| step.input_columns())
+            .collect();
+        let keys: BTreeSet<&str> = self
+            .steps
+            .iter()
+            .filter_map(|step| step.group_by())
+            .flatten()
+            .map(|key| key.as_str())
+            .filter(|key| !encoded.contains(key))
+            .filter(|key| data.column(key).is_ok_and(|c| c.dtype().is_string()))
+            .collect();
+        if keys.is_empty() {
+            return Ok(Cow::Borrowed(data));
+        }
+
+        let categorical = DataType::from_categories(Categories::global());
+        let casts: Vec<Expr> = keys
+            .iter()
+            .map(|key| col(*key).cast(categorical.clone()))
+            .collect();
+        info!(columns = ?keys, "Casting group_by columns to Categorical");
+        Ok(Cow::Owned(
+            data.clone().lazy().with_columns(casts).collect()?,
+        ))
+    }
+
+    /// Window aggregations over the same `group_by` columns, in the same
+    /// order, as step indices in config order; only lists two or more steps
+    /// share. Steps reading a column `data` lacks, e.g. an earlier step's
+    /// feature, are left out so they can run in their turn.
+    pub fn shared_windows(&self, data: &DataFrame) -> Vec<Vec<usize>> {
+        let mut windows: BTreeMap<&[String], Vec<usize>> = BTreeMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let Some(group_by) = step.group_by().filter(|g| !g.is_empty()) else {
+                continue;
+            };
+            let readable = step.input_columns().iter().all(|c| data.column(c).is_ok());
+            if step.name().is_some() && readable {
+                windows.entry(group_by).or_default().push(i);
+            }
+        }
+        let mut windows: Vec<Vec<usize>> = windows
+            .into_values()
+            .filter(|steps| steps.len() > 1)
+            .collect();
+        windows.sort();
+        windows
+    }
+
+    /// Runs the window steps at `indices`, which share their `group_by`, as
+    /// one `with_columns` over `data`: polars partitions the data once and
+    /// every step adds its column in the same pass. The pass is timed as a
+    /// whole, so the steps' stats have no duration.
+    fn apply_window(
+        &self,
+        indices: &[usize],
+        data: &DataFrame,
+        parent: &Span,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        let mut exprs = Vec::with_capacity(indices.len());
+        for &i in indices {
+            let step = &self.steps[i];
+            let expr = step.feature_expr(data).map_err(|e| {
+                PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+            })?;
+            exprs.extend(expr);
+        }
+
+        let start = Instant::now();
+        let result = data.clone().lazy().with_columns(exprs).collect()?;
+        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        info!(
+            steps = indices.len(),
+            group_by = ?self.steps[indices[0]].group_by().unwrap_or_default(),
+            duration_ms,
+            "Shared window computed"
+        );
+
+        let mut stats = Vec::with_capacity(indices.len());
+        for &i in indices {
+            let step = &self.steps[i];
+            let Some(name) = step.name() else {
+                continue;
+            };
+            let mut output = data.clone();
+            output.with_column(result.column(&format!("feature_{}", name))?.clone())?;
+            let span = step.span(parent);
+            let _enter = span.enter();
+            stats.push(step.finish_step(i, data, &output, None));
+        }
+        Ok((result, stats))
+    }
+
+    /// Input columns any step or the key check reads; key columns
+    /// steps write are left out.
+    pub fn input_columns(&self) -> BTreeSet<&str> {
+        let keys: BTreeSet<&str> = self.steps.iter().flat_map(|s| s.key_columns()).collect();
+        self.steps
+            .iter()
+            .flat_map(|step| step.input_columns())
+            .chain(
+                self.keys
+                    .iter()
+                    .flat_map(|k| k.columns.iter().map(|c| c.as_str())),
+            )
+            .filter(|column| !keys.contains(column))
+            .collect()
+    }
+
+    /// Rejects out-of-range coverage thresholds, steps reading a key column
+    /// before the step writing it, and steps sharing a `name`,
+    /// which would overwrite each other's `feature_<name>` column. Lists every
+    /// name collision, not just the first.
+    fn validate(&self, filepath: &str) -> Result<(), ConfigError> {
+        for (i, step) in self.steps.iter().enumerate() {
+            if let Some(error) = step.invalid() {
+                return Err(ConfigError::ParseError {
+                    path: filepath.to_string(),
+                    error: format!("step {}: {}", i + 1, error),
+                });
+            }
+        }
+
+        // Keys are added before any step runs, but the SQL and Python
+        // translations run the steps in order.
+        for (i, step) in self.steps.iter().enumerate() {
+            for key in step.key_columns() {
+                if let Some(first) = self.steps[..i]
+                    .iter()
+                    .position(|earlier| earlier.key_columns().contains(&key))
+                {
+                    return Err(ConfigError::ParseError {
+                        path: filepath.to_string(),
+                        error: format!(
+                            "steps {} and {} both write the key column '{}'",
+                            first + 1,
+                            i + 1,
+                            key
+                        ),
+                    });
+                }
+                if let Some(reader) = self.steps[..i]
+                    .iter()
+                    .position(|earlier| earlier.input_columns().contains(&key))
+                {
+                    return Err(ConfigError::ParseError {
+                        path: filepath.to_string(),
+                        error: format!(
+                            "step {} reads '{}' before step {} writes it; move the {} step first",
+                            reader + 1,
+                            key,
+                            i + 1,
+                            step.function()
+                        ),
+                    });
+                }
+            }
+        }
+
+        let mut first_by_name: HashMap<&str, usize> = HashMap::new();
+        let mut collisions = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let Some(name) = step.name() else {
+                continue;
+            };
+            match first_by_name.get(name) {
+                Some(first) => collisions.push(format!(
+                    "steps {} and {} are both named '{}'",
+                    first,
+                    i + 1,
+                    name
+                )),
+                None => {
+                    first_by_name.insert(name, i + 1);
+                }
+            }
+        }
+
+        if collisions.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::ParseError {
+                path: filepath.to_string(),
+                error: format!(
+                    "duplicate feature names: {}; give each step a distinct 'name'",
+                    collisions.join("; ")
+                ),
+            })
+        }
+    }
+
+    pub fn apply_with(
+        &self,
+        data: &DataFrame,
+        strategy: ExecutionStrategy,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        match strategy {
+            ExecutionStrategy::Sequential => self.apply(data),
+            ExecutionStrategy::Rayon => self.apply_parallel(data),
+            ExecutionStrategy::Threads => self.apply_parallel_threads(data),
+            ExecutionStrategy::Lazy => self.apply_lazy(data),
+            ExecutionStrategy::Streaming => self.apply_streaming(data),
+        }
+    }
+
+    /// Like [`apply_parallel`](Self::apply_parallel), but window, ratio and
+    /// threshold steps become expressions of a single lazy plan over `data`,
+    /// collected once so polars can share scans and group keys between them.
+    /// Steps that need the data itself (`map`, `calendar`, `ip_features`,
+    /// `url_parse`, `email_domain`, `json_extract`, `geohash`,
+    /// `anomaly_score`, `random_feature`, `expect`, `ohe`, `multi_hot`) run
+    /// eagerly.
+    ///
+    /// Fused steps cannot be timed apart: their stats have no duration and
+    /// the plan's collect time is logged once.
+    pub fn apply_lazy(
+        &self,
+        data: &DataFrame,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        self.apply_fused(data, Engine::InMemory)
+    }
+
+    /// [`apply_lazy`](Self::apply_lazy) with the plan collected by polars'
+    /// streaming engine, which processes the data in morsels.
+    pub fn apply_streaming(
+        &self,
+        data: &DataFrame,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        self.apply_fused(data, Engine::Streaming)
+    }
+
+    fn apply_fused(
+        &self,
+        data: &DataFrame,
+        engine: Engine,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        let data = &*self.prepare(data)?;
+        let parent = Span::current();
+        let mut fused = Vec::new();
+        let mut eager = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let expr = step.feature_expr(data).map_err(|e| {
+                PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+            })?;
+            match expr {
+                Some(expr) => fused.push((i, step, expr)),
+                None => eager.push((i, step, step.apply_step(i, data, &parent)?)),
+            }
+        }
+
+        let start = Instant::now();
+        let mut result = data
+            .clone()
+            .lazy()
+            .with_columns(
+                fused
+                    .iter()
+                    .map(|(_, _, expr)| expr.clone())
+                    .collect::<Vec<_>>(),
+            )
+            .collect_with_engine(engine)?;
+        info!(
+            steps = fused.len(),
+            engine = ?engine,
+            duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+            "Lazy plan collected"
+        );
+
+        let mut output_columns = Vec::new();
+        let mut stats = Vec::with_capacity(self.steps.len());
+        for (i, step, _) in &fused {
+            let Some(name) = step.name() else {
+                continue;
+            };
+            let column = format!("feature_{}", name);
+            let mut output = data.clone();
+            output.with_column(result.column(&column)?.clone())?;
+            let span = step.span(&parent);
+            let _enter = span.enter();
+            stats.push(step.finish_step(*i, data, &output, None));
+            output_columns.push(column);
+        }
+
+        for (_, step, (feature_df, step_stats)) in eager {
+            let cols: Vec<String> = match step {
+                FeatureConfig::Ohe { .. } | FeatureConfig::MultiHot { .. } => feature_df
+                    .get_column_names()
+                    .iter()
+                    .filter(|c| is_encoded_column(c))
+                    .map(|c| c.to_string())
+                    .collect(),
+                _ => step
+                    .name()
+                    .map(|n| vec![format!("feature_{}", n)])
+                    .unwrap_or_default(),
+            };
+            for col_name in &cols {
+                if let Ok(col) = feature_df.column(col_name) {
+                    result.with_column(col.clone())?;
+                }
+            }
+            output_columns.extend(cols);
+            stats.push(step_stats);
+        }
+
+        output_columns.sort();
+        output_columns.dedup();
+        stats.sort_by_key(|s| s.step);
+        Ok((result.select(output_columns)?, stats))
+    }
+
+    pub fn apply_parallel(
+        &self,
+        data: &DataFrame,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        let data = &*self.prepare(data)?;
+        // Workers steal other steps while polars waits, so spans are parented
+        // explicitly rather than on whichever step the thread has entered.
+        let parent = Span::current();
+        // Steps sharing a window run together as one task.
+        let windows = self.shared_windows(data);
+        let singles = (0..self.steps.len())
+            .filter(|i| !windows.iter().any(|w| w.contains(i)))
+            .map(|i| vec![i]);
+        let tasks: Vec<Vec<usize>> = windows.iter().cloned().chain(singles).collect();
+        let results: Vec<(DataFrame, Vec<String>, Vec<StepStats>)> = tasks
             .par_iter()
-            .map(|step| {
-                let feature_df = step.apply_feature(data)?;
+            .map(|task| {
+                let &[i] = task.as_slice() else {
+                    let (feature_df, stats) = self.apply_window(task, data, &parent)?;
+                    let cols = task
+                        .iter()
+                        .filter_map(|&j| self.steps[j].name().map(|n| format!("feature_{}", n)))
+                        .collect();
+                    return Ok((feature_df, cols, stats));
+                };
+                let step = &self.steps[i];
+                let (feature_df, stats) = step.apply_step(i, data, &parent)?;
 
                 let cols: Vec<String> = match step {
-                    FeatureConfig::Ohe { .. } => feature_df
+                    FeatureConfig::Ohe { .. } | FeatureConfig::MultiHot { .. } => feature_df
                         .get_column_names()
                         .iter()
-                        .filter(|c| c.contains("__ohe__"))
+                        .filter(|c| is_encoded_column(c))
                         .map(|c| c.to_string())
                         .collect(),
                     _ => step
@@ -111,18 +1476,20 @@ impl FeaturePipeline {
                         .unwrap_or_default(),
                 };
 
-                Ok((feature_df, cols))
+                Ok((feature_df, cols, vec![stats]))
             })
             .collect::<Result<Vec<_>, PipelineStepError>>()?;
 
-        let mut output_columns: Vec<String> =
-            results.iter().flat_map(|(_, cols)| cols.clone()).collect();
+        let mut output_columns: Vec<String> = results
+            .iter()
+            .flat_map(|(_, cols, _)| cols.clone())
+            .collect();
 
         output_columns.sort();
         output_columns.dedup();
 
         let mut result = data.clone();
-        for (feature_df, cols) in &results {
+        for (feature_df, cols, _) in &results {
             for col_name in cols {
                 if let Ok(col) = feature_df.column(col_name) {
                     result.with_column(col.clone())?;
@@ -131,17 +1498,29 @@ impl FeaturePipeline {
         }
 
         result = result.select(output_columns)?;
-        Ok(result)
+        let mut stats: Vec<StepStats> = results
+            .into_iter()
+            .flat_map(|(_, _, stats)| stats)
+            .collect();
+        stats.sort_by_key(|s| s.step);
+        Ok((result, stats))
     }
 
-    pub fn apply_parallel_threads(&self, data: &DataFrame) -> Result<DataFrame, PipelineStepError> {
-        let data = Arc::new(data.clone());
+    pub fn apply_parallel_threads(
+        &self,
+        data: &DataFrame,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        let data = Arc::new(self.prepare(data)?.into_owned());
 
-        let tasks: Vec<_> = self.steps.clone()
+        let tasks: Vec<_> = self
+            .steps
+            .clone()
             .into_iter()
-            .map(|step| {
+            .enumerate()
+            .map(|(i, step)| {
                 let data = Arc::clone(&data);
-                thread::spawn(move || step.apply_feature(&data))
+                let parent = Span::current();
+                thread::spawn(move || step.apply_step(i, &data, &parent))
             })
             .collect();
 
@@ -151,16 +1530,18 @@ impl FeaturePipeline {
             .filter_map(|step| step.name().map(|n| format!("feature_{}", n)))
             .collect();
 
-        let feature_dfs: Vec<DataFrame> = tasks
+        let (feature_dfs, stats): (Vec<DataFrame>, Vec<StepStats>) = tasks
             .into_iter()
             .map(|task| task.join().unwrap())
-            .collect::<Result<Vec<_>, PipelineStepError>>()?;
+            .collect::<Result<Vec<_>, PipelineStepError>>()?
+            .into_iter()
+            .unzip();
 
         for df in &feature_dfs {
             let ohe_cols: Vec<String> = df
                 .get_column_names()
                 .iter()
-                .filter(|c| c.contains("__ohe__"))
+                .filter(|c| is_encoded_column(c))
                 .map(|c| c.to_string())
                 .collect();
             output_columns.extend(ohe_cols);
@@ -179,48 +1560,356 @@ impl FeaturePipeline {
         output_columns.dedup();
         result = result.select(output_columns)?;
 
-        Ok(result)
+        Ok((result, stats))
     }
 
-    pub fn apply(&self, data: &DataFrame) -> Result<DataFrame, PipelineStepError> {
-        let mut result = data.clone();
+    /// Runs the steps in order, returning the feature columns and each step's
+    /// stats.
+    pub fn apply(
+        &self,
+        data: &DataFrame,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        let mut result = self.prepare(data)?.into_owned();
+        let windows = self.shared_windows(&result);
         let mut output_columns: Vec<String> = vec![];
-        for step in &self.steps {
-            result = step.apply_feature(&result)?;
+        let mut stats = Vec::with_capacity(self.steps.len());
+        for (i, step) in self.steps.iter().enumerate() {
+            // A shared window runs whole at its first step.
+            if let Some(window) = windows.iter().find(|w| w.contains(&i)) {
+                if window[0] == i {
+                    let window_stats;
+                    (result, window_stats) =
+                        self.apply_window(window, &result, &Span::current())?;
+                    stats.extend(window_stats);
+                    output_columns.extend(
+                        window.iter().filter_map(|&j| {
+                            self.steps[j].name().map(|n| format!("feature_{}", n))
+                        }),
+                    );
+                }
+                continue;
+            }
+
+            let step_stats;
+            (result, step_stats) = step.apply_step(i, &result, &Span::current())?;
+            stats.push(step_stats);
             match step {
-                FeatureConfig::Ohe { .. } => {
+                FeatureConfig::Ohe { .. } | FeatureConfig::MultiHot { .. } => {
                     let ohe_cols: Vec<String> = result
                         .get_column_names()
                         .iter()
-                        .filter(|col| col.contains("__ohe__"))
+                        .filter(|col| is_encoded_column(col))
                         .map(|col| col.to_string())
                         .collect();
                     output_columns.extend(ohe_cols);
                 }
-                _ => {
-                    if let Some(name) = step.name() {
-                        output_columns.push(format!("feature_{}", name));
+                _ => {
+                    if let Some(name) = step.name() {
+                        output_columns.push(format!("feature_{}", name));
+                    }
+                }
+            }
+        }
+
+        output_columns.sort();
+        output_columns.dedup();
+        stats.sort_by_key(|s| s.step);
+
+        result = result.select(output_columns)?;
+
+        Ok((result, stats))
+    }
+}
+
+impl FeatureConfig {
+    /// [`apply_feature`](Self::apply_feature) inside a `step` span under `parent`,
+    /// logging how the step changed the frame's shape. `index` is the step's
+    /// 0-based position, recorded in the returned stats.
+    pub fn apply_step(
+        &self,
+        index: usize,
+        data: &DataFrame,
+        parent: &Span,
+    ) -> Result<(DataFrame, StepStats), PipelineStepError> {
+        let span = self.span(parent);
+        let _enter = span.enter();
+        let start = Instant::now();
+        let result = self.apply_feature(data).map_err(|e| {
+            PipelineStepError::in_step(index, self.function(), self.name(), data, e)
+        })?;
+        let stats = self.finish_step(
+            index,
+            data,
+            &result,
+            Some(start.elapsed().as_secs_f64() * 1000.0),
+        );
+        Ok((result, stats))
+    }
+
+    fn span(&self, parent: &Span) -> Span {
+        info_span!(
+            parent: parent,
+            "step",
+            function = self.function(),
+            feature = self.name().unwrap_or_default()
+        )
+    }
+
+    /// Stats of a step that turned `input` into `output`, logged in the
+    /// current span.
+    fn finish_step(
+        &self,
+        index: usize,
+        input: &DataFrame,
+        output: &DataFrame,
+        duration_ms: Option<f64>,
+    ) -> StepStats {
+        let stats = StepStats::new(
+            index,
+            self.function(),
+            self.name(),
+            input,
+            output,
+            duration_ms,
+        );
+        info!(
+            input = ?input.shape(),
+            output = ?output.shape(),
+            duration_ms = stats.duration_ms,
+            frame_bytes = stats.frame_bytes_out,
+            rss_bytes = stats.rss_bytes,
+            "Step finished: {}",
+            stats.summary()
+        );
+        stats
+    }
+
+    /// The column a window, ratio, threshold or list step adds, checked against
+    /// `data`; `None` for steps that need the data itself (`map`, `calendar`,
+    /// `ip_features`, `url_parse`, `email_domain`, `json_extract`,
+    /// `parse_number`, `blocking_key`, `geohash`, `expect`, `ohe`) and
+    /// aggregations without `group_by`.
+    pub fn feature_expr(&self, data: &DataFrame) -> Result<Option<Expr>, PipelineStepError> {
+        match self {
+            Self::Mean {
+                column,
+                group_by,
+                name,
+                null_policy,
+                ..
+            } if !group_by.is_empty() => {
+                let feature_col_name = format!("feature_{name}");
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                for i in group_by.iter() {
+                    if !self.is_column_exists(data, i) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: i.clone(),
+                            available: data
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
+                    }
+                }
+
+                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+
+                let feature = null_policy.unwrap_or_default().over(
+                    data,
+                    name,
+                    column,
+                    col(column).mean(),
+                    groupby_cols,
+                )?;
+
+                Ok(Some(feature.alias(feature_col_name)))
+            }
+            Self::Max {
+                column,
+                group_by,
+                name,
+                null_policy,
+                ..
+            } if !group_by.is_empty() => {
+                let feature_col_name = format!("feature_{name}");
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                for i in group_by.iter() {
+                    if !self.is_column_exists(data, i) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: i.clone(),
+                            available: data
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
+                    }
+                }
+
+                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+
+                let feature = null_policy.unwrap_or_default().over(
+                    data,
+                    name,
+                    column,
+                    col(column).max(),
+                    groupby_cols,
+                )?;
+
+                Ok(Some(feature.alias(feature_col_name)))
+            }
+            Self::Sum {
+                column,
+                group_by,
+                name,
+                null_policy,
+                ..
+            } if !group_by.is_empty() => {
+                let feature_col_name = format!("feature_{name}");
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                for i in group_by.iter() {
+                    if !self.is_column_exists(data, i) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: i.clone(),
+                            available: data
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
+                    }
+                }
+
+                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+
+                let feature = null_policy.unwrap_or_default().over(
+                    data,
+                    name,
+                    column,
+                    col(column).sum(),
+                    groupby_cols,
+                )?;
+
+                Ok(Some(feature.alias(feature_col_name)))
+            }
+            Self::Min {
+                column,
+                group_by,
+                name,
+                null_policy,
+                ..
+            } if !group_by.is_empty() => {
+                let feature_col_name = format!("feature_{name}");
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                for i in group_by.iter() {
+                    if !self.is_column_exists(data, i) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: i.clone(),
+                            available: data
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
+                    }
+                }
+
+                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+
+                let feature = null_policy.unwrap_or_default().over(
+                    data,
+                    name,
+                    column,
+                    col(column).min(),
+                    groupby_cols,
+                )?;
+
+                Ok(Some(feature.alias(feature_col_name)))
+            }
+            Self::Count {
+                column,
+                group_by,
+                name,
+                ..
+            } if !group_by.is_empty() => {
+                let feature_col_name = format!("feature_{name}");
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                for i in group_by.iter() {
+                    if !self.is_column_exists(data, i) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: i.clone(),
+                            available: data
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
                     }
                 }
-            }
-        }
-
-        output_columns.sort();
-        output_columns.dedup();
-
-        result = result.select(output_columns)?;
 
-        Ok(result)
-    }
-}
+                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
 
-impl FeatureConfig {
-    pub fn apply_feature(&self, data: &DataFrame) -> Result<DataFrame, PipelineStepError> {
-        match self {
-            Self::Mean {
+                Ok(Some(
+                    col(column).len().over(groupby_cols).alias(feature_col_name),
+                ))
+            }
+            Self::CountNonNull {
                 column,
                 group_by,
                 name,
+                null_policy,
+                ..
             } if !group_by.is_empty() => {
                 let feature_col_name = format!("feature_{name}");
                 if !self.is_column_exists(data, column) {
@@ -249,19 +1938,105 @@ impl FeatureConfig {
 
                 let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
 
-                Ok(data
+                let feature = null_policy.unwrap_or_default().over(
+                    data,
+                    name,
+                    column,
+                    col(column).count(),
+                    groupby_cols,
+                )?;
+
+                Ok(Some(feature.alias(feature_col_name)))
+            }
+            Self::LooTargetEncode {
+                column,
+                group_by,
+                noise,
+                seed,
+                name,
+                ..
+            } if !group_by.is_empty() => {
+                let feature_col_name = format!("feature_{name}");
+                if let Some(missing) = std::iter::once(column)
+                    .chain(group_by)
+                    .find(|c| !self.is_column_exists(data, c))
+                {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: missing.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+
+                // The group's sum and count less the row's own value, which
+                // is 0 and uncounted when null.
+                let target = col(column).cast(DataType::Float64);
+                let own = target.clone().is_not_null().cast(DataType::Float64);
+                let others = target
                     .clone()
-                    .lazy()
-                    .with_columns([col(column)
-                        .mean()
-                        .over(groupby_cols)
-                        .alias(feature_col_name)])
-                    .collect()?)
+                    .count()
+                    .over(groupby_cols.clone())
+                    .cast(DataType::Float64)
+                    - own;
+                let sum =
+                    target.clone().sum().over(groupby_cols) - target.clone().fill_null(lit(0.0));
+                let mut feature = when(others.clone().gt(lit(0.0)))
+                    .then(sum / others)
+                    .otherwise(target.mean());
+                if let Some(noise) = noise {
+                    let z = noise::column(
+                        noise::stream(*seed, name),
+                        data.height(),
+                        Distribution::Normal,
+                        "z",
+                    );
+                    feature = feature
+                        * (lit(1.0) + lit(*noise) * lit(z.as_materialized_series().clone()));
+                }
+
+                Ok(Some(feature.alias(feature_col_name)))
             }
-            Self::Max {
+            Self::RobustScale {
+                column,
+                group_by,
+                name,
+                ..
+            } => {
+                if let Some(missing) = std::iter::once(column)
+                    .chain(group_by)
+                    .find(|c| !self.is_column_exists(data, c))
+                {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: missing.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                let [mut median, mut scale] = robust_stats(column);
+                if !group_by.is_empty() {
+                    let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+                    median = median.over(groupby_cols.clone());
+                    scale = scale.over(groupby_cols);
+                }
+                let feature = (col(column).cast(DataType::Float64) - median) / scale;
+
+                Ok(Some(feature.alias(format!("feature_{name}"))))
+            }
+            Self::PercentRank {
                 column,
                 group_by,
                 name,
+                null_policy,
+                ..
             } if !group_by.is_empty() => {
                 let feature_col_name = format!("feature_{name}");
                 if !self.is_column_exists(data, column) {
@@ -290,16 +2065,142 @@ impl FeatureConfig {
 
                 let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
 
-                Ok(data
-                    .clone()
-                    .lazy()
-                    .with_columns([col(column).max().over(groupby_cols).alias(feature_col_name)])
-                    .collect()?)
+                // (rank - 1) / (n - 1) over the group's non-null values, as SQL's
+                // PERCENT_RANK; a lone value ranks 0.
+                let rank = col(column)
+                    .rank(
+                        RankOptions {
+                            method: RankMethod::Min,
+                            descending: false,
+                        },
+                        None,
+                    )
+                    .cast(DataType::Float64);
+                let n = col(column).count().cast(DataType::Float64);
+                let percent_rank = when(col(column).is_null())
+                    .then(lit(NULL).cast(DataType::Float64))
+                    .otherwise(
+                        when(n.clone().gt(lit(1.0)))
+                            .then((rank - lit(1.0)) / (n - lit(1.0)))
+                            .otherwise(lit(0.0)),
+                    );
+                let feature = null_policy.unwrap_or_default().over(
+                    data,
+                    name,
+                    column,
+                    percent_rank,
+                    groupby_cols,
+                )?;
+
+                Ok(Some(feature.alias(feature_col_name)))
             }
-            Self::Sum {
+
+            Self::RollingByTime {
+                column,
+                time_column,
+                window,
+                agg,
+                group_by,
+                name,
+                ..
+            } if !group_by.is_empty() => {
+                let feature_col_name = format!("feature_{name}");
+                for i in [column, time_column].into_iter().chain(group_by) {
+                    if !self.is_column_exists(data, i) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: i.clone(),
+                            available: data
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
+                    }
+                }
+
+                let time = match data.column(time_column)?.dtype() {
+                    DataType::String => col(time_column.as_str()).str().to_datetime(
+                        Some(TimeUnit::Milliseconds),
+                        None,
+                        StrptimeOptions::default(),
+                        lit("raise"),
+                    ),
+                    _ => col(time_column.as_str()),
+                };
+                let options = RollingOptionsDynamicWindow {
+                    window_size: Duration::try_parse(window)?,
+                    min_periods: 1,
+                    closed_window: ClosedWindow::Right,
+                    fn_params: None,
+                };
+                // `rolling_*_by` rejects nulls, so null values are summed as
+                // zero and left out of the count instead.
+                let sum = col(column.as_str())
+                    .cast(DataType::Float64)
+                    .fill_null(lit(0.0))
+                    .rolling_sum_by(time.clone(), options.clone());
+                let count = col(column.as_str())
+                    .is_not_null()
+                    .cast(DataType::UInt32)
+                    .rolling_sum_by(time, options);
+                let feature = match agg {
+                    RollingAgg::Sum => sum,
+                    RollingAgg::Mean => when(count.clone().gt(lit(0)))
+                        .then(sum / count.cast(DataType::Float64))
+                        .otherwise(lit(NULL).cast(DataType::Float64)),
+                    RollingAgg::Count => count,
+                };
+                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+
+                Ok(Some(feature.over(groupby_cols).alias(feature_col_name)))
+            }
+
+            Self::Ratio {
+                numerator,
+                denominator,
+                name,
+                ..
+            } => {
+                let feature_col_name = format!("feature_{name}");
+                if !self.is_column_exists(data, numerator) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: numerator.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                if !self.is_column_exists(data, denominator) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: denominator.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                // As floats, so integer columns don't floor, and null for a
+                // zero denominator, as the SQL translation gives.
+                let denominator = col(denominator).cast(DataType::Float64);
+                Ok(Some(
+                    when(denominator.clone().eq(lit(0.0)))
+                        .then(lit(NULL).cast(DataType::Float64))
+                        .otherwise(col(numerator).cast(DataType::Float64) / denominator)
+                        .alias(feature_col_name),
+                ))
+            }
+
+            Self::CountDistinct {
                 column,
                 group_by,
                 name,
+                null_policy,
+                ..
             } if !group_by.is_empty() => {
                 let feature_col_name = format!("feature_{name}");
                 if !self.is_column_exists(data, column) {
@@ -326,21 +2227,323 @@ impl FeatureConfig {
                     }
                 }
 
-                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+
+                let feature = null_policy.unwrap_or_default().over(
+                    data,
+                    name,
+                    column,
+                    col(column).drop_nulls().n_unique(),
+                    groupby_cols,
+                )?;
+
+                Ok(Some(feature.alias(feature_col_name)))
+            }
+
+            Self::Threshold {
+                column,
+                threshold,
+                comparator,
+                name,
+                ..
+            } => {
+                let feature_col_name = format!("feature_{name}");
+                let feature = match comparator {
+                    Comparator::Gt => col(column).gt(*threshold),
+                    Comparator::Lt => col(column).lt(*threshold),
+                };
+                Ok(Some(feature.alias(feature_col_name)))
+            }
+
+            Self::ListLen { column, name, .. }
+            | Self::ListContains { column, name, .. }
+            | Self::ListAgg { column, name, .. } => {
+                let feature_col_name = format!("feature_{name}");
+                let dtype = data
+                    .column(column)
+                    .map_err(|_| PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    })?
+                    .dtype();
+                if !matches!(dtype, DataType::List(_)) {
+                    return Err(polars_err!(
+                        InvalidOperation: "column '{}' must be a list, got {}", column, dtype
+                    )
+                    .into());
+                }
+                let feature = match self {
+                    Self::ListContains { value, .. } => col(column)
+                        .cast(DataType::List(Box::new(DataType::String)))
+                        .list()
+                        .contains(lit(value.as_str()), false),
+                    Self::ListAgg {
+                        agg: ListAgg::Mean, ..
+                    } => col(column).list().mean(),
+                    Self::ListAgg {
+                        agg: ListAgg::Sum, ..
+                    } => col(column).list().sum().cast(DataType::Float64),
+                    _ => col(column).list().len(),
+                };
+                Ok(Some(feature.alias(feature_col_name)))
+            }
+
+            _ => Ok(None),
+        }
+    }
+
+    pub fn apply_feature(&self, data: &DataFrame) -> Result<DataFrame, PipelineStepError> {
+        if let Some(feature) = self.feature_expr(data)? {
+            return Ok(data.clone().lazy().with_columns([feature]).collect()?);
+        }
+
+        match self {
+            Self::Map {
+                column,
+                mapping,
+                default,
+                name,
+                ..
+            } => {
+                let feature_col_name = format!("feature_{name}");
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                if let Some(report) = self.join_report(data)? {
+                    report.enforce(&format!("lookup on '{}'", column))?;
+                }
+
+                let keys = data.column(column)?.cast(&DataType::String)?;
+                let mapped: Float64Chunked = keys
+                    .str()?
+                    .iter()
+                    .map(|key| key.and_then(|k| mapping.get(k).copied()).or(*default))
+                    .collect();
+
+                let mut result = data.clone();
+                result.with_column(mapped.with_name(feature_col_name.into()).into_column())?;
+                Ok(result)
+            }
+
+            Self::Calendar {
+                column,
+                country,
+                region,
+                output,
+                name,
+                ..
+            } => {
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                let dates = calendar::dates(data.column(column)?)?;
+                let calendar =
+                    Calendar::covering(*country, region.as_deref(), &self.extra_holidays(), &dates);
+                let mut result = data.clone();
+                result.with_column(calendar.column(&format!("feature_{name}"), *output, &dates))?;
+                Ok(result)
+            }
+
+            Self::IpFeatures {
+                column,
+                output,
+                name,
+                ..
+            } => {
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                let text = data.column(column)?.cast(&DataType::String)?;
+                let addresses = text.str()?.iter().map(|t| t.and_then(ip::parse));
+                let values = if output.is_prefix() {
+                    addresses
+                        .map(|a| a.and_then(|a| output.prefix(a)))
+                        .collect::<StringChunked>()
+                        .with_name(name.as_str().into())
+                        .into_column()
+                } else {
+                    addresses
+                        .map(|a| a.and_then(|a| output.flag(a)))
+                        .collect::<BooleanChunked>()
+                        .with_name(format!("feature_{name}").into())
+                        .into_column()
+                };
+
+                let mut result = data.clone();
+                result.with_column(values)?;
+                Ok(result)
+            }
+
+            Self::JsonExtract { column, fields, .. } => {
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                let paths = fields
+                    .iter()
+                    .map(|f| json::parse_path(&f.path))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| polars_err!(ComputeError: "{}", e))?;
+                let text = data.column(column)?.cast(&DataType::String)?;
+                // Each document is parsed once for all fields.
+                let mut values: Vec<Vec<Option<serde_json::Value>>> =
+                    vec![Vec::with_capacity(data.height()); fields.len()];
+                for document in text.str()?.iter() {
+                    let document =
+                        document.and_then(|d| serde_json::from_str::<serde_json::Value>(d).ok());
+                    for ((field, path), column) in fields.iter().zip(&paths).zip(&mut values) {
+                        column.push(
+                            document
+                                .as_ref()
+                                .and_then(|d| json::extract(d, path))
+                                .and_then(|v| field.dtype.convert(v)),
+                        );
+                    }
+                }
+
+                let mut result = data.clone();
+                for (field, values) in fields.iter().zip(values) {
+                    let name = field.name.as_str().into();
+                    let column = match field.dtype {
+                        JsonType::String => values
+                            .iter()
+                            .map(|v| v.as_ref().and_then(|v| v.as_str()))
+                            .collect::<StringChunked>()
+                            .with_name(name)
+                            .into_column(),
+                        JsonType::Float => values
+                            .iter()
+                            .map(|v| v.as_ref().and_then(|v| v.as_f64()))
+                            .collect::<Float64Chunked>()
+                            .with_name(name)
+                            .into_column(),
+                        JsonType::Int => values
+                            .iter()
+                            .map(|v| v.as_ref().and_then(|v| v.as_i64()))
+                            .collect::<Int64Chunked>()
+                            .with_name(name)
+                            .into_column(),
+                        JsonType::Bool => values
+                            .iter()
+                            .map(|v| v.as_ref().and_then(|v| v.as_bool()))
+                            .collect::<BooleanChunked>()
+                            .with_name(name)
+                            .into_column(),
+                    };
+                    result.with_column(column)?;
+                }
+                Ok(result)
+            }
+
+            Self::RandomFeature {
+                distribution,
+                seed,
+                name,
+                ..
+            } => {
+                let values = noise::column(
+                    noise::stream(*seed, name),
+                    data.height(),
+                    *distribution,
+                    &format!("feature_{name}"),
+                );
+                let mut result = data.clone();
+                result.with_column(values)?;
+                Ok(result)
+            }
+
+            Self::AnomalyScore { columns, name, .. } => {
+                let model = self.anomaly_model(data)?;
+                let mut result = data.clone();
+                result.with_column(model.column(data, columns, &format!("feature_{name}"))?)?;
+                Ok(result)
+            }
+
+            Self::QuantileTransform {
+                column,
+                distribution,
+                name,
+                ..
+            } => {
+                let map = self.quantile_map(data)?;
+                let mut result = data.clone();
+                result.with_column(map.column(
+                    data.column(column)?,
+                    *distribution,
+                    &format!("feature_{name}"),
+                )?)?;
+                Ok(result)
+            }
+
+            Self::MultiHot {
+                column, separator, ..
+            } => {
+                let tags = self.multi_hot_tags(data)?;
+                let mut result = data.clone();
+                for tag in multi_hot::columns(data.column(column)?, separator, &tags, column)? {
+                    result.with_column(tag)?;
+                }
+                Ok(result)
+            }
+
+            Self::PowerTransform { column, name, .. } => {
+                let fit = self.power_fit(data)?;
+                let mut result = data.clone();
+                result
+                    .with_column(fit.column(data.column(column)?, &format!("feature_{name}"))?)?;
+                Ok(result)
+            }
 
-                Ok(data
-                    .clone()
-                    .lazy()
-                    .with_columns([col(column).sum().over(groupby_cols).alias(feature_col_name)])
-                    .collect()?)
+            Self::WoeEncode { column, name, .. } => {
+                let table = self.woe_table(data)?;
+                let mut result = data.clone();
+                result
+                    .with_column(table.encode(data.column(column)?, &format!("feature_{name}"))?)?;
+                Ok(result)
             }
-            Self::Min {
-                column,
-                group_by,
+
+            Self::BlockingKey {
+                columns,
+                encoding,
                 name,
-            } if !group_by.is_empty() => {
-                let feature_col_name = format!("feature_{name}");
-                if !self.is_column_exists(data, column) {
+                ..
+            } => {
+                if let Some(column) = columns.iter().find(|c| !self.is_column_exists(data, c)) {
                     return Err(PipelineStepError::ColumnNotFound {
                         found: column.clone(),
                         available: data
@@ -351,33 +2554,44 @@ impl FeatureConfig {
                     });
                 }
 
-                for i in group_by.iter() {
-                    if !self.is_column_exists(data, i) {
-                        return Err(PipelineStepError::ColumnNotFound {
-                            found: i.clone(),
-                            available: data
-                                .get_column_names()
-                                .iter()
-                                .map(|s| s.to_string())
-                                .collect(),
-                        });
-                    }
-                }
+                let text = columns
+                    .iter()
+                    .map(|c| data.column(c)?.cast(&DataType::String))
+                    .collect::<PolarsResult<Vec<_>>>()?;
+                let text = text
+                    .iter()
+                    .map(|c| c.str())
+                    .collect::<PolarsResult<Vec<_>>>()?;
+                let keys: StringChunked = (0..data.height())
+                    .map(|row| {
+                        let values: Vec<Option<&str>> = text.iter().map(|c| c.get(row)).collect();
+                        blocking::key(&values, *encoding)
+                    })
+                    .collect();
 
-                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+                let mut result = data.clone();
+                result.with_column(keys.with_name(name.as_str().into()).into_column())?;
+                Ok(result)
+            }
 
-                Ok(data
-                    .clone()
-                    .lazy()
-                    .with_columns([col(column).min().over(groupby_cols).alias(feature_col_name)])
-                    .collect()?)
+            Self::CollapseRare {
+                column,
+                other,
+                name,
+                ..
+            } => {
+                let kept = self.kept_categories(data)?;
+                let mut result = data.clone();
+                result.with_column(kept.column(data.column(column)?, other, name)?)?;
+                Ok(result)
             }
-            Self::Count {
+
+            Self::CleanText {
                 column,
-                group_by,
+                operations,
                 name,
-            } if !group_by.is_empty() => {
-                let feature_col_name = format!("feature_{name}");
+                ..
+            } => {
                 if !self.is_column_exists(data, column) {
                     return Err(PipelineStepError::ColumnNotFound {
                         found: column.clone(),
@@ -389,40 +2603,28 @@ impl FeatureConfig {
                     });
                 }
 
-                for i in group_by.iter() {
-                    if !self.is_column_exists(data, i) {
-                        return Err(PipelineStepError::ColumnNotFound {
-                            found: i.clone(),
-                            available: data
-                                .get_column_names()
-                                .iter()
-                                .map(|s| s.to_string())
-                                .collect(),
-                        });
-                    }
-                }
-
-                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
-
-                Ok(data
-                    .clone()
-                    .lazy()
-                    .with_columns([col(column)
-                        .count()
-                        .over(groupby_cols)
-                        .alias(feature_col_name)])
-                    .collect()?)
+                let values = data.column(column)?.cast(&DataType::String)?;
+                let cleaned: StringChunked = values
+                    .str()?
+                    .iter()
+                    .map(|v| v.and_then(|v| text::clean(v, operations)))
+                    .collect();
+                let mut result = data.clone();
+                result.with_column(cleaned.with_name(name.as_str().into()).into_column())?;
+                Ok(result)
             }
 
-            Self::Ratio {
-                numerator,
-                denominator,
+            Self::ParseNumber {
+                column,
+                decimal,
+                thousands,
+                currency,
                 name,
+                ..
             } => {
-                let feature_col_name = format!("feature_{name}");
-                if !self.is_column_exists(data, numerator) {
+                if !self.is_column_exists(data, column) {
                     return Err(PipelineStepError::ColumnNotFound {
-                        found: numerator.clone(),
+                        found: column.clone(),
                         available: data
                             .get_column_names()
                             .iter()
@@ -431,9 +2633,33 @@ impl FeatureConfig {
                     });
                 }
 
-                if !self.is_column_exists(data, denominator) {
+                let values = data.column(column)?;
+                let numbers = if values.dtype().is_string() {
+                    values
+                        .str()?
+                        .iter()
+                        .map(|t| t.and_then(|t| number::parse(t, *decimal, *thousands, currency)))
+                        .collect::<Float64Chunked>()
+                        .into_column()
+                } else {
+                    values.cast(&DataType::Float64)?
+                };
+
+                let mut result = data.clone();
+                result.with_column(numbers.with_name(name.as_str().into()))?;
+                Ok(result)
+            }
+
+            Self::EmailDomain {
+                column,
+                output,
+                free_mail,
+                name,
+                ..
+            } => {
+                if !self.is_column_exists(data, column) {
                     return Err(PipelineStepError::ColumnNotFound {
-                        found: denominator.clone(),
+                        found: column.clone(),
                         available: data
                             .get_column_names()
                             .iter()
@@ -442,19 +2668,34 @@ impl FeatureConfig {
                     });
                 }
 
-                Ok(data
-                    .clone()
-                    .lazy()
-                    .with_columns([(col(numerator) / col(denominator)).alias(feature_col_name)])
-                    .collect()?)
+                let text = data.column(column)?.cast(&DataType::String)?;
+                let domains = text.str()?.iter().map(|t| t.and_then(email::domain));
+                let values = match output {
+                    EmailOutput::Domain => domains
+                        .collect::<StringChunked>()
+                        .with_name(name.as_str().into())
+                        .into_column(),
+                    EmailOutput::IsFreeMail => {
+                        let providers = email::free_mail(free_mail.as_deref());
+                        domains
+                            .map(|d| d.map(|d| providers.contains(&d)))
+                            .collect::<BooleanChunked>()
+                            .with_name(format!("feature_{name}").into())
+                            .into_column()
+                    }
+                };
+
+                let mut result = data.clone();
+                result.with_column(values)?;
+                Ok(result)
             }
 
-            Self::CountDistinct {
+            Self::UrlParse {
                 column,
-                group_by,
+                output,
                 name,
-            } if !group_by.is_empty() => {
-                let feature_col_name = format!("feature_{name}");
+                ..
+            } => {
                 if !self.is_column_exists(data, column) {
                     return Err(PipelineStepError::ColumnNotFound {
                         found: column.clone(),
@@ -466,7 +2707,33 @@ impl FeatureConfig {
                     });
                 }
 
-                for i in group_by.iter() {
+                let text = data.column(column)?.cast(&DataType::String)?;
+                let urls = text.str()?.iter().map(|t| t.and_then(url::parse));
+                let values = if output.is_key() {
+                    urls.map(|u| u.and_then(|u| output.key(&u)))
+                        .collect::<StringChunked>()
+                        .with_name(name.as_str().into())
+                        .into_column()
+                } else {
+                    urls.map(|u| u.and_then(|u| output.count(&u)))
+                        .collect::<UInt32Chunked>()
+                        .with_name(format!("feature_{name}").into())
+                        .into_column()
+                };
+
+                let mut result = data.clone();
+                result.with_column(values)?;
+                Ok(result)
+            }
+
+            Self::Geohash {
+                latitude,
+                longitude,
+                precision,
+                name,
+                ..
+            } => {
+                for i in [latitude, longitude] {
                     if !self.is_column_exists(data, i) {
                         return Err(PipelineStepError::ColumnNotFound {
                             found: i.clone(),
@@ -479,44 +2746,42 @@ impl FeatureConfig {
                     }
                 }
 
-                let groupby_cols: Vec<Expr> = group_by.iter().map(col).collect();
+                let lat = data.column(latitude)?.cast(&DataType::Float64)?;
+                let lon = data.column(longitude)?.cast(&DataType::Float64)?;
+                let hashes: StringChunked = lat
+                    .f64()?
+                    .iter()
+                    .zip(lon.f64()?.iter())
+                    .map(|(lat, lon)| geohash::encode(lat?, lon?, *precision))
+                    .collect();
 
-                Ok(data
-                    .clone()
-                    .lazy()
-                    .with_columns([col(column)
-                        .n_unique()
-                        .over(groupby_cols)
-                        .alias(feature_col_name)])
-                    .collect()?)
+                let mut result = data.clone();
+                result.with_column(hashes.with_name(name.as_str().into()).into_column())?;
+                Ok(result)
             }
 
-            Self::Threshold {
-                column,
-                threshold,
-                comparator,
+            Self::Expect {
                 name,
+                checks,
+                on_failure,
+                ..
             } => {
-                let feature_col_name = format!("feature_{name}");
-                match comparator.as_ref() {
-                    "gt" => Ok(data
-                        .clone()
-                        .lazy()
-                        .with_columns([col(column).gt(*threshold).alias(feature_col_name)])
-                        .collect()?),
-                    "lt" => Ok(data
-                        .clone()
-                        .lazy()
-                        .with_columns([col(column).lt(*threshold).alias(feature_col_name)])
-                        .collect()?),
-                    _ => Ok(data.clone()),
-                }
+                expect::enforce(&expect::evaluate(
+                    0,
+                    name.as_deref(),
+                    checks,
+                    *on_failure,
+                    data,
+                )?)?;
+                Ok(data.clone())
             }
 
             Self::Ohe {
                 columns,
                 drop_first,
                 drop_nulls,
+                handle_unknown,
+                ..
             } => {
                 for col in columns.iter() {
                     if !self.is_column_exists(data, col) {
@@ -530,33 +2795,995 @@ impl FeatureConfig {
                         });
                     }
                 }
+                let mut data = Cow::Borrowed(data);
+                for (column, kept) in self.ohe_kept(&data)? {
+                    let folded = kept.column(data.column(&column)?, OHE_OTHER, &column)?;
+                    data.to_mut().with_column(folded)?;
+                }
                 let col_strs: Vec<&str> = columns.iter().map(|s| s.as_str()).collect();
-                Ok(data.clone().columns_to_dummies(
+                let mut dummies = data.into_owned().columns_to_dummies(
                     col_strs,
                     Some("__ohe__"),
-                    *drop_first,
+                    false,
                     *drop_nulls,
-                )?)
+                )?;
+                if *drop_first {
+                    // Polars drops the first category seen, which moves with row
+                    // order; the smallest non-null one is the same every run.
+                    for column in columns {
+                        let prefix = format!("{}__ohe__", column);
+                        let first = dummies
+                            .get_column_names()
+                            .into_iter()
+                            .filter(|c| c.starts_with(&prefix) && c[prefix.len()..] != *"null")
+                            .filter(|c| {
+                                *handle_unknown != UnknownCategory::Other
+                                    || c[prefix.len()..] != *OHE_OTHER
+                            })
+                            .min()
+                            .cloned();
+                        if let Some(first) = first {
+                            dummies = dummies.drop(&first)?;
+                        }
+                    }
+                }
+                if *handle_unknown == UnknownCategory::Other {
+                    // Unseen categories need the column even when the data
+                    // has no `other` of its own.
+                    let dtype = dummies
+                        .get_columns()
+                        .iter()
+                        .find(|c| c.name().contains("__ohe__"))
+                        .map_or(DataType::Int32, |c| c.dtype().clone());
+                    for column in columns {
+                        let other = format!("{}__ohe__{}", column, OHE_OTHER);
+                        if dummies.column(&other).is_err() {
+                            let zeros = Column::full_null(other.into(), dummies.height(), &dtype)
+                                .fill_null(FillNullStrategy::Zero)?;
+                            dummies.with_column(zeros)?;
+                        }
+                    }
+                }
+                Ok(dummies)
             }
             _ => Ok(data.clone()),
         }
     }
 
+    /// The extra `holidays` of a `calendar` step; empty for other steps.
+    /// Unparseable dates are rejected when the config loads.
+    pub fn extra_holidays(&self) -> Vec<chrono::NaiveDate> {
+        match self {
+            Self::Calendar { holidays, .. } => holidays
+                .iter()
+                .filter_map(|day| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The distance an `anomaly_score` step computes, fitted on `data`.
+    pub(crate) fn anomaly_model(&self, data: &DataFrame) -> Result<Mahalanobis, PipelineStepError> {
+        let Self::AnomalyScore { columns, .. } = self else {
+            return Err(
+                polars_err!(InvalidOperation: "{} is not an anomaly_score step", self.function())
+                    .into(),
+            );
+        };
+        if let Some(column) = columns.iter().find(|c| !self.is_column_exists(data, c)) {
+            return Err(PipelineStepError::ColumnNotFound {
+                found: column.clone(),
+                available: data
+                    .get_column_names()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            });
+        }
+        Ok(Mahalanobis::fit(data, columns)?)
+    }
+
+    /// The `mean` step a `loo_target_encode` step is fitted as: new rows have
+    /// no target to leave out, so they get their group's full mean.
+    pub(crate) fn fitted_mean(&self) -> Option<Self> {
+        let Self::LooTargetEncode {
+            column,
+            group_by,
+            name,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        Some(Self::Mean {
+            column: column.clone(),
+            group_by: group_by.clone(),
+            name: name.clone(),
+            null_policy: Some(NullPolicy::Ignore),
+            description: None,
+            owner: None,
+            tags: Vec::new(),
+        })
+    }
+
+    /// The quantiles a `quantile_transform` step maps with, fitted on `data`.
+    pub fn quantile_map(&self, data: &DataFrame) -> Result<QuantileMap, PipelineStepError> {
+        let Self::QuantileTransform {
+            column, quantiles, ..
+        } = self
+        else {
+            return Err(polars_err!(
+                InvalidOperation: "{} is not a quantile_transform step", self.function()
+            )
+            .into());
+        };
+        if !self.is_column_exists(data, column) {
+            return Err(PipelineStepError::ColumnNotFound {
+                found: column.clone(),
+                available: data
+                    .get_column_names()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            });
+        }
+        Ok(QuantileMap::fit(data.column(column)?, *quantiles)?)
+    }
+
+    /// The lambda a `power_transform` step transforms with, fitted on `data`.
+    pub fn power_fit(&self, data: &DataFrame) -> Result<PowerFit, PipelineStepError> {
+        let Self::PowerTransform { column, method, .. } = self else {
+            return Err(polars_err!(
+                InvalidOperation: "{} is not a power_transform step", self.function()
+            )
+            .into());
+        };
+        if !self.is_column_exists(data, column) {
+            return Err(PipelineStepError::ColumnNotFound {
+                found: column.clone(),
+                available: data
+                    .get_column_names()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            });
+        }
+        Ok(PowerFit::fit(data.column(column)?, *method)?)
+    }
+
+    /// The non-null categories of each column an `ohe` step encodes, as
+    /// strings, learned from `data`; empty for other steps.
+    pub fn ohe_categories(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, BTreeSet<String>>, PipelineStepError> {
+        let Self::Ohe { columns, .. } = self else {
+            return Ok(BTreeMap::new());
+        };
+        columns
+            .iter()
+            .map(|column| {
+                let counts = profiling::value_counts(data.column(column)?)?;
+                Ok((column.clone(), counts.into_keys().collect()))
+            })
+            .collect()
+    }
+
+    /// The categories of each column a limited `ohe` step keeps, fitted on
+    /// `data`; only columns where some categories fold into `other`.
+    pub(crate) fn ohe_kept(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, KeptCategories>, PipelineStepError> {
+        let mut kept = BTreeMap::new();
+        let Self::Ohe {
+            columns,
+            max_categories,
+            min_frequency,
+            ..
+        } = self
+        else {
+            return Ok(kept);
+        };
+        if max_categories.is_none() && min_frequency.is_none() {
+            return Ok(kept);
+        }
+        for column in columns {
+            let categories =
+                KeptCategories::fit(data.column(column)?, None, *min_frequency, *max_categories)?;
+            if categories.collapsed > 0 {
+                kept.insert(column.clone(), categories);
+            }
+        }
+        Ok(kept)
+    }
+
+    /// The tags a `multi_hot` step writes columns for, learned from `data`.
+    pub fn multi_hot_tags(&self, data: &DataFrame) -> Result<Vec<String>, PipelineStepError> {
+        let Self::MultiHot {
+            column,
+            separator,
+            max_tags,
+            ..
+        } = self
+        else {
+            return Err(polars_err!(
+                InvalidOperation: "{} is not a multi_hot step", self.function()
+            )
+            .into());
+        };
+        if !self.is_column_exists(data, column) {
+            return Err(PipelineStepError::ColumnNotFound {
+                found: column.clone(),
+                available: data
+                    .get_column_names()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            });
+        }
+        Ok(multi_hot::fit(data.column(column)?, separator, *max_tags)?)
+    }
+
+    /// The categories a `collapse_rare` step keeps, fitted on `data`.
+    pub fn kept_categories(&self, data: &DataFrame) -> Result<KeptCategories, PipelineStepError> {
+        let Self::CollapseRare {
+            column,
+            min_count,
+            min_fraction,
+            ..
+        } = self
+        else {
+            return Err(polars_err!(
+                InvalidOperation: "{} is not a collapse_rare step", self.function()
+            )
+            .into());
+        };
+        if !self.is_column_exists(data, column) {
+            return Err(PipelineStepError::ColumnNotFound {
+                found: column.clone(),
+                available: data
+                    .get_column_names()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            });
+        }
+        KeptCategories::fit(data.column(column)?, *min_count, *min_fraction, None)
+    }
+
+    /// The bins and weights a `woe_encode` step encodes with, fitted on
+    /// `data`.
+    pub fn woe_table(&self, data: &DataFrame) -> Result<WoeTable, PipelineStepError> {
+        let Self::WoeEncode {
+            column,
+            target,
+            bins,
+            positive,
+            ..
+        } = self
+        else {
+            return Err(
+                polars_err!(InvalidOperation: "{} is not a woe_encode step", self.function())
+                    .into(),
+            );
+        };
+        if let Some(missing) = [column, target]
+            .into_iter()
+            .find(|c| !self.is_column_exists(data, c))
+        {
+            return Err(PipelineStepError::ColumnNotFound {
+                found: missing.clone(),
+                available: data
+                    .get_column_names()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            });
+        }
+        Ok(WoeTable::fit(
+            data.column(column)?,
+            data.column(target)?,
+            *bins,
+            positive.as_deref(),
+        )?)
+    }
+
     fn is_column_exists(&self, data: &DataFrame, col_name: &str) -> bool {
         data.get_column_names().iter().any(|col| *col == col_name)
     }
 
+    /// Match statistics of a `map` step against `data`; `None` for other steps.
+    pub fn join_report(&self, data: &DataFrame) -> Result<Option<JoinReport>, PipelineStepError> {
+        let Self::Map {
+            column,
+            mapping,
+            name,
+            min_coverage,
+            warn_coverage,
+            ..
+        } = self
+        else {
+            return Ok(None);
+        };
+        let keys = joins::row_keys(data, std::slice::from_ref(column))?;
+        let matched: Vec<bool> = keys
+            .iter()
+            .map(|k| k.as_ref().is_some_and(|k| mapping.contains_key(k)))
+            .collect();
+        Ok(Some(JoinReport::new(
+            name,
+            "map",
+            &keys,
+            &matched,
+            *min_coverage,
+            *warn_coverage,
+        )))
+    }
+
+    /// Why the step can't run as configured, e.g. an empty `name` or a
+    /// non-finite `threshold`; `None` when it's valid.
+    pub(crate) fn invalid(&self) -> Option<String> {
+        if self
+            .name()
+            .into_iter()
+            .chain(self.key_columns())
+            .any(|name| name.trim().is_empty())
+        {
+            return Some("'name' must not be empty".to_string());
+        }
+        if let Some(column) = self.input_columns().iter().find(|c| c.trim().is_empty()) {
+            return Some(format!("column name '{}' must not be empty", column));
+        }
+        match self {
+            Self::Threshold { threshold, .. } if !threshold.is_finite() => {
+                Some(format!("'threshold' must be finite, got {}", threshold))
+            }
+            Self::Map { mapping, .. } if mapping.is_empty() => {
+                Some("'mapping' must not be empty".to_string())
+            }
+            Self::RollingByTime { window, .. } => match Duration::try_parse(window) {
+                Ok(duration) if duration.is_zero() || duration.negative() => {
+                    Some(format!("'window' must be positive, got '{}'", window))
+                }
+                Ok(_) => None,
+                Err(_) => Some(format!(
+                    "'window' must be a duration such as '7d' or '12h', got '{}'",
+                    window
+                )),
+            },
+            Self::ParseNumber { column, name, .. } if column == name => {
+                Some("'name' must differ from 'column'".to_string())
+            }
+            Self::ParseNumber {
+                decimal, thousands, ..
+            } if Some(*decimal) == *thousands => {
+                Some("'decimal' and 'thousands' must differ".to_string())
+            }
+            Self::ParseNumber {
+                decimal, thousands, ..
+            } if [Some(*decimal), *thousands]
+                .into_iter()
+                .flatten()
+                .any(|c| c.is_ascii_digit() || c.is_whitespace()) =>
+            {
+                Some("'decimal' and 'thousands' must not be digits or whitespace".to_string())
+            }
+            Self::ParseNumber { currency, .. } if currency.iter().any(|c| c.is_empty()) => {
+                Some("'currency' must list non-empty symbols".to_string())
+            }
+            Self::CollapseRare {
+                min_count: None,
+                min_fraction: None,
+                ..
+            } => Some("set 'min_count', 'min_fraction' or both".to_string()),
+            Self::CollapseRare {
+                min_count: Some(0), ..
+            } => Some("'min_count' must be positive".to_string()),
+            Self::CollapseRare {
+                min_fraction: Some(fraction),
+                ..
+            } if !(*fraction > 0.0 && *fraction < 1.0) => Some(format!(
+                "'min_fraction' must be between 0 and 1, got {}",
+                fraction
+            )),
+            Self::Ohe {
+                max_categories: Some(0),
+                ..
+            } => Some("'max_categories' must be positive".to_string()),
+            Self::Ohe {
+                min_frequency: Some(fraction),
+                ..
+            } if !(*fraction > 0.0 && *fraction < 1.0) => Some(format!(
+                "'min_frequency' must be between 0 and 1, got {}",
+                fraction
+            )),
+            Self::MultiHot { separator, .. } if separator.is_empty() => {
+                Some("'separator' must not be empty".to_string())
+            }
+            Self::MultiHot {
+                max_tags: Some(0), ..
+            } => Some("'max_tags' must be positive".to_string()),
+            Self::CleanText { operations, .. } if operations.is_empty() => {
+                Some("'operations' must not be empty".to_string())
+            }
+            Self::BlockingKey { columns, .. } | Self::AnomalyScore { columns, .. }
+                if columns.is_empty() =>
+            {
+                Some("'columns' must not be empty".to_string())
+            }
+            Self::LooTargetEncode {
+                noise: Some(noise), ..
+            } if !(noise.is_finite() && *noise > 0.0) => {
+                Some(format!("'noise' must be positive, got {}", noise))
+            }
+            Self::QuantileTransform { quantiles, .. } if *quantiles < 2 => {
+                Some(format!("'quantiles' must be at least 2, got {}", quantiles))
+            }
+            Self::WoeEncode { bins, .. } if *bins < 2 => {
+                Some(format!("'bins' must be at least 2, got {}", bins))
+            }
+            Self::WoeEncode { column, target, .. } if column == target => {
+                Some("'target' must differ from 'column'".to_string())
+            }
+            Self::AnomalyScore { columns, .. } => {
+                let mut seen = BTreeSet::new();
+                columns
+                    .iter()
+                    .find(|c| !seen.insert(c.as_str()))
+                    .map(|c| format!("column '{}' is listed twice", c))
+            }
+            Self::JsonExtract { fields, .. } if fields.is_empty() => {
+                Some("'fields' must not be empty".to_string())
+            }
+            Self::JsonExtract { fields, .. } => fields
+                .iter()
+                .find_map(|f| json::parse_path(&f.path).err())
+                .or_else(|| {
+                    let mut names = BTreeSet::new();
+                    fields
+                        .iter()
+                        .find(|f| !names.insert(&f.name))
+                        .map(|f| format!("field name '{}' is used twice", f.name))
+                }),
+            Self::EmailDomain {
+                output: EmailOutput::Domain,
+                free_mail: Some(_),
+                ..
+            } => Some("'free_mail' only applies to output 'is_free_mail'".to_string()),
+            Self::EmailDomain {
+                free_mail: Some(providers),
+                ..
+            } if providers.is_empty() || providers.iter().any(|d| d.trim().is_empty()) => {
+                Some("'free_mail' must list non-empty domains".to_string())
+            }
+            Self::Geohash { precision, .. }
+                if !(1..=geohash::MAX_PRECISION).contains(precision) =>
+            {
+                Some(format!(
+                    "'precision' must be between 1 and {}, got {}",
+                    geohash::MAX_PRECISION,
+                    precision
+                ))
+            }
+            Self::Calendar {
+                country,
+                region: Some(region),
+                ..
+            } if !country.regions().contains(&region.as_str()) => Some(match country.regions() {
+                [] => format!("{:?} has no regions, got '{}'", country, region),
+                regions => format!(
+                    "'region' must be one of {} for {:?}, got '{}'",
+                    regions.join(", "),
+                    country,
+                    region
+                ),
+            }),
+            Self::Calendar { holidays, .. } => holidays
+                .iter()
+                .find(|day| chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").is_err())
+                .map(|day| format!("'holidays' must be dates as YYYY-MM-DD, got '{}'", day)),
+            Self::Map {
+                min_coverage,
+                warn_coverage,
+                ..
+            } => joins::invalid_threshold(*min_coverage, *warn_coverage)
+                .map(|field| format!("'{}' must be between 0 and 1", field)),
+            _ => None,
+        }
+    }
+
     pub fn name(&self) -> Option<&str> {
         match self {
             Self::Mean { name, .. }
+            | Self::LooTargetEncode { name, .. }
             | Self::Sum { name, .. }
             | Self::Max { name, .. }
             | Self::Min { name, .. }
             | Self::Count { name, .. }
+            | Self::CountNonNull { name, .. }
+            | Self::PercentRank { name, .. }
+            | Self::RollingByTime { name, .. }
+            | Self::Calendar { name, .. }
             | Self::CountDistinct { name, .. }
             | Self::Ratio { name, .. }
-            | Self::Threshold { name, .. } => Some(name),
-            Self::Ohe { .. } => None,
+            | Self::Threshold { name, .. }
+            | Self::ListLen { name, .. }
+            | Self::ListContains { name, .. }
+            | Self::ListAgg { name, .. }
+            | Self::AnomalyScore { name, .. }
+            | Self::WoeEncode { name, .. }
+            | Self::QuantileTransform { name, .. }
+            | Self::PowerTransform { name, .. }
+            | Self::RobustScale { name, .. }
+            | Self::RandomFeature { name, .. }
+            | Self::Map { name, .. } => Some(name),
+            Self::IpFeatures { name, output, .. } if !output.is_prefix() => Some(name),
+            Self::UrlParse { name, output, .. } if !output.is_key() => Some(name),
+            Self::EmailDomain { name, output, .. } if *output == EmailOutput::IsFreeMail => {
+                Some(name)
+            }
+            Self::IpFeatures { .. }
+            | Self::EmailDomain { .. }
+            | Self::ParseNumber { .. }
+            | Self::CleanText { .. }
+            | Self::CollapseRare { .. }
+            | Self::BlockingKey { .. }
+            | Self::JsonExtract { .. }
+            | Self::UrlParse { .. }
+            | Self::Geohash { .. }
+            | Self::Expect { .. }
+            | Self::Ohe { .. }
+            | Self::MultiHot { .. } => None,
+        }
+    }
+
+    /// Columns a step writes for later steps to read rather than as
+    /// features: those of `json_extract`, `parse_number`, `clean_text`,
+    /// `collapse_rare`, `blocking_key`, `geohash`, `ip_features` prefixes and
+    /// `url_parse` and `email_domain` domains. Empty for other steps.
+    pub fn key_columns(&self) -> Vec<&str> {
+        match self {
+            Self::JsonExtract { fields, .. } => fields.iter().map(|f| f.name.as_str()).collect(),
+            Self::Geohash { name, .. }
+            | Self::ParseNumber { name, .. }
+            | Self::CleanText { name, .. }
+            | Self::CollapseRare { name, .. }
+            | Self::BlockingKey { name, .. } => vec![name],
+            Self::IpFeatures { name, output, .. } if output.is_prefix() => vec![name],
+            Self::UrlParse { name, output, .. } if output.is_key() => vec![name],
+            Self::EmailDomain { name, output, .. } if *output == EmailOutput::Domain => {
+                vec![name]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn null_policy_mut(&mut self) -> Option<&mut Option<NullPolicy>> {
+        match self {
+            Self::Mean { null_policy, .. }
+            | Self::Sum { null_policy, .. }
+            | Self::Max { null_policy, .. }
+            | Self::Min { null_policy, .. }
+            | Self::CountNonNull { null_policy, .. }
+            | Self::PercentRank { null_policy, .. }
+            | Self::CountDistinct { null_policy, .. } => Some(null_policy),
+            _ => None,
+        }
+    }
+
+    /// Whether the step's value for a row depends on other rows: group
+    /// aggregations (the group's statistic), `ohe` (the set of categories),
+    /// `anomaly_score` (the mean and covariance), `woe_encode` (the weights),
+    /// `quantile_transform` (the quantiles), `power_transform` (the lambda),
+    /// `robust_scale` (the medians and IQRs), `collapse_rare` (the kept
+    /// categories) and `multi_hot` (the set of tags).
+    /// Such steps must see the full data; see
+    /// [`FittedPipeline`](crate::pipeline::fitted::FittedPipeline).
+    /// `rolling_by_time` is not fitted: a fitted pipeline rejects it, since
+    /// its windows need the rows before each batch.
+    pub fn requires_fit(&self) -> bool {
+        match self {
+            Self::Ohe { .. }
+            | Self::AnomalyScore { .. }
+            | Self::WoeEncode { .. }
+            | Self::QuantileTransform { .. }
+            | Self::PowerTransform { .. }
+            | Self::RobustScale { .. }
+            | Self::CollapseRare { .. }
+            | Self::MultiHot { .. } => true,
+            Self::RollingByTime { .. } => false,
+            _ => self.group_by().is_some_and(|g| !g.is_empty()),
+        }
+    }
+
+    pub fn group_by(&self) -> Option<&[String]> {
+        match self {
+            Self::Mean { group_by, .. }
+            | Self::LooTargetEncode { group_by, .. }
+            | Self::Sum { group_by, .. }
+            | Self::Max { group_by, .. }
+            | Self::Min { group_by, .. }
+            | Self::Count { group_by, .. }
+            | Self::CountNonNull { group_by, .. }
+            | Self::PercentRank { group_by, .. }
+            | Self::RollingByTime { group_by, .. }
+            | Self::CountDistinct { group_by, .. } => Some(group_by),
+            Self::Ratio { .. }
+            | Self::Threshold { .. }
+            | Self::ListLen { .. }
+            | Self::ListContains { .. }
+            | Self::ListAgg { .. }
+            | Self::AnomalyScore { .. }
+            | Self::WoeEncode { .. }
+            | Self::QuantileTransform { .. }
+            | Self::PowerTransform { .. }
+            | Self::RobustScale { .. }
+            | Self::RandomFeature { .. }
+            | Self::Calendar { .. }
+            | Self::IpFeatures { .. }
+            | Self::UrlParse { .. }
+            | Self::EmailDomain { .. }
+            | Self::JsonExtract { .. }
+            | Self::ParseNumber { .. }
+            | Self::CleanText { .. }
+            | Self::CollapseRare { .. }
+            | Self::BlockingKey { .. }
+            | Self::Geohash { .. }
+            | Self::Map { .. }
+            | Self::Expect { .. }
+            | Self::Ohe { .. }
+            | Self::MultiHot { .. } => None,
+        }
+    }
+
+    /// The `function` tag this step was configured with.
+    pub fn function(&self) -> &'static str {
+        match self {
+            Self::Mean { .. } => "mean",
+            Self::LooTargetEncode { .. } => "loo_target_encode",
+            Self::Sum { .. } => "sum",
+            Self::Max { .. } => "max",
+            Self::Min { .. } => "min",
+            Self::Threshold { .. } => "threshold",
+            Self::Ratio { .. } => "ratio",
+            Self::Map { .. } => "map",
+            Self::ListLen { .. } => "list_len",
+            Self::ListContains { .. } => "list_contains",
+            Self::ListAgg { .. } => "list_agg",
+            Self::AnomalyScore { .. } => "anomaly_score",
+            Self::WoeEncode { .. } => "woe_encode",
+            Self::QuantileTransform { .. } => "quantile_transform",
+            Self::PowerTransform { .. } => "power_transform",
+            Self::RobustScale { .. } => "robust_scale",
+            Self::RandomFeature { .. } => "random_feature",
+            Self::Expect { .. } => "expect",
+            Self::CountDistinct { .. } => "count_distinct",
+            Self::Count { .. } => "count",
+            Self::CountNonNull { .. } => "count_non_null",
+            Self::PercentRank { .. } => "percent_rank",
+            Self::RollingByTime { .. } => "rolling_by_time",
+            Self::Calendar { .. } => "calendar",
+            Self::IpFeatures { .. } => "ip_features",
+            Self::UrlParse { .. } => "url_parse",
+            Self::EmailDomain { .. } => "email_domain",
+            Self::JsonExtract { .. } => "json_extract",
+            Self::ParseNumber { .. } => "parse_number",
+            Self::CleanText { .. } => "clean_text",
+            Self::CollapseRare { .. } => "collapse_rare",
+            Self::BlockingKey { .. } => "blocking_key",
+            Self::Geohash { .. } => "geohash",
+            Self::Ohe { .. } => "ohe",
+            Self::MultiHot { .. } => "multi_hot",
+        }
+    }
+
+    /// Input columns the step reads.
+    pub fn input_columns(&self) -> Vec<&str> {
+        match self {
+            Self::Mean {
+                column, group_by, ..
+            }
+            | Self::LooTargetEncode {
+                column, group_by, ..
+            }
+            | Self::RobustScale {
+                column, group_by, ..
+            }
+            | Self::Sum {
+                column, group_by, ..
+            }
+            | Self::Max {
+                column, group_by, ..
+            }
+            | Self::Min {
+                column, group_by, ..
+            }
+            | Self::Count {
+                column, group_by, ..
+            }
+            | Self::CountNonNull {
+                column, group_by, ..
+            }
+            | Self::PercentRank {
+                column, group_by, ..
+            }
+            | Self::CountDistinct {
+                column, group_by, ..
+            } => std::iter::once(column)
+                .chain(group_by)
+                .map(|c| c.as_str())
+                .collect(),
+            Self::RollingByTime {
+                column,
+                time_column,
+                group_by,
+                ..
+            } => [column, time_column]
+                .into_iter()
+                .chain(group_by)
+                .map(|c| c.as_str())
+                .collect(),
+            Self::Threshold { column, .. }
+            | Self::ListLen { column, .. }
+            | Self::ListContains { column, .. }
+            | Self::ListAgg { column, .. }
+            | Self::Calendar { column, .. }
+            | Self::IpFeatures { column, .. }
+            | Self::UrlParse { column, .. }
+            | Self::EmailDomain { column, .. }
+            | Self::JsonExtract { column, .. }
+            | Self::ParseNumber { column, .. }
+            | Self::CleanText { column, .. }
+            | Self::CollapseRare { column, .. }
+            | Self::MultiHot { column, .. }
+            | Self::Map { column, .. } => vec![column],
+            Self::Ratio {
+                numerator,
+                denominator,
+                ..
+            } => vec![numerator, denominator],
+            Self::Geohash {
+                latitude,
+                longitude,
+                ..
+            } => vec![latitude, longitude],
+            Self::Expect { checks, .. } => checks.iter().flat_map(|c| c.columns()).collect(),
+            Self::RandomFeature { .. } => Vec::new(),
+            Self::WoeEncode { column, target, .. } => vec![column, target],
+            Self::QuantileTransform { column, .. } | Self::PowerTransform { column, .. } => {
+                vec![column]
+            }
+            Self::Ohe { columns, .. }
+            | Self::BlockingKey { columns, .. }
+            | Self::AnomalyScore { columns, .. } => columns.iter().map(|c| c.as_str()).collect(),
+        }
+    }
+
+    pub fn metadata(&self) -> StepMetadata<'_> {
+        match self {
+            Self::Mean {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::LooTargetEncode {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Sum {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Max {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Min {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Threshold {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Ratio {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::ListLen {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::ListContains {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::ListAgg {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::AnomalyScore {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::WoeEncode {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::QuantileTransform {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::PowerTransform {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::RobustScale {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::RandomFeature {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::CountDistinct {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Count {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::CountNonNull {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::PercentRank {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::RollingByTime {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Calendar {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::IpFeatures {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::UrlParse {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::EmailDomain {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::JsonExtract {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::ParseNumber {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::CollapseRare {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::CleanText {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::BlockingKey {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Geohash {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Map {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Expect {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::Ohe {
+                description,
+                owner,
+                tags,
+                ..
+            }
+            | Self::MultiHot {
+                description,
+                owner,
+                tags,
+                ..
+            } => StepMetadata {
+                description: description.as_deref(),
+                owner: owner.as_deref(),
+                tags,
+            },
         }
     }
 }
+
+/// Descriptive fields shared by every step: who owns it and why it exists.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct StepMetadata<'a> {
+    pub description: Option<&'a str>,
+    pub owner: Option<&'a str>,
+    pub tags: &'a [String],
+}
@@ -0,0 +1,1524 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{Datelike, NaiveDate};
+use ndarray::{Array1, Array2};
+use polars::prelude::*;
+use serde_json::Value;
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::anomaly::Mahalanobis;
+use crate::pipeline::blocking;
+use crate::pipeline::calendar::{Calendar, CalendarOutput};
+use crate::pipeline::email::{self, EmailOutput};
+use crate::pipeline::features::{
+    Comparator, FeatureConfig, FeaturePipeline, ListAgg, NullPolicy, OHE_OTHER, UnknownCategory,
+    robust_stats,
+};
+use crate::pipeline::geohash;
+use crate::pipeline::ip;
+use crate::pipeline::json;
+use crate::pipeline::multi_hot;
+use crate::pipeline::noise;
+use crate::pipeline::number;
+use crate::pipeline::power::PowerFit;
+use crate::pipeline::quantile::QuantileMap;
+use crate::pipeline::rare::KeptCategories;
+use crate::pipeline::text;
+use crate::pipeline::url;
+use crate::pipeline::woe::WoeTable;
+
+/// Separator `ohe` puts between a column name and its category.
+const OHE_SEPARATOR: &str = "__ohe__";
+
+/// Column [`FittedPipeline::to_matrix`] carries the target in while rows are
+/// dropped.
+const TARGET_COLUMN: &str = "__target__";
+
+/// Features, their column names and the target vector, as returned by
+/// [`FittedPipeline::to_matrix`].
+pub type FeatureMatrix<T> = (Array2<T>, Vec<String>, Option<Array1<f64>>);
+
+/// Sorted values of a `percent_rank` column per group key.
+type GroupValues = HashMap<Vec<Option<String>>, Option<Vec<f64>>>;
+
+/// Median and scale of a `robust_scale` column per group key; the empty key
+/// without `group_by`.
+type GroupScales = HashMap<Vec<Option<String>>, Option<(f64, f64)>>;
+
+/// How [`FittedPipeline::to_matrix`] treats null features.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MatrixNulls {
+    /// Fail, naming the first feature with nulls.
+    #[default]
+    Error,
+    /// Keep them as NaN, for models that handle missing values.
+    Nan,
+    /// Replace them with a constant, e.g. 0.
+    Fill(f64),
+    /// Drop the rows with any null feature.
+    DropRows,
+}
+
+/// What a step learned from the fit data.
+#[derive(Debug, Clone)]
+enum Fitted {
+    /// Nothing; the step is applied to each batch as configured.
+    Stateless,
+    /// The group aggregation's value per group, keyed by the `group_by`
+    /// columns cast to strings. Groups absent from the fit data get null.
+    Groups {
+        key_columns: Vec<String>,
+        table: DataFrame,
+        /// `table` as a hash map, for [`FittedPipeline::transform_row`].
+        values: HashMap<Vec<Option<String>>, Option<f64>>,
+    },
+    /// The sorted non-null values of `percent_rank`'s column per group, keyed
+    /// like `Groups`, which rows are ranked against; `None` for groups with
+    /// nulls under the `propagate` policy.
+    Ranks { values: GroupValues },
+    /// The mean and covariance `anomaly_score` rows are scored against.
+    Anomaly { model: Mahalanobis },
+    /// The bins and weights of evidence `woe_encode` encodes with; the
+    /// target isn't read again.
+    Woe { table: WoeTable },
+    /// The fit data's quantiles `quantile_transform` maps values between.
+    Quantiles { map: QuantileMap },
+    /// The lambda `power_transform` transforms with.
+    Power { fit: PowerFit },
+    /// The categories `collapse_rare` keeps; others, including ones absent
+    /// from the fit data, are collapsed.
+    Categories { kept: KeptCategories },
+    /// The tags `multi_hot` writes columns for, sorted. Tags absent from the
+    /// fit data are ignored.
+    Tags { tags: Vec<String> },
+    /// The median and scale `robust_scale` applies per group, keyed like
+    /// `Groups`. Groups absent from the fit data get null.
+    Scales { values: GroupScales },
+    /// The dummy columns `ohe` produced: source column, category (`None` for
+    /// nulls) and dummy column name, and how each source column's values
+    /// map onto the categories.
+    Dummies {
+        dummies: Vec<(String, Option<String>, String)>,
+        sources: BTreeMap<String, OheSource>,
+        dtype: DataType,
+    },
+}
+
+/// What an `ohe` step learned about one source column.
+#[derive(Debug, Clone)]
+struct OheSource {
+    /// Non-null categories of the fit data, as strings.
+    seen: BTreeSet<String>,
+    /// The categories kept under `max_categories` or `min_frequency`, when
+    /// some fold into `other`.
+    kept: Option<KeptCategories>,
+    unknown: UnknownCategory,
+}
+
+impl OheSource {
+    /// The category whose dummy `value` of the column `source` sets.
+    fn category<'a>(&'a self, source: &str, value: &'a str) -> PolarsResult<&'a str> {
+        if !self.seen.contains(value) {
+            match self.unknown {
+                UnknownCategory::Ignore => {}
+                UnknownCategory::Other => return Ok(OHE_OTHER),
+                UnknownCategory::Error => {
+                    return Err(polars_err!(
+                        ComputeError: "column '{}' has category '{}', absent from the fit data",
+                        source,
+                        value
+                    ));
+                }
+            }
+        }
+        Ok(match &self.kept {
+            Some(kept) => kept.value(value, OHE_OTHER),
+            None => value,
+        })
+    }
+}
+
+/// A feature pipeline whose steps that look beyond the current row were
+/// evaluated on the full data once, so that the pipeline can then be applied
+/// to a large input batch by batch in bounded memory.
+///
+/// Steps that need fitting (see [`FeatureConfig::requires_fit`]) are group
+/// aggregations, whose per-group values are looked up for each batch
+/// (`loo_target_encode`'s as full group means), `ohe`, whose categories are
+/// fixed so every batch gets the same columns, `anomaly_score`, whose rows
+/// are scored against the fit data's mean and covariance, `woe_encode`,
+/// whose weights come from the fit data's target, `quantile_transform`,
+/// which maps values through the fit data's quantiles, `power_transform`,
+/// whose lambda is fitted once, `robust_scale`, which applies the fit
+/// data's medians and IQRs, `collapse_rare`, which keeps the fit data's
+/// frequent categories, and `multi_hot`, whose tag vocabulary is fixed so
+/// every batch gets the same columns. `ohe` dummies are named
+/// `<column>__ohe__<category>` and `multi_hot` ones `<column>__mh__<tag>`.
+/// All other steps run on each batch alone: `map` coverage thresholds and
+/// `expect` checks are therefore enforced per batch. Pipelines with
+/// `rolling_by_time` steps, whose trailing windows need earlier rows, are
+/// rejected.
+#[derive(Debug, Clone)]
+pub struct FittedPipeline {
+    steps: Vec<(FeatureConfig, Fitted)>,
+    columns: Vec<String>,
+    /// Positions in `columns` of each step's outputs, in the order
+    /// `row_step` returns them.
+    outputs: Vec<Vec<usize>>,
+}
+
+impl FittedPipeline {
+    /// Fits `pipeline` on `data`, which should be the full input (or a sample
+    /// covering every group and category).
+    pub fn fit(pipeline: &FeaturePipeline, data: &DataFrame) -> Result<Self, PipelineStepError> {
+        let data = &*pipeline.with_key_columns(data)?;
+        let mut steps = Vec::with_capacity(pipeline.steps.len());
+        let mut columns = Vec::new();
+        for (i, step) in pipeline.steps.iter().enumerate() {
+            // Cutting windows at batch boundaries would silently differ from
+            // the features the pipeline was trained on.
+            if let FeatureConfig::RollingByTime { .. } = step {
+                let error = polars_err!(
+                    InvalidOperation: "rolling_by_time windows reach back over earlier rows, which a fitted pipeline doesn't keep; compute it with `run` instead"
+                );
+                return Err(PipelineStepError::in_step(
+                    i,
+                    step.function(),
+                    step.name(),
+                    data,
+                    error.into(),
+                ));
+            }
+            let feature = step.name().map(|name| format!("feature_{}", name));
+            if !step.requires_fit() {
+                columns.extend(feature);
+                steps.push((step.clone(), Fitted::Stateless));
+                continue;
+            }
+
+            if let FeatureConfig::PercentRank {
+                column,
+                group_by,
+                null_policy,
+                ..
+            } = step
+            {
+                // Checks the columns and the `error` null policy.
+                let values = step
+                    .feature_expr(data)
+                    .and_then(|_| rank_values(data, group_by, column, *null_policy))
+                    .map_err(|e| {
+                        PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                    })?;
+                columns.extend(feature);
+                steps.push((step.clone(), Fitted::Ranks { values }));
+                continue;
+            }
+
+            if let FeatureConfig::AnomalyScore { .. } = step {
+                let model = step.anomaly_model(data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                })?;
+                columns.extend(feature);
+                steps.push((step.clone(), Fitted::Anomaly { model }));
+                continue;
+            }
+
+            if let FeatureConfig::WoeEncode { .. } = step {
+                let table = step.woe_table(data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                })?;
+                columns.extend(feature);
+                steps.push((step.clone(), Fitted::Woe { table }));
+                continue;
+            }
+
+            if let FeatureConfig::RobustScale {
+                column, group_by, ..
+            } = step
+            {
+                let values = step
+                    .feature_expr(data)
+                    .and_then(|_| scale_values(data, group_by, column))
+                    .map_err(|e| {
+                        PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                    })?;
+                columns.extend(feature);
+                steps.push((step.clone(), Fitted::Scales { values }));
+                continue;
+            }
+
+            if let FeatureConfig::QuantileTransform { .. } = step {
+                let map = step.quantile_map(data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                })?;
+                columns.extend(feature);
+                steps.push((step.clone(), Fitted::Quantiles { map }));
+                continue;
+            }
+
+            if let FeatureConfig::CollapseRare { .. } = step {
+                let kept = step.kept_categories(data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                })?;
+                steps.push((step.clone(), Fitted::Categories { kept }));
+                continue;
+            }
+
+            if let FeatureConfig::MultiHot { column, .. } = step {
+                let tags = step.multi_hot_tags(data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                })?;
+                columns.extend(
+                    tags.iter()
+                        .map(|tag| format!("{}{}{}", column, multi_hot::SEPARATOR, tag)),
+                );
+                steps.push((step.clone(), Fitted::Tags { tags }));
+                continue;
+            }
+
+            if let FeatureConfig::PowerTransform { .. } = step {
+                let fit = step.power_fit(data).map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                })?;
+                columns.extend(feature);
+                steps.push((step.clone(), Fitted::Power { fit }));
+                continue;
+            }
+
+            let output = step
+                .fitted_mean()
+                .as_ref()
+                .unwrap_or(step)
+                .apply_feature(data)
+                .map_err(|e| {
+                    PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                })?;
+            let fitted = match (step.group_by(), feature) {
+                (Some(group_by), Some(feature)) => {
+                    let key_columns: Vec<String> =
+                        group_by.iter().map(|k| format!("__key__{k}")).collect();
+                    let table = output
+                        .lazy()
+                        .select(
+                            group_by
+                                .iter()
+                                .zip(&key_columns)
+                                .map(|(k, alias)| col(k).cast(DataType::String).alias(alias))
+                                .chain([col(&feature)])
+                                .collect::<Vec<_>>(),
+                        )
+                        .collect()?
+                        .unique_stable(Some(&key_columns), UniqueKeepStrategy::First, None)?;
+                    let values = group_values(&table, &key_columns, &feature)?;
+                    columns.push(feature);
+                    Fitted::Groups {
+                        key_columns,
+                        table,
+                        values,
+                    }
+                }
+                _ => {
+                    let mut dummies = Vec::new();
+                    let mut dtype = DataType::UInt8;
+                    for dummy in output.get_columns() {
+                        let Some((source, category)) = dummy.name().split_once(OHE_SEPARATOR)
+                        else {
+                            continue;
+                        };
+                        dtype = dummy.dtype().clone();
+                        dummies.push((
+                            source.to_string(),
+                            (category != "null").then(|| category.to_string()),
+                            dummy.name().to_string(),
+                        ));
+                        columns.push(dummy.name().to_string());
+                    }
+                    let sources = ohe_sources(step, data).map_err(|e| {
+                        PipelineStepError::in_step(i, step.function(), step.name(), data, e)
+                    })?;
+                    Fitted::Dummies {
+                        dummies,
+                        sources,
+                        dtype,
+                    }
+                }
+            };
+            steps.push((step.clone(), fitted));
+        }
+
+        columns.sort();
+        columns.dedup();
+        let outputs = steps
+            .iter()
+            .map(|(step, fitted)| {
+                let names: Vec<String> = match fitted {
+                    Fitted::Dummies { dummies, .. } => {
+                        dummies.iter().map(|(.., name)| name.clone()).collect()
+                    }
+                    Fitted::Tags { tags } => {
+                        let column = step.input_columns()[0];
+                        tags.iter()
+                            .map(|tag| format!("{}{}{}", column, multi_hot::SEPARATOR, tag))
+                            .collect()
+                    }
+                    _ => step
+                        .name()
+                        .filter(|_| !matches!(step, FeatureConfig::Expect { .. }))
+                        .map(|name| vec![format!("feature_{}", name)])
+                        .unwrap_or_default(),
+                };
+                names
+                    .iter()
+                    .filter_map(|name| columns.binary_search(name).ok())
+                    .collect()
+            })
+            .collect();
+        Ok(Self {
+            steps,
+            columns,
+            outputs,
+        })
+    }
+
+    /// Output columns, the same for every batch.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// The feature columns for one batch of input rows, in row order.
+    /// `random_feature` numbers the batch's rows from 0, so a seeded step
+    /// draws the same values for every batch passed here; see
+    /// [`transform_batches`](Self::transform_batches).
+    pub fn transform(&self, batch: &DataFrame) -> Result<DataFrame, PipelineStepError> {
+        self.transform_from(batch, 0)
+    }
+
+    /// [`transform`](Self::transform) with the batch's first row numbered
+    /// `first_row`.
+    fn transform_from(
+        &self,
+        batch: &DataFrame,
+        first_row: u64,
+    ) -> Result<DataFrame, PipelineStepError> {
+        let mut result = batch.clone();
+        for (i, (step, fitted)) in self.steps.iter().enumerate() {
+            let input = &result;
+            let output = match fitted {
+                Fitted::Stateless if matches!(step, FeatureConfig::RandomFeature { .. }) => {
+                    random_columns(input, step, first_row)
+                }
+                Fitted::Stateless => step.apply_feature(input),
+                Fitted::Groups {
+                    key_columns, table, ..
+                } => lookup(
+                    input,
+                    step.group_by().unwrap_or_default(),
+                    key_columns,
+                    table,
+                ),
+                Fitted::Ranks { values } => rank_columns(input, step, values),
+                Fitted::Anomaly { model } => anomaly_columns(input, step, model),
+                Fitted::Woe { table } => woe_columns(input, step, table),
+                Fitted::Quantiles { map } => quantile_columns(input, step, map),
+                Fitted::Power { fit } => power_columns(input, step, fit),
+                Fitted::Categories { kept } => rare_columns(input, step, kept),
+                Fitted::Tags { tags } => tag_columns(input, step, tags),
+                Fitted::Scales { values } => scale_columns(input, step, values),
+                Fitted::Dummies {
+                    dummies,
+                    sources,
+                    dtype,
+                } => dummy_columns(input, dummies, sources, dtype),
+            };
+            result = output.map_err(|e| {
+                PipelineStepError::in_step(i, step.function(), step.name(), input, e)
+            })?;
+        }
+        Ok(result.select(self.columns.iter().cloned())?)
+    }
+
+    /// The features of a single row, as floats in [`columns`](Self::columns)
+    /// order, without building a frame: group values are looked up in tables
+    /// hashed at [`fit`](Self::fit) and every other step is evaluated on the
+    /// row's values directly. For online scoring, where a one-row
+    /// [`transform`](Self::transform) costs more than the features themselves.
+    ///
+    /// `threshold` flags and dummies are 1 or 0. `expect` checks and `map`
+    /// coverage describe whole batches and are not enforced. A row has no
+    /// position in the data, so a seeded `random_feature` gives every row the
+    /// value of row 0; it is only meaningful in `run` and
+    /// [`transform_batches`](Self::transform_batches).
+    pub fn transform_row(
+        &self,
+        row: &HashMap<String, Value>,
+    ) -> Result<Vec<Option<f64>>, PipelineStepError> {
+        // `None` until the step writing the column has run, so a step reads
+        // a feature computed before it and the input otherwise.
+        let mut features: Vec<Option<Option<f64>>> = vec![None; self.columns.len()];
+        let keys = self.key_values(row)?;
+        for (i, ((step, fitted), outputs)) in self.steps.iter().zip(&self.outputs).enumerate() {
+            let cell = |column: &str| -> Result<Cell<'_>, PipelineStepError> {
+                let feature = self
+                    .columns
+                    .binary_search_by(|c| c.as_str().cmp(column))
+                    .ok()
+                    .and_then(|index| features[index]);
+                match (feature, keys.get(column).or_else(|| row.get(column))) {
+                    (Some(value), _) => Ok(Cell::Feature(value)),
+                    (None, Some(value)) => Ok(Cell::Input(value)),
+                    (None, None) => Err(PipelineStepError::ColumnNotFound {
+                        found: column.to_string(),
+                        available: row.keys().cloned().collect(),
+                    }),
+                }
+            };
+            let values = row_step(step, fitted, cell).map_err(|e| PipelineStepError::Step {
+                step: i + 1,
+                function: step.function(),
+                name: step.name().map(str::to_string),
+                source: Box::new(e),
+            })?;
+            for (&index, value) in outputs.iter().zip(values) {
+                features[index] = Some(value);
+            }
+        }
+        Ok(features.into_iter().map(Option::flatten).collect())
+    }
+
+    /// The columns steps such as `geohash` and `parse_number` add to `row`,
+    /// as the values steps reading them see.
+    fn key_values(
+        &self,
+        row: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, PipelineStepError> {
+        let mut keys = HashMap::new();
+        for (i, (step, fitted)) in self.steps.iter().enumerate() {
+            let names = step.key_columns();
+            if names.is_empty() {
+                continue;
+            }
+            // Key columns earlier steps wrote are read like input columns.
+            let input = |column: &str| match keys.get(column).or_else(|| row.get(column)) {
+                Some(value) => Ok(Cell::Input(value)),
+                None => Err(PipelineStepError::ColumnNotFound {
+                    found: column.to_string(),
+                    available: row.keys().cloned().collect(),
+                }),
+            };
+            let text = |column: &str| input(column).map(Cell::key);
+            let values: Result<Vec<Option<Value>>, PipelineStepError> = match step {
+                FeatureConfig::JsonExtract { column, fields, .. } => text(column).map(|text| {
+                    let document = text.and_then(|t| serde_json::from_str::<Value>(&t).ok());
+                    fields
+                        .iter()
+                        .map(|field| {
+                            let path = json::parse_path(&field.path).ok()?;
+                            json::extract(document.as_ref()?, &path)
+                                .and_then(|v| field.dtype.convert(v))
+                        })
+                        .collect()
+                }),
+                FeatureConfig::Geohash {
+                    latitude,
+                    longitude,
+                    precision,
+                    ..
+                } => input(latitude)
+                    .and_then(|lat| lat.number(latitude))
+                    .and_then(|lat| Ok((lat, input(longitude)?.number(longitude)?)))
+                    .map(|point| match point {
+                        (Some(lat), Some(lon)) => geohash::encode(lat, lon, *precision),
+                        _ => None,
+                    })
+                    .map(|hash| vec![hash.map(Value::String)]),
+                FeatureConfig::IpFeatures { column, output, .. } => text(column).map(|text| {
+                    let prefix = text
+                        .and_then(|t| ip::parse(&t))
+                        .and_then(|a| output.prefix(a));
+                    vec![prefix.map(Value::String)]
+                }),
+                FeatureConfig::BlockingKey {
+                    columns, encoding, ..
+                } => columns
+                    .iter()
+                    .map(|c| text(c))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|values| {
+                        let values: Vec<Option<&str>> =
+                            values.iter().map(Option::as_deref).collect();
+                        vec![blocking::key(&values, *encoding).map(Value::String)]
+                    }),
+                FeatureConfig::ParseNumber {
+                    column,
+                    decimal,
+                    thousands,
+                    currency,
+                    ..
+                } => input(column).and_then(|c| {
+                    let value = match c {
+                        Cell::Input(Value::String(text)) => {
+                            number::parse(text, *decimal, *thousands, currency)
+                        }
+                        other => other.number(column)?,
+                    };
+                    Ok(vec![value.map(Value::from)])
+                }),
+                FeatureConfig::CleanText {
+                    column, operations, ..
+                } => text(column).map(|value| {
+                    vec![
+                        value
+                            .and_then(|v| text::clean(&v, operations))
+                            .map(Value::String),
+                    ]
+                }),
+                FeatureConfig::CollapseRare { column, other, .. } => text(column).map(|value| {
+                    let Fitted::Categories { kept } = fitted else {
+                        return vec![None];
+                    };
+                    vec![value.map(|v| Value::String(kept.value(&v, other).to_string()))]
+                }),
+                FeatureConfig::EmailDomain { column, .. } => text(column)
+                    .map(|text| vec![text.and_then(|t| email::domain(&t)).map(Value::String)]),
+                FeatureConfig::UrlParse { column, output, .. } => text(column).map(|text| {
+                    let key = text.and_then(|t| url::parse(&t).and_then(|u| output.key(&u)));
+                    vec![key.map(Value::String)]
+                }),
+                _ => Ok(Vec::new()),
+            };
+            let values = values.map_err(|e| PipelineStepError::Step {
+                step: i + 1,
+                function: step.function(),
+                name: names.first().map(|n| n.to_string()),
+                source: Box::new(e),
+            })?;
+            for (name, value) in names.into_iter().zip(values) {
+                keys.insert(name.to_string(), value.unwrap_or(Value::Null));
+            }
+        }
+        Ok(keys)
+    }
+
+    /// The features of `data` as a matrix for Rust ML crates such as linfa,
+    /// with the [`columns`](Self::columns) its columns stand for and, given a
+    /// `target` column of `data`, the target vector. Features are cast to
+    /// `N`, e.g. [`Float32Type`]; `threshold` flags and dummies become 1 or 0.
+    ///
+    /// Rows with a null target are dropped, as training does; null features
+    /// are handled per `nulls`.
+    pub fn to_matrix<N: PolarsNumericType>(
+        &self,
+        data: &DataFrame,
+        nulls: MatrixNulls,
+        target: Option<&str>,
+    ) -> Result<FeatureMatrix<N::Native>, PipelineStepError> {
+        let mut features = self.transform(data)?;
+        if let Some(target) = target {
+            let values = data.column(target)?.cast(&DataType::Float64)?;
+            features.with_column(values.with_name(TARGET_COLUMN.into()))?;
+            features = features.drop_nulls(Some(&[TARGET_COLUMN.to_string()]))?;
+        }
+
+        let columns: Vec<Expr> = self
+            .columns
+            .iter()
+            .map(|c| {
+                let value = col(c).cast(DataType::Float64);
+                match nulls {
+                    MatrixNulls::Nan => value.fill_null(lit(f64::NAN)),
+                    MatrixNulls::Fill(fill) => value.fill_null(lit(fill)),
+                    MatrixNulls::Error | MatrixNulls::DropRows => value,
+                }
+            })
+            .collect();
+        let mut features = features.lazy().with_columns(columns).collect()?;
+        match nulls {
+            MatrixNulls::Error => {
+                if let Some(column) = self
+                    .columns
+                    .iter()
+                    .find(|c| features.column(c).is_ok_and(|c| c.null_count() > 0))
+                {
+                    return Err(polars_err!(
+                        ComputeError: "feature '{}' has {} null(s); pick a null policy other than error",
+                        column,
+                        features.column(column)?.null_count()
+                    )
+                    .into());
+                }
+            }
+            MatrixNulls::DropRows => features = features.drop_nulls(Some(&self.columns))?,
+            MatrixNulls::Nan | MatrixNulls::Fill(_) => {}
+        }
+
+        let matrix = features
+            .select(self.columns.iter().cloned())?
+            .to_ndarray::<N>(IndexOrder::C)?;
+        let target = match target {
+            Some(_) => Some(
+                features
+                    .column(TARGET_COLUMN)?
+                    .f64()?
+                    .into_no_null_iter()
+                    .collect::<Array1<f64>>(),
+            ),
+            None => None,
+        };
+        Ok((matrix, self.columns.clone(), target))
+    }
+
+    /// Transforms `batches` one at a time, e.g. chunks of a file too large to
+    /// load whole. Only the current batch and its features are held in memory.
+    /// Rows are numbered across batches, so a seeded `random_feature` draws
+    /// the same values as over the whole input at once.
+    pub fn transform_batches<'a>(
+        &'a self,
+        batches: impl Iterator<Item = DataFrame> + 'a,
+    ) -> impl Iterator<Item = Result<DataFrame, PipelineStepError>> + 'a {
+        let mut first_row = 0;
+        batches.map(move |batch| {
+            let result = self.transform_from(&batch, first_row);
+            first_row += batch.height() as u64;
+            result
+        })
+    }
+}
+
+fn lookup(
+    data: &DataFrame,
+    group_by: &[String],
+    key_columns: &[String],
+    table: &DataFrame,
+) -> Result<DataFrame, PipelineStepError> {
+    Ok(data
+        .clone()
+        .lazy()
+        .join(
+            table.clone().lazy(),
+            group_by
+                .iter()
+                .map(|k| col(k).cast(DataType::String))
+                .collect::<Vec<_>>(),
+            key_columns.iter().map(col).collect::<Vec<_>>(),
+            JoinArgs {
+                maintain_order: MaintainOrderJoin::Left,
+                // Window aggregations treat null keys as a group of their own.
+                nulls_equal: true,
+                ..JoinArgs::new(JoinType::Left).with_coalesce(JoinCoalesce::KeepColumns)
+            },
+        )
+        .drop(by_name(key_columns.iter().cloned(), true))
+        .collect()?)
+}
+
+/// Adds `percent_rank` feature of `step` to `data`, ranking each row's value
+/// against the fitted values of its group.
+fn rank_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    values: &GroupValues,
+) -> Result<DataFrame, PipelineStepError> {
+    let group_by = step.group_by().unwrap_or_default();
+    let keys = string_columns(data, group_by)?;
+    let column = data
+        .column(step.input_columns()[0])?
+        .cast(&DataType::Float64)?;
+    let ranks: Float64Chunked = column
+        .f64()?
+        .iter()
+        .enumerate()
+        .map(|(row, x)| {
+            let key: Vec<Option<String>> = keys
+                .iter()
+                .map(|k| k.get(row).map(str::to_string))
+                .collect();
+            rank(values.get(&key)?.as_deref()?, x?)
+        })
+        .collect();
+    let name = format!("feature_{}", step.name().unwrap_or_default());
+    let mut result = data.clone();
+    result.with_column(ranks.with_name(name.into()).into_column())?;
+    Ok(result)
+}
+
+/// The percentile rank of `x` among `sorted`: the share of the other values
+/// below it, at most 1 for values past the largest. `None` for an empty group.
+fn rank(sorted: &[f64], x: f64) -> Option<f64> {
+    let below = sorted.partition_point(|v| *v < x);
+    match sorted.len() {
+        0 => None,
+        1 => Some(0.0),
+        n => Some((below as f64 / (n - 1) as f64).min(1.0)),
+    }
+}
+
+/// `columns` of `data` cast to strings, as group keys are compared.
+fn string_columns(
+    data: &DataFrame,
+    columns: &[String],
+) -> Result<Vec<StringChunked>, PipelineStepError> {
+    columns
+        .iter()
+        .map(|c| Ok(data.column(c)?.cast(&DataType::String)?.str()?.clone()))
+        .collect()
+}
+
+/// The sorted non-null values of `column` per `group_by` key in `data`.
+fn rank_values(
+    data: &DataFrame,
+    group_by: &[String],
+    column: &str,
+    policy: Option<NullPolicy>,
+) -> Result<GroupValues, PipelineStepError> {
+    let keys = string_columns(data, group_by)?;
+    let column = data.column(column)?.cast(&DataType::Float64)?;
+    let mut values = GroupValues::new();
+    for (row, x) in column.f64()?.iter().enumerate() {
+        let key = keys
+            .iter()
+            .map(|k| k.get(row).map(str::to_string))
+            .collect();
+        let group = values.entry(key).or_insert_with(|| Some(Vec::new()));
+        match x {
+            Some(x) => {
+                if let Some(group) = group {
+                    group.push(x);
+                }
+            }
+            None if policy == Some(NullPolicy::Propagate) => *group = None,
+            None => {}
+        }
+    }
+    for group in values.values_mut().flatten() {
+        group.sort_by(f64::total_cmp);
+    }
+    Ok(values)
+}
+
+/// The median and scale of `column` per `group_by` key in `data`, computed
+/// by the same expressions as the step.
+fn scale_values(
+    data: &DataFrame,
+    group_by: &[String],
+    column: &str,
+) -> Result<GroupScales, PipelineStepError> {
+    let key_columns: Vec<String> = group_by.iter().map(|k| format!("__key__{k}")).collect();
+    let [median, scale] = robust_stats(column);
+    let stats = [median.alias("__median__"), scale.alias("__scale__")];
+    let table = if group_by.is_empty() {
+        data.clone().lazy().select(stats).collect()?
+    } else {
+        data.clone()
+            .lazy()
+            .group_by(
+                group_by
+                    .iter()
+                    .zip(&key_columns)
+                    .map(|(k, alias)| col(k).cast(DataType::String).alias(alias))
+                    .collect::<Vec<_>>(),
+            )
+            .agg(stats)
+            .collect()?
+    };
+    let keys = key_columns
+        .iter()
+        .map(|k| Ok(table.column(k)?.str()?.clone()))
+        .collect::<PolarsResult<Vec<_>>>()?;
+    let medians = table.column("__median__")?.f64()?.clone();
+    let scales = table.column("__scale__")?.f64()?.clone();
+    Ok((0..table.height())
+        .map(|row| {
+            let key = keys
+                .iter()
+                .map(|k| k.get(row).map(str::to_string))
+                .collect();
+            (key, medians.get(row).zip(scales.get(row)))
+        })
+        .collect())
+}
+
+/// Adds the `robust_scale` feature of `step` to `data`, scaling each row's
+/// value by the fitted median and scale of its group.
+fn scale_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    values: &GroupScales,
+) -> Result<DataFrame, PipelineStepError> {
+    let FeatureConfig::RobustScale { group_by, .. } = step else {
+        return Ok(data.clone());
+    };
+    let keys = string_columns(data, group_by)?;
+    let column = source(data, step)?.cast(&DataType::Float64)?;
+    let scaled: Float64Chunked = column
+        .f64()?
+        .iter()
+        .enumerate()
+        .map(|(row, x)| {
+            let key: Vec<Option<String>> = keys
+                .iter()
+                .map(|k| k.get(row).map(str::to_string))
+                .collect();
+            let (median, scale) = (*values.get(&key)?)?;
+            Some((x? - median) / scale)
+        })
+        .collect();
+    let name = format!("feature_{}", step.name().unwrap_or_default());
+    let mut result = data.clone();
+    result.with_column(scaled.with_name(name.into()).into_column())?;
+    Ok(result)
+}
+
+/// Adds the `anomaly_score` feature of `step` to `data`, scoring each row
+/// against the fitted `model`.
+fn anomaly_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    model: &Mahalanobis,
+) -> Result<DataFrame, PipelineStepError> {
+    let columns: Vec<String> = step.input_columns().iter().map(|c| c.to_string()).collect();
+    if let Some(column) = columns.iter().find(|c| data.column(c).is_err()) {
+        return Err(PipelineStepError::ColumnNotFound {
+            found: column.clone(),
+            available: data
+                .get_column_names()
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        });
+    }
+    let name = format!("feature_{}", step.name().unwrap_or_default());
+    let mut result = data.clone();
+    result.with_column(model.column(data, &columns, &name)?)?;
+    Ok(result)
+}
+
+/// Adds the `woe_encode` feature of `step` to `data` with the fitted
+/// `table`.
+fn woe_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    table: &WoeTable,
+) -> Result<DataFrame, PipelineStepError> {
+    let values = source(data, step)?;
+    let name = format!("feature_{}", step.name().unwrap_or_default());
+    let mut result = data.clone();
+    result.with_column(table.encode(values, &name)?)?;
+    Ok(result)
+}
+
+/// Adds the `quantile_transform` feature of `step` to `data`, mapping
+/// values through the fitted `map`.
+fn quantile_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    map: &QuantileMap,
+) -> Result<DataFrame, PipelineStepError> {
+    let FeatureConfig::QuantileTransform {
+        distribution, name, ..
+    } = step
+    else {
+        return Ok(data.clone());
+    };
+    let values = source(data, step)?;
+    let mut result = data.clone();
+    result.with_column(map.column(values, *distribution, &format!("feature_{name}"))?)?;
+    Ok(result)
+}
+
+/// Adds the `power_transform` feature of `step` to `data` with the fitted
+/// lambda.
+fn power_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    fit: &PowerFit,
+) -> Result<DataFrame, PipelineStepError> {
+    let values = source(data, step)?;
+    let name = format!("feature_{}", step.name().unwrap_or_default());
+    let mut result = data.clone();
+    result.with_column(fit.column(values, &name)?)?;
+    Ok(result)
+}
+
+/// Adds the `collapse_rare` key column of `step` to `data`, collapsing
+/// categories the fit didn't keep.
+fn rare_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    kept: &KeptCategories,
+) -> Result<DataFrame, PipelineStepError> {
+    let FeatureConfig::CollapseRare { other, name, .. } = step else {
+        return Ok(data.clone());
+    };
+    let values = source(data, step)?;
+    let mut result = data.clone();
+    result.with_column(kept.column(values, other, name)?)?;
+    Ok(result)
+}
+
+/// Adds the `random_feature` column of `step` to `data`, its rows numbered
+/// from `first_row`.
+fn random_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    first_row: u64,
+) -> Result<DataFrame, PipelineStepError> {
+    let FeatureConfig::RandomFeature {
+        distribution,
+        seed,
+        name,
+        ..
+    } = step
+    else {
+        return Ok(data.clone());
+    };
+    let stream = noise::stream(*seed, name);
+    let values: Vec<f64> = (first_row..first_row + data.height() as u64)
+        .map(|row| noise::value(stream, row, *distribution))
+        .collect();
+    let mut result = data.clone();
+    result.with_column(Float64Chunked::from_vec(
+        format!("feature_{name}").into(),
+        values,
+    ))?;
+    Ok(result)
+}
+
+/// Adds the `multi_hot` columns of `step` for the fit's tags to `data`.
+fn tag_columns(
+    data: &DataFrame,
+    step: &FeatureConfig,
+    tags: &[String],
+) -> Result<DataFrame, PipelineStepError> {
+    let FeatureConfig::MultiHot {
+        column, separator, ..
+    } = step
+    else {
+        return Ok(data.clone());
+    };
+    let values = source(data, step)?;
+    let mut result = data.clone();
+    for tag in multi_hot::columns(values, separator, tags, column)? {
+        result.with_column(tag)?;
+    }
+    Ok(result)
+}
+
+/// The first column `step` reads, for steps fitted on a single column.
+fn source<'a>(data: &'a DataFrame, step: &FeatureConfig) -> Result<&'a Column, PipelineStepError> {
+    let column = step.input_columns()[0];
+    data.column(column)
+        .map_err(|_| PipelineStepError::ColumnNotFound {
+            found: column.to_string(),
+            available: data
+                .get_column_names()
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        })
+}
+
+/// The categories an `ohe` `step` saw in `data`, with its limits and policy
+/// for unseen ones; empty for other steps.
+fn ohe_sources(
+    step: &FeatureConfig,
+    data: &DataFrame,
+) -> Result<BTreeMap<String, OheSource>, PipelineStepError> {
+    let FeatureConfig::Ohe { handle_unknown, .. } = step else {
+        return Ok(BTreeMap::new());
+    };
+    let mut kept = step.ohe_kept(data)?;
+    Ok(step
+        .ohe_categories(data)?
+        .into_iter()
+        .map(|(source, seen)| {
+            let read = OheSource {
+                seen,
+                kept: kept.remove(&source),
+                unknown: *handle_unknown,
+            };
+            (source, read)
+        })
+        .collect())
+}
+
+fn dummy_columns(
+    data: &DataFrame,
+    dummies: &[(String, Option<String>, String)],
+    sources: &BTreeMap<String, OheSource>,
+    dtype: &DataType,
+) -> Result<DataFrame, PipelineStepError> {
+    // Sources are read as their dummy categories, from scratch columns.
+    let mut data = data.clone();
+    let mut scratch = Vec::new();
+    for (source, read) in sources {
+        let name = format!("__ohe_source__{source}");
+        let values = data.column(source)?.cast(&DataType::String)?;
+        let categories = values
+            .str()?
+            .iter()
+            .map(|v| v.map(|v| read.category(source, v)).transpose())
+            .collect::<PolarsResult<StringChunked>>()?;
+        data.with_column(categories.with_name(name.as_str().into()).into_column())?;
+        scratch.push(name);
+    }
+    let columns: Vec<Expr> = dummies
+        .iter()
+        .map(|(source, category, name)| {
+            let values = col(format!("__ohe_source__{source}"));
+            let matches = match category {
+                Some(category) => values.eq(lit(category.as_str())).fill_null(lit(false)),
+                None => values.is_null(),
+            };
+            matches.cast(dtype.clone()).alias(name)
+        })
+        .collect();
+    Ok(data
+        .lazy()
+        .with_columns(columns)
+        .drop(by_name(scratch, true))
+        .collect()?)
+}
+
+/// A value a step reads in [`FittedPipeline::transform_row`].
+#[derive(Debug, Clone, Copy)]
+enum Cell<'a> {
+    Input(&'a Value),
+    /// The output of an earlier step.
+    Feature(Option<f64>),
+}
+
+impl<'a> Cell<'a> {
+    fn number(self, column: &str) -> Result<Option<f64>, PipelineStepError> {
+        match self {
+            Self::Feature(value) => Ok(value),
+            Self::Input(Value::Null) => Ok(None),
+            Self::Input(Value::Bool(b)) => Ok(Some(f64::from(u8::from(*b)))),
+            Self::Input(Value::Number(n)) => Ok(n.as_f64()),
+            Self::Input(other) => Err(polars_err!(
+                InvalidOperation: "column '{}' must be numeric, got {}", column, other
+            )
+            .into()),
+        }
+    }
+
+    /// The day of a date or datetime string, as polars parses ISO 8601.
+    fn date(self, column: &str) -> Result<Option<NaiveDate>, PipelineStepError> {
+        match self {
+            Self::Input(Value::Null) | Self::Feature(None) => Ok(None),
+            Self::Input(Value::String(s))
+                if let Some(day) = s
+                    .get(..10)
+                    .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()) =>
+            {
+                Ok(Some(day))
+            }
+            other => Err(polars_err!(
+                InvalidOperation: "column '{}' must be a date, got {:?}", column, other
+            )
+            .into()),
+        }
+    }
+
+    /// The elements of a list input; `None` for null.
+    fn list(self, column: &str) -> Result<Option<&'a [Value]>, PipelineStepError> {
+        match self {
+            Self::Input(Value::Null) | Self::Feature(None) => Ok(None),
+            Self::Input(Value::Array(items)) => Ok(Some(items)),
+            other => Err(polars_err!(
+                InvalidOperation: "column '{}' must be a list, got {:?}", column, other
+            )
+            .into()),
+        }
+    }
+
+    /// The value cast to a string as polars casts it, e.g. `30` for an
+    /// integer and `30.0` for a float; `None` for null.
+    fn key(self) -> Option<String> {
+        let float = |x: f64| {
+            if x.is_finite() && x.fract() == 0.0 {
+                format!("{:.1}", x)
+            } else {
+                x.to_string()
+            }
+        };
+        match self {
+            Self::Feature(value) => value.map(float),
+            Self::Input(Value::Null) => None,
+            Self::Input(Value::String(s)) => Some(s.clone()),
+            Self::Input(Value::Number(n)) if n.is_f64() => n.as_f64().map(float),
+            Self::Input(other) => Some(other.to_string()),
+        }
+    }
+}
+
+/// The outputs of one step for a single row, in `FittedPipeline::outputs`
+/// order.
+fn row_step<'a>(
+    step: &FeatureConfig,
+    fitted: &Fitted,
+    cell: impl Fn(&str) -> Result<Cell<'a>, PipelineStepError>,
+) -> Result<Vec<Option<f64>>, PipelineStepError> {
+    match fitted {
+        Fitted::Groups { values, .. } => {
+            let key = step
+                .group_by()
+                .unwrap_or_default()
+                .iter()
+                .map(|k| cell(k).map(Cell::key))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(vec![values.get(&key).copied().flatten()]);
+        }
+        Fitted::Ranks { values } => {
+            let key = step
+                .group_by()
+                .unwrap_or_default()
+                .iter()
+                .map(|k| cell(k).map(Cell::key))
+                .collect::<Result<Vec<_>, _>>()?;
+            let column = step.input_columns()[0];
+            let value = cell(column)?.number(column)?;
+            return Ok(vec![
+                value.and_then(|x| rank(values.get(&key)?.as_deref()?, x)),
+            ]);
+        }
+        Fitted::Anomaly { model } => {
+            let values = step
+                .input_columns()
+                .into_iter()
+                .map(|c| cell(c)?.number(c))
+                .collect::<Result<Option<Vec<f64>>, _>>()?;
+            let values = values.filter(|x| x.iter().all(|v| v.is_finite()));
+            return Ok(vec![values.map(|x| model.score(&x))]);
+        }
+        Fitted::Woe { table } => {
+            let column = step.input_columns()[0];
+            let key = match table.edges {
+                Some(_) => cell(column)?.number(column)?.and_then(|x| table.bin_key(x)),
+                None => cell(column)?.key(),
+            };
+            return Ok(vec![table.weight(key.as_deref())]);
+        }
+        Fitted::Scales { values } => {
+            let FeatureConfig::RobustScale {
+                column, group_by, ..
+            } = step
+            else {
+                return Ok(vec![None]);
+            };
+            let key = group_by
+                .iter()
+                .map(|k| cell(k).map(Cell::key))
+                .collect::<Result<Vec<_>, _>>()?;
+            let value = cell(column)?.number(column)?;
+            return Ok(vec![value.and_then(|x| {
+                let (median, scale) = values.get(&key).copied().flatten()?;
+                Some((x - median) / scale)
+            })]);
+        }
+        Fitted::Quantiles { map } => {
+            let FeatureConfig::QuantileTransform {
+                column,
+                distribution,
+                ..
+            } = step
+            else {
+                return Ok(vec![None]);
+            };
+            let value = cell(column)?.number(column)?;
+            return Ok(vec![
+                value
+                    .filter(|x| !x.is_nan())
+                    .map(|x| map.value(x, *distribution)),
+            ]);
+        }
+        // Key columns are computed up front by `key_values`.
+        Fitted::Categories { .. } => return Ok(Vec::new()),
+        Fitted::Tags { tags } => {
+            let FeatureConfig::MultiHot {
+                column, separator, ..
+            } = step
+            else {
+                return Ok(Vec::new());
+            };
+            let value = cell(column)?.key();
+            let row: Vec<&str> = value
+                .iter()
+                .flat_map(|value| multi_hot::split(value, separator))
+                .collect();
+            return Ok(tags
+                .iter()
+                .map(|tag| Some(f64::from(u8::from(row.contains(&tag.as_str())))))
+                .collect());
+        }
+        Fitted::Power { fit } => {
+            let column = step.input_columns()[0];
+            let value = cell(column)?.number(column)?;
+            return Ok(vec![value.and_then(|x| fit.value(x))]);
+        }
+        Fitted::Dummies {
+            dummies, sources, ..
+        } => {
+            return dummies
+                .iter()
+                .map(|(source, category, _)| {
+                    let key = cell(source)?.key();
+                    let key = match (sources.get(source), &key) {
+                        (Some(read), Some(value)) => Some(read.category(source, value)?),
+                        _ => key.as_deref(),
+                    };
+                    Ok(Some(f64::from(u8::from(key == category.as_deref()))))
+                })
+                .collect();
+        }
+        Fitted::Stateless => {}
+    }
+
+    let value = match step {
+        FeatureConfig::Threshold {
+            column,
+            threshold,
+            comparator,
+            ..
+        } => cell(column)?.number(column)?.map(|x| {
+            let flag = match comparator {
+                Comparator::Gt => x > *threshold,
+                Comparator::Lt => x < *threshold,
+            };
+            f64::from(u8::from(flag))
+        }),
+        FeatureConfig::Ratio {
+            numerator,
+            denominator,
+            ..
+        } => {
+            let (n, d) = (cell(numerator)?, cell(denominator)?);
+            n.number(numerator)?
+                .zip(d.number(denominator)?)
+                .and_then(|(n, d)| (d != 0.0).then(|| n / d))
+        }
+        // A lone row has no position; it gets row 0's value.
+        FeatureConfig::RandomFeature {
+            distribution,
+            seed,
+            name,
+            ..
+        } => Some(noise::value(noise::stream(*seed, name), 0, *distribution)),
+        FeatureConfig::ListLen { column, .. } => {
+            cell(column)?.list(column)?.map(|items| items.len() as f64)
+        }
+        FeatureConfig::ListContains { column, value, .. } => {
+            cell(column)?.list(column)?.map(|items| {
+                let found = items
+                    .iter()
+                    .any(|item| Cell::Input(item).key().as_ref() == Some(value));
+                f64::from(u8::from(found))
+            })
+        }
+        FeatureConfig::ListAgg { column, agg, .. } => match cell(column)?.list(column)? {
+            Some(items) => {
+                let values = items
+                    .iter()
+                    .map(|item| Cell::Input(item).number(column))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let values: Vec<f64> = values.into_iter().flatten().collect();
+                match agg {
+                    ListAgg::Sum => Some(values.iter().sum()),
+                    ListAgg::Mean if values.is_empty() => None,
+                    ListAgg::Mean => Some(values.iter().sum::<f64>() / values.len() as f64),
+                }
+            }
+            None => None,
+        },
+        FeatureConfig::Map {
+            column,
+            mapping,
+            default,
+            ..
+        } => cell(column)?
+            .key()
+            .and_then(|k| mapping.get(&k).copied())
+            .or(*default),
+        FeatureConfig::Calendar {
+            column,
+            country,
+            region,
+            output,
+            ..
+        } => cell(column)?.date(column)?.and_then(|day| {
+            let calendar = Calendar::new(
+                *country,
+                region.as_deref(),
+                &step.extra_holidays(),
+                day.year()..=day.year() + 1,
+            );
+            match output {
+                CalendarOutput::IsHoliday => Some(f64::from(u8::from(calendar.is_holiday(day)))),
+                CalendarOutput::IsBusinessDay => {
+                    Some(f64::from(u8::from(calendar.is_business_day(day))))
+                }
+                CalendarOutput::DaysToNextHoliday => {
+                    calendar.days_to_next_holiday(day).map(f64::from)
+                }
+            }
+        }),
+        FeatureConfig::IpFeatures { column, output, .. } if !output.is_prefix() => cell(column)?
+            .key()
+            .and_then(|text| ip::parse(&text))
+            .and_then(|a| output.flag(a))
+            .map(|flag| f64::from(u8::from(flag))),
+        FeatureConfig::UrlParse { column, output, .. } if !output.is_key() => cell(column)?
+            .key()
+            .and_then(|text| url::parse(&text).and_then(|u| output.count(&u)))
+            .map(f64::from),
+        FeatureConfig::EmailDomain {
+            column,
+            output: EmailOutput::IsFreeMail,
+            free_mail,
+            ..
+        } => cell(column)?
+            .key()
+            .and_then(|text| email::domain(&text))
+            .map(|domain| {
+                let providers = email::free_mail(free_mail.as_deref());
+                f64::from(u8::from(providers.contains(&domain)))
+            }),
+        // Key columns are computed up front by `key_values`.
+        FeatureConfig::Geohash { .. }
+        | FeatureConfig::IpFeatures { .. }
+        | FeatureConfig::UrlParse { .. }
+        | FeatureConfig::EmailDomain { .. }
+        | FeatureConfig::JsonExtract { .. }
+        | FeatureConfig::ParseNumber { .. }
+        | FeatureConfig::CleanText { .. }
+        | FeatureConfig::BlockingKey { .. }
+        | FeatureConfig::Expect { .. } => return Ok(Vec::new()),
+        step => {
+            return Err(PipelineStepError::EmptyGroupby {
+                feature_name: step.name().unwrap_or_default().to_string(),
+            });
+        }
+    };
+    Ok(vec![value])
+}
+
+/// The rows of a fitted group table as key to value.
+fn group_values(
+    table: &DataFrame,
+    key_columns: &[String],
+    feature: &str,
+) -> Result<HashMap<Vec<Option<String>>, Option<f64>>, PipelineStepError> {
+    let keys = key_columns
+        .iter()
+        .map(|k| Ok(table.column(k)?.str()?.clone()))
+        .collect::<PolarsResult<Vec<_>>>()?;
+    let values = table.column(feature)?.cast(&DataType::Float64)?;
+    Ok(values
+        .f64()?
+        .iter()
+        .enumerate()
+        .map(|(row, value)| {
+            let key = keys
+                .iter()
+                .map(|k| k.get(row).map(str::to_string))
+                .collect();
+            (key, value)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::format::ConfigFormat;
+
+    const CONFIG: &str = r#"
+steps:
+  - function: mean
+    column: amount
+    group_by: [city]
+    name: avg_amount_by_city
+  - function: count
+    column: amount
+    group_by: [city]
+    name: orders_by_city
+  - function: ratio
+    numerator: amount
+    denominator: items
+    name: amount_per_item
+  - function: threshold
+    column: amount
+    threshold: 10.0
+    comparator: gt
+    name: big_order
+  - function: ohe
+    columns: [city]
+    drop_first: false
+    drop_nulls: false
+  - function: multi_hot
+    column: tags
+"#;
+
+    fn data() -> DataFrame {
+        df!(
+            "city" => ["paris", "rome", "paris", "oslo", "rome"],
+            "amount" => [Some(12.0), Some(4.0), None, Some(30.0), Some(8.0)],
+            "items" => [3i64, 0, 2, 4, 1],
+            "tags" => [Some("new|promo"), Some("promo"), None, Some("vip|new"), Some("")]
+        )
+        .unwrap()
+    }
+
+    fn as_f64(column: &Column) -> Vec<Option<f64>> {
+        column
+            .cast(&DataType::Float64)
+            .unwrap()
+            .f64()
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn transform_row_matches_transform() {
+        let pipeline = FeaturePipeline::from_content("test", CONFIG, ConfigFormat::Yaml).unwrap();
+        let data = data();
+        let fitted = FittedPipeline::fit(&pipeline, &data).unwrap();
+        let batch = fitted.transform(&data).unwrap();
+        assert_eq!(
+            batch.get_column_names_str(),
+            fitted.columns().iter().map(String::as_str).collect::<Vec<_>>()
+        );
+
+        let rows: Vec<HashMap<String, Value>> = serde_json::from_value(serde_json::json!([
+            {"city": "paris", "amount": 12.0, "items": 3, "tags": "new|promo"},
+            {"city": "rome", "amount": 4.0, "items": 0, "tags": "promo"},
+            {"city": "paris", "amount": null, "items": 2, "tags": null},
+            {"city": "oslo", "amount": 30.0, "items": 4, "tags": "vip|new"},
+            {"city": "rome", "amount": 8.0, "items": 1, "tags": ""},
+        ]))
+        .unwrap();
+        for (i, row) in rows.iter().enumerate() {
+            let features = fitted.transform_row(row).unwrap();
+            for (column, value) in batch.get_columns().iter().zip(features) {
+                assert_eq!(
+                    as_f64(column)[i],
+                    value,
+                    "row {}, column {}",
+                    i,
+                    column.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fitted_columns_keep_their_encoding_separators() {
+        let pipeline = FeaturePipeline::from_content("test", CONFIG, ConfigFormat::Yaml).unwrap();
+        let fitted = FittedPipeline::fit(&pipeline, &data()).unwrap();
+        let columns = fitted.columns();
+        assert!(columns.iter().any(|c| c == "city__ohe__paris"));
+        assert!(columns.iter().any(|c| c == "tags__mh__vip"));
+        assert!(!columns.iter().any(|c| c.starts_with("tags__ohe__")));
+    }
+}
@@ -0,0 +1,43 @@
+//! Geohash encoding for `geohash` steps: a latitude and longitude as a
+//! base-32 string naming the cell that holds them, each extra character
+//! splitting the cell into 32. Nearby points share a prefix, so a geohash
+//! groups rows by area.
+
+const ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Characters of the longest geohash, about 37mm by 19mm at the equator.
+pub const MAX_PRECISION: usize = 12;
+
+/// The `precision`-character geohash of a point; `None` when it lies
+/// outside the valid latitude and longitude range, or is NaN.
+pub fn encode(latitude: f64, longitude: f64, precision: usize) -> Option<String> {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+    let (mut lat, mut lon) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::with_capacity(precision);
+    let (mut index, mut bits, mut even) = (0, 0, true);
+    // Bits alternate between longitude and latitude, five to a character.
+    while hash.len() < precision {
+        let (range, value) = if even {
+            (&mut lon, longitude)
+        } else {
+            (&mut lat, latitude)
+        };
+        let mid = (range.0 + range.1) / 2.0;
+        index <<= 1;
+        if value >= mid {
+            index |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+        even = !even;
+        bits += 1;
+        if bits == 5 {
+            hash.push(char::from(ALPHABET[index]));
+            (index, bits) = (0, 0);
+        }
+    }
+    Some(hash)
+}
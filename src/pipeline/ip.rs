@@ -0,0 +1,73 @@
+//! IP address parsing for `ip_features` steps. Addresses are IPv4 or IPv6
+//! text; IPv4-mapped IPv6 addresses (`::ffff:10.0.0.1`) count as IPv4.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What an `ip_features` step computes for each address.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IpOutput {
+    /// Not publicly routable: RFC 1918, loopback, link-local and IPv6
+    /// unique local addresses.
+    IsPrivate,
+    IsIpv6,
+    /// The IPv4 `/16` network, e.g. `192.168.0.0/16`, as a key column.
+    #[serde(rename = "prefix_16")]
+    Prefix16,
+    /// The IPv4 `/24` network, e.g. `192.168.1.0/24`, as a key column.
+    #[serde(rename = "prefix_24")]
+    Prefix24,
+}
+
+impl IpOutput {
+    /// Whether the output is a string key rather than a flag feature.
+    pub fn is_prefix(self) -> bool {
+        matches!(self, Self::Prefix16 | Self::Prefix24)
+    }
+
+    /// The flag for `ip`; `None` for prefix outputs.
+    pub fn flag(self, ip: IpAddr) -> Option<bool> {
+        match self {
+            Self::IsPrivate => Some(is_private(ip)),
+            Self::IsIpv6 => Some(ip.is_ipv6()),
+            Self::Prefix16 | Self::Prefix24 => None,
+        }
+    }
+
+    /// The network of `ip` for prefix outputs; `None` for flags and IPv6.
+    pub fn prefix(self, ip: IpAddr) -> Option<String> {
+        let bits = match self {
+            Self::Prefix16 => 16,
+            Self::Prefix24 => 24,
+            Self::IsPrivate | Self::IsIpv6 => return None,
+        };
+        let IpAddr::V4(ip) = ip else {
+            return None;
+        };
+        let network = Ipv4Addr::from_bits(ip.to_bits() & (u32::MAX << (32 - bits)));
+        Some(format!("{}/{}", network, bits))
+    }
+}
+
+/// The address in `text`, surrounding whitespace ignored; `None` when it
+/// isn't one.
+pub fn parse(text: &str) -> Option<IpAddr> {
+    match text.trim().parse().ok()? {
+        IpAddr::V6(ip) => Some(ip.to_ipv4_mapped().map_or(IpAddr::V6(ip), IpAddr::V4)),
+        ip => Some(ip),
+    }
+}
+
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            // fc00::/7 unique local, fe80::/10 link-local.
+            ip.is_loopback() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80
+        }
+    }
+}
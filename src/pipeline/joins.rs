@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+
+/// Unmatched keys listed per step in the join report.
+pub const TOP_UNMATCHED: usize = 10;
+
+/// How many frame rows may share a join key.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Relationship {
+    /// Several rows may share a key, e.g. many orders per customer.
+    #[default]
+    ManyToOne,
+    /// Every key identifies a single row; repeated keys fail the step.
+    OneToOne,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinStatus {
+    Ok,
+    /// Coverage below `warn_coverage`.
+    Warn,
+    /// Coverage below `min_coverage`.
+    Fail,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct UnmatchedKey {
+    pub key: String,
+    pub rows: usize,
+}
+
+/// Match statistics of a `map` or `external_target` step.
+#[derive(Serialize, Debug, Clone)]
+pub struct JoinReport {
+    pub step: String,
+    pub function: &'static str,
+    pub rows: usize,
+    pub matched: usize,
+    pub unmatched: usize,
+    /// Matched share of rows, from 0 to 1.
+    pub coverage: f64,
+    /// The most frequent unmatched keys, up to [`TOP_UNMATCHED`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub top_unmatched: Vec<UnmatchedKey>,
+    pub status: JoinStatus,
+}
+
+impl JoinReport {
+    /// `keys[i]` is row `i`'s key, `None` when any key column is null, and
+    /// `matched[i]` whether the row found a match.
+    pub fn new(
+        step: &str,
+        function: &'static str,
+        keys: &[Option<String>],
+        matched: &[bool],
+        min_coverage: Option<f64>,
+        warn_coverage: Option<f64>,
+    ) -> Self {
+        let rows = matched.len();
+        let hits = matched.iter().filter(|m| **m).count();
+        let coverage = if rows == 0 {
+            1.0
+        } else {
+            hits as f64 / rows as f64
+        };
+
+        let mut unmatched: HashMap<&str, usize> = HashMap::new();
+        for (key, _) in keys.iter().zip(matched).filter(|(_, m)| !**m) {
+            *unmatched
+                .entry(key.as_deref().unwrap_or("null"))
+                .or_default() += 1;
+        }
+        let mut top_unmatched: Vec<UnmatchedKey> = unmatched
+            .into_iter()
+            .map(|(key, rows)| UnmatchedKey {
+                key: key.to_string(),
+                rows,
+            })
+            .collect();
+        top_unmatched.sort_by(|a, b| b.rows.cmp(&a.rows).then(a.key.cmp(&b.key)));
+        top_unmatched.truncate(TOP_UNMATCHED);
+
+        let status = if min_coverage.is_some_and(|min| coverage < min) {
+            JoinStatus::Fail
+        } else if warn_coverage.is_some_and(|warn| coverage < warn) {
+            JoinStatus::Warn
+        } else {
+            JoinStatus::Ok
+        };
+
+        Self {
+            step: step.to_string(),
+            function,
+            rows,
+            matched: hits,
+            unmatched: rows - hits,
+            coverage,
+            top_unmatched,
+            status,
+        }
+    }
+
+    /// Errors when coverage is below `min_coverage`, naming `source`.
+    pub fn enforce(&self, source: &str) -> Result<(), PipelineStepError> {
+        if self.status != JoinStatus::Fail {
+            return Ok(());
+        }
+        Err(polars_err!(
+            ComputeError: "step '{}': {} matched {} of {} rows ({:.1}%), below min_coverage; top unmatched keys {:?}",
+            self.step,
+            source,
+            self.matched,
+            self.rows,
+            self.coverage * 100.0,
+            self.top_unmatched.iter().map(|k| k.key.as_str()).collect::<Vec<_>>()
+        )
+        .into())
+    }
+}
+
+/// Row keys of `columns` in `data` as strings, multi-column keys joined by `, `.
+pub fn row_keys(
+    data: &DataFrame,
+    columns: &[String],
+) -> Result<Vec<Option<String>>, PipelineStepError> {
+    let values: Vec<StringChunked> = columns
+        .iter()
+        .map(|c| Ok(data.column(c)?.cast(&DataType::String)?.str()?.clone()))
+        .collect::<Result<_, PipelineStepError>>()?;
+    Ok((0..data.height())
+        .map(|row| {
+            values
+                .iter()
+                .map(|v| v.get(row))
+                .collect::<Option<Vec<_>>>()
+                .map(|parts| parts.join(", "))
+        })
+        .collect())
+}
+
+/// Fails a one-to-one join whose frame repeats a key.
+pub fn check_one_to_one(step: &str, keys: &[Option<String>]) -> Result<(), PipelineStepError> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for key in keys.iter().flatten() {
+        *counts.entry(key).or_default() += 1;
+    }
+    let mut repeated: Vec<&str> = counts
+        .into_iter()
+        .filter(|(_, rows)| *rows > 1)
+        .map(|(key, _)| key)
+        .collect();
+    if repeated.is_empty() {
+        return Ok(());
+    }
+    repeated.sort();
+    Err(polars_err!(
+        ComputeError: "step '{}': relationship is one_to_one but {} key(s) repeat, e.g. {:?}",
+        step,
+        repeated.len(),
+        &repeated[..repeated.len().min(TOP_UNMATCHED)]
+    )
+    .into())
+}
+
+/// Rejects thresholds outside 0..=1; returns the offending field.
+pub fn invalid_threshold(
+    min_coverage: Option<f64>,
+    warn_coverage: Option<f64>,
+) -> Option<&'static str> {
+    let out_of_range = |c: Option<f64>| c.is_some_and(|c| !(0.0..=1.0).contains(&c));
+    if out_of_range(min_coverage) {
+        Some("min_coverage")
+    } else if out_of_range(warn_coverage) {
+        Some("warn_coverage")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,105 @@
+//! JSON path extraction for `json_extract` steps. Paths are `$` followed by
+//! `.key` and `[index]` segments, e.g. `$.device.os` or `$.items[0].price`;
+//! keys are letters, digits and underscores so every target can quote them.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The type a field's values are read as; values of another type, objects
+/// and arrays become null.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonType {
+    /// Strings as they are, numbers and booleans as their JSON text.
+    #[default]
+    String,
+    /// Numbers, and strings holding one.
+    Float,
+    /// Integers, and strings holding one.
+    Int,
+    /// `true` and `false`, as booleans or strings.
+    Bool,
+}
+
+impl JsonType {
+    /// `value` as this type; `None` when it isn't one.
+    pub fn convert(self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (_, Value::Null | Value::Array(_) | Value::Object(_)) => None,
+            (Self::String, Value::String(_)) => Some(value.clone()),
+            (Self::String, other) => Some(Value::String(other.to_string())),
+            (Self::Float, Value::Number(n)) => n.as_f64().map(Value::from),
+            (Self::Float, Value::String(s)) => s.trim().parse::<f64>().ok().map(Value::from),
+            (Self::Int, Value::Number(n)) => n.as_i64().map(Value::from),
+            (Self::Int, Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::from),
+            (Self::Bool, Value::Bool(_)) => Some(value.clone()),
+            (Self::Bool, Value::String(s)) => match s.as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// One value a `json_extract` step reads into a column.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct JsonField {
+    /// e.g. `$.device.os`.
+    pub path: String,
+    /// Column the values are written to.
+    pub name: String,
+    /// `string` when omitted.
+    #[serde(rename = "type", default)]
+    pub dtype: JsonType,
+}
+
+/// A step of a path into a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// The segments of `path`, or why it isn't one.
+pub fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let invalid = || {
+        format!(
+            "'{}' is not a JSON path such as '$.device.os' or '$.items[0]'",
+            path
+        )
+    };
+    let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(Segment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after.split_once(']').ok_or_else(invalid)?;
+            segments.push(Segment::Index(index.parse().map_err(|_| invalid())?));
+            rest = after;
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+/// The value at `path` in `document`; `None` when there is none.
+pub fn extract<'a>(document: &'a Value, path: &[Segment]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(document, |value, segment| match segment {
+            Segment::Key(key) => value.get(key),
+            Segment::Index(index) => value.get(index),
+        })
+}
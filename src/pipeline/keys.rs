@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+
+/// Column holding the occurrences of each key while grouping.
+const COUNT_COLUMN: &str = "__key_count";
+
+/// Column holding each row's position while sorting.
+const ROW_COLUMN: &str = "__key_row";
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKeyAction {
+    /// Log the duplicates and carry on.
+    #[default]
+    Warn,
+    /// Fail the run before features are computed.
+    Fail,
+}
+
+fn default_max_examples() -> usize {
+    20
+}
+
+/// Columns that should identify a row, checked before features are computed.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KeysConfig {
+    pub columns: Vec<String>,
+
+    #[serde(default)]
+    pub action: DuplicateKeyAction,
+
+    /// Most repeated keys listed in the report.
+    #[serde(default = "default_max_examples")]
+    pub max_examples: usize,
+
+    /// Sorts the output rows by these columns, so two runs over identical
+    /// input write byte-identical files.
+    #[serde(default)]
+    pub sort_outputs: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DuplicateKey {
+    pub key: BTreeMap<String, Option<String>>,
+    pub count: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DuplicateKeyReport {
+    pub columns: Vec<String>,
+    /// Keys occurring more than once.
+    pub duplicated_keys: usize,
+    /// Rows beyond the first for each duplicated key.
+    pub extra_rows: usize,
+    /// The most repeated keys, up to `max_examples`.
+    pub examples: Vec<DuplicateKey>,
+}
+
+impl KeysConfig {
+    /// Finds repeated keys in `data`; `None` when every key is unique.
+    pub fn check(&self, data: &DataFrame) -> Result<Option<DuplicateKeyReport>, PipelineStepError> {
+        for column in &self.columns {
+            if !data.get_column_names().iter().any(|c| c.as_str() == column) {
+                return Err(PipelineStepError::ColumnNotFound {
+                    found: column.clone(),
+                    available: data
+                        .get_column_names()
+                        .iter()
+                        .map(|s| s.to_string())
+                        .collect(),
+                });
+            }
+        }
+
+        let duplicates = data
+            .clone()
+            .lazy()
+            .group_by(self.columns.iter().map(col).collect::<Vec<_>>())
+            .agg([len().alias(COUNT_COLUMN)])
+            .filter(col(COUNT_COLUMN).gt(lit(1)))
+            .sort_by_exprs(
+                std::iter::once(col(COUNT_COLUMN))
+                    .chain(self.columns.iter().map(col))
+                    .collect::<Vec<_>>(),
+                SortMultipleOptions::default().with_order_descending_multi(
+                    std::iter::once(true).chain(self.columns.iter().map(|_| false)),
+                ),
+            )
+            .collect()?;
+        if duplicates.height() == 0 {
+            return Ok(None);
+        }
+
+        let counts: Vec<usize> = duplicates
+            .column(COUNT_COLUMN)?
+            .cast(&DataType::UInt64)?
+            .u64()?
+            .into_no_null_iter()
+            .map(|c| c as usize)
+            .collect();
+        let keys = duplicates
+            .select(&self.columns)?
+            .head(Some(self.max_examples));
+        let keys: Vec<StringChunked> = keys
+            .get_columns()
+            .iter()
+            .map(|c| Ok(c.cast(&DataType::String)?.str()?.clone()))
+            .collect::<Result<_, PipelineStepError>>()?;
+
+        let examples = (0..keys.first().map_or(0, |k| k.len()))
+            .map(|row| DuplicateKey {
+                key: self
+                    .columns
+                    .iter()
+                    .zip(&keys)
+                    .map(|(name, values)| (name.clone(), values.get(row).map(str::to_string)))
+                    .collect(),
+                count: counts[row],
+            })
+            .collect();
+
+        Ok(Some(DuplicateKeyReport {
+            columns: self.columns.clone(),
+            duplicated_keys: counts.len(),
+            extra_rows: counts.iter().map(|c| c - 1).sum(),
+            examples,
+        }))
+    }
+
+    /// Positions of the rows of `keys`, which holds the key columns, in key
+    /// order with nulls last. Rows with equal keys, e.g. oversampled copies,
+    /// keep their order.
+    pub fn sort_order(&self, keys: &DataFrame) -> Result<IdxCa, PipelineStepError> {
+        let sorted = keys
+            .clone()
+            .lazy()
+            .with_row_index(ROW_COLUMN, None)
+            .sort(
+                &self.columns,
+                SortMultipleOptions::default()
+                    .with_maintain_order(true)
+                    .with_nulls_last(true),
+            )
+            .select([col(ROW_COLUMN)])
+            .collect()?;
+        Ok(sorted.column(ROW_COLUMN)?.idx()?.clone())
+    }
+}
@@ -1,53 +1,700 @@
-use crate::errors::PipelineStepError;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Instant;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::config::entry::DatasetConfig;
+use crate::config::format::ConfigFormat;
+use crate::config::{migrate, overlay};
+use crate::data::load_dataset;
+use crate::errors::{ConfigError, PipelineStepError};
+use crate::pipeline::balance::BalanceConfig;
+use crate::pipeline::features::{Comparator, FeaturePipeline};
+use crate::pipeline::joins::{self, JoinReport, Relationship};
+use crate::pipeline::stats::StepStats;
 use polars::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_yaml::from_reader;
-use std::{fs::File, io::BufReader};
+use tracing::{info, info_span};
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "function")]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(tag = "function", deny_unknown_fields)]
 pub enum LabelsConfig {
     #[serde(rename = "existing_target")]
     ExistingTarget {
         column: String,
+        /// Target name; written as the `label_{name}` column.
+        #[serde(default = "default_target_name")]
+        name: String,
         encode: bool,
         drop_original: bool,
     },
+
+    /// Binary target: 1 where `column` compares true against `threshold`, else 0.
+    #[serde(rename = "threshold_target")]
+    ThresholdTarget {
+        column: String,
+        /// Target name; written as the `label_{name}` column.
+        #[serde(default = "default_target_name")]
+        name: String,
+        threshold: f64,
+        comparator: Comparator,
+        #[serde(default)]
+        drop_original: bool,
+    },
+
+    /// Integer classes from a continuous column. Set either `breakpoints`
+    /// (ascending; class `i` holds values in `[b[i-1], b[i])`) or `quantiles`,
+    /// the number of equal-frequency classes.
+    #[serde(rename = "binned_target")]
+    BinnedTarget {
+        column: String,
+        /// Target name; written as the `label_{name}` column.
+        #[serde(default = "default_target_name")]
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        breakpoints: Option<Vec<f64>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        quantiles: Option<usize>,
+        #[serde(default)]
+        drop_original: bool,
+    },
+
+    /// Numeric target copied from `column`, optionally transformed. Fitted
+    /// parameters are kept so predictions can be mapped back; see
+    /// [`TargetTransform::inverse`].
+    #[serde(rename = "regression_target")]
+    RegressionTarget {
+        column: String,
+        /// Target name; written as the `label_{name}` column.
+        #[serde(default = "default_target_name")]
+        name: String,
+        #[serde(default)]
+        transform: TransformKind,
+        /// Box-Cox lambda; estimated by maximum likelihood when omitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lambda: Option<f64>,
+        #[serde(default)]
+        drop_original: bool,
+    },
+
+    /// Target read from a separate labels file and left-joined onto the frame
+    /// on `keys`. Rows without a match get a null label; the run fails when
+    /// the matched share is below `min_coverage`, and warns below
+    /// `warn_coverage`.
+    #[serde(rename = "external_target")]
+    ExternalTarget {
+        source: Box<DatasetConfig>,
+        keys: Vec<String>,
+        /// Key columns in the labels file, matched to `keys` by position;
+        /// defaults to `keys`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source_keys: Option<Vec<String>>,
+        /// Label column in the labels file.
+        column: String,
+        /// Target name; written as the `label_{name}` column.
+        #[serde(default = "default_target_name")]
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_coverage: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        warn_coverage: Option<f64>,
+        /// `one_to_one` fails the step when the frame repeats a key.
+        #[serde(default)]
+        relationship: Relationship,
+    },
+
+    /// 1 when another row of the same `entity` with a truthy `event` falls
+    /// within `horizon_days` after this row's `timestamp`, else 0. Only events
+    /// strictly after the row count. Rows whose horizon ends past `cutoff`
+    /// (the latest timestamp when omitted) can't be labeled without looking
+    /// beyond the observed data and get a null label.
+    #[serde(rename = "future_event")]
+    FutureEvent {
+        entity: String,
+        timestamp: String,
+        event: String,
+        horizon_days: u32,
+        /// Target name; written as the `label_{name}` column.
+        #[serde(default = "default_target_name")]
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cutoff: Option<String>,
+    },
+}
+
+/// Name of a label step's target when none is given.
+pub const DEFAULT_TARGET: &str = "target";
+
+/// Prefix of the label columns in the pipeline output.
+pub const LABEL_PREFIX: &str = "label_";
+
+fn default_target_name() -> String {
+    DEFAULT_TARGET.to_string()
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct LabelsPipeline {
+    /// Schema version; see [`crate::config::migrate`]. Absent means version 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+
+    /// Entrypoint dataset this pipeline reads; the primary dataset when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
+
     pub steps: Vec<LabelsConfig>,
 
+    /// Target the model is trained on; the first step's when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub train_target: Option<String>,
+
+    /// Resample rows to equalize the classes of the training target.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<BalanceConfig>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 }
 
 impl LabelsPipeline {
     pub fn from_yaml(filepath: &str) -> Result<Self, PipelineStepError> {
-        let config_yaml = File::open(filepath)?;
-        let reader = BufReader::new(config_yaml);
-        let config: LabelsPipeline = from_reader(reader)?;
+        Self::from_file(filepath, Some(ConfigFormat::Yaml))
+    }
+
+    pub fn from_file(
+        filepath: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<Self, PipelineStepError> {
+        Self::from_layers(filepath, &[], format)
+    }
+
+    /// Loads `filepath` with `overlays` merged on top; see [`overlay::merge`].
+    pub fn from_layers(
+        filepath: &str,
+        overlays: &[String],
+        format: Option<ConfigFormat>,
+    ) -> Result<Self, PipelineStepError> {
+        let config: LabelsPipeline = overlay::load(filepath, overlays, format)?;
+        migrate::check_version(filepath, config.version)?;
+        config.validate(filepath)?;
         Ok(config)
     }
 
-    pub fn apply(&self, data: &DataFrame) -> Result<DataFrame, PipelineStepError> {
-        let mut result = data.clone();
+    /// Rejects label steps named like a feature step of `features`, so every
+    /// name in lineage, reports and `train_target` refers to one step.
+    pub fn check_names_against(
+        &self,
+        filepath: &str,
+        features: &FeaturePipeline,
+    ) -> Result<(), ConfigError> {
+        for (i, step) in self.steps.iter().enumerate() {
+            if let Some(j) = features
+                .steps
+                .iter()
+                .position(|f| f.name() == Some(step.name()))
+            {
+                return Err(ConfigError::ParseError {
+                    path: filepath.to_string(),
+                    error: format!(
+                        "step {}: label name '{}' is also used by features step {}",
+                        i + 1,
+                        step.name(),
+                        j + 1
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self, filepath: &str) -> Result<(), ConfigError> {
+        let invalid = |error: String| ConfigError::ParseError {
+            path: filepath.to_string(),
+            error,
+        };
+
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if let Some(first) = seen.insert(step.name(), i + 1) {
+                return Err(invalid(format!(
+                    "steps {} and {} are both named '{}'; give each label step a distinct 'name'",
+                    first,
+                    i + 1,
+                    step.name()
+                )));
+            }
+        }
+
+        if let Some(target) = &self.train_target
+            && !seen.contains_key(target.as_str())
+        {
+            return Err(invalid(format!(
+                "train_target '{}' does not match any label step. Available {:?}",
+                target,
+                self.steps.iter().map(|s| s.name()).collect::<Vec<_>>()
+            )));
+        }
+
+        for (i, step) in self.steps.iter().enumerate() {
+            if let LabelsConfig::ExternalTarget {
+                keys,
+                source_keys,
+                min_coverage,
+                warn_coverage,
+                ..
+            } = step
+            {
+                if keys.is_empty() {
+                    return Err(invalid(format!("step {}: 'keys' must not be empty", i + 1)));
+                }
+                if source_keys.as_ref().is_some_and(|s| s.len() != keys.len()) {
+                    return Err(invalid(format!(
+                        "step {}: 'source_keys' must list as many columns as 'keys'",
+                        i + 1
+                    )));
+                }
+                if let Some(field) = joins::invalid_threshold(*min_coverage, *warn_coverage) {
+                    return Err(invalid(format!(
+                        "step {}: '{}' must be between 0 and 1",
+                        i + 1,
+                        field
+                    )));
+                }
+            }
+
+            if let LabelsConfig::BinnedTarget {
+                breakpoints,
+                quantiles,
+                ..
+            } = step
+            {
+                let error = match (breakpoints, quantiles) {
+                    (Some(_), Some(_)) | (None, None) => {
+                        Some("set exactly one of 'breakpoints' or 'quantiles'")
+                    }
+                    (Some(b), None) if b.is_empty() => Some("'breakpoints' must not be empty"),
+                    (Some(b), None) if b.windows(2).any(|w| w[0] >= w[1]) => {
+                        Some("'breakpoints' must be strictly ascending")
+                    }
+                    (None, Some(q)) if *q < 2 => Some("'quantiles' must be at least 2"),
+                    _ => None,
+                };
+                if let Some(error) = error {
+                    return Err(invalid(format!("step {}: {}", i + 1, error)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Name of the target used for training.
+    pub fn train_target(&self) -> &str {
+        self.train_target
+            .as_deref()
+            .or_else(|| self.steps.first().map(|s| s.name()))
+            .unwrap_or(DEFAULT_TARGET)
+    }
+
+    /// Input columns any step reads.
+    pub fn input_columns(&self) -> BTreeSet<&str> {
+        self.steps
+            .iter()
+            .flat_map(|step| step.input_columns())
+            .collect()
+    }
+
+    /// Output column holding the training target.
+    pub fn train_column(&self) -> String {
+        format!("{}{}", LABEL_PREFIX, self.train_target())
+    }
+
+    /// Bin edges used by `binned_target` steps, keyed by target name. Saved
+    /// with run outputs so quantile bins can be reproduced on new data.
+    pub fn bin_edges(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, Vec<f64>>, PipelineStepError> {
+        let mut edges = BTreeMap::new();
+        for step in &self.steps {
+            if let LabelsConfig::BinnedTarget { name, .. } = step {
+                edges.insert(name.clone(), step.breakpoints(data)?);
+            }
+        }
+        Ok(edges)
+    }
+
+    /// Class encodings of `existing_target` steps with `encode: true`, keyed by
+    /// target name. Saved with run outputs so predictions can be decoded.
+    pub fn encodings(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, LabelEncoding>, PipelineStepError> {
+        let mut encodings = BTreeMap::new();
         for step in &self.steps {
-            result = step.apply(&result)?;
+            if let LabelsConfig::ExistingTarget {
+                name,
+                column,
+                encode: true,
+                ..
+            } = step
+            {
+                encodings.insert(name.clone(), LabelEncoding::fit(data.column(column)?)?);
+            }
+        }
+        Ok(encodings)
+    }
+
+    /// Match statistics of `external_target` steps, from the input `data` and
+    /// the labels [`LabelsPipeline::apply`] produced from it: unmatched rows
+    /// are the null labels.
+    pub fn join_reports(
+        &self,
+        data: &DataFrame,
+        labels: &DataFrame,
+    ) -> Result<Vec<JoinReport>, PipelineStepError> {
+        let mut reports = Vec::new();
+        for step in &self.steps {
+            reports.extend(step.join_report(data, labels)?);
+        }
+        Ok(reports)
+    }
+
+    /// Fitted transforms of `regression_target` steps, keyed by target name.
+    /// Saved with run outputs so predictions can be inverse-transformed.
+    pub fn transforms(
+        &self,
+        data: &DataFrame,
+    ) -> Result<BTreeMap<String, TargetTransform>, PipelineStepError> {
+        let mut transforms = BTreeMap::new();
+        for step in &self.steps {
+            if let LabelsConfig::RegressionTarget {
+                name,
+                transform: kind,
+                ..
+            } = step
+                && *kind != TransformKind::None
+            {
+                transforms.insert(name.clone(), step.transform(data)?);
+            }
+        }
+        Ok(transforms)
+    }
+
+    /// Runs the steps in order, returning the label columns and each step's
+    /// stats.
+    pub fn apply(
+        &self,
+        data: &DataFrame,
+    ) -> Result<(DataFrame, Vec<StepStats>), PipelineStepError> {
+        let mut result = data.clone();
+        let mut stats = Vec::with_capacity(self.steps.len());
+        for (i, step) in self.steps.iter().enumerate() {
+            let span = info_span!("step", function = step.function(), target = step.name());
+            let _enter = span.enter();
+            let start = Instant::now();
+            let output = step.apply(&result).map_err(|e| {
+                PipelineStepError::in_step(i, step.function(), Some(step.name()), &result, e)
+            })?;
+            let step_stats = StepStats::new(
+                i,
+                step.function(),
+                Some(step.name()),
+                &result,
+                &output,
+                Some(start.elapsed().as_secs_f64() * 1000.0),
+            );
+            info!(
+                input = ?result.shape(),
+                output = ?output.shape(),
+                duration_ms = step_stats.duration_ms,
+                frame_bytes = step_stats.frame_bytes_out,
+                rss_bytes = step_stats.rss_bytes,
+                "Step finished: {}",
+                step_stats.summary()
+            );
+            stats.push(step_stats);
+            result = output;
+        }
+        result = result.select(self.steps.iter().map(|s| s.output_column()))?;
+        Ok((result, stats))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformKind {
+    #[default]
+    None,
+    /// `ln(1 + y)`; tolerates zeros, e.g. revenue.
+    Log,
+    /// `(y - mean) / std`.
+    Standardize,
+    /// `(y^lambda - 1) / lambda`, or `ln(y)` at lambda 0. Requires `y > 0`.
+    BoxCox,
+}
+
+/// A target transform with its fitted parameters.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "transform", rename_all = "snake_case")]
+pub enum TargetTransform {
+    None,
+    Log,
+    Standardize { mean: f64, std: f64 },
+    BoxCox { lambda: f64 },
+}
+
+impl TargetTransform {
+    /// Fits `kind` to the non-null `values`. An explicit `lambda` is used as is.
+    pub fn fit(
+        kind: TransformKind,
+        lambda: Option<f64>,
+        values: &Float64Chunked,
+    ) -> Result<Self, PipelineStepError> {
+        match kind {
+            TransformKind::None => Ok(Self::None),
+            TransformKind::Log => {
+                if values.into_iter().flatten().any(|y| y <= -1.0) {
+                    return Err(polars_err!(
+                        ComputeError: "log transform of '{}' requires values > -1",
+                        values.name()
+                    )
+                    .into());
+                }
+                Ok(Self::Log)
+            }
+            TransformKind::Standardize => {
+                let mean = values.mean().unwrap_or(0.0);
+                let std = values.std(1).filter(|s| *s > 0.0).unwrap_or(1.0);
+                Ok(Self::Standardize { mean, std })
+            }
+            TransformKind::BoxCox => {
+                let ys: Vec<f64> = values.into_iter().flatten().collect();
+                if ys.iter().any(|y| *y <= 0.0) {
+                    return Err(polars_err!(
+                        ComputeError: "box_cox transform of '{}' requires values > 0",
+                        values.name()
+                    )
+                    .into());
+                }
+                Ok(Self::BoxCox {
+                    lambda: lambda.unwrap_or_else(|| box_cox_lambda(&ys)),
+                })
+            }
+        }
+    }
+
+    pub fn apply(&self, y: f64) -> f64 {
+        match *self {
+            Self::None => y,
+            Self::Log => y.ln_1p(),
+            Self::Standardize { mean, std } => (y - mean) / std,
+            Self::BoxCox { lambda: 0.0 } => y.ln(),
+            Self::BoxCox { lambda } => (y.powf(lambda) - 1.0) / lambda,
+        }
+    }
+
+    /// Maps a transformed value (e.g. a prediction) back to the original scale.
+    pub fn inverse(&self, y: f64) -> f64 {
+        match *self {
+            Self::None => y,
+            Self::Log => y.exp_m1(),
+            Self::Standardize { mean, std } => y * std + mean,
+            Self::BoxCox { lambda: 0.0 } => y.exp(),
+            Self::BoxCox { lambda } => (y * lambda + 1.0).powf(1.0 / lambda),
         }
-        result = result.select(["target"])?;
-        Ok(result)
+    }
+}
+
+/// Lambda in [-3, 3] (step 0.01) maximizing the Box-Cox log-likelihood.
+fn box_cox_lambda(ys: &[f64]) -> f64 {
+    let n = ys.len() as f64;
+    let log_sum: f64 = ys.iter().map(|y| y.ln()).sum();
+    let log_likelihood = |lambda: f64| {
+        let transform = TargetTransform::BoxCox { lambda };
+        let transformed: Vec<f64> = ys.iter().map(|y| transform.apply(*y)).collect();
+        let mean = transformed.iter().sum::<f64>() / n;
+        let variance = transformed.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / n;
+        (lambda - 1.0) * log_sum - n / 2.0 * variance.ln()
+    };
+    (-300..=300)
+        .map(|i| i as f64 / 100.0)
+        .map(|lambda| (lambda, log_likelihood(lambda)))
+        .filter(|(_, llf)| llf.is_finite())
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(1.0, |(lambda, _)| lambda)
+}
+
+/// Mapping between original string classes and their integer codes: class `i`
+/// is encoded as `i`. Values unseen at fit time encode to `-1`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LabelEncoding {
+    pub classes: Vec<String>,
+}
+
+impl LabelEncoding {
+    /// Unique non-null values of `column` in their natural order: numbers
+    /// numerically, `false` before `true`, strings and categoricals lexically.
+    /// Classes are stored as their string form.
+    pub fn fit(column: &Column) -> Result<Self, PipelineStepError> {
+        let column = match column.dtype() {
+            DataType::Categorical(_, _) | DataType::Enum(_, _) => column.cast(&DataType::String)?,
+            _ => column.clone(),
+        };
+        let classes = column
+            .drop_nulls()
+            .unique()?
+            .sort(Default::default())?
+            .cast(&DataType::String)?
+            .str()?
+            .into_iter()
+            .flatten()
+            .map(|s| s.to_string())
+            .collect();
+        Ok(Self { classes })
+    }
+
+    /// Adds `output` with the code of each value of `column`, matched by string
+    /// form through a join on the class table; unseen values and nulls get `-1`.
+    pub fn transform(
+        &self,
+        data: &DataFrame,
+        column: &str,
+        output: &str,
+    ) -> Result<DataFrame, PipelineStepError> {
+        let codes = df!(
+            "__class__" => &self.classes,
+            output => (0..self.classes.len() as i32).collect::<Vec<_>>(),
+        )?;
+
+        Ok(data
+            .clone()
+            .lazy()
+            .join(
+                codes.lazy(),
+                [col(column).cast(DataType::String)],
+                [col("__class__")],
+                JoinArgs {
+                    maintain_order: MaintainOrderJoin::Left,
+                    ..JoinArgs::new(JoinType::Left).with_coalesce(JoinCoalesce::KeepColumns)
+                },
+            )
+            .drop(by_name(["__class__"], true))
+            .with_columns([col(output).fill_null(lit(-1i32))])
+            .collect()?)
+    }
+
+    pub fn encode(&self, class: &str) -> i32 {
+        self.classes
+            .iter()
+            .position(|c| c == class)
+            .map_or(-1, |i| i as i32)
+    }
+
+    /// The original class for `code`, or `None` for `-1` and unknown codes.
+    pub fn decode(&self, code: i32) -> Option<&str> {
+        usize::try_from(code)
+            .ok()
+            .and_then(|i| self.classes.get(i))
+            .map(String::as_str)
     }
 }
 
 impl LabelsConfig {
+    /// Match statistics of an `external_target` step, from the input `data`
+    /// and a frame holding its output column; `None` for other steps.
+    pub fn join_report(
+        &self,
+        data: &DataFrame,
+        labels: &DataFrame,
+    ) -> Result<Option<JoinReport>, PipelineStepError> {
+        let Self::ExternalTarget {
+            keys,
+            min_coverage,
+            warn_coverage,
+            ..
+        } = self
+        else {
+            return Ok(None);
+        };
+        let matched: Vec<bool> = labels
+            .column(&self.output_column())?
+            .is_not_null()
+            .into_iter()
+            .map(|m| m.unwrap_or(false))
+            .collect();
+        Ok(Some(JoinReport::new(
+            self.name(),
+            "external_target",
+            &joins::row_keys(data, keys)?,
+            &matched,
+            *min_coverage,
+            *warn_coverage,
+        )))
+    }
+
+    /// The `function` tag this step was configured with.
+    pub fn function(&self) -> &'static str {
+        match self {
+            Self::ExistingTarget { .. } => "existing_target",
+            Self::ThresholdTarget { .. } => "threshold_target",
+            Self::BinnedTarget { .. } => "binned_target",
+            Self::RegressionTarget { .. } => "regression_target",
+            Self::ExternalTarget { .. } => "external_target",
+            Self::FutureEvent { .. } => "future_event",
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::ExistingTarget { name, .. }
+            | Self::ThresholdTarget { name, .. }
+            | Self::BinnedTarget { name, .. }
+            | Self::RegressionTarget { name, .. }
+            | Self::ExternalTarget { name, .. }
+            | Self::FutureEvent { name, .. } => name,
+        }
+    }
+
+    /// Input columns the target is built from.
+    pub fn source_columns(&self) -> Vec<&str> {
+        match self {
+            Self::ExistingTarget { column, .. }
+            | Self::ThresholdTarget { column, .. }
+            | Self::BinnedTarget { column, .. }
+            | Self::RegressionTarget { column, .. } => vec![column],
+            Self::FutureEvent { event, .. } => vec![event],
+            Self::ExternalTarget { .. } => Vec::new(),
+        }
+    }
+
+    /// Input columns the step reads, join keys and event columns included.
+    pub fn input_columns(&self) -> Vec<&str> {
+        match self {
+            Self::ExternalTarget { keys, .. } => keys.iter().map(|k| k.as_str()).collect(),
+            Self::FutureEvent {
+                entity,
+                timestamp,
+                event,
+                ..
+            } => vec![entity, timestamp, event],
+            _ => self.source_columns(),
+        }
+    }
+
+    /// Column this step writes, e.g. `label_target`.
+    pub fn output_column(&self) -> String {
+        format!("{}{}", LABEL_PREFIX, self.name())
+    }
+
     pub fn apply(&self, data: &DataFrame) -> Result<DataFrame, PipelineStepError> {
         match self {
             Self::ExistingTarget {
                 column,
                 encode,
                 drop_original,
+                ..
             } => {
                 if !self.is_column_exists(data, column) {
                     return Err(PipelineStepError::ColumnNotFound {
@@ -64,7 +711,7 @@ impl LabelsConfig {
                     let mut result = data
                         .clone()
                         .lazy()
-                        .with_columns([col(column).alias("target")])
+                        .with_columns([col(column).alias(self.output_column())])
                         .collect()?;
                     if *drop_original {
                         result = result.drop(column)?;
@@ -72,27 +719,85 @@ impl LabelsConfig {
                     return Ok(result);
                 }
 
-                let unique: Vec<String> = data
-                    .column(column)?
-                    .unique()?
-                    .sort(Default::default())?
-                    .str()?
-                    .into_iter()
-                    .flatten()
-                    .map(|s| s.to_string())
-                    .collect();
+                let mut result = LabelEncoding::fit(data.column(column)?)?.transform(
+                    data,
+                    column,
+                    &self.output_column(),
+                )?;
+
+                if *drop_original {
+                    result = result.drop(column)?;
+                }
+
+                Ok(result)
+            }
+
+            Self::ThresholdTarget {
+                column,
+                threshold,
+                comparator,
+                drop_original,
+                ..
+            } => {
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                let condition = match comparator {
+                    Comparator::Gt => col(column).gt(lit(*threshold)),
+                    Comparator::Lt => col(column).lt(lit(*threshold)),
+                };
+
+                let mut result = data
+                    .clone()
+                    .lazy()
+                    .with_columns([condition.cast(DataType::Int32).alias(self.output_column())])
+                    .collect()?;
+
+                if *drop_original {
+                    result = result.drop(column)?;
+                }
+
+                Ok(result)
+            }
 
-                let mut expr = lit(-1i32);
-                for (i, val) in unique.iter().enumerate() {
-                    expr = when(col(column).eq(lit(val.as_str())))
-                        .then(lit(i as i32))
+            Self::BinnedTarget {
+                column,
+                drop_original,
+                ..
+            } => {
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                let mut expr = lit(0i32);
+                for (i, edge) in self.breakpoints(data)?.iter().enumerate() {
+                    expr = when(col(column).gt_eq(lit(*edge)))
+                        .then(lit(i as i32 + 1))
                         .otherwise(expr);
                 }
+                let expr = when(col(column).is_null())
+                    .then(lit(NULL).cast(DataType::Int32))
+                    .otherwise(expr);
 
                 let mut result = data
                     .clone()
                     .lazy()
-                    .with_columns([expr.alias("target")])
+                    .with_columns([expr.alias(self.output_column())])
                     .collect()?;
 
                 if *drop_original {
@@ -101,10 +806,283 @@ impl LabelsConfig {
 
                 Ok(result)
             }
+
+            Self::RegressionTarget {
+                column,
+                drop_original,
+                ..
+            } => {
+                if !self.is_column_exists(data, column) {
+                    return Err(PipelineStepError::ColumnNotFound {
+                        found: column.clone(),
+                        available: data
+                            .get_column_names()
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect(),
+                    });
+                }
+
+                let transform = self.transform(data)?;
+                let values = data.column(column)?.cast(&DataType::Float64)?;
+                let target: Float64Chunked = values
+                    .f64()?
+                    .into_iter()
+                    .map(|y| y.map(|y| transform.apply(y)))
+                    .collect();
+
+                let mut result = data.clone();
+                result.with_column(target.with_name(self.output_column().into()).into_column())?;
+                if *drop_original {
+                    result = result.drop(column)?;
+                }
+                Ok(result)
+            }
+
+            Self::ExternalTarget {
+                source,
+                keys,
+                source_keys,
+                column,
+                relationship,
+                ..
+            } => {
+                for key in keys {
+                    if !self.is_column_exists(data, key) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: key.clone(),
+                            available: data
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
+                    }
+                }
+
+                if *relationship == Relationship::OneToOne {
+                    joins::check_one_to_one(self.name(), &joins::row_keys(data, keys)?)?;
+                }
+
+                let external = load_dataset(source)?;
+                let source_keys = source_keys.as_ref().unwrap_or(keys);
+                for name in source_keys.iter().chain([column]) {
+                    if !self.is_column_exists(&external, name) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: name.clone(),
+                            available: external
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
+                    }
+                }
+
+                // Keys are compared as strings, so `42` in one file matches
+                // `42` in the other whatever dtype each was inferred as.
+                let key_columns: Vec<String> = keys.iter().map(|k| format!("__key__{k}")).collect();
+                let external = external
+                    .lazy()
+                    .select(
+                        source_keys
+                            .iter()
+                            .zip(&key_columns)
+                            .map(|(k, alias)| col(k).cast(DataType::String).alias(alias))
+                            .chain([col(column).alias(self.output_column())])
+                            .collect::<Vec<_>>(),
+                    )
+                    .collect()?;
+
+                if external.select(&key_columns)?.is_duplicated()?.any() {
+                    return Err(polars_err!(
+                        ComputeError: "labels file '{}' has duplicate keys {:?}",
+                        source.path,
+                        source_keys
+                    )
+                    .into());
+                }
+
+                let result = data
+                    .clone()
+                    .lazy()
+                    .join(
+                        external.lazy(),
+                        keys.iter()
+                            .map(|k| col(k).cast(DataType::String))
+                            .collect::<Vec<_>>(),
+                        key_columns.iter().map(col).collect::<Vec<_>>(),
+                        JoinArgs {
+                            maintain_order: MaintainOrderJoin::Left,
+                            ..JoinArgs::new(JoinType::Left).with_coalesce(JoinCoalesce::KeepColumns)
+                        },
+                    )
+                    .drop(by_name(key_columns, true))
+                    .collect()?;
+
+                if let Some(report) = self.join_report(data, &result)? {
+                    report.enforce(&format!("labels file '{}'", source.path))?;
+                }
+
+                Ok(result)
+            }
+
+            Self::FutureEvent {
+                entity,
+                timestamp,
+                event,
+                horizon_days,
+                cutoff,
+                ..
+            } => {
+                for column in [entity, timestamp, event] {
+                    if !self.is_column_exists(data, column) {
+                        return Err(PipelineStepError::ColumnNotFound {
+                            found: column.clone(),
+                            available: data
+                                .get_column_names()
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect(),
+                        });
+                    }
+                }
+
+                let entities = data.column(entity)?.cast(&DataType::String)?;
+                let entities = entities.str()?;
+                let times = to_millis(data.column(timestamp)?.as_materialized_series())?;
+                let events = data.column(event)?.cast(&DataType::Boolean)?;
+                let events = events.bool()?;
+
+                let cutoff = match cutoff {
+                    Some(cutoff) => {
+                        to_millis(&Series::new("cutoff".into(), [cutoff.as_str()]))?.get(0)
+                    }
+                    None => times.max(),
+                };
+                let horizon = i64::from(*horizon_days) * MILLIS_PER_DAY;
+
+                // Sorted event times per entity, for a binary search per row.
+                let mut event_times: HashMap<&str, Vec<i64>> = HashMap::new();
+                for ((key, time), is_event) in entities.iter().zip(times.iter()).zip(events.iter())
+                {
+                    if let (Some(key), Some(time), Some(true)) = (key, time, is_event) {
+                        event_times.entry(key).or_default().push(time);
+                    }
+                }
+                for times in event_times.values_mut() {
+                    times.sort_unstable();
+                }
+
+                let labels: Int32Chunked = entities
+                    .iter()
+                    .zip(times.iter())
+                    .map(|(key, time)| {
+                        let (key, time, cutoff) = (key?, time?, cutoff?);
+                        if time + horizon > cutoff {
+                            return None;
+                        }
+                        let occurs = event_times.get(key).is_some_and(|times| {
+                            let next = times.partition_point(|&t| t <= time);
+                            times.get(next).is_some_and(|&t| t <= time + horizon)
+                        });
+                        Some(i32::from(occurs))
+                    })
+                    .collect();
+
+                let mut result = data.clone();
+                result.with_column(labels.with_name(self.output_column().into()).into_column())?;
+                Ok(result)
+            }
         }
     }
 
+    /// The fitted transform of a `regression_target` step on `data`.
+    fn transform(&self, data: &DataFrame) -> Result<TargetTransform, PipelineStepError> {
+        let Self::RegressionTarget {
+            column,
+            transform,
+            lambda,
+            ..
+        } = self
+        else {
+            return Ok(TargetTransform::None);
+        };
+        let values = data.column(column)?.cast(&DataType::Float64)?;
+        TargetTransform::fit(*transform, *lambda, values.f64()?)
+    }
+
+    /// Explicit breakpoints, or interior quantiles of `column` in `data`.
+    fn breakpoints(&self, data: &DataFrame) -> Result<Vec<f64>, PipelineStepError> {
+        let Self::BinnedTarget {
+            column,
+            breakpoints,
+            quantiles,
+            ..
+        } = self
+        else {
+            return Ok(Vec::new());
+        };
+
+        if let Some(breakpoints) = breakpoints {
+            return Ok(breakpoints.clone());
+        }
+
+        let values = data.column(column)?.cast(&DataType::Float64)?;
+        let values = values.f64()?;
+        let classes = quantiles.unwrap_or(2);
+        let mut edges = Vec::with_capacity(classes - 1);
+        for i in 1..classes {
+            if let Some(edge) =
+                values.quantile(i as f64 / classes as f64, QuantileMethod::Linear)?
+            {
+                edges.push(edge);
+            }
+        }
+        // Heavily tied data can produce repeated quantiles; those bins would be empty.
+        edges.dedup();
+        Ok(edges)
+    }
+
     fn is_column_exists(&self, data: &DataFrame, col_name: &str) -> bool {
         data.get_column_names().iter().any(|col| *col == col_name)
     }
 }
+
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// Dates, datetimes or ISO 8601 strings as milliseconds since the epoch.
+pub(crate) fn to_millis(values: &Series) -> Result<Int64Chunked, PipelineStepError> {
+    if let Ok(strings) = values.str() {
+        return strings
+            .iter()
+            .map(|value| {
+                value
+                    .map(|v| {
+                        parse_millis(v).ok_or_else(|| {
+                            polars_err!(
+                                ComputeError: "'{}' in column '{}' is not an ISO 8601 date or datetime",
+                                v,
+                                values.name()
+                            )
+                        })
+                    })
+                    .transpose()
+            })
+            .collect::<PolarsResult<Int64Chunked>>()
+            .map_err(PipelineStepError::from);
+    }
+
+    let millis = values
+        .cast(&DataType::Datetime(TimeUnit::Milliseconds, None))?
+        .cast(&DataType::Int64)?;
+    Ok(millis.i64()?.clone())
+}
+
+pub(crate) fn parse_millis(value: &str) -> Option<i64> {
+    value
+        .parse::<NaiveDateTime>()
+        .ok()
+        .or_else(|| value.parse::<NaiveDate>().ok()?.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc().timestamp_millis())
+}
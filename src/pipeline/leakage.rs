@@ -0,0 +1,171 @@
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::features::FeaturePipeline;
+use crate::pipeline::labels::LabelsPipeline;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakageAction {
+    /// Log the findings and carry on.
+    #[default]
+    Warn,
+    /// Fail the run before outputs are written.
+    Fail,
+}
+
+fn default_max_correlation() -> f64 {
+    0.95
+}
+
+/// Checks features against the training target before outputs are written.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LeakageConfig {
+    /// Features whose absolute Pearson correlation with the target reaches this
+    /// value are flagged.
+    #[serde(default = "default_max_correlation")]
+    pub max_correlation: f64,
+
+    #[serde(default)]
+    pub action: LeakageAction,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakageKind {
+    /// Same values as the target on every row.
+    Copy,
+    /// Computed from a column the target is built from.
+    DerivedFromTarget,
+    /// Correlation with the target at or above `max_correlation`.
+    Correlated,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LeakageIssue {
+    pub column: String,
+    pub kind: LeakageKind,
+    pub message: String,
+}
+
+impl LeakageConfig {
+    /// Compares every column of `features` with the training target in
+    /// `labels`. Non-numeric features are only checked for derivation.
+    pub fn check(
+        &self,
+        features_pipeline: &FeaturePipeline,
+        labels_pipeline: &LabelsPipeline,
+        features: &DataFrame,
+        labels: &DataFrame,
+    ) -> Result<Vec<LeakageIssue>, PipelineStepError> {
+        let target_column = labels_pipeline.train_column();
+        let target_sources: Vec<&str> = labels_pipeline
+            .steps
+            .iter()
+            .find(|s| s.output_column() == target_column)
+            .map(|s| s.source_columns())
+            .unwrap_or_default();
+        let target = numeric(labels.column(&target_column)?)?;
+
+        let columns: Vec<String> = features
+            .get_column_names()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+
+        let mut issues = Vec::new();
+        for entry in features_pipeline.lineage(&columns) {
+            if let Some(source) = entry
+                .sources
+                .iter()
+                .find(|s| target_sources.contains(&s.as_str()))
+            {
+                issues.push(LeakageIssue {
+                    message: format!(
+                        "'{}' is computed from '{}', which the target '{}' is built from",
+                        entry.column, source, target_column
+                    ),
+                    column: entry.column,
+                    kind: LeakageKind::DerivedFromTarget,
+                });
+            }
+        }
+
+        for column in &columns {
+            if issues.iter().any(|i| &i.column == column) {
+                continue;
+            }
+            let Ok(feature) = numeric(features.column(column)?) else {
+                continue;
+            };
+
+            let pairs: Vec<(f64, f64)> = feature
+                .iter()
+                .zip(target.iter())
+                .filter_map(|(x, y)| Some((x?, y?)))
+                .collect();
+            if pairs.is_empty() {
+                continue;
+            }
+
+            if pairs.iter().all(|(x, y)| x == y) {
+                issues.push(LeakageIssue {
+                    column: column.clone(),
+                    kind: LeakageKind::Copy,
+                    message: format!(
+                        "'{}' is identical to the target '{}'",
+                        column, target_column
+                    ),
+                });
+            } else if let Some(r) = pearson(&pairs)
+                && r.abs() >= self.max_correlation
+            {
+                issues.push(LeakageIssue {
+                    column: column.clone(),
+                    kind: LeakageKind::Correlated,
+                    message: format!(
+                        "'{}' has correlation {:.3} with the target '{}'",
+                        column, r, target_column
+                    ),
+                });
+            }
+        }
+
+        Ok(issues)
+    }
+}
+
+fn numeric(column: &Column) -> Result<Float64Chunked, PipelineStepError> {
+    if !column.dtype().is_primitive_numeric() && !column.dtype().is_bool() {
+        return Err(polars_err!(
+            ComputeError: "column '{}' is not numeric",
+            column.name()
+        )
+        .into());
+    }
+    Ok(column.cast(&DataType::Float64)?.f64()?.clone())
+}
+
+/// Pearson correlation; `None` when either side is constant.
+pub(crate) fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    // Checked exactly: rounding in the mean leaves a tiny variance for
+    // constant columns, which would otherwise correlate perfectly.
+    let (first_x, first_y) = *pairs.first()?;
+    if pairs.iter().all(|(x, _)| *x == first_x) || pairs.iter().all(|(_, y)| *y == first_y) {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let (mean_x, mean_y) = pairs
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    (var_x > 0.0 && var_y > 0.0).then(|| cov / (var_x * var_y).sqrt())
+}
@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+use crate::pipeline::features::{FeatureConfig, FeaturePipeline};
+use crate::pipeline::multi_hot;
+
+/// Where an output column came from and who to ask about it.
+#[derive(Serialize, Debug, Clone)]
+pub struct LineageEntry {
+    pub column: String,
+    /// 1-based position of the producing step in the config.
+    pub step: usize,
+    pub function: &'static str,
+    pub name: Option<String>,
+    pub sources: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl FeaturePipeline {
+    /// Maps each produced column back to the step that created it. Columns that
+    /// no step accounts for (e.g. passthrough inputs) are left out.
+    pub fn lineage(&self, output_columns: &[String]) -> Vec<LineageEntry> {
+        output_columns
+            .iter()
+            .filter_map(|column| {
+                let (i, step) = self
+                    .steps
+                    .iter()
+                    .enumerate()
+                    .find(|(_, step)| produces(step, column))?;
+                let metadata = step.metadata();
+                Some(LineageEntry {
+                    column: column.clone(),
+                    step: i + 1,
+                    function: step.function(),
+                    name: step.name().map(str::to_string),
+                    sources: match step {
+                        FeatureConfig::Ohe { .. } => column
+                            .split_once("__ohe__")
+                            .map(|(source, _)| vec![source.to_string()])
+                            .unwrap_or_default(),
+                        _ => step.input_columns().into_iter().map(String::from).collect(),
+                    },
+                    description: metadata.description.map(str::to_string),
+                    owner: metadata.owner.map(str::to_string),
+                    tags: metadata.tags.to_vec(),
+                })
+            })
+            .collect()
+    }
+}
+
+fn produces(step: &FeatureConfig, column: &str) -> bool {
+    match step {
+        FeatureConfig::Ohe { columns, .. } => column
+            .split_once("__ohe__")
+            .is_some_and(|(source, _)| columns.iter().any(|c| c == source)),
+        FeatureConfig::MultiHot { column: source, .. } => column
+            .split_once(multi_hot::SEPARATOR)
+            .is_some_and(|(prefix, _)| prefix == source),
+        _ => step
+            .name()
+            .is_some_and(|name| column.strip_prefix("feature_") == Some(name)),
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::pipeline::features::{FeatureConfig, FeaturePipeline};
+use crate::pipeline::multi_hot;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LintCode {
+    DuplicateName,
+    EmptyGroupBy,
+    EmptyOheColumns,
+    DuplicateOheColumn,
+    OheSeparatorInName,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub code: LintCode,
+    /// 1-based position of the step in the config.
+    pub step: usize,
+    pub name: Option<String>,
+    pub message: String,
+}
+
+impl FeaturePipeline {
+    /// Checks the config for mistakes that would otherwise surface as silently
+    /// missing features. Returns every issue found, not just the first.
+    pub fn lint(&self) -> Vec<LintIssue> {
+        let mut issues = Vec::new();
+        let mut first_by_name: HashMap<&str, usize> = HashMap::new();
+        let mut first_by_ohe_column: HashMap<&str, usize> = HashMap::new();
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let position = i + 1;
+            let issue = |severity, code, message: String| LintIssue {
+                severity,
+                code,
+                step: position,
+                name: step.name().map(|n| n.to_string()),
+                message,
+            };
+
+            if let Some(name) = step.name() {
+                match first_by_name.get(name) {
+                    Some(first) => issues.push(issue(
+                        Severity::Error,
+                        LintCode::DuplicateName,
+                        format!(
+                            "Feature name '{}' is already used by step {}; only one output is kept",
+                            name, first
+                        ),
+                    )),
+                    None => {
+                        first_by_name.insert(name, position);
+                    }
+                }
+
+                if let Some(separator) = ["__ohe__", multi_hot::SEPARATOR]
+                    .into_iter()
+                    .find(|separator| name.contains(separator))
+                {
+                    issues.push(issue(
+                        Severity::Warning,
+                        LintCode::OheSeparatorInName,
+                        format!(
+                            "Feature name '{}' contains the encoding separator '{}'",
+                            name, separator
+                        ),
+                    ));
+                }
+            }
+
+            if step.group_by().is_some_and(|g| g.is_empty()) {
+                issues.push(issue(
+                    Severity::Error,
+                    LintCode::EmptyGroupBy,
+                    "Aggregation has an empty group_by and is skipped".to_string(),
+                ));
+            }
+
+            match step {
+                FeatureConfig::Ohe { columns, .. } if columns.is_empty() => {
+                    issues.push(issue(
+                        Severity::Warning,
+                        LintCode::EmptyOheColumns,
+                        "OHE step has no columns and produces no output".to_string(),
+                    ));
+                }
+                FeatureConfig::Ohe { .. } | FeatureConfig::MultiHot { .. } => {
+                    for column in step.input_columns() {
+                        match first_by_ohe_column.get(column) {
+                            Some(first) => issues.push(issue(
+                                Severity::Warning,
+                                LintCode::DuplicateOheColumn,
+                                format!(
+                                    "Column '{}' is already encoded into dummies by step {}; duplicate output is dropped",
+                                    column, first
+                                ),
+                            )),
+                            None => {
+                                first_by_ohe_column.insert(column, position);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        issues
+    }
+}
@@ -1,2 +1,35 @@
+pub mod anomaly;
+pub mod balance;
+pub mod blocking;
+pub mod builder;
+pub mod calendar;
+pub mod correlation;
+pub mod email;
+pub mod expect;
 pub mod features;
+pub mod fitted;
+pub mod geohash;
+pub mod ip;
+pub mod joins;
+pub mod json;
+pub mod keys;
 pub mod labels;
+pub mod leakage;
+pub mod lineage;
+pub mod lint;
+pub mod multi_hot;
+pub mod noise;
+pub mod number;
+pub mod power;
+pub mod python;
+pub mod quality;
+pub mod quantile;
+pub mod rare;
+pub mod relevance;
+pub mod selection;
+pub mod split;
+pub mod sql;
+pub mod stats;
+pub mod text;
+pub mod url;
+pub mod woe;
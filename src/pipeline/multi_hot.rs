@@ -0,0 +1,73 @@
+//! Multi-hot encoding for `multi_hot` steps: a delimited string column such as
+//! `tag1|tag3` as one 0/1 column per known tag, for multi-valued attributes a
+//! single `ohe` category can't hold.
+
+use std::collections::{BTreeSet, HashMap};
+
+use polars::prelude::*;
+
+/// Separator between the source column and the tag in output column names,
+/// apart from `ohe`'s so the two encodings can't be mistaken for each other.
+pub const SEPARATOR: &str = "__mh__";
+
+/// The tags of `value`: split on `separator`, trimmed, empty ones skipped.
+pub fn split<'a>(value: &'a str, separator: &'a str) -> impl Iterator<Item = &'a str> {
+    value
+        .split(separator)
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+}
+
+/// The tags of `values`, sorted; with `max_tags`, only that many of those on
+/// the most rows, ties broken by tag.
+pub fn fit(values: &Column, separator: &str, max_tags: Option<usize>) -> PolarsResult<Vec<String>> {
+    let text = values.cast(&DataType::String)?;
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for value in text.str()?.into_iter().flatten() {
+        let row: BTreeSet<&str> = split(value, separator).collect();
+        for tag in row {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let mut tags: Vec<String> = ranked
+        .into_iter()
+        .take(max_tags.unwrap_or(usize::MAX))
+        .map(|(tag, _)| tag.to_string())
+        .collect();
+    tags.sort();
+    Ok(tags)
+}
+
+/// The 0/1 column of each of `tags`, named `<source>__mh__<tag>`; nulls and
+/// tags not among `tags` set none.
+pub fn columns(
+    values: &Column,
+    separator: &str,
+    tags: &[String],
+    source: &str,
+) -> PolarsResult<Vec<Column>> {
+    let text = values.cast(&DataType::String)?;
+    let index: HashMap<&str, usize> = tags
+        .iter()
+        .enumerate()
+        .map(|(i, tag)| (tag.as_str(), i))
+        .collect();
+    let mut flags = vec![vec![0i32; text.len()]; tags.len()];
+    for (row, value) in text.str()?.iter().enumerate() {
+        for tag in value.into_iter().flat_map(|value| split(value, separator)) {
+            if let Some(&i) = index.get(tag) {
+                flags[i][row] = 1;
+            }
+        }
+    }
+    Ok(tags
+        .iter()
+        .zip(flags)
+        .map(|(tag, flags)| {
+            Int32Chunked::from_vec(format!("{}{}{}", source, SEPARATOR, tag).into(), flags)
+                .into_column()
+        })
+        .collect())
+}
@@ -0,0 +1,63 @@
+//! Seeded noise for `random_feature` steps. Each value is a hash of the seed,
+//! the step's name and the row's position rather than a draw from a stateful
+//! generator, so two steps with one seed differ, reruns are identical and the
+//! Python target computes the same numbers.
+
+use std::collections::hash_map::RandomState;
+use std::f64::consts::PI;
+use std::hash::BuildHasher;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The distribution a `random_feature` step draws from, or a
+/// `quantile_transform` step maps onto.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Distribution {
+    /// Uniform on `[0, 1)`.
+    Uniform,
+    /// Standard normal, by the Box-Muller transform.
+    Normal,
+}
+
+/// The stream of values a step named `name` draws with `seed`; a fresh one
+/// per call when `seed` is `None`.
+pub fn stream(seed: Option<u64>, name: &str) -> u64 {
+    let seed = seed.unwrap_or_else(|| RandomState::new().hash_one(name));
+    // FNV-1a of the name, so the Python target can repeat it.
+    let name = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    seed ^ name
+}
+
+/// The value of the row at `row` in `stream`.
+pub fn value(stream: u64, row: u64, distribution: Distribution) -> f64 {
+    match distribution {
+        Distribution::Uniform => uniform(stream, row),
+        Distribution::Normal => {
+            let (u, v) = (uniform(stream, 2 * row), uniform(stream, 2 * row + 1));
+            (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * PI * v).cos()
+        }
+    }
+}
+
+/// The values of `height` rows as the column `name`.
+pub fn column(stream: u64, height: usize, distribution: Distribution, name: &str) -> Column {
+    let values = (0..height as u64)
+        .map(|row| value(stream, row, distribution))
+        .collect();
+    Float64Chunked::from_vec(name.into(), values).into_column()
+}
+
+/// The `counter`th uniform of `stream`: splitmix64's output for that step,
+/// its top 53 bits as a fraction.
+fn uniform(stream: u64, counter: u64) -> f64 {
+    let mut z = stream.wrapping_add(counter.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
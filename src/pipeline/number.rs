@@ -0,0 +1,23 @@
+//! Locale-aware number parsing for `parse_number` steps, e.g. `1.234,56 €`
+//! with `.` grouping thousands and `,` marking decimals.
+
+/// The number written in `text`: `currency` symbols, whitespace and the
+/// `thousands` separator are removed and `decimal` is read as the decimal
+/// point. `None` when what remains isn't a finite number.
+pub fn parse(
+    text: &str,
+    decimal: char,
+    thousands: Option<char>,
+    currency: &[String],
+) -> Option<f64> {
+    let mut text = text.to_string();
+    for symbol in currency {
+        text = text.replace(symbol.as_str(), "");
+    }
+    let number: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && Some(*c) != thousands)
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect();
+    number.parse().ok().filter(|x: &f64| x.is_finite())
+}
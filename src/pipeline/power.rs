@@ -0,0 +1,143 @@
+//! Power transforms for `power_transform` steps: the Box-Cox or Yeo-Johnson
+//! lambda that makes a column most normal-like, by maximum likelihood on the
+//! fit data, and the transform with it.
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Lambdas are searched for within ±this value; beyond it the transform
+/// overflows on ordinary data anyway.
+const LIMIT: f64 = 5.0;
+
+/// Golden-section iterations, narrowing the search to about 1e-11.
+const ITERATIONS: usize = 60;
+
+/// The family of power transforms a `power_transform` step fits.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerMethod {
+    /// Yeo-Johnson, defined for any value.
+    #[default]
+    YeoJohnson,
+    /// Box-Cox, for strictly positive values only.
+    BoxCox,
+}
+
+/// A fitted `power_transform` step.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct PowerFit {
+    pub method: PowerMethod,
+    pub lambda: f64,
+}
+
+impl PowerFit {
+    /// Fitted on the finite values of `values`: the lambda in [-5, 5]
+    /// maximizing the log-likelihood of the transformed values under a
+    /// normal distribution.
+    pub fn fit(values: &Column, method: PowerMethod) -> PolarsResult<Self> {
+        let values = values.cast(&DataType::Float64)?;
+        let fit: Vec<f64> = values
+            .f64()?
+            .iter()
+            .flatten()
+            .filter(|x| x.is_finite())
+            .collect();
+        if fit.is_empty() {
+            polars_bail!(ComputeError: "column '{}' has no finite values", values.name());
+        }
+        if method == PowerMethod::BoxCox && fit.iter().any(|x| *x <= 0.0) {
+            polars_bail!(
+                ComputeError: "box_cox needs positive values, but column '{}' has some at or below 0",
+                values.name()
+            );
+        }
+        // Without spread every lambda is equally likely; 1 keeps the shape.
+        if fit.iter().all(|x| *x == fit[0]) {
+            return Ok(Self {
+                method,
+                lambda: 1.0,
+            });
+        }
+
+        // The Jacobian's share of the log-likelihood, up to its factor
+        // lambda - 1.
+        let jacobian: f64 = match method {
+            PowerMethod::BoxCox => fit.iter().map(|x| x.ln()).sum(),
+            PowerMethod::YeoJohnson => fit.iter().map(|x| x.signum() * x.abs().ln_1p()).sum(),
+        };
+        let likelihood = |lambda: f64| {
+            let transformed: Vec<f64> = fit.iter().map(|x| transform(*x, method, lambda)).collect();
+            let n = transformed.len() as f64;
+            let mean = transformed.iter().sum::<f64>() / n;
+            let variance = transformed.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / n;
+            let value = (lambda - 1.0) * jacobian - n / 2.0 * variance.ln();
+            // Overflow, or a lambda extreme enough to round every value to
+            // one, rules the lambda out.
+            if value.is_finite() {
+                value
+            } else {
+                f64::NEG_INFINITY
+            }
+        };
+
+        let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+        let (mut a, mut b) = (-LIMIT, LIMIT);
+        for _ in 0..ITERATIONS {
+            let c = b - ratio * (b - a);
+            let d = a + ratio * (b - a);
+            if likelihood(c) > likelihood(d) {
+                b = d;
+            } else {
+                a = c;
+            }
+        }
+        Ok(Self {
+            method,
+            lambda: (a + b) / 2.0,
+        })
+    }
+
+    /// `x` transformed; `None` for NaN and, under Box-Cox, values at or
+    /// below 0.
+    pub fn value(&self, x: f64) -> Option<f64> {
+        if x.is_nan() || (self.method == PowerMethod::BoxCox && x <= 0.0) {
+            return None;
+        }
+        Some(transform(x, self.method, self.lambda))
+    }
+
+    /// The values of `values` transformed as the column `name`.
+    pub fn column(&self, values: &Column, name: &str) -> PolarsResult<Column> {
+        let values = values.cast(&DataType::Float64)?;
+        let transformed: Float64Chunked = values
+            .f64()?
+            .iter()
+            .map(|x| x.and_then(|x| self.value(x)))
+            .collect();
+        Ok(transformed.with_name(name.into()).into_column())
+    }
+}
+
+/// `x` under `method` with `lambda`, the limits taken at the lambdas where
+/// the formula divides by 0.
+fn transform(x: f64, method: PowerMethod, lambda: f64) -> f64 {
+    match method {
+        PowerMethod::BoxCox if lambda.abs() < f64::EPSILON => x.ln(),
+        PowerMethod::BoxCox => (x.powf(lambda) - 1.0) / lambda,
+        PowerMethod::YeoJohnson if x >= 0.0 => {
+            if lambda.abs() < f64::EPSILON {
+                x.ln_1p()
+            } else {
+                ((x + 1.0).powf(lambda) - 1.0) / lambda
+            }
+        }
+        PowerMethod::YeoJohnson => {
+            if (lambda - 2.0).abs() < f64::EPSILON {
+                -(-x).ln_1p()
+            } else {
+                -((1.0 - x).powf(2.0 - lambda) - 1.0) / (2.0 - lambda)
+            }
+        }
+    }
+}
@@ -0,0 +1,1394 @@
+//! Compiles a features config into a standalone Python script using Polars,
+//! for teams that can't run the Rust binary. The script reproduces each step
+//! with the same expression the pipeline builds, `map` coverage thresholds
+//! and `expect` checks included, and writes the same feature columns.
+
+use std::fmt::Write as _;
+
+use crate::pipeline::blocking::KeyEncoding;
+use crate::pipeline::calendar::{CalendarOutput, Rule, Shift};
+use crate::pipeline::email::{self, EmailOutput};
+use crate::pipeline::expect::{Expectation, OnFailure};
+use crate::pipeline::features::{
+    Comparator, FeatureConfig, FeaturePipeline, ListAgg, NullPolicy, RollingAgg, UnknownCategory,
+};
+use crate::pipeline::ip::IpOutput;
+use crate::pipeline::json::{self, JsonType, Segment};
+use crate::pipeline::noise::Distribution;
+use crate::pipeline::power::PowerMethod;
+use crate::pipeline::text::TextOperation;
+use crate::pipeline::url::UrlOutput;
+
+/// Helpers every script starts with, after the imports.
+const PRELUDE: &str = r##"FEATURE_COLUMNS = r"^(feature_.*|.*__ohe__.*|.*__mh__.*)$"
+
+
+def _ratio(numerator: str, denominator: str) -> pl.Expr:
+    # As floats, null for a zero denominator, as in the Rust pipeline and SQL.
+    denominator = pl.col(denominator).cast(pl.Float64)
+    return pl.when(denominator != 0).then(pl.col(numerator).cast(pl.Float64) / denominator)
+
+
+def _check(passed: bool, message: str, on_failure: str) -> None:
+    if passed:
+        return
+    if on_failure == "fail":
+        raise ValueError(message)
+    print(f"warning: {message}", file=sys.stderr)
+
+
+def _coverage(df: pl.DataFrame, column: str, mapping: dict) -> float:
+    if df.height == 0:
+        return 1.0
+    return df.select(pl.col(column).cast(pl.String).is_in(list(mapping)).mean()).item()
+
+
+def _ohe(
+    df: pl.DataFrame,
+    columns: list,
+    drop_first: bool,
+    drop_nulls: bool,
+    max_categories=None,
+    min_frequency=None,
+    other=False,
+) -> pl.DataFrame:
+    if max_categories is not None or min_frequency is not None:
+        # Categories ranked by count, then value, as in the Rust pipeline.
+        for column in columns:
+            values = df[column].cast(pl.String).to_list()
+            counts = {}
+            for value in values:
+                if value is not None:
+                    counts[value] = counts.get(value, 0) + 1
+            ranked = sorted(counts, key=lambda value: (-counts[value], value))
+            if min_frequency is not None:
+                ranked = [value for value in ranked if counts[value] >= min_frequency * df.height]
+            kept = set(ranked[:max_categories])
+            if len(kept) < len(counts):
+                folded = [v if v is None or v in kept else "other" for v in values]
+                df = df.with_columns(pl.Series(column, folded, dtype=pl.String))
+    df = df.to_dummies(columns, separator="__ohe__", drop_nulls=drop_nulls)
+    if drop_first:
+        # The smallest non-null category, as in the Rust pipeline, not the first seen.
+        for column in columns:
+            prefix = f"{column}__ohe__"
+            excluded = [prefix + "null"] + ([prefix + "other"] if other else [])
+            dummies = [c for c in df.columns if c.startswith(prefix) and c not in excluded]
+            if dummies:
+                df = df.drop(min(dummies))
+    if other:
+        # Always present, for the categories a fitted pipeline hasn't seen.
+        for column in columns:
+            if f"{column}__ohe__other" not in df.columns:
+                df = df.with_columns(pl.lit(0, dtype=pl.UInt8).alias(f"{column}__ohe__other"))
+    return df
+
+
+def _multi_hot(df: pl.DataFrame, column: str, separator: str, max_tags) -> pl.DataFrame:
+    # Tags trimmed and counted once per row, ranked as in the Rust pipeline.
+    rows = [
+        {tag.strip() for tag in value.split(separator) if tag.strip()} if value is not None else set()
+        for value in df[column].cast(pl.String).to_list()
+    ]
+    counts = {}
+    for row in rows:
+        for tag in row:
+            counts[tag] = counts.get(tag, 0) + 1
+    tags = sorted(sorted(counts, key=lambda tag: (-counts[tag], tag))[:max_tags])
+    return df.with_columns(
+        pl.Series(f"{column}__mh__{tag}", [int(tag in row) for row in rows], dtype=pl.UInt8)
+        for tag in tags
+    )
+
+
+def _time(df: pl.DataFrame, column: str) -> pl.Expr:
+    # ISO 8601 strings are parsed, as in the Rust pipeline.
+    if df.schema[column] == pl.String:
+        return pl.col(column).str.to_datetime(time_unit="ms")
+    return pl.col(column)
+
+
+def _easter(year: int) -> date:
+    a, b, c = year % 19, year // 100, year % 100
+    d, e = divmod(b, 4)
+    f = (b + 8) // 25
+    g = (b - f + 1) // 3
+    h = (19 * a + b - d - g + 15) % 30
+    i, k = divmod(c, 4)
+    l = (32 + 2 * e + 2 * i - h - k) % 7
+    m = (a + 11 * h + 22 * l) // 451
+    n = h + l - 7 * m + 114
+    return date(year, n // 31, n % 31 + 1)
+
+
+def _holiday(rule: tuple, year: int):
+    kind, since = rule[0], rule[-1]
+    if since is not None and year < since:
+        return None
+    if kind == "easter":
+        return _easter(year) + timedelta(days=rule[1])
+    if kind == "nth":
+        _, month, weekday, n, _ = rule
+        if n > 0:
+            first = date(year, month, 1)
+            return first + timedelta(days=(weekday - first.weekday()) % 7 + 7 * (n - 1))
+        last = date(year + month // 12, month % 12 + 1, 1) - timedelta(days=1)
+        return last - timedelta(days=(last.weekday() - weekday) % 7 + 7 * (-n - 1))
+    _, month, day, _, _ = rule
+    return date(year, month, day)
+
+
+def _holidays(rules: list, extra: list, first: int, last: int) -> list:
+    # Weekend holidays also give a weekday off: "observed" moves Saturdays
+    # back and Sundays forward, "substitute" to the next free weekday.
+    days = {date.fromisoformat(day) for day in extra}
+    for year in range(first, last + 1):
+        dates = [(d, r[3] if r[0] == "fixed" else None) for r in rules if (d := _holiday(r, year))]
+        days.update(d for d, _ in dates)
+        for day, shift in dates:
+            if day.weekday() < 5 or shift is None:
+                continue
+            if shift == "observed":
+                days.add(day + timedelta(days=-1 if day.weekday() == 5 else 1))
+                continue
+            day += timedelta(days=1)
+            while day.weekday() >= 5 or day in days:
+                day += timedelta(days=1)
+            days.add(day)
+    return sorted(days)
+
+
+def _calendar(df: pl.DataFrame, column: str, rules: list, extra: list, output: str) -> pl.Expr:
+    day = _time(df, column).cast(pl.Date)
+    first, last = df.select(day.dt.year().min(), day.dt.year().max().alias("last")).row(0)
+    holidays = _holidays(rules, extra, first, last + 1) if first is not None else _holidays([], extra, 0, -1)
+    if output == "is_holiday":
+        return day.is_in(holidays)
+    if output == "is_business_day":
+        return (day.dt.weekday() <= 5) & ~day.is_in(holidays)
+
+    def days_to_next(d: date):
+        i = bisect_left(holidays, d)
+        return (holidays[i] - d).days if i < len(holidays) else None
+
+    return day.map_elements(days_to_next, return_dtype=pl.UInt32)
+
+
+_GEOHASH = "0123456789bcdefghjkmnpqrstuvwxyz"
+
+
+def _encode_geohash(latitude, longitude, precision: int):
+    if latitude is None or longitude is None:
+        return None
+    if not (-90 <= latitude <= 90 and -180 <= longitude <= 180):
+        return None
+    lat, lon = [-90.0, 90.0], [-180.0, 180.0]
+    chars, index, bits, even = [], 0, 0, True
+    # Bits alternate between longitude and latitude, five to a character.
+    while len(chars) < precision:
+        span, value = (lon, longitude) if even else (lat, latitude)
+        mid = (span[0] + span[1]) / 2
+        index <<= 1
+        if value >= mid:
+            index |= 1
+            span[0] = mid
+        else:
+            span[1] = mid
+        even = not even
+        bits += 1
+        if bits == 5:
+            chars.append(_GEOHASH[index])
+            index, bits = 0, 0
+    return "".join(chars)
+
+
+def _geohash(latitude: str, longitude: str, precision: int) -> pl.Expr:
+    return pl.struct(
+        pl.col(latitude).cast(pl.Float64).alias("lat"),
+        pl.col(longitude).cast(pl.Float64).alias("lon"),
+    ).map_elements(
+        lambda point: _encode_geohash(point["lat"], point["lon"], precision),
+        return_dtype=pl.String,
+    )
+
+
+_PRIVATE = [
+    ipaddress.ip_network(n)
+    for n in ("10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16", "127.0.0.0/8", "169.254.0.0/16")
+    + ("::1/128", "fc00::/7", "fe80::/10")
+]
+
+
+def _ip(text: str, output: str):
+    try:
+        ip = ipaddress.ip_address(text.strip())
+    except ValueError:
+        return None
+    if ip.version == 6 and ip.ipv4_mapped is not None:
+        ip = ip.ipv4_mapped
+    if output == "is_private":
+        return any(ip.version == n.version and ip in n for n in _PRIVATE)
+    if output == "is_ipv6":
+        return ip.version == 6
+    if ip.version != 4:
+        return None
+    bits = 16 if output == "prefix_16" else 24
+    return str(ipaddress.ip_network(f"{ip}/{bits}", strict=False))
+
+
+def _ip_features(column: str, output: str) -> pl.Expr:
+    dtype = pl.String if output.startswith("prefix_") else pl.Boolean
+    return pl.col(column).cast(pl.String).map_elements(lambda t: _ip(t, output), return_dtype=dtype)
+
+
+def _json_value(text: str, path: list, dtype: str):
+    try:
+        value = json.loads(text)
+    except ValueError:
+        return None
+    for segment in path:
+        if isinstance(segment, int):
+            value = value[segment] if isinstance(value, list) and segment < len(value) else None
+        else:
+            value = value.get(segment) if isinstance(value, dict) else None
+    if value is None or isinstance(value, (dict, list)):
+        return None
+    if dtype == "string":
+        return value if isinstance(value, str) else json.dumps(value)
+    if dtype == "bool":
+        return value if isinstance(value, bool) else {"true": True, "false": False}.get(value)
+    if isinstance(value, bool):
+        return None
+    if isinstance(value, str):
+        try:
+            return float(value.strip()) if dtype == "float" else int(value.strip())
+        except ValueError:
+            return None
+    if dtype == "float":
+        return float(value)
+    return value if isinstance(value, int) else None
+
+
+def _json(column: str, path: list, dtype: str) -> pl.Expr:
+    # Each field parses the document again; fine for the sizes scripts see.
+    return_dtype = {"string": pl.String, "float": pl.Float64, "int": pl.Int64, "bool": pl.Boolean}[dtype]
+    return pl.col(column).cast(pl.String).map_elements(
+        lambda text: _json_value(text, path, dtype), return_dtype=return_dtype
+    )
+
+
+def _parse_number(df: pl.DataFrame, column: str, decimal: str, thousands, currency: list) -> pl.Expr:
+    # Numeric columns are only cast, as in the Rust pipeline.
+    if df.schema[column] != pl.String:
+        return pl.col(column).cast(pl.Float64)
+    text = pl.col(column)
+    for symbol in currency:
+        text = text.str.replace_all(symbol, "", literal=True)
+    text = text.str.replace_all(r"\s", "")
+    if thousands is not None:
+        text = text.str.replace_all(thousands, "", literal=True)
+    if decimal != ".":
+        text = text.str.replace_all(decimal, ".", literal=True)
+    number = text.cast(pl.Float64, strict=False)
+    return pl.when(number.is_finite()).then(number)
+
+
+_SOUNDEX = {c: d for d, letters in zip("123456", ["bfpv", "cgjkqsxz", "dt", "l", "mn", "r"]) for c in letters}
+
+
+def _soundex(word: str) -> str:
+    if not (word[:1].isascii() and word[:1].isalpha()):
+        return word
+    code, last = word[0].upper(), _SOUNDEX.get(word[0])
+    for c in word[1:]:
+        # "h" and "w" don't separate consonants with the same digit.
+        if c in "hw":
+            continue
+        digit = _SOUNDEX.get(c)
+        if digit is not None and digit != last:
+            code += digit
+            if len(code) == 4:
+                break
+        last = digit
+    return code.ljust(4, "0")
+
+
+def _blocking_key(columns: list, encoding: str) -> pl.Expr:
+    text = pl.concat_str([pl.col(c).cast(pl.String) for c in columns], separator=" ", ignore_nulls=True)
+    key = text.str.to_lowercase().str.replace_all(r"[^\p{Alphabetic}\p{N}\s]", "")
+    key = key.str.replace_all(r"\s+", " ").str.strip_chars(" ")
+    if encoding == "soundex":
+        key = key.map_elements(lambda k: " ".join(_soundex(w) for w in k.split(" ")), return_dtype=pl.String)
+    return pl.when(key != "").then(key)
+
+
+def _clean_text(column: str, operations: list) -> pl.Expr:
+    # Each operation as the Rust pipeline applies it, in order.
+    text = pl.col(column).cast(pl.String)
+    for operation in operations:
+        if operation == "trim":
+            text = text.str.strip_chars()
+        elif operation == "collapse_whitespace":
+            text = text.str.replace_all(r"\s+", " ")
+        elif operation == "lowercase":
+            text = text.str.to_lowercase()
+        elif operation in ("nfc", "nfkc"):
+            text = text.str.normalize(operation.upper())
+        elif operation == "strip_accents":
+            text = text.str.normalize("NFD").str.replace_all(r"\p{M}", "").str.normalize("NFC")
+        else:
+            text = text.str.replace_all(r"[^\p{Alphabetic}\p{N}\s]", "")
+    return pl.when(text != "").then(text)
+
+
+def _collapse_rare(column: str, min_count, min_fraction, other: str) -> pl.Expr:
+    # Categories counted as strings over all rows, nulls included.
+    x = pl.col(column).cast(pl.String)
+    count = pl.len().over(x)
+    kept = pl.lit(True)
+    if min_count is not None:
+        kept = kept & (count >= min_count)
+    if min_fraction is not None:
+        kept = kept & (count >= min_fraction * pl.len())
+    return pl.when(x.is_null()).then(None).when(kept).then(x).otherwise(pl.lit(other))
+
+
+def _email_domain(column: str) -> pl.Expr:
+    # What follows the last "@", as in the Rust pipeline.
+    domain = pl.col(column).cast(pl.String).str.strip_chars().str.extract(r".@([^@\s]+)$", 1)
+    domain = domain.str.to_lowercase().str.strip_chars_end(".")
+    return pl.when(domain != "").then(domain)
+
+
+def _url(text: str, output: str):
+    # Split by hand as in the Rust pipeline, so scheme-less URLs parse too.
+    text = text.strip()
+    scheme, sep, rest = text.partition("://")
+    if not (sep and scheme and all(c.isascii() and (c.isalnum() or c in "+-.") for c in scheme)):
+        rest = text[2:] if text.startswith("//") else text
+    rest = rest.split("#")[0]
+    end = min([i for i in (rest.find("/"), rest.find("?")) if i >= 0], default=len(rest))
+    host, remainder = rest[:end].rsplit("@")[-1], rest[end:]
+    host = host[: host.index("]") + 1] if host.startswith("[") and "]" in host else host.split(":")[0]
+    host = host.rstrip(".").lower()
+    if not host or any(c.isspace() for c in host):
+        return None
+    path, _, query = remainder.partition("?")
+    if output == "domain":
+        return host.removeprefix("www.")
+    if output == "tld":
+        try:
+            ipaddress.ip_address(host)
+            return None
+        except ValueError:
+            pass
+        if host.startswith("[") or "." not in host:
+            return None
+        return host.rsplit(".")[-1] or None
+    if output == "path_depth":
+        return len([p for p in path.split("/") if p])
+    return len([p for p in query.split("&") if p])
+
+
+def _url_parse(column: str, output: str) -> pl.Expr:
+    dtype = pl.String if output in ("domain", "tld") else pl.UInt32
+    return pl.col(column).cast(pl.String).map_elements(lambda t: _url(t, output), return_dtype=dtype)
+
+
+def _anomaly_score(df: pl.DataFrame, columns: list) -> pl.Series:
+    # Mahalanobis distance from the mean of the rows where every column is
+    # finite, as in the Rust pipeline; other rows get null.
+    import numpy as np
+
+    x = df.select(pl.col(c).cast(pl.Float64) for c in columns).to_numpy()
+    complete = np.isfinite(x).all(axis=1)
+    fit = x[complete]
+    if len(fit) <= len(columns):
+        raise ValueError(f"anomaly_score needs more complete rows than columns ({len(columns)}), got {len(fit)}")
+    mean = fit.mean(axis=0)
+    inverse = np.linalg.inv(np.atleast_2d(np.cov(fit, rowvar=False)))
+    d = x - mean
+    scores = np.sqrt(np.maximum(np.einsum("ij,jk,ik->i", d, inverse, d), 0.0))
+    return pl.Series(np.where(complete, scores, np.nan)).fill_nan(None)
+
+
+_MASK = (1 << 64) - 1
+
+
+def _uniform(stream: int, counter: int) -> float:
+    # splitmix64, as in the Rust pipeline.
+    z = (stream + (counter + 1) * 0x9E3779B97F4A7C15) & _MASK
+    z = ((z ^ (z >> 30)) * 0xBF58476D1CE4E5B9) & _MASK
+    z = ((z ^ (z >> 27)) * 0x94D049BB133111EB) & _MASK
+    z ^= z >> 31
+    return (z >> 11) / (1 << 53)
+
+
+def _random_feature(df: pl.DataFrame, seed, name: str, distribution: str) -> pl.Series:
+    # A hash of the seed, the step name and the row, so values match the Rust pipeline.
+    stream = random.getrandbits(64) if seed is None else seed
+    h = 0xCBF29CE484222325
+    for byte in name.encode():
+        h = ((h ^ byte) * 0x100000001B3) & _MASK
+    stream ^= h
+    if distribution == "uniform":
+        values = [_uniform(stream, row) for row in range(df.height)]
+    else:
+        values = [
+            math.sqrt(-2.0 * math.log(1.0 - _uniform(stream, 2 * row)))
+            * math.cos(2.0 * math.pi * _uniform(stream, 2 * row + 1))
+            for row in range(df.height)
+        ]
+    return pl.Series(values, dtype=pl.Float64)
+
+
+def _woe_encode(df: pl.DataFrame, column: str, target: str, bins: int, positive) -> pl.Series:
+    # Weight of evidence fitted on the rows with a target, as in the Rust
+    # pipeline: equal-frequency bins of numbers, counts smoothed by 0.5.
+    y = df[target]
+    if positive is not None:
+        events = [None if t is None else t == positive for t in y.cast(pl.String).to_list()]
+    else:
+        if not (y.dtype.is_numeric() or y.dtype == pl.Boolean):
+            raise ValueError(f"set 'positive' to the event class of string target '{target}'")
+        y = y.cast(pl.Float64).to_list()
+        classes = sorted({t for t in y if t is not None})
+        if len(classes) != 2:
+            raise ValueError(f"target '{target}' must have two values, got {len(classes)}")
+        events = [None if t is None else t == classes[1] for t in y]
+    x = df[column]
+    if x.dtype.is_numeric() or x.dtype == pl.Boolean:
+        x = x.cast(pl.Float64).to_list()
+        fit = sorted(v for v, e in zip(x, events) if v is not None and e is not None)
+        distinct = sorted(set(fit))
+        if len(distinct) <= bins:
+            edges = distinct[:-1]
+        else:
+            edges = sorted({fit[k * len(fit) // bins] for k in range(1, bins)})
+        keys = [None if v is None else str(bisect_left(edges, v)) for v in x]
+    else:
+        keys = x.cast(pl.String).to_list()
+    counts = {}
+    for key, event in zip(keys, events):
+        if event is not None:
+            counts.setdefault(key, [0, 0])[0 if event else 1] += 1
+    total_events = sum(c[0] for c in counts.values())
+    total_non_events = sum(c[1] for c in counts.values())
+    if total_events == 0 or total_non_events == 0:
+        raise ValueError(f"target '{target}' needs both events and non-events")
+    smoothed = 0.5 * len(counts)
+    woe = {
+        key: math.log(
+            ((non_events + 0.5) / (total_non_events + smoothed))
+            / ((events + 0.5) / (total_events + smoothed))
+        )
+        for key, (events, non_events) in counts.items()
+    }
+    return pl.Series([woe.get(key) for key in keys], dtype=pl.Float64)
+
+
+def _loo_target_encode(column: str, group_by: list) -> pl.Expr:
+    # The group's mean without the row's own value; the overall mean for
+    # rows alone in their group.
+    y = pl.col(column).cast(pl.Float64)
+    others = y.count().over(group_by) - y.is_not_null().cast(pl.Float64)
+    loo = (y.sum().over(group_by) - y.fill_null(0.0)) / others
+    return pl.when(others > 0).then(loo).otherwise(y.mean())
+
+
+def _quantile_transform(df: pl.DataFrame, column: str, quantiles: int, distribution: str) -> pl.Series:
+    # The empirical CDF of the column's finite values at evenly spaced
+    # quantiles, as in the Rust pipeline.
+    x = df[column].cast(pl.Float64).to_list()
+    fit = sorted(v for v in x if v is not None and math.isfinite(v))
+    if not fit:
+        raise ValueError(f"column '{column}' has no finite values")
+    n, m = len(fit), min(quantiles, len(fit))
+    references = []
+    for k in range(m):
+        position = k * (n - 1) / (m - 1) if m > 1 else 0.0
+        lower = int(position)
+        fraction = position - lower
+        if lower + 1 < n:
+            references.append(fit[lower] + fraction * (fit[lower + 1] - fit[lower]))
+        else:
+            references.append(fit[lower])
+
+    def cdf(v: float) -> float:
+        if m == 1:
+            return 0.5
+        below, through = bisect_left(references, v), bisect_right(references, v)
+        if through > below:
+            return (below + through - 1) / 2.0 / (m - 1)
+        if below == 0:
+            return 0.0
+        if below == m:
+            return 1.0
+        fraction = (v - references[below - 1]) / (references[below] - references[below - 1])
+        return (below - 1 + fraction) / (m - 1)
+
+    def mapped(v: float) -> float:
+        p = cdf(v)
+        if distribution == "uniform":
+            return p
+        return NormalDist().inv_cdf(min(max(p, 1e-7), 1.0 - 1e-7))
+
+    return pl.Series([None if v is None or math.isnan(v) else mapped(v) for v in x], dtype=pl.Float64)
+
+
+def _power_transform(df: pl.DataFrame, column: str, method: str) -> pl.Series:
+    # The lambda maximizing the normal log-likelihood of the transformed
+    # finite values, by golden-section search over [-5, 5] as in the Rust
+    # pipeline.
+    def transform(v: float, lmbda: float) -> float:
+        if method == "box_cox":
+            return math.log(v) if abs(lmbda) < sys.float_info.epsilon else (v**lmbda - 1.0) / lmbda
+        if v >= 0.0:
+            if abs(lmbda) < sys.float_info.epsilon:
+                return math.log1p(v)
+            return ((v + 1.0) ** lmbda - 1.0) / lmbda
+        if abs(lmbda - 2.0) < sys.float_info.epsilon:
+            return -math.log1p(-v)
+        return -((1.0 - v) ** (2.0 - lmbda) - 1.0) / (2.0 - lmbda)
+
+    def mapped(v: float, lmbda: float):
+        if math.isnan(v) or (method == "box_cox" and v <= 0.0):
+            return None
+        return transform(v, lmbda)
+
+    x = df[column].cast(pl.Float64).to_list()
+    fit = [v for v in x if v is not None and math.isfinite(v)]
+    if not fit:
+        raise ValueError(f"column '{column}' has no finite values")
+    if method == "box_cox" and any(v <= 0.0 for v in fit):
+        raise ValueError(f"box_cox needs positive values, but column '{column}' has some at or below 0")
+    if all(v == fit[0] for v in fit):
+        return pl.Series([None if v is None else mapped(v, 1.0) for v in x], dtype=pl.Float64)
+    if method == "box_cox":
+        jacobian = sum(math.log(v) for v in fit)
+    else:
+        jacobian = sum(math.copysign(1.0, v) * math.log1p(abs(v)) for v in fit)
+
+    def likelihood(lmbda: float) -> float:
+        try:
+            y = [transform(v, lmbda) for v in fit]
+            mean = sum(y) / len(y)
+            variance = sum((t - mean) ** 2 for t in y) / len(y)
+            value = (lmbda - 1.0) * jacobian - len(y) / 2.0 * math.log(variance)
+        except (OverflowError, ValueError):
+            return -math.inf
+        return value if math.isfinite(value) else -math.inf
+
+    ratio = (math.sqrt(5.0) - 1.0) / 2.0
+    a, b = -5.0, 5.0
+    for _ in range(60):
+        c, d = b - ratio * (b - a), a + ratio * (b - a)
+        if likelihood(c) > likelihood(d):
+            b = d
+        else:
+            a = c
+    lmbda = (a + b) / 2.0
+    return pl.Series([None if v is None else mapped(v, lmbda) for v in x], dtype=pl.Float64)
+
+
+def _robust_scale(column: str, group_by: list) -> pl.Expr:
+    # Centered on the median and divided by the IQR, or by 1 when it is 0.
+    x = pl.col(column).cast(pl.Float64)
+    median = x.median()
+    iqr = x.quantile(0.75, "linear") - x.quantile(0.25, "linear")
+    scale = pl.when(iqr == 0).then(1.0).otherwise(iqr)
+    if group_by:
+        median, scale = median.over(group_by), scale.over(group_by)
+    return (x - median) / scale
+
+
+def _no_nulls(df: pl.DataFrame, step: str, column: str) -> None:
+    nulls = df[column].null_count()
+    if nulls > 0:
+        raise ValueError(
+            f"step '{step}': column '{column}' has {nulls} null(s) and null_policy is 'error'"
+        )
+"##;
+
+/// A Python module that computes the features of `pipeline`, loaded from
+/// `source`, as `features(df)` and, run as a script, from an input file to an
+/// output file.
+pub fn compile(pipeline: &FeaturePipeline, source: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "#!/usr/bin/env python3");
+    let _ = writeln!(
+        out,
+        "\"\"\"Features of {}, generated by features-pipeline; do not edit.",
+        source
+    );
+    out.push_str(
+        "\nUsage: python features.py INPUT OUTPUT\n\n\
+         Reads INPUT (CSV or Parquet), computes the features and writes them to\n\
+         OUTPUT, as CSV or Parquet by extension. Requires polars >= 1.0.\n\"\"\"\n\n",
+    );
+    out.push_str(
+        "import ipaddress\nimport json\nimport math\nimport random\nimport sys\nfrom bisect import bisect_left, bisect_right\nfrom datetime import date, timedelta\nfrom statistics import NormalDist\n\n\
+         import polars as pl\n\n",
+    );
+    out.push_str(PRELUDE);
+
+    out.push_str("\n\ndef features(df: pl.DataFrame) -> pl.DataFrame:\n");
+    for (i, step) in pipeline.steps.iter().enumerate() {
+        let _ = writeln!(out, "    # {}. {}", i + 1, label(step));
+        for line in step_lines(step) {
+            let _ = writeln!(out, "    {}", line);
+        }
+    }
+    out.push_str("    return df.select(pl.col(FEATURE_COLUMNS))\n");
+
+    out.push_str(
+        r#"
+
+def _read(path: str) -> pl.DataFrame:
+    return pl.read_parquet(path) if path.endswith(".parquet") else pl.read_csv(path)
+
+
+def _write(df: pl.DataFrame, path: str) -> None:
+    if path.endswith(".parquet"):
+        df.write_parquet(path)
+    else:
+        df.write_csv(path)
+
+
+if __name__ == "__main__":
+    if len(sys.argv) != 3:
+        sys.exit(f"usage: {sys.argv[0]} INPUT OUTPUT")
+    _write(features(_read(sys.argv[1])), sys.argv[2])
+"#,
+    );
+    out
+}
+
+fn label(step: &FeatureConfig) -> String {
+    match step.name().or(step.key_columns().first().copied()) {
+        Some(name) => format!("{} '{}'", step.function(), name),
+        None => step.function().to_string(),
+    }
+}
+
+/// The statements applying `step` to `df`.
+fn step_lines(step: &FeatureConfig) -> Vec<String> {
+    let feature = step.name().map(|name| quote(&format!("feature_{}", name)));
+    let with_column = |expr: String| {
+        vec![format!(
+            "df = df.with_columns(({}).alias({}))",
+            expr,
+            feature.as_deref().unwrap_or_default()
+        )]
+    };
+    let aggregate = |agg: String, column: &str, group_by: &[String], policy: NullPolicy| {
+        if group_by.is_empty() {
+            return vec!["# no group_by: produces no column".to_string()];
+        }
+        let over = list(group_by);
+        let mut lines = Vec::new();
+        let expr = match policy {
+            NullPolicy::Ignore => format!("{}.over({})", agg, over),
+            NullPolicy::Propagate => format!(
+                "pl.when(pl.col({col}).null_count().over({over}) > 0)\
+                 .then(None).otherwise({agg}.over({over}))",
+                col = quote(column),
+                over = over,
+                agg = agg
+            ),
+            NullPolicy::Error => {
+                lines.push(format!(
+                    "_no_nulls(df, {}, {})",
+                    quote(step.name().unwrap_or_default()),
+                    quote(column)
+                ));
+                format!("{}.over({})", agg, over)
+            }
+        };
+        lines.extend(with_column(expr));
+        lines
+    };
+    let col = |column: &str| format!("pl.col({})", quote(column));
+
+    match step {
+        FeatureConfig::Mean {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => aggregate(
+            format!("{}.mean()", col(column)),
+            column,
+            group_by,
+            null_policy.unwrap_or_default(),
+        ),
+        FeatureConfig::LooTargetEncode {
+            column,
+            group_by,
+            noise,
+            seed,
+            name,
+            ..
+        } => {
+            if group_by.is_empty() {
+                return vec!["# no group_by: produces no column".to_string()];
+            }
+            let mut expr = format!("_loo_target_encode({}, {})", quote(column), list(group_by));
+            if let Some(noise) = noise {
+                expr = format!(
+                    "{} * (1.0 + {} * _random_feature(df, {}, {}, \"normal\"))",
+                    expr,
+                    number(*noise),
+                    seed.map_or("None".to_string(), |s| s.to_string()),
+                    quote(name)
+                );
+            }
+            with_column(expr)
+        }
+        FeatureConfig::Sum {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => aggregate(
+            format!("{}.sum()", col(column)),
+            column,
+            group_by,
+            null_policy.unwrap_or_default(),
+        ),
+        FeatureConfig::Max {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => aggregate(
+            format!("{}.max()", col(column)),
+            column,
+            group_by,
+            null_policy.unwrap_or_default(),
+        ),
+        FeatureConfig::Min {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => aggregate(
+            format!("{}.min()", col(column)),
+            column,
+            group_by,
+            null_policy.unwrap_or_default(),
+        ),
+        FeatureConfig::Count {
+            column, group_by, ..
+        } => aggregate(
+            format!("{}.len()", col(column)),
+            column,
+            group_by,
+            NullPolicy::Ignore,
+        ),
+        FeatureConfig::CountNonNull {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => aggregate(
+            format!("{}.count()", col(column)),
+            column,
+            group_by,
+            null_policy.unwrap_or_default(),
+        ),
+        FeatureConfig::PercentRank {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => aggregate(
+            format!(
+                "pl.when({c}.is_null()).then(None).otherwise(pl.when({c}.count() > 1)\
+                 .then(({c}.rank(\"min\") - 1) / ({c}.count() - 1)).otherwise(0.0))",
+                c = col(column)
+            ),
+            column,
+            group_by,
+            null_policy.unwrap_or_default(),
+        ),
+        FeatureConfig::RollingByTime {
+            column,
+            time_column,
+            window,
+            agg,
+            group_by,
+            ..
+        } => {
+            let rolling = |values: String| {
+                format!(
+                    "{}.rolling_sum_by(_time(df, {}), window_size={})",
+                    values,
+                    quote(time_column),
+                    quote(window)
+                )
+            };
+            let sum = rolling(format!("{}.cast(pl.Float64).fill_null(0.0)", col(column)));
+            let count = rolling(format!("{}.is_not_null().cast(pl.UInt32)", col(column)));
+            let expr = match agg {
+                RollingAgg::Sum => sum,
+                RollingAgg::Mean => {
+                    format!("pl.when({count} > 0).then({sum} / {count}).otherwise(None)")
+                }
+                RollingAgg::Count => count,
+            };
+            aggregate(expr, column, group_by, NullPolicy::Ignore)
+        }
+        FeatureConfig::Calendar {
+            column,
+            country,
+            region,
+            output,
+            holidays,
+            ..
+        } => {
+            let rules: Vec<String> = country
+                .holidays(region.as_deref())
+                .map(|holiday| {
+                    let since = holiday.since.map_or("None".to_string(), |y| y.to_string());
+                    match holiday.rule {
+                        Rule::Fixed { month, day, shift } => {
+                            let shift = match shift {
+                                Shift::None => "None",
+                                Shift::Observed => "\"observed\"",
+                                Shift::Substitute => "\"substitute\"",
+                            };
+                            format!("(\"fixed\", {}, {}, {}, {})", month, day, shift, since)
+                        }
+                        Rule::Nth { month, weekday, n } => format!(
+                            "(\"nth\", {}, {}, {}, {})",
+                            month,
+                            weekday.num_days_from_monday(),
+                            n,
+                            since
+                        ),
+                        Rule::Easter { offset } => format!("(\"easter\", {}, {})", offset, since),
+                    }
+                })
+                .collect();
+            let output = match output {
+                CalendarOutput::IsHoliday => "is_holiday",
+                CalendarOutput::IsBusinessDay => "is_business_day",
+                CalendarOutput::DaysToNextHoliday => "days_to_next_holiday",
+            };
+            with_column(format!(
+                "_calendar(df, {}, [{}], {}, \"{}\")",
+                quote(column),
+                rules.join(", "),
+                list(holidays),
+                output
+            ))
+        }
+        FeatureConfig::Geohash {
+            latitude,
+            longitude,
+            precision,
+            name,
+            ..
+        } => vec![format!(
+            "df = df.with_columns(_geohash({}, {}, {}).alias({}))",
+            quote(latitude),
+            quote(longitude),
+            precision,
+            quote(name)
+        )],
+        FeatureConfig::IpFeatures {
+            column,
+            output,
+            name,
+            ..
+        } => {
+            let output = match output {
+                IpOutput::IsPrivate => "is_private",
+                IpOutput::IsIpv6 => "is_ipv6",
+                IpOutput::Prefix16 => "prefix_16",
+                IpOutput::Prefix24 => "prefix_24",
+            };
+            let expr = format!("_ip_features({}, \"{}\")", quote(column), output);
+            if step.key_columns().is_empty() {
+                with_column(expr)
+            } else {
+                vec![format!(
+                    "df = df.with_columns({}.alias({}))",
+                    expr,
+                    quote(name)
+                )]
+            }
+        }
+        FeatureConfig::JsonExtract { column, fields, .. } => {
+            let columns: Vec<String> = fields
+                .iter()
+                .map(|field| {
+                    let path: Vec<String> = json::parse_path(&field.path)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|segment| match segment {
+                            Segment::Key(key) => quote(&key),
+                            Segment::Index(index) => index.to_string(),
+                        })
+                        .collect();
+                    let dtype = match field.dtype {
+                        JsonType::String => "string",
+                        JsonType::Float => "float",
+                        JsonType::Int => "int",
+                        JsonType::Bool => "bool",
+                    };
+                    format!(
+                        "_json({}, [{}], \"{}\").alias({})",
+                        quote(column),
+                        path.join(", "),
+                        dtype,
+                        quote(&field.name)
+                    )
+                })
+                .collect();
+            vec![format!("df = df.with_columns({})", columns.join(", "))]
+        }
+        FeatureConfig::EmailDomain {
+            column,
+            output,
+            free_mail,
+            name,
+            ..
+        } => {
+            let domain = format!("_email_domain({})", quote(column));
+            match output {
+                EmailOutput::Domain => vec![format!(
+                    "df = df.with_columns({}.alias({}))",
+                    domain,
+                    quote(name)
+                )],
+                EmailOutput::IsFreeMail => {
+                    let providers: Vec<String> =
+                        email::free_mail(free_mail.as_deref()).into_iter().collect();
+                    with_column(format!("{}.is_in({})", domain, list(&providers)))
+                }
+            }
+        }
+        FeatureConfig::UrlParse {
+            column,
+            output,
+            name,
+            ..
+        } => {
+            let output = match output {
+                UrlOutput::Domain => "domain",
+                UrlOutput::Tld => "tld",
+                UrlOutput::PathDepth => "path_depth",
+                UrlOutput::QueryParams => "query_params",
+            };
+            let expr = format!("_url_parse({}, \"{}\")", quote(column), output);
+            if step.key_columns().is_empty() {
+                with_column(expr)
+            } else {
+                vec![format!(
+                    "df = df.with_columns({}.alias({}))",
+                    expr,
+                    quote(name)
+                )]
+            }
+        }
+        FeatureConfig::CountDistinct {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => aggregate(
+            format!("{}.drop_nulls().n_unique()", col(column)),
+            column,
+            group_by,
+            null_policy.unwrap_or_default(),
+        ),
+        FeatureConfig::Ratio {
+            numerator,
+            denominator,
+            ..
+        } => with_column(format!(
+            "_ratio({}, {})",
+            quote(numerator),
+            quote(denominator)
+        )),
+        FeatureConfig::Threshold {
+            column,
+            threshold,
+            comparator,
+            ..
+        } => {
+            let op = match comparator {
+                Comparator::Gt => ">",
+                Comparator::Lt => "<",
+            };
+            with_column(format!("{} {} {}", col(column), op, number(*threshold)))
+        }
+        FeatureConfig::BlockingKey {
+            columns,
+            encoding,
+            name,
+            ..
+        } => {
+            let encoding = match encoding {
+                KeyEncoding::Normalized => "normalized",
+                KeyEncoding::Soundex => "soundex",
+            };
+            vec![format!(
+                "df = df.with_columns(_blocking_key({}, \"{}\").alias({}))",
+                list(columns),
+                encoding,
+                quote(name)
+            )]
+        }
+        FeatureConfig::ParseNumber {
+            column,
+            decimal,
+            thousands,
+            currency,
+            name,
+            ..
+        } => vec![format!(
+            "df = df.with_columns(_parse_number(df, {}, {}, {}, {}).alias({}))",
+            quote(column),
+            quote(&decimal.to_string()),
+            thousands.map_or("None".to_string(), |t| quote(&t.to_string())),
+            list(currency),
+            quote(name)
+        )],
+        FeatureConfig::CleanText {
+            column,
+            operations,
+            name,
+            ..
+        } => {
+            let operations: Vec<String> = operations
+                .iter()
+                .map(|o| {
+                    match o {
+                        TextOperation::Trim => "trim",
+                        TextOperation::CollapseWhitespace => "collapse_whitespace",
+                        TextOperation::Lowercase => "lowercase",
+                        TextOperation::Nfc => "nfc",
+                        TextOperation::Nfkc => "nfkc",
+                        TextOperation::StripAccents => "strip_accents",
+                        TextOperation::RemovePunctuation => "remove_punctuation",
+                    }
+                    .to_string()
+                })
+                .collect();
+            vec![format!(
+                "df = df.with_columns(_clean_text({}, {}).alias({}))",
+                quote(column),
+                list(&operations),
+                quote(name)
+            )]
+        }
+        FeatureConfig::CollapseRare {
+            column,
+            min_count,
+            min_fraction,
+            other,
+            name,
+            ..
+        } => vec![format!(
+            "df = df.with_columns(_collapse_rare({}, {}, {}, {}).alias({}))",
+            quote(column),
+            min_count.map_or("None".to_string(), |n| n.to_string()),
+            min_fraction.map_or("None".to_string(), number),
+            quote(other),
+            quote(name)
+        )],
+        FeatureConfig::ListLen { column, .. } => with_column(format!("{}.list.len()", col(column))),
+        FeatureConfig::ListContains { column, value, .. } => with_column(format!(
+            "{}.cast(pl.List(pl.String)).list.contains({})",
+            col(column),
+            quote(value)
+        )),
+        FeatureConfig::RandomFeature {
+            distribution,
+            seed,
+            name,
+            ..
+        } => with_column(format!(
+            "_random_feature(df, {}, {}, {})",
+            seed.map_or("None".to_string(), |s| s.to_string()),
+            quote(name),
+            quote(match distribution {
+                Distribution::Uniform => "uniform",
+                Distribution::Normal => "normal",
+            })
+        )),
+        FeatureConfig::AnomalyScore { columns, .. } => {
+            with_column(format!("_anomaly_score(df, {})", list(columns)))
+        }
+        FeatureConfig::PowerTransform { column, method, .. } => with_column(format!(
+            "_power_transform(df, {}, {})",
+            quote(column),
+            quote(match method {
+                PowerMethod::YeoJohnson => "yeo_johnson",
+                PowerMethod::BoxCox => "box_cox",
+            })
+        )),
+        FeatureConfig::RobustScale {
+            column, group_by, ..
+        } => with_column(format!(
+            "_robust_scale({}, {})",
+            quote(column),
+            list(group_by)
+        )),
+        FeatureConfig::QuantileTransform {
+            column,
+            distribution,
+            quantiles,
+            ..
+        } => with_column(format!(
+            "_quantile_transform(df, {}, {}, {})",
+            quote(column),
+            quantiles,
+            quote(match distribution {
+                Distribution::Uniform => "uniform",
+                Distribution::Normal => "normal",
+            })
+        )),
+        FeatureConfig::WoeEncode {
+            column,
+            target,
+            bins,
+            positive,
+            ..
+        } => with_column(format!(
+            "_woe_encode(df, {}, {}, {}, {})",
+            quote(column),
+            quote(target),
+            bins,
+            positive.as_deref().map_or("None".to_string(), quote)
+        )),
+        FeatureConfig::ListAgg { column, agg, .. } => with_column(match agg {
+            ListAgg::Mean => format!("{}.list.mean()", col(column)),
+            ListAgg::Sum => format!("{}.list.sum().cast(pl.Float64)", col(column)),
+        }),
+        FeatureConfig::Map {
+            column,
+            mapping,
+            default,
+            name,
+            min_coverage,
+            warn_coverage,
+            ..
+        } => {
+            let table: Vec<String> = mapping
+                .iter()
+                .map(|(k, v)| format!("{}: {}", quote(k), number(*v)))
+                .collect();
+            let mut lines = vec![format!("mapping = {{{}}}", table.join(", "))];
+            if min_coverage.is_some() || warn_coverage.is_some() {
+                lines.push(format!(
+                    "coverage = _coverage(df, {}, mapping)",
+                    quote(column)
+                ));
+                for (threshold, on_failure) in [(min_coverage, "fail"), (warn_coverage, "warn")] {
+                    if let Some(threshold) = threshold {
+                        lines.push(format!(
+                            "_check(coverage >= {}, {}, \"{}\")",
+                            number(*threshold),
+                            quote(&format!("map '{}': coverage below {}", name, threshold)),
+                            on_failure
+                        ));
+                    }
+                }
+            }
+            lines.extend(with_column(format!(
+                "{}.cast(pl.String).replace_strict(mapping, default={}, return_dtype=pl.Float64)",
+                col(column),
+                default.map_or("None".to_string(), number)
+            )));
+            lines
+        }
+        FeatureConfig::Expect {
+            checks, on_failure, ..
+        } => {
+            let on_failure = match on_failure {
+                OnFailure::Fail => "fail",
+                OnFailure::Warn => "warn",
+            };
+            checks
+                .iter()
+                .map(|check| {
+                    format!(
+                        "_check({}, {}, \"{}\")",
+                        check_expr(check),
+                        quote(&format!("expectation {} failed", check)),
+                        on_failure
+                    )
+                })
+                .collect()
+        }
+        FeatureConfig::Ohe {
+            columns,
+            drop_first,
+            drop_nulls,
+            max_categories,
+            min_frequency,
+            handle_unknown,
+            ..
+        } => {
+            let mut limits = String::new();
+            if max_categories.is_some() || min_frequency.is_some() {
+                limits = format!(
+                    ", {}, {}",
+                    max_categories.map_or("None".to_string(), |n| n.to_string()),
+                    min_frequency.map_or("None".to_string(), number)
+                );
+            }
+            if *handle_unknown == UnknownCategory::Other {
+                limits.push_str(", other=True");
+            }
+            vec![format!(
+                "df = _ohe(df, {}, {}, {}{})",
+                list(columns),
+                boolean(*drop_first),
+                boolean(*drop_nulls),
+                limits
+            )]
+        }
+        FeatureConfig::MultiHot {
+            column,
+            separator,
+            max_tags,
+            ..
+        } => vec![format!(
+            "df = _multi_hot(df, {}, {}, {})",
+            quote(column),
+            quote(separator),
+            max_tags.map_or("None".to_string(), |n| n.to_string())
+        )],
+    }
+}
+
+/// A Python expression that is true when `check` holds for `df`.
+fn check_expr(check: &Expectation) -> String {
+    match check {
+        Expectation::NullRate { column, max } => format!(
+            "df.height == 0 or df[{}].null_count() / df.height <= {}",
+            quote(column),
+            number(*max)
+        ),
+        Expectation::Unique { columns } => {
+            format!("not df.select({}).is_duplicated().any()", list(columns))
+        }
+        Expectation::Min { column, at_least } => format!(
+            "df[{col}].cast(pl.Float64).min() is None or df[{col}].cast(pl.Float64).min() >= {min}",
+            col = quote(column),
+            min = number(*at_least)
+        ),
+        Expectation::Max { column, at_most } => format!(
+            "df[{col}].cast(pl.Float64).max() is None or df[{col}].cast(pl.Float64).max() <= {max}",
+            col = quote(column),
+            max = number(*at_most)
+        ),
+        Expectation::ValuesIn { column, values } => format!(
+            "df[{}].drop_nulls().cast(pl.String).is_in({}).all()",
+            quote(column),
+            list(values)
+        ),
+    }
+}
+
+/// A Python string literal; JSON string escapes are valid Python.
+fn quote(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+fn list(values: &[String]) -> String {
+    let items: Vec<String> = values.iter().map(|v| quote(v)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:?}", value)
+    } else if value.is_nan() {
+        "float(\"nan\")".to_string()
+    } else {
+        format!("float(\"{}inf\")", if value < 0.0 { "-" } else { "" })
+    }
+}
+
+fn boolean(value: bool) -> &'static str {
+    if value { "True" } else { "False" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::format::ConfigFormat;
+    use crate::pipeline::sql::{SqlDialect, SqlQuery};
+
+    const CONFIG: &str = r#"
+steps:
+  - {function: mean, column: amount, group_by: [city], name: avg_amount}
+  - {function: count_distinct, column: user, group_by: [city], name: users}
+  - {function: ratio, numerator: amount, denominator: items, name: per_item}
+  - {function: threshold, column: amount, threshold: 10.0, comparator: gt, name: big}
+  - {function: ohe, columns: [city], drop_first: false, drop_nulls: false}
+  - {function: multi_hot, column: tags}
+"#;
+
+    fn pipeline() -> FeaturePipeline {
+        FeaturePipeline::from_content("test", CONFIG, ConfigFormat::Yaml).unwrap()
+    }
+
+    /// Feature columns the script assigns, in step order.
+    fn aliases(script: &str) -> Vec<&str> {
+        script
+            .lines()
+            .filter_map(|line| line.split(".alias(\"").nth(1)?.split('"').next())
+            .filter(|name| name.starts_with("feature_"))
+            .collect()
+    }
+
+    #[test]
+    fn script_and_query_compute_the_same_features() {
+        let pipeline = pipeline();
+        let script = compile(&pipeline, "test.yaml");
+        for dialect in [SqlDialect::Postgres, SqlDialect::BigQuery] {
+            let query = SqlQuery::compile(&pipeline, "tx", dialect);
+            assert_eq!(aliases(&script), query.features, "{:?}", dialect);
+        }
+    }
+
+    #[test]
+    fn steps_without_sql_are_still_scripted() {
+        let pipeline = pipeline();
+        let query = SqlQuery::compile(&pipeline, "tx", SqlDialect::Postgres);
+        let skipped: Vec<&str> = query
+            .skipped
+            .iter()
+            .map(|s| s.split(':').next().unwrap())
+            .collect();
+        assert_eq!(skipped, ["ohe", "multi_hot"]);
+
+        let script = compile(&pipeline, "test.yaml");
+        assert!(script.contains("df = _ohe(df, [\"city\"], False, False)"));
+        assert!(script.contains("df = _multi_hot(df, \"tags\", \"|\", None)"));
+        assert!(script.contains(r#"FEATURE_COLUMNS = r"^(feature_.*|.*__ohe__.*|.*__mh__.*)$""#));
+    }
+
+    #[test]
+    fn ratio_divides_as_floats_with_null_for_zero_in_both() {
+        let pipeline = pipeline();
+        let query = SqlQuery::compile(&pipeline, "tx", SqlDialect::Postgres);
+        assert!(
+            query
+                .sql
+                .contains(r#"CAST("amount" AS DOUBLE PRECISION) / NULLIF("items", 0)"#)
+        );
+
+        let script = compile(&pipeline, "test.yaml");
+        assert!(script.contains(r#"(_ratio("amount", "items")).alias("feature_per_item")"#));
+        assert!(script.contains("pl.when(denominator != 0)"));
+        assert!(script.contains("pl.col(numerator).cast(pl.Float64) / denominator"));
+    }
+}
@@ -0,0 +1,75 @@
+use polars::prelude::*;
+use serde::Serialize;
+
+use crate::errors::PipelineStepError;
+use crate::profiling;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Degeneracy {
+    /// Every value is null.
+    AllNull,
+    /// A single distinct non-null value.
+    Constant,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FeatureQuality {
+    pub column: String,
+    pub null_fraction: f64,
+    /// Sample variance of the non-null values; `None` for non-numeric columns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variance: Option<f64>,
+    pub distinct: usize,
+    /// Share of rows equal to zero (or `false`).
+    pub zero_fraction: f64,
+    /// Share of rows holding the most frequent non-null value.
+    pub dominant_fraction: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub degenerate: Option<Degeneracy>,
+}
+
+/// Diagnostics for every column of a feature frame, before nulls are filled.
+pub fn assess(features: &DataFrame) -> Result<Vec<FeatureQuality>, PipelineStepError> {
+    features
+        .get_columns()
+        .iter()
+        .map(|column| {
+            let rows = column.len().max(1) as f64;
+            let nulls = column.null_count();
+            let distinct = column.drop_nulls().n_unique()?;
+            let dominant = profiling::value_counts(column)?
+                .into_values()
+                .max()
+                .unwrap_or(0);
+
+            let (variance, zeros) =
+                if column.dtype().is_primitive_numeric() || column.dtype().is_bool() {
+                    let values = column.cast(&DataType::Float64)?;
+                    let values = values.f64()?;
+                    let zeros = values.into_iter().flatten().filter(|v| *v == 0.0).count();
+                    (values.var(1), zeros)
+                } else {
+                    (None, 0)
+                };
+
+            let degenerate = if nulls == column.len() {
+                Some(Degeneracy::AllNull)
+            } else if distinct == 1 {
+                Some(Degeneracy::Constant)
+            } else {
+                None
+            };
+
+            Ok(FeatureQuality {
+                column: column.name().to_string(),
+                null_fraction: nulls as f64 / rows,
+                variance,
+                distinct,
+                zero_fraction: zeros as f64 / rows,
+                dominant_fraction: dominant as f64 / rows,
+                degenerate,
+            })
+        })
+        .collect()
+}
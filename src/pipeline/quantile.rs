@@ -0,0 +1,213 @@
+//! Empirical CDFs for `quantile_transform` steps: a value's position among
+//! the fit data's quantiles, as a uniform on [0, 1] or mapped through the
+//! inverse normal CDF (rank-gauss).
+
+use polars::prelude::*;
+use serde::Serialize;
+
+use crate::pipeline::noise::Distribution;
+
+/// Positions this close to 0 or 1 are clipped before the inverse normal
+/// CDF, so the fit data's extremes map to about ±5.2 rather than infinity.
+const BOUND: f64 = 1e-7;
+
+/// The fit data's quantiles at evenly spaced levels from 0 to 1.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct QuantileMap {
+    pub references: Vec<f64>,
+}
+
+impl QuantileMap {
+    /// Fitted on the finite values of `values`, at `quantiles` levels or one
+    /// per value when there are fewer.
+    pub fn fit(values: &Column, quantiles: usize) -> PolarsResult<Self> {
+        let values = values.cast(&DataType::Float64)?;
+        let mut sorted: Vec<f64> = values
+            .f64()?
+            .iter()
+            .flatten()
+            .filter(|x| x.is_finite())
+            .collect();
+        if sorted.is_empty() {
+            polars_bail!(ComputeError: "column '{}' has no finite values", values.name());
+        }
+        sorted.sort_by(f64::total_cmp);
+
+        let (n, m) = (sorted.len(), quantiles.min(sorted.len()));
+        let references = (0..m)
+            .map(|k| {
+                // Linear interpolation between the order statistics around
+                // level k / (m - 1).
+                let position = if m > 1 {
+                    (k * (n - 1)) as f64 / (m - 1) as f64
+                } else {
+                    0.0
+                };
+                let (lower, fraction) = (position.floor() as usize, position.fract());
+                match sorted.get(lower + 1) {
+                    Some(upper) => sorted[lower] + fraction * (upper - sorted[lower]),
+                    None => sorted[lower],
+                }
+            })
+            .collect();
+        Ok(Self { references })
+    }
+
+    /// The level of `x` among the references, interpolated between them and
+    /// clipped to [0, 1]. A value equal to several references gets their
+    /// middle level.
+    pub fn cdf(&self, x: f64) -> f64 {
+        let r = &self.references;
+        if r.len() == 1 {
+            return 0.5;
+        }
+        let last = (r.len() - 1) as f64;
+        let below = r.partition_point(|v| *v < x);
+        let through = r.partition_point(|v| *v <= x);
+        if through > below {
+            return (below + through - 1) as f64 / 2.0 / last;
+        }
+        if below == 0 {
+            return 0.0;
+        }
+        if below == r.len() {
+            return 1.0;
+        }
+        let fraction = (x - r[below - 1]) / (r[below] - r[below - 1]);
+        ((below - 1) as f64 + fraction) / last
+    }
+
+    /// `x` mapped onto `distribution`.
+    pub fn value(&self, x: f64, distribution: Distribution) -> f64 {
+        let p = self.cdf(x);
+        match distribution {
+            Distribution::Uniform => p,
+            Distribution::Normal => inverse_normal(p.clamp(BOUND, 1.0 - BOUND)),
+        }
+    }
+
+    /// The values of `values` mapped onto `distribution` as the column
+    /// `name`; null for nulls and NaN.
+    pub fn column(
+        &self,
+        values: &Column,
+        distribution: Distribution,
+        name: &str,
+    ) -> PolarsResult<Column> {
+        let values = values.cast(&DataType::Float64)?;
+        let mapped: Float64Chunked = values
+            .f64()?
+            .iter()
+            .map(|x| {
+                x.filter(|x| !x.is_nan())
+                    .map(|x| self.value(x, distribution))
+            })
+            .collect();
+        Ok(mapped.with_name(name.into()).into_column())
+    }
+}
+
+/// The standard normal quantile of `p` by Wichura's AS241, as Python's
+/// `statistics.NormalDist.inv_cdf`.
+fn inverse_normal(p: f64) -> f64 {
+    let q = p - 0.5;
+    if q.abs() <= 0.425 {
+        let r = 0.180625 - q * q;
+        let num = horner(
+            &[
+                2509.0809287301227,
+                33430.57558358813,
+                67265.7709270087,
+                45921.95393154987,
+                13731.69376550946,
+                1971.5909503065513,
+                133.14166789178438,
+                3.3871328727963665,
+            ],
+            r,
+        );
+        let den = horner(
+            &[
+                5226.495278852854,
+                28729.085735721943,
+                39307.89580009271,
+                21213.794301586597,
+                5394.196021424751,
+                687.1870074920579,
+                42.31333070160091,
+                1.0,
+            ],
+            r,
+        );
+        return q * num / den;
+    }
+    let r = (-(if q <= 0.0 { p } else { 1.0 - p }).ln()).sqrt();
+    let (num, den) = if r <= 5.0 {
+        let r = r - 1.6;
+        (
+            horner(
+                &[
+                    0.0007745450142783414,
+                    0.022723844989269184,
+                    0.2417807251774506,
+                    1.2704582524523684,
+                    3.6478483247632045,
+                    5.769497221460691,
+                    4.630337846156546,
+                    1.4234371107496835,
+                ],
+                r,
+            ),
+            horner(
+                &[
+                    1.0507500716444169e-09,
+                    0.0005475938084995345,
+                    0.015198666563616457,
+                    0.14810397642748008,
+                    0.6897673349851,
+                    1.6763848301838038,
+                    2.053191626637759,
+                    1.0,
+                ],
+                r,
+            ),
+        )
+    } else {
+        let r = r - 5.0;
+        (
+            horner(
+                &[
+                    2.0103343992922881e-07,
+                    2.7115555687434876e-05,
+                    0.0012426609473880784,
+                    0.026532189526576124,
+                    0.29656057182850487,
+                    1.7848265399172913,
+                    5.463784911164114,
+                    6.657904643501103,
+                ],
+                r,
+            ),
+            horner(
+                &[
+                    2.0442631033899397e-15,
+                    1.421511758316446e-07,
+                    1.8463183175100548e-05,
+                    0.0007868691311456133,
+                    0.014875361290850615,
+                    0.1369298809227358,
+                    0.599832206555888,
+                    1.0,
+                ],
+                r,
+            ),
+        )
+    };
+    let x = num / den;
+    if q < 0.0 { -x } else { x }
+}
+
+/// The polynomial with `coefficients`, highest power first, at `r`.
+fn horner(coefficients: &[f64], r: f64) -> f64 {
+    coefficients.iter().fold(0.0, |acc, c| acc * r + c)
+}
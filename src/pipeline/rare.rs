@@ -0,0 +1,77 @@
+//! Rare categories for `collapse_rare` steps and limited `ohe` steps: the
+//! categories frequent enough in the fit data to keep, every other one
+//! written as a single token, so dummy widths and group counts stay bounded
+//! on messy columns.
+
+use std::collections::BTreeSet;
+
+use polars::prelude::*;
+use serde::Serialize;
+
+use crate::errors::PipelineStepError;
+use crate::profiling;
+
+/// The categories a `collapse_rare` step, or an `ohe` step for one column,
+/// keeps.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct KeptCategories {
+    /// Categories written as they are, compared as strings.
+    pub kept: BTreeSet<String>,
+    /// Categories of the fit data written as the other token.
+    pub collapsed: usize,
+}
+
+impl KeptCategories {
+    /// Fitted on `values`: categories with at least `min_count` rows and at
+    /// least `min_fraction` of all rows, nulls included, are kept, and of
+    /// those at most the `max_categories` most frequent, ties broken by
+    /// value.
+    pub fn fit(
+        values: &Column,
+        min_count: Option<usize>,
+        min_fraction: Option<f64>,
+        max_categories: Option<usize>,
+    ) -> Result<Self, PipelineStepError> {
+        let rows = values.len() as f64;
+        let counts = profiling::value_counts(values)?;
+        let total = counts.len();
+        let mut frequent: Vec<(String, usize)> = counts
+            .into_iter()
+            .filter(|(_, count)| {
+                min_count.is_none_or(|n| *count >= n)
+                    && min_fraction.is_none_or(|f| *count as f64 >= f * rows)
+            })
+            .collect();
+        frequent.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let kept: BTreeSet<String> = frequent
+            .into_iter()
+            .take(max_categories.unwrap_or(usize::MAX))
+            .map(|(category, _)| category)
+            .collect();
+        Ok(Self {
+            collapsed: total - kept.len(),
+            kept,
+        })
+    }
+
+    /// `category` if it was kept, otherwise `other`.
+    pub fn value<'a>(&'a self, category: &'a str, other: &'a str) -> &'a str {
+        if self.kept.contains(category) {
+            category
+        } else {
+            other
+        }
+    }
+
+    /// The values of `values` with rare categories as `other`, as the string
+    /// column `name`; nulls stay null.
+    pub fn column(&self, values: &Column, other: &str, name: &str) -> PolarsResult<Column> {
+        let text = values.cast(&DataType::String)?;
+        let collapsed: StringChunked = text
+            .str()?
+            .iter()
+            .map(|v| v.map(|v| self.value(v, other)))
+            .collect();
+        Ok(collapsed.with_name(name.into()).into_column())
+    }
+}
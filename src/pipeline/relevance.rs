@@ -0,0 +1,154 @@
+use std::collections::{BTreeSet, HashMap};
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::leakage::pearson;
+
+fn default_bins() -> usize {
+    10
+}
+
+/// How much each feature alone tells about the training target, written as
+/// `relevance.json` to guide pruning: features near the bottom add columns
+/// without information.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RelevanceConfig {
+    /// Equal-frequency bins numeric features with more distinct values are
+    /// cut into for mutual information.
+    #[serde(default = "default_bins")]
+    pub bins: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FeatureRelevance {
+    pub column: String,
+    /// Mutual information with the target, in nats; 0 for an independent
+    /// feature.
+    pub mutual_information: f64,
+    /// Pearson correlation with the target's class codes; `None` when either
+    /// is constant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation: Option<f64>,
+    /// Area under the ROC curve of the feature as a score for the larger
+    /// class; binary targets only. Below 0.5 when lower values mean that
+    /// class.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auc: Option<f64>,
+}
+
+impl RelevanceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.bins < 2 {
+            return Err(format!(
+                "relevance: 'bins' must be at least 2, got {}",
+                self.bins
+            ));
+        }
+        Ok(())
+    }
+
+    /// The relevance of every numeric column of `features` to `target`, most
+    /// relevant first. Rows where either is null are skipped.
+    pub fn rank(
+        &self,
+        features: &DataFrame,
+        target: &Column,
+    ) -> Result<Vec<FeatureRelevance>, PipelineStepError> {
+        let target = target.cast(&DataType::Float64)?;
+        let target = target.f64()?;
+        let classes: BTreeSet<u64> = target.into_iter().flatten().map(f64::to_bits).collect();
+        let positive = match classes.len() {
+            2 => classes.iter().map(|c| f64::from_bits(*c)).reduce(f64::max),
+            _ => None,
+        };
+
+        let mut ranked = Vec::new();
+        for column in features.get_columns() {
+            if !(column.dtype().is_primitive_numeric() || column.dtype().is_bool()) {
+                continue;
+            }
+            let values = column.cast(&DataType::Float64)?;
+            let pairs: Vec<(f64, f64)> = values
+                .f64()?
+                .iter()
+                .zip(target.iter())
+                .filter_map(|(x, y)| Some((x?, y?)))
+                .collect();
+            ranked.push(FeatureRelevance {
+                column: column.name().to_string(),
+                mutual_information: mutual_information(&pairs, self.bins),
+                correlation: pearson(&pairs),
+                auc: positive.and_then(|p| auc(&pairs, p)),
+            });
+        }
+        ranked.sort_by(|a, b| b.mutual_information.total_cmp(&a.mutual_information));
+        Ok(ranked)
+    }
+}
+
+/// Mutual information of `(feature, class)` pairs, the feature cut into at
+/// most `bins` equal-frequency bins when it has more distinct values.
+fn mutual_information(pairs: &[(f64, f64)], bins: usize) -> f64 {
+    if pairs.is_empty() {
+        return 0.0;
+    }
+    let mut sorted: Vec<f64> = pairs.iter().map(|(x, _)| *x).collect();
+    sorted.sort_by(f64::total_cmp);
+    let mut distinct = sorted.clone();
+    distinct.dedup();
+    // Upper bin edges; equal values always share a bin.
+    let mut edges: Vec<f64> = if distinct.len() <= bins {
+        distinct
+    } else {
+        (1..bins).map(|k| sorted[k * sorted.len() / bins]).collect()
+    };
+    edges.dedup();
+    let bin = |x: f64| edges.partition_point(|e| *e < x);
+
+    let n = pairs.len() as f64;
+    let mut joint: HashMap<(usize, u64), f64> = HashMap::new();
+    let mut by_bin: HashMap<usize, f64> = HashMap::new();
+    let mut by_class: HashMap<u64, f64> = HashMap::new();
+    for (x, y) in pairs {
+        let (b, c) = (bin(*x), y.to_bits());
+        *joint.entry((b, c)).or_default() += 1.0;
+        *by_bin.entry(b).or_default() += 1.0;
+        *by_class.entry(c).or_default() += 1.0;
+    }
+    let information: f64 = joint
+        .iter()
+        .map(|((b, c), count)| {
+            let p = count / n;
+            p * (p / (by_bin[b] / n * by_class[c] / n)).ln()
+        })
+        .sum();
+    information.max(0.0)
+}
+
+/// The probability that a random row of class `positive` has a higher
+/// feature value than a random row of another class, ties counting half.
+fn auc(pairs: &[(f64, f64)], positive: f64) -> Option<f64> {
+    let mut sorted = pairs.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (mut rank_sum, mut positives) = (0.0, 0.0);
+    let mut start = 0;
+    while start < sorted.len() {
+        let end = start + sorted[start..].partition_point(|(x, _)| *x == sorted[start].0);
+        // Tied values share the mean of their 1-based ranks.
+        let rank = (start + end + 1) as f64 / 2.0;
+        for (_, y) in &sorted[start..end] {
+            if *y == positive {
+                rank_sum += rank;
+                positives += 1.0;
+            }
+        }
+        start = end;
+    }
+    let negatives = sorted.len() as f64 - positives;
+    (positives > 0.0 && negatives > 0.0)
+        .then(|| (rank_sum - positives * (positives + 1.0) / 2.0) / (positives * negatives))
+}
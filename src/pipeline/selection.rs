@@ -0,0 +1,152 @@
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::correlation::CorrelationConfig;
+use crate::profiling;
+
+fn default_max_correlation() -> f64 {
+    0.95
+}
+
+/// Drops features that can't help the model before outputs are written:
+/// constant and near-constant ones, then one of each highly correlated pair.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SelectionConfig {
+    /// Features whose sample variance is at or below this value are dropped;
+    /// 0 drops only constant ones.
+    #[serde(default)]
+    pub min_variance: f64,
+
+    /// Features whose most frequent value fills more than this share of rows,
+    /// e.g. 0.99, are dropped; off when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_dominant_fraction: Option<f64>,
+
+    /// Of each pair whose absolute Pearson correlation reaches this value,
+    /// the feature later in column order is dropped.
+    #[serde(default = "default_max_correlation")]
+    pub max_correlation: f64,
+}
+
+/// Why a feature was dropped.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum DropReason {
+    /// A single value on every row.
+    Constant,
+    /// Variance at or below `min_variance`.
+    LowVariance { variance: f64 },
+    /// One value on more than `max_dominant_fraction` of rows.
+    DominantValue { fraction: f64 },
+    /// Correlated with a feature that was kept.
+    Correlated { with: String, correlation: f64 },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DroppedFeature {
+    pub column: String,
+    #[serde(flatten)]
+    pub reason: DropReason,
+}
+
+impl SelectionConfig {
+    /// Why the thresholds can't be applied; `Ok` when they're valid.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.min_variance.is_finite() && self.min_variance >= 0.0) {
+            return Err(format!(
+                "select_features: 'min_variance' must be non-negative, got {}",
+                self.min_variance
+            ));
+        }
+        if let Some(fraction) = self.max_dominant_fraction
+            && !(fraction > 0.0 && fraction < 1.0)
+        {
+            return Err(format!(
+                "select_features: 'max_dominant_fraction' must be between 0 and 1, got {}",
+                fraction
+            ));
+        }
+        if !(self.max_correlation > 0.0 && self.max_correlation <= 1.0) {
+            return Err(format!(
+                "select_features: 'max_correlation' must be in (0, 1], got {}",
+                self.max_correlation
+            ));
+        }
+        Ok(())
+    }
+
+    /// The columns of `features` to drop, in column order within each
+    /// filter. Non-numeric columns are kept.
+    pub fn select(&self, features: &DataFrame) -> Result<Vec<DroppedFeature>, PipelineStepError> {
+        let numeric: Vec<&Column> = features
+            .get_columns()
+            .iter()
+            .filter(|c| c.dtype().is_primitive_numeric() || c.dtype().is_bool())
+            .collect();
+
+        let mut dropped = Vec::new();
+        let mut kept = Vec::new();
+        for column in numeric {
+            match self.spread(column)? {
+                Some(reason) => dropped.push(DroppedFeature {
+                    column: column.name().to_string(),
+                    reason,
+                }),
+                None => kept.push(column.clone()),
+            }
+        }
+
+        // Greedy in column order: a feature is dropped for correlating with
+        // one kept before it, so of each group of copies the first remains.
+        let matrix = CorrelationConfig {
+            include_label: false,
+            redundant_above: self.max_correlation,
+        }
+        .compute(&DataFrame::new(kept)?, None)?;
+        let mut keep = vec![true; matrix.columns.len()];
+        for j in 0..matrix.columns.len() {
+            let partner = (0..j).find_map(|i| {
+                let r = matrix.values[i][j]?;
+                (keep[i] && r.abs() >= self.max_correlation).then_some((i, r))
+            });
+            if let Some((i, r)) = partner {
+                keep[j] = false;
+                dropped.push(DroppedFeature {
+                    column: matrix.columns[j].clone(),
+                    reason: DropReason::Correlated {
+                        with: matrix.columns[i].clone(),
+                        correlation: r,
+                    },
+                });
+            }
+        }
+        Ok(dropped)
+    }
+
+    /// Why `column` varies too little to keep; `None` when it varies enough.
+    fn spread(&self, column: &Column) -> Result<Option<DropReason>, PipelineStepError> {
+        if column.drop_nulls().n_unique()? <= 1 {
+            return Ok(Some(DropReason::Constant));
+        }
+        let values = column.cast(&DataType::Float64)?;
+        if let Some(variance) = values.f64()?.var(1)
+            && variance <= self.min_variance
+        {
+            return Ok(Some(DropReason::LowVariance { variance }));
+        }
+        if let Some(max) = self.max_dominant_fraction {
+            let dominant = profiling::value_counts(column)?
+                .into_values()
+                .max()
+                .unwrap_or(0);
+            let fraction = dominant as f64 / column.len().max(1) as f64;
+            if fraction > max {
+                return Ok(Some(DropReason::DominantValue { fraction }));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,260 @@
+//! Assigns every input row to a `train`, `validation` or `test` split before
+//! labels are filtered and classes balanced, so resampled rows stay in the
+//! split of the row they copy.
+
+use std::collections::HashMap;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::labels::{parse_millis, to_millis};
+
+/// Name of the split-assignment column.
+pub const SPLIT_COLUMN: &str = "split";
+
+pub const TRAIN: &str = "train";
+pub const VALIDATION: &str = "validation";
+pub const TEST: &str = "test";
+
+/// Splits in output order.
+pub const SPLITS: [&str; 3] = [TRAIN, VALIDATION, TEST];
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitOutput {
+    /// A `split` column in `features.csv` and `labels.csv`.
+    #[default]
+    Column,
+    /// `features_<split>.csv` and `labels_<split>.csv` per split.
+    Files,
+    /// Both of the above.
+    Both,
+}
+
+impl SplitOutput {
+    pub fn column(self) -> bool {
+        matches!(self, Self::Column | Self::Both)
+    }
+
+    pub fn files(self) -> bool {
+        matches!(self, Self::Files | Self::Both)
+    }
+}
+
+/// How rows are assigned to splits. Training uses the `train` rows and
+/// evaluates on the `test` rows.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(tag = "method", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SplitConfig {
+    /// Shuffles rows and takes the `test` and `validation` fractions of them.
+    Random {
+        test: f64,
+        #[serde(default)]
+        validation: f64,
+        /// Seed for the shuffle; runs are not reproducible when omitted.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+        #[serde(default)]
+        output: SplitOutput,
+    },
+    /// Like `random`, over the distinct values of `column` instead of rows, so
+    /// all rows of an entity land in the same split. Fractions are of
+    /// entities, not rows; null is an entity of its own.
+    Group {
+        column: String,
+        test: f64,
+        #[serde(default)]
+        validation: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+        #[serde(default)]
+        output: SplitOutput,
+    },
+    /// Rows at or after `test_from` (a date or datetime in `column`) are
+    /// `test`, those from `validation_from` up to it are `validation`, and
+    /// earlier rows are `train`.
+    Time {
+        column: String,
+        test_from: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        validation_from: Option<String>,
+        #[serde(default)]
+        output: SplitOutput,
+    },
+}
+
+impl SplitConfig {
+    pub fn output(&self) -> SplitOutput {
+        match self {
+            Self::Random { output, .. }
+            | Self::Group { output, .. }
+            | Self::Time { output, .. } => *output,
+        }
+    }
+
+    /// Seeds the shuffle with `seed` unless the config sets its own.
+    pub fn default_seed(&mut self, seed: u64) {
+        match self {
+            Self::Random { seed: own, .. } | Self::Group { seed: own, .. } => {
+                own.get_or_insert(seed);
+            }
+            Self::Time { .. } => {}
+        }
+    }
+
+    /// The input column the split reads, if any.
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            Self::Random { .. } => None,
+            Self::Group { column, .. } | Self::Time { column, .. } => Some(column),
+        }
+    }
+
+    /// Checks fractions and cutoffs, returning what is wrong with them.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Random {
+                test, validation, ..
+            }
+            | Self::Group {
+                test, validation, ..
+            } => {
+                if !(*test > 0.0 && *test < 1.0) {
+                    return Err("split: 'test' must be between 0 and 1".to_string());
+                }
+                if !(*validation >= 0.0 && test + validation < 1.0) {
+                    return Err(
+                        "split: 'validation' must be at least 0 and leave rows for 'train'"
+                            .to_string(),
+                    );
+                }
+            }
+            Self::Time {
+                test_from,
+                validation_from,
+                ..
+            } => {
+                let cutoff = |name: &str, value: &str| {
+                    parse_millis(value).ok_or_else(|| {
+                        format!(
+                            "split: '{}' of '{}' is not an ISO 8601 date or datetime",
+                            name, value
+                        )
+                    })
+                };
+                let test_from = cutoff("test_from", test_from)?;
+                if let Some(validation_from) = validation_from
+                    && cutoff("validation_from", validation_from)? >= test_from
+                {
+                    return Err("split: 'validation_from' must be before 'test_from'".into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The split of every row of `data`, as a `split` column.
+    pub fn assign(&self, data: &DataFrame) -> Result<Series, PipelineStepError> {
+        let splits: Vec<&str> = match self {
+            Self::Random {
+                test,
+                validation,
+                seed,
+                ..
+            } => {
+                let order = shuffled(data.height(), *seed)?;
+                let mut splits = vec![TRAIN; data.height()];
+                for (rank, row) in order.into_iter().enumerate() {
+                    splits[row] = by_rank(rank, data.height(), *test, *validation);
+                }
+                splits
+            }
+            Self::Group {
+                column,
+                test,
+                validation,
+                seed,
+                ..
+            } => {
+                let keys = data.column(column)?.cast(&DataType::String)?;
+                let keys = keys.str()?;
+                let mut groups: HashMap<Option<&str>, usize> = HashMap::new();
+                let rows: Vec<usize> = keys
+                    .iter()
+                    .map(|key| {
+                        let next = groups.len();
+                        *groups.entry(key).or_insert(next)
+                    })
+                    .collect();
+
+                let mut group_splits = vec![TRAIN; groups.len()];
+                for (rank, group) in shuffled(groups.len(), *seed)?.into_iter().enumerate() {
+                    group_splits[group] = by_rank(rank, groups.len(), *test, *validation);
+                }
+                rows.into_iter().map(|group| group_splits[group]).collect()
+            }
+            Self::Time {
+                column,
+                test_from,
+                validation_from,
+                ..
+            } => {
+                let cutoff = |value: &str| {
+                    parse_millis(value).ok_or_else(|| {
+                        polars_err!(ComputeError: "'{}' is not an ISO 8601 date or datetime", value)
+                    })
+                };
+                let test_from = cutoff(test_from)?;
+                let validation_from = validation_from.as_deref().map(cutoff).transpose()?;
+                let times = to_millis(data.column(column)?.as_materialized_series())?;
+                if times.null_count() > 0 {
+                    return Err(polars_err!(
+                        ComputeError: "split column '{}' has {} null(s); every row needs a time",
+                        column,
+                        times.null_count()
+                    )
+                    .into());
+                }
+                times
+                    .into_no_null_iter()
+                    .map(|time| {
+                        if time >= test_from {
+                            TEST
+                        } else if validation_from.is_some_and(|from| time >= from) {
+                            VALIDATION
+                        } else {
+                            TRAIN
+                        }
+                    })
+                    .collect()
+            }
+        };
+        Ok(Series::new(SPLIT_COLUMN.into(), splits))
+    }
+}
+
+/// `0..n` in random order.
+fn shuffled(n: usize, seed: Option<u64>) -> Result<Vec<usize>, PipelineStepError> {
+    let order = IdxCa::from_vec("rows".into(), (0..n as IdxSize).collect());
+    Ok(order
+        .sample_n(n, false, true, seed)?
+        .into_no_null_iter()
+        .map(|i| i as usize)
+        .collect())
+}
+
+/// The split of the item at `rank` of `n` shuffled items: the first `test`
+/// fraction is `test`, the next `validation` fraction `validation`.
+fn by_rank(rank: usize, n: usize, test: f64, validation: f64) -> &'static str {
+    let tests = (n as f64 * test).round() as usize;
+    let validations = (n as f64 * validation).round() as usize;
+    if rank < tests {
+        TEST
+    } else if rank < tests + validations {
+        VALIDATION
+    } else {
+        TRAIN
+    }
+}
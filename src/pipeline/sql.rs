@@ -0,0 +1,687 @@
+//! Compiles a features config into a single SQL query, so the same feature
+//! definitions can be run in the warehouse, e.g. for backfills.
+//!
+//! Each step becomes a CTE adding its feature to the previous one's rows, so
+//! a step can read the features of the steps before it, as in the pipeline.
+//! The final `SELECT` returns the pipeline's `keys` columns and the features;
+//! columns steps write for later steps to read, e.g. `json_extract` fields,
+//! are added by CTEs too but not returned.
+//! `ohe` (whose columns depend on the data) and `expect` have no SQL form and
+//! are left out; [`SqlQuery::skipped`] lists them.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+use serde::Serialize;
+
+use crate::pipeline::blocking::KeyEncoding;
+use crate::pipeline::email::{self, EmailOutput};
+use crate::pipeline::features::{Comparator, FeatureConfig, FeaturePipeline, ListAgg, NullPolicy};
+use crate::pipeline::json::{self, JsonType, Segment};
+use crate::pipeline::text::TextOperation;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SqlDialect {
+    Postgres,
+    BigQuery,
+}
+
+impl SqlDialect {
+    fn ident(self, name: &str) -> String {
+        match self {
+            Self::Postgres => format!("\"{}\"", name.replace('"', "\"\"")),
+            Self::BigQuery => format!("`{}`", name.replace('`', "\\`")),
+        }
+    }
+
+    fn string(self, value: &str) -> String {
+        match self {
+            Self::Postgres => format!("'{}'", value.replace('\'', "''")),
+            Self::BigQuery => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+        }
+    }
+
+    fn string_type(self) -> &'static str {
+        match self {
+            Self::Postgres => "TEXT",
+            Self::BigQuery => "STRING",
+        }
+    }
+
+    /// `numerator / denominator` as floats; null when the denominator is 0.
+    fn divide(self, numerator: &str, denominator: &str) -> String {
+        match self {
+            Self::Postgres => format!(
+                "CAST({} AS DOUBLE PRECISION) / NULLIF({}, 0)",
+                numerator, denominator
+            ),
+            Self::BigQuery => format!("SAFE_DIVIDE({}, {})", numerator, denominator),
+        }
+    }
+
+    /// The value at `path`, written `text` in the config, of the JSON in
+    /// `column` as `dtype`.
+    fn json_value(self, column: &str, text: &str, path: &[Segment], dtype: JsonType) -> String {
+        match self {
+            Self::Postgres => {
+                let segments: Vec<String> = path
+                    .iter()
+                    .map(|segment| match segment {
+                        Segment::Key(key) => key.clone(),
+                        Segment::Index(index) => index.to_string(),
+                    })
+                    .collect();
+                let value = format!(
+                    "CAST({} AS JSONB) #>> {}",
+                    column,
+                    self.string(&format!("{{{}}}", segments.join(",")))
+                );
+                match dtype {
+                    JsonType::String => format!("({})", value),
+                    JsonType::Float => format!("CAST({} AS DOUBLE PRECISION)", value),
+                    JsonType::Int => format!("CAST({} AS BIGINT)", value),
+                    JsonType::Bool => format!("CAST({} AS BOOLEAN)", value),
+                }
+            }
+            Self::BigQuery => {
+                let value = format!("JSON_VALUE({}, {})", column, self.string(text));
+                match dtype {
+                    JsonType::String => value,
+                    JsonType::Float => format!("SAFE_CAST({} AS FLOAT64)", value),
+                    JsonType::Int => format!("SAFE_CAST({} AS INT64)", value),
+                    JsonType::Bool => format!("SAFE_CAST({} AS BOOL)", value),
+                }
+            }
+        }
+    }
+
+    /// The number written in `column`, as `number::parse` reads it. Postgres
+    /// fails the query on a value that doesn't parse; BigQuery gives null.
+    fn parse_number(
+        self,
+        column: &str,
+        decimal: char,
+        thousands: Option<char>,
+        currency: &[String],
+    ) -> String {
+        let mut text = format!("CAST({} AS {})", column, self.string_type());
+        for symbol in currency {
+            text = format!("REPLACE({}, {}, '')", text, self.string(symbol));
+        }
+        text = match self {
+            Self::Postgres => format!("REGEXP_REPLACE({}, '[[:space:]]', '', 'g')", text),
+            Self::BigQuery => format!("REGEXP_REPLACE({}, '[[:space:]]', '')", text),
+        };
+        if let Some(thousands) = thousands {
+            text = format!(
+                "REPLACE({}, {}, '')",
+                text,
+                self.string(&thousands.to_string())
+            );
+        }
+        if decimal != '.' {
+            text = format!(
+                "REPLACE({}, {}, '.')",
+                text,
+                self.string(&decimal.to_string())
+            );
+        }
+        match self {
+            Self::Postgres => format!("CAST(NULLIF({}, '') AS DOUBLE PRECISION)", text),
+            Self::BigQuery => format!("SAFE_CAST({} AS FLOAT64)", text),
+        }
+    }
+
+    /// Elements of the array in `column`; null for a null array.
+    fn array_length(self, column: &str) -> String {
+        match self {
+            Self::Postgres => format!("CARDINALITY({})", column),
+            Self::BigQuery => format!("ARRAY_LENGTH({})", column),
+        }
+    }
+
+    /// The normalized `blocking_key` of `columns`, as `blocking::key` writes
+    /// it; null when it is empty.
+    fn blocking_key(self, columns: &[String]) -> String {
+        let text: Vec<String> = columns
+            .iter()
+            .map(|c| format!("CAST({} AS {})", c, self.string_type()))
+            .collect();
+        let (joined, flags) = match self {
+            Self::Postgres => (format!("CONCAT_WS(' ', {})", text.join(", ")), ", 'g'"),
+            Self::BigQuery => (format!("ARRAY_TO_STRING([{}], ' ')", text.join(", ")), ""),
+        };
+        let letters = match self {
+            Self::Postgres => "[^[:alnum:][:space:]]",
+            Self::BigQuery => r"[^\p{L}\p{N}\s]",
+        };
+        format!(
+            "NULLIF(TRIM(REGEXP_REPLACE(REGEXP_REPLACE(LOWER({}), {}, ''{}), '[[:space:]]+', ' '{})), '')",
+            joined,
+            self.string(letters),
+            flags,
+            flags
+        )
+    }
+
+    /// The text in `column` cleaned as `text::clean` does; null when it is
+    /// empty. Postgres patterns have no class for combining marks, so
+    /// `strip_accents` compiles on BigQuery only.
+    fn clean_text(
+        self,
+        column: &str,
+        operations: &[TextOperation],
+    ) -> Result<String, &'static str> {
+        let flags = match self {
+            Self::Postgres => ", 'g'",
+            Self::BigQuery => "",
+        };
+        let mut text = format!("CAST({} AS {})", column, self.string_type());
+        for operation in operations {
+            text = match operation {
+                TextOperation::Trim => format!(
+                    "REGEXP_REPLACE({}, '^[[:space:]]+|[[:space:]]+$', ''{})",
+                    text, flags
+                ),
+                TextOperation::CollapseWhitespace => {
+                    format!("REGEXP_REPLACE({}, '[[:space:]]+', ' '{})", text, flags)
+                }
+                TextOperation::Lowercase => format!("LOWER({})", text),
+                TextOperation::Nfc => format!("NORMALIZE({}, NFC)", text),
+                TextOperation::Nfkc => format!("NORMALIZE({}, NFKC)", text),
+                TextOperation::StripAccents => match self {
+                    Self::Postgres => {
+                        return Err("stripping accents has no Postgres form without extensions");
+                    }
+                    Self::BigQuery => format!(
+                        "NORMALIZE(REGEXP_REPLACE(NORMALIZE({}, NFD), r'\\p{{M}}', ''), NFC)",
+                        text
+                    ),
+                },
+                TextOperation::RemovePunctuation => {
+                    let letters = match self {
+                        Self::Postgres => "[^[:alnum:][:space:]]",
+                        Self::BigQuery => r"[^\p{L}\p{N}\s]",
+                    };
+                    format!(
+                        "REGEXP_REPLACE({}, {}, ''{})",
+                        text,
+                        self.string(letters),
+                        flags
+                    )
+                }
+            };
+        }
+        Ok(format!("NULLIF({}, '')", text))
+    }
+
+    /// The lowercased domain of an email address, as `email::domain` finds
+    /// it; null when there is none.
+    fn email_domain(self, column: &str) -> String {
+        let pattern = self.string(".@([^@[:space:]]+)$");
+        let extract = match self {
+            Self::Postgres => format!("SUBSTRING(TRIM(CAST({} AS TEXT)) FROM {})", column, pattern),
+            Self::BigQuery => format!(
+                "REGEXP_EXTRACT(TRIM(CAST({} AS STRING)), {})",
+                column, pattern
+            ),
+        };
+        format!("NULLIF(RTRIM(LOWER({}), '.'), '')", extract)
+    }
+
+    /// The geohash of a point; Postgres needs the PostGIS extension.
+    fn geohash(self, latitude: &str, longitude: &str, precision: usize) -> String {
+        match self {
+            Self::Postgres => format!(
+                "ST_GeoHash(ST_SetSRID(ST_MakePoint({}, {}), 4326), {})",
+                longitude, latitude, precision
+            ),
+            Self::BigQuery => format!(
+                "ST_GEOHASH(ST_GEOGPOINT({}, {}), {})",
+                longitude, latitude, precision
+            ),
+        }
+    }
+}
+
+/// A compiled features config.
+#[derive(Serialize, Debug, Clone)]
+pub struct SqlQuery {
+    pub dialect: SqlDialect,
+    pub sql: String,
+    /// Feature columns the query returns, in step order.
+    pub features: Vec<String>,
+    /// Steps with no SQL form, as `<function>` or `<function> '<name>'` and why.
+    pub skipped: Vec<String>,
+}
+
+impl SqlQuery {
+    /// Compiles `pipeline` to a query over `table`, which holds the input rows.
+    pub fn compile(pipeline: &FeaturePipeline, table: &str, dialect: SqlDialect) -> Self {
+        let q = |name: &str| dialect.ident(name);
+        let mut ctes: Vec<String> = Vec::new();
+        let mut features = Vec::new();
+        let mut skipped = Vec::new();
+        // Key columns of skipped steps, which later steps can't read.
+        let mut missing_keys = BTreeSet::new();
+        let mut previous = table.split('.').map(q).collect::<Vec<_>>().join(".");
+
+        for (i, step) in pipeline.steps.iter().enumerate() {
+            let keys = step.key_columns();
+            let label = match step.name().or(keys.first().copied()) {
+                Some(name) => format!("{} '{}'", step.function(), name),
+                None => step.function().to_string(),
+            };
+            // A key column is added like a feature but not returned;
+            // `json_extract` names its own columns.
+            let (column, is_feature) = match (step.name(), keys.first()) {
+                (Some(name), _) => (format!("feature_{}", name), true),
+                (None, Some(key)) => (key.to_string(), false),
+                (None, None) => {
+                    let reason = match step {
+                        FeatureConfig::Ohe { .. } | FeatureConfig::MultiHot { .. } => {
+                            "its columns depend on the data"
+                        }
+                        _ => "it produces no feature",
+                    };
+                    skipped.push(format!("{}: {}", label, reason));
+                    continue;
+                }
+            };
+            if let Some(key) = step
+                .input_columns()
+                .into_iter()
+                .find(|c| missing_keys.contains(c))
+            {
+                skipped.push(format!(
+                    "{}: it reads '{}', which was not compiled",
+                    label, key
+                ));
+                missing_keys.extend(keys);
+                continue;
+            }
+            let cte = format!("step_{}", i + 1);
+            let body = match step_sql(step, &previous, &column, dialect) {
+                Ok(body) => body,
+                Err(reason) => {
+                    skipped.push(format!("{}: {}", label, reason));
+                    missing_keys.extend(keys);
+                    continue;
+                }
+            };
+            ctes.push(format!("{} AS (\n{}\n)", cte, body));
+            if is_feature {
+                features.push(column);
+            }
+            previous = cte;
+        }
+
+        let mut sql = String::new();
+        let _ = writeln!(
+            sql,
+            "-- Generated by features-pipeline ({:?}); do not edit.",
+            dialect
+        );
+        for step in &skipped {
+            let _ = writeln!(sql, "-- Not compiled: {}", step);
+        }
+        if !ctes.is_empty() {
+            let _ = writeln!(sql, "WITH {}", ctes.join(",\n"));
+        }
+        let columns: Vec<String> = pipeline
+            .keys
+            .iter()
+            .flat_map(|k| &k.columns)
+            .chain(&features)
+            .map(|c| format!("  {}", q(c)))
+            .collect();
+        let _ = write!(sql, "SELECT\n{}\nFROM {};\n", columns.join(",\n"), previous);
+        Self {
+            dialect,
+            sql,
+            features,
+            skipped,
+        }
+    }
+}
+
+/// The body of the CTE adding `feature`, or a `json_extract` step's fields,
+/// to the rows of `from`.
+fn step_sql(
+    step: &FeatureConfig,
+    from: &str,
+    feature: &str,
+    dialect: SqlDialect,
+) -> Result<String, &'static str> {
+    let q = |name: &str| dialect.ident(name);
+    let select = |expr: String| format!("  SELECT *, {} AS {}\n  FROM {}", expr, q(feature), from);
+
+    if let FeatureConfig::JsonExtract { column, fields, .. } = step {
+        let columns: Vec<String> = fields
+            .iter()
+            .map(|field| {
+                let path = json::parse_path(&field.path).map_err(|_| "invalid JSON path")?;
+                Ok(format!(
+                    "{} AS {}",
+                    dialect.json_value(&q(column), &field.path, &path, field.dtype),
+                    q(&field.name)
+                ))
+            })
+            .collect::<Result<_, &'static str>>()?;
+        return Ok(format!(
+            "  SELECT *, {}\n  FROM {}",
+            columns.join(", "),
+            from
+        ));
+    }
+
+    if let FeatureConfig::CountDistinct {
+        column, group_by, ..
+    } = step
+    {
+        if group_by.is_empty() {
+            return Err("no group_by");
+        }
+        // Neither dialect takes `COUNT(DISTINCT ...)` as a window function in
+        // general, so the counts are grouped and joined back.
+        let keys: Vec<String> = group_by.iter().map(|k| q(k)).collect();
+        let on: Vec<String> = keys
+            .iter()
+            .map(|k| format!("r.{} IS NOT DISTINCT FROM g.{}", k, k))
+            .collect();
+        return Ok(format!(
+            "  SELECT r.*, g.{feature}\n  FROM {from} AS r\n  LEFT JOIN (\n    SELECT {keys}, \
+             COUNT(DISTINCT {column}) AS {feature}\n    FROM {from}\n    GROUP BY {keys}\n  ) AS g \
+             ON {on}",
+            feature = q(feature),
+            from = from,
+            keys = keys.join(", "),
+            column = q(column),
+            on = on.join(" AND "),
+        ));
+    }
+
+    let window = |agg: String, column: &str, group_by: &[String], policy: Option<NullPolicy>| {
+        if group_by.is_empty() {
+            return Err("no group_by");
+        }
+        let over = format!(
+            "OVER (PARTITION BY {})",
+            group_by.iter().map(|k| q(k)).collect::<Vec<_>>().join(", ")
+        );
+        Ok(match policy.unwrap_or_default() {
+            // `error` can't fail a query; the nulls are ignored as by default.
+            NullPolicy::Ignore | NullPolicy::Error => format!("{} {}", agg, over),
+            NullPolicy::Propagate => format!(
+                "CASE WHEN COUNT(*) {over} > COUNT({column}) {over} THEN NULL ELSE {agg} {over} END",
+                over = over,
+                column = q(column),
+                agg = agg
+            ),
+        })
+    };
+
+    let expr = match step {
+        FeatureConfig::Mean {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => window(
+            format!("AVG({})", q(column)),
+            column,
+            group_by,
+            *null_policy,
+        )?,
+        FeatureConfig::LooTargetEncode { noise: Some(_), .. } => {
+            return Err("seeded noise has no portable SQL form");
+        }
+        FeatureConfig::LooTargetEncode {
+            column, group_by, ..
+        } => {
+            if group_by.is_empty() {
+                return Err("no group_by");
+            }
+            let over = format!(
+                "OVER (PARTITION BY {})",
+                group_by.iter().map(|k| q(k)).collect::<Vec<_>>().join(", ")
+            );
+            let column = q(column);
+            // Rows alone in their group divide by 0, so get the overall mean.
+            let own = format!("CASE WHEN {column} IS NULL THEN 0 ELSE 1 END");
+            format!(
+                "COALESCE({}, AVG({column}) OVER ())",
+                dialect.divide(
+                    &format!("SUM({column}) {over} - COALESCE({column}, 0)"),
+                    &format!("COUNT({column}) {over} - {own}"),
+                ),
+            )
+        }
+        FeatureConfig::Sum {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => window(
+            format!("SUM({})", q(column)),
+            column,
+            group_by,
+            *null_policy,
+        )?,
+        FeatureConfig::Max {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => window(
+            format!("MAX({})", q(column)),
+            column,
+            group_by,
+            *null_policy,
+        )?,
+        FeatureConfig::Min {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => window(
+            format!("MIN({})", q(column)),
+            column,
+            group_by,
+            *null_policy,
+        )?,
+        FeatureConfig::Count {
+            column, group_by, ..
+        } => window("COUNT(*)".to_string(), column, group_by, None)?,
+        FeatureConfig::CountNonNull {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => window(
+            format!("COUNT({})", q(column)),
+            column,
+            group_by,
+            *null_policy,
+        )?,
+        FeatureConfig::PercentRank {
+            column,
+            group_by,
+            null_policy,
+            ..
+        } => {
+            if group_by.is_empty() {
+                return Err("no group_by");
+            }
+            let keys = group_by.iter().map(|k| q(k)).collect::<Vec<_>>().join(", ");
+            let column = q(column);
+            // Nulls are ranked apart, so they don't count towards the group's size.
+            let rank = format!(
+                "CASE WHEN {column} IS NULL THEN NULL ELSE PERCENT_RANK() OVER \
+                 (PARTITION BY {keys}, {column} IS NULL ORDER BY {column}) END",
+            );
+            match null_policy.unwrap_or_default() {
+                NullPolicy::Ignore | NullPolicy::Error => rank,
+                NullPolicy::Propagate => format!(
+                    "CASE WHEN COUNT(*) {over} > COUNT({column}) {over} THEN NULL ELSE {rank} END",
+                    over = format_args!("OVER (PARTITION BY {})", keys),
+                ),
+            }
+        }
+        FeatureConfig::RollingByTime { .. } => {
+            return Err("time windows have no portable SQL form");
+        }
+        FeatureConfig::Calendar { .. } => {
+            return Err("holiday calendars have no portable SQL form");
+        }
+        FeatureConfig::IpFeatures { .. } => {
+            return Err("IP parsing has no portable SQL form");
+        }
+        FeatureConfig::RandomFeature { .. } => {
+            return Err("seeded random values have no portable SQL form");
+        }
+        FeatureConfig::AnomalyScore { .. } => {
+            return Err("anomaly scores need a matrix inverse, which has no portable SQL form");
+        }
+        FeatureConfig::RobustScale { .. } => {
+            return Err("medians and quartiles have no portable SQL window form");
+        }
+        FeatureConfig::QuantileTransform { .. } => {
+            return Err("quantiles are fitted on the data and have no portable SQL form");
+        }
+        FeatureConfig::PowerTransform { .. } => {
+            return Err(
+                "power transform lambdas are fitted on the data and have no portable SQL form",
+            );
+        }
+        FeatureConfig::WoeEncode { .. } => {
+            return Err("weights of evidence are fitted on the data and have no portable SQL form");
+        }
+        FeatureConfig::EmailDomain {
+            column,
+            output,
+            free_mail,
+            ..
+        } => {
+            let domain = dialect.email_domain(&q(column));
+            match output {
+                EmailOutput::Domain => domain,
+                EmailOutput::IsFreeMail => {
+                    let providers: Vec<String> = email::free_mail(free_mail.as_deref())
+                        .iter()
+                        .map(|d| dialect.string(d))
+                        .collect();
+                    format!("{} IN ({})", domain, providers.join(", "))
+                }
+            }
+        }
+        FeatureConfig::UrlParse { .. } => {
+            return Err("URL parsing has no portable SQL form");
+        }
+        FeatureConfig::Geohash {
+            latitude,
+            longitude,
+            precision,
+            ..
+        } => dialect.geohash(&q(latitude), &q(longitude), *precision),
+        FeatureConfig::Ratio {
+            numerator,
+            denominator,
+            ..
+        } => dialect.divide(&q(numerator), &q(denominator)),
+        FeatureConfig::Threshold {
+            column,
+            threshold,
+            comparator,
+            ..
+        } => {
+            let op = match comparator {
+                Comparator::Gt => ">",
+                Comparator::Lt => "<",
+            };
+            format!("{} {} {}", q(column), op, threshold)
+        }
+        FeatureConfig::BlockingKey {
+            columns, encoding, ..
+        } => match encoding {
+            KeyEncoding::Normalized => {
+                let columns: Vec<String> = columns.iter().map(|c| q(c)).collect();
+                dialect.blocking_key(&columns)
+            }
+            KeyEncoding::Soundex => return Err("Soundex keys have no portable SQL form"),
+        },
+        FeatureConfig::CleanText {
+            column, operations, ..
+        } => dialect.clean_text(&q(column), operations)?,
+        FeatureConfig::CollapseRare {
+            column,
+            min_count,
+            min_fraction,
+            other,
+            ..
+        } => {
+            let column = q(column);
+            let count = format!("COUNT(*) OVER (PARTITION BY {column})");
+            let mut kept = Vec::new();
+            if let Some(n) = min_count {
+                kept.push(format!("{count} >= {n}"));
+            }
+            if let Some(f) = min_fraction {
+                kept.push(format!("{count} >= {f} * COUNT(*) OVER ()"));
+            }
+            format!(
+                "CASE WHEN {column} IS NULL THEN NULL WHEN {} THEN CAST({column} AS {}) ELSE {} END",
+                kept.join(" AND "),
+                dialect.string_type(),
+                dialect.string(other)
+            )
+        }
+        FeatureConfig::ParseNumber {
+            column,
+            decimal,
+            thousands,
+            currency,
+            ..
+        } => dialect.parse_number(&q(column), *decimal, *thousands, currency),
+        FeatureConfig::ListLen { column, .. } => dialect.array_length(&q(column)),
+        // Subqueries over `UNNEST` run on either dialect; the `CASE` keeps
+        // null arrays null, as polars does.
+        FeatureConfig::ListContains { column, value, .. } => format!(
+            "CASE WHEN {column} IS NULL THEN NULL ELSE EXISTS (SELECT 1 FROM UNNEST({column}) AS x \
+             WHERE CAST(x AS {string}) = {value}) END",
+            column = q(column),
+            string = dialect.string_type(),
+            value = dialect.string(value),
+        ),
+        FeatureConfig::ListAgg { column, agg, .. } => format!(
+            "CASE WHEN {column} IS NULL THEN NULL ELSE (SELECT {agg} FROM UNNEST({column}) AS x) END",
+            column = q(column),
+            agg = match agg {
+                ListAgg::Mean => "AVG(x)",
+                ListAgg::Sum => "COALESCE(SUM(x), 0)",
+            },
+        ),
+        FeatureConfig::Map {
+            column,
+            mapping,
+            default,
+            ..
+        } => {
+            let mut case = format!("CASE CAST({} AS {})", q(column), dialect.string_type());
+            for (key, value) in mapping {
+                let _ = write!(case, " WHEN {} THEN {}", dialect.string(key), value);
+            }
+            match default {
+                Some(default) => format!("{} ELSE {} END", case, default),
+                None => format!("{} END", case),
+            }
+        }
+        FeatureConfig::CountDistinct { .. }
+        | FeatureConfig::JsonExtract { .. }
+        | FeatureConfig::Expect { .. }
+        | FeatureConfig::Ohe { .. }
+        | FeatureConfig::MultiHot { .. } => unreachable!("handled by the caller"),
+    };
+    Ok(select(expr))
+}
@@ -0,0 +1,138 @@
+use polars::prelude::*;
+use serde::Serialize;
+
+use crate::bench;
+
+/// Columns named in a step summary before the rest are elided.
+const NAMED_COLUMNS: usize = 5;
+
+/// Wall time and shape changes of one step in a pipeline run.
+#[derive(Serialize, Debug, Clone)]
+pub struct StepStats {
+    /// 1-based position in `steps`.
+    pub step: usize,
+    pub function: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// `None` for steps computed together with others, in a lazy plan or a
+    /// shared window, which can't be timed apart.
+    pub duration_ms: Option<f64>,
+    pub rows_in: usize,
+    pub rows_out: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns_added: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns_removed: Vec<String>,
+    /// Columns kept under the same name with a different dtype.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub columns_retyped: Vec<String>,
+    /// Estimated heap size of the frame the step received. Buffers shared
+    /// with other frames are counted in full, so this is an upper bound.
+    pub frame_bytes_in: usize,
+    pub frame_bytes_out: usize,
+    /// Resident set size of the whole process right after the step; with a
+    /// parallel strategy it includes steps running at the same time, and for
+    /// steps fused into a lazy plan it is taken after the plan was collected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rss_bytes: Option<u64>,
+    /// Highest resident set size of the process so far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_rss_bytes: Option<u64>,
+}
+
+impl StepStats {
+    /// Compares the frame a step received with the one it returned.
+    pub fn new(
+        index: usize,
+        function: &'static str,
+        name: Option<&str>,
+        input: &DataFrame,
+        output: &DataFrame,
+        duration_ms: Option<f64>,
+    ) -> Self {
+        let input_schema = input.schema();
+        let output_schema = output.schema();
+
+        let mut columns_added = Vec::new();
+        let mut columns_retyped = Vec::new();
+        for (column, dtype) in output_schema.iter() {
+            match input_schema.get(column) {
+                None => columns_added.push(column.to_string()),
+                Some(before) if before != dtype => columns_retyped.push(column.to_string()),
+                Some(_) => {}
+            }
+        }
+        let columns_removed = input_schema
+            .iter_names()
+            .filter(|column| !output_schema.contains(column))
+            .map(|column| column.to_string())
+            .collect();
+
+        Self {
+            step: index + 1,
+            function,
+            name: name.map(str::to_string),
+            duration_ms,
+            rows_in: input.height(),
+            rows_out: output.height(),
+            columns_added,
+            columns_removed,
+            columns_retyped,
+            frame_bytes_in: input.estimated_size(),
+            frame_bytes_out: output.estimated_size(),
+            rss_bytes: bench::memory_bytes(),
+            peak_rss_bytes: bench::peak_memory_bytes(),
+        }
+    }
+
+    /// Shape changes in words, e.g. `+14 columns (a, b, ...), -12403 rows`.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.rows_out != self.rows_in {
+            let delta = self.rows_out as i64 - self.rows_in as i64;
+            parts.push(format!(
+                "{:+} {}",
+                delta,
+                plural(delta.unsigned_abs(), "row")
+            ));
+        }
+        for (sign, verb, columns) in [
+            ("+", "", &self.columns_added),
+            ("-", "", &self.columns_removed),
+            ("~", " retyped", &self.columns_retyped),
+        ] {
+            if !columns.is_empty() {
+                parts.push(format!(
+                    "{}{} {}{} ({})",
+                    sign,
+                    columns.len(),
+                    plural(columns.len() as u64, "column"),
+                    verb,
+                    named(columns)
+                ));
+            }
+        }
+
+        if parts.is_empty() {
+            "no shape change".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+fn plural(count: u64, noun: &str) -> String {
+    if count == 1 {
+        noun.to_string()
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+fn named(columns: &[String]) -> String {
+    let mut names = columns[..columns.len().min(NAMED_COLUMNS)].join(", ");
+    if columns.len() > NAMED_COLUMNS {
+        names.push_str(&format!(", ... {} more", columns.len() - NAMED_COLUMNS));
+    }
+    names
+}
@@ -0,0 +1,70 @@
+//! Text cleaning for `clean_text` steps: spellings of one category, e.g.
+//! `Germany ` and `germany`, reduced to one value before later steps group
+//! or one-hot encode them.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// One operation of a `clean_text` step.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextOperation {
+    /// Leading and trailing whitespace removed.
+    Trim,
+    /// Each run of whitespace replaced by a single space.
+    CollapseWhitespace,
+    Lowercase,
+    /// Unicode canonical composition, so `é` typed as `e` and a combining
+    /// accent equals the single character.
+    Nfc,
+    /// Unicode compatibility composition: NFC, and also full-width letters,
+    /// ligatures and the like as their plain forms.
+    Nfkc,
+    /// Accents and other combining marks removed, e.g. `Zürich` to `Zurich`.
+    StripAccents,
+    /// Characters other than letters, digits and whitespace removed.
+    RemovePunctuation,
+}
+
+/// `text` with `operations` applied in order; `None` when nothing is left.
+pub fn clean(text: &str, operations: &[TextOperation]) -> Option<String> {
+    let mut text = text.to_string();
+    for operation in operations {
+        text = match operation {
+            TextOperation::Trim => text.trim().to_string(),
+            TextOperation::CollapseWhitespace => collapse_whitespace(&text),
+            TextOperation::Lowercase => text.to_lowercase(),
+            TextOperation::Nfc => text.nfc().collect(),
+            TextOperation::Nfkc => text.nfkc().collect(),
+            TextOperation::StripAccents => text
+                .nfd()
+                .filter(|c| !is_combining_mark(*c))
+                .nfc()
+                .collect(),
+            TextOperation::RemovePunctuation => text
+                .chars()
+                .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+                .collect(),
+        };
+    }
+    (!text.is_empty()).then_some(text)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_run = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_run {
+                collapsed.push(' ');
+            }
+            in_run = true;
+        } else {
+            collapsed.push(c);
+            in_run = false;
+        }
+    }
+    collapsed
+}
@@ -0,0 +1,106 @@
+//! URL decomposition for `url_parse` steps. URLs are split by hand rather
+//! than validated, so scheme-less referrers such as `example.com/a?b=1`
+//! parse too.
+
+use std::net::IpAddr;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What a `url_parse` step computes for each URL.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlOutput {
+    /// The lowercased host without a leading `www.`, as a key column.
+    Domain,
+    /// The host's last label, e.g. `uk` for `bbc.co.uk`, as a key column;
+    /// null for IP hosts.
+    Tld,
+    /// Non-empty path segments, e.g. 2 for `/a/b/`.
+    PathDepth,
+    /// Non-empty `&`-separated query parameters.
+    QueryParams,
+}
+
+impl UrlOutput {
+    /// Whether the output is a string key rather than a count feature.
+    pub fn is_key(self) -> bool {
+        matches!(self, Self::Domain | Self::Tld)
+    }
+
+    /// The key of `url` for key outputs; `None` for counts.
+    pub fn key(self, url: &Url<'_>) -> Option<String> {
+        match self {
+            Self::Domain => Some(
+                url.host
+                    .strip_prefix("www.")
+                    .unwrap_or(&url.host)
+                    .to_string(),
+            ),
+            Self::Tld if url.host.starts_with('[') || url.host.parse::<IpAddr>().is_ok() => None,
+            Self::Tld => url
+                .host
+                .rsplit_once('.')
+                .map(|(_, tld)| tld.to_string())
+                .filter(|tld| !tld.is_empty()),
+            Self::PathDepth | Self::QueryParams => None,
+        }
+    }
+
+    /// The count of `url` for count outputs; `None` for keys.
+    pub fn count(self, url: &Url<'_>) -> Option<u32> {
+        let count = |text: &str, separator: char| {
+            text.split(separator)
+                .filter(|part| !part.is_empty())
+                .count() as u32
+        };
+        match self {
+            Self::PathDepth => Some(count(url.path, '/')),
+            Self::QueryParams => Some(url.query.map_or(0, |query| count(query, '&'))),
+            Self::Domain | Self::Tld => None,
+        }
+    }
+}
+
+/// The parts of a URL the outputs read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url<'a> {
+    /// Lowercased, without user info, port or a trailing dot.
+    pub host: String,
+    pub path: &'a str,
+    pub query: Option<&'a str>,
+}
+
+/// The parts of `text`, surrounding whitespace ignored; `None` when its
+/// host is empty or holds whitespace, as free text would.
+pub fn parse(text: &str) -> Option<Url<'_>> {
+    let text = text.trim();
+    // A `://` in the query of a scheme-less URL is not a scheme.
+    let rest = match text.split_once("://") {
+        Some((scheme, rest))
+            if !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)) =>
+        {
+            rest
+        }
+        _ => text.strip_prefix("//").unwrap_or(text),
+    };
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (authority, remainder) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = match host.find(']') {
+        Some(end) if host.starts_with('[') => &host[..=end],
+        _ => host.split(':').next().unwrap_or_default(),
+    };
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return None;
+    }
+    let (path, query) = match remainder.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (remainder, None),
+    };
+    Some(Url { host, path, query })
+}
@@ -0,0 +1,196 @@
+//! Weight of evidence for `woe_encode` steps: each bin of a column encoded as
+//! `ln(share of non-events / share of events)`, so bins riskier than average
+//! are negative, as in credit scorecards.
+
+use std::collections::BTreeMap;
+
+use polars::prelude::*;
+use serde::Serialize;
+
+/// Added to every bin's counts, so bins without events or non-events get a
+/// finite weight.
+const SMOOTHING: f64 = 0.5;
+
+/// A fitted `woe_encode` step: its bins and their weights.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WoeTable {
+    /// Upper bin edges of a numeric column, the last bin open above; `None`
+    /// when categories are bins.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edges: Option<Vec<f64>>,
+    pub bins: Vec<WoeBin>,
+    /// Sum over bins of the share difference times the weight; above 0.3 is
+    /// commonly read as strong.
+    pub information_value: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct WoeBin {
+    /// Category, or bin index of a numeric column; `None` for nulls.
+    pub bin: Option<String>,
+    pub events: usize,
+    pub non_events: usize,
+    pub woe: f64,
+}
+
+impl WoeTable {
+    /// Fitted on the rows of `values` whose `target` is not null. The event
+    /// is `target` equal to `positive`, as a string, or by default the
+    /// larger of a numeric or boolean target's two values.
+    pub fn fit(
+        values: &Column,
+        target: &Column,
+        bins: usize,
+        positive: Option<&str>,
+    ) -> PolarsResult<Self> {
+        let events = events(target, positive)?;
+        let edges = match numeric(values)? {
+            Some(numbers) => {
+                let mut sorted: Vec<f64> = numbers
+                    .iter()
+                    .zip(events.iter())
+                    .filter_map(|(x, event)| event.and(x))
+                    .collect();
+                sorted.sort_by(f64::total_cmp);
+                Some(edges(&sorted, bins))
+            }
+            None => None,
+        };
+
+        let keys = keys(values, edges.as_deref())?;
+        let mut counts: BTreeMap<Option<String>, (usize, usize)> = BTreeMap::new();
+        for (key, event) in keys.into_iter().zip(events.iter().copied()) {
+            let Some(event) = event else {
+                continue;
+            };
+            let count = counts.entry(key).or_default();
+            if event {
+                count.0 += 1;
+            } else {
+                count.1 += 1;
+            }
+        }
+        let total_events: usize = counts.values().map(|c| c.0).sum();
+        let total_non_events: usize = counts.values().map(|c| c.1).sum();
+        if total_events == 0 || total_non_events == 0 {
+            polars_bail!(
+                ComputeError: "target '{}' needs both events and non-events", target.name()
+            );
+        }
+
+        let smoothed = SMOOTHING * counts.len() as f64;
+        let mut information_value = 0.0;
+        let bins = counts
+            .into_iter()
+            .map(|(bin, (events, non_events))| {
+                let event_share = (events as f64 + SMOOTHING) / (total_events as f64 + smoothed);
+                let non_event_share =
+                    (non_events as f64 + SMOOTHING) / (total_non_events as f64 + smoothed);
+                let woe = (non_event_share / event_share).ln();
+                information_value += (non_event_share - event_share) * woe;
+                WoeBin {
+                    bin,
+                    events,
+                    non_events,
+                    woe,
+                }
+            })
+            .collect();
+        Ok(Self {
+            edges,
+            bins,
+            information_value,
+        })
+    }
+
+    /// The weight of each value of `values`; null for bins the fit data
+    /// didn't have.
+    pub fn encode(&self, values: &Column, name: &str) -> PolarsResult<Column> {
+        let weights: Float64Chunked = keys(values, self.edges.as_deref())?
+            .iter()
+            .map(|key| self.weight(key.as_deref()))
+            .collect();
+        Ok(weights.with_name(name.into()).into_column())
+    }
+
+    /// The weight of the bin `key`, as [`bin_key`](Self::bin_key) gives it.
+    pub fn weight(&self, key: Option<&str>) -> Option<f64> {
+        self.bins
+            .iter()
+            .find(|b| b.bin.as_deref() == key)
+            .map(|b| b.woe)
+    }
+
+    /// The bin of the number `x`; `None` when categories are bins.
+    pub fn bin_key(&self, x: f64) -> Option<String> {
+        let edges = self.edges.as_ref()?;
+        Some(edges.partition_point(|e| *e < x).to_string())
+    }
+}
+
+/// Whether each row of `target` is an event; `None` for null targets.
+fn events(target: &Column, positive: Option<&str>) -> PolarsResult<Vec<Option<bool>>> {
+    if let Some(positive) = positive {
+        let text = target.cast(&DataType::String)?;
+        return Ok(text
+            .str()?
+            .iter()
+            .map(|t| t.map(|t| t == positive))
+            .collect());
+    }
+    let Some(numbers) = numeric(target)? else {
+        polars_bail!(
+            ComputeError: "set 'positive' to the event class of string target '{}'", target.name()
+        );
+    };
+    let mut distinct: Vec<f64> = numbers.iter().flatten().collect();
+    distinct.sort_by(f64::total_cmp);
+    distinct.dedup();
+    if distinct.len() != 2 {
+        polars_bail!(
+            ComputeError: "target '{}' must have two values, got {}", target.name(), distinct.len()
+        );
+    }
+    Ok(numbers
+        .iter()
+        .map(|y| y.map(|y| y == distinct[1]))
+        .collect())
+}
+
+/// `column` as floats when it is numeric or boolean.
+fn numeric(column: &Column) -> PolarsResult<Option<Float64Chunked>> {
+    if !(column.dtype().is_primitive_numeric() || column.dtype().is_bool()) {
+        return Ok(None);
+    }
+    Ok(Some(column.cast(&DataType::Float64)?.f64()?.clone()))
+}
+
+/// Upper edges of at most `bins` equal-frequency bins of the `sorted`
+/// values; each distinct value is its own bin when there are few enough.
+fn edges(sorted: &[f64], bins: usize) -> Vec<f64> {
+    let mut distinct = sorted.to_vec();
+    distinct.dedup();
+    if distinct.len() <= bins {
+        // Values above the largest fall in its bin.
+        distinct.pop();
+        return distinct;
+    }
+    let mut edges: Vec<f64> = (1..bins).map(|k| sorted[k * sorted.len() / bins]).collect();
+    edges.dedup();
+    edges
+}
+
+/// The bin of each row: its index for numbers with `edges`, otherwise the
+/// value as a string; `None` for nulls.
+fn keys(values: &Column, edges: Option<&[f64]>) -> PolarsResult<Vec<Option<String>>> {
+    if let Some(edges) = edges {
+        let numbers = values.cast(&DataType::Float64)?;
+        return Ok(numbers
+            .f64()?
+            .iter()
+            .map(|x| x.map(|x| edges.partition_point(|e| *e < x).to_string()))
+            .collect());
+    }
+    let text = values.cast(&DataType::String)?;
+    Ok(text.str()?.iter().map(|t| t.map(str::to_string)).collect())
+}
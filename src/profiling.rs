@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+
+fn default_top_k() -> usize {
+    10
+}
+
+fn default_bins() -> usize {
+    10
+}
+
+/// Column statistics written as `profile.json` and `profile.html`.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ProfilingConfig {
+    /// Most frequent values kept per non-numeric column.
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+
+    /// Equal-width histogram bins per numeric column.
+    #[serde(default = "default_bins")]
+    pub bins: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NumericSummary {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Sample standard deviation; `None` with fewer than two values.
+    pub std: Option<f64>,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Histogram {
+    /// `counts.len() + 1` bin edges; the last bin includes its upper edge.
+    pub edges: Vec<f64>,
+    pub counts: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub dtype: String,
+    pub count: usize,
+    pub null_count: usize,
+    pub null_rate: f64,
+    pub distinct: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numeric: Option<NumericSummary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<Histogram>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_values: Vec<ValueCount>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FrameProfile {
+    pub name: String,
+    pub rows: usize,
+    pub columns: Vec<ColumnProfile>,
+}
+
+impl ProfilingConfig {
+    pub fn profile(&self, name: &str, data: &DataFrame) -> Result<FrameProfile, PipelineStepError> {
+        let columns = data
+            .get_columns()
+            .iter()
+            .map(|column| self.profile_column(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(FrameProfile {
+            name: name.to_string(),
+            rows: data.height(),
+            columns,
+        })
+    }
+
+    pub(crate) fn profile_column(
+        &self,
+        column: &Column,
+    ) -> Result<ColumnProfile, PipelineStepError> {
+        let count = column.len();
+        let null_count = column.null_count();
+        let mut profile = ColumnProfile {
+            name: column.name().to_string(),
+            dtype: column.dtype().to_string(),
+            count,
+            null_count,
+            null_rate: if count == 0 {
+                0.0
+            } else {
+                null_count as f64 / count as f64
+            },
+            distinct: column.drop_nulls().n_unique()?,
+            numeric: None,
+            histogram: None,
+            top_values: Vec::new(),
+        };
+
+        if column.dtype().is_primitive_numeric() {
+            let values = column.cast(&DataType::Float64)?;
+            let values = values.f64()?;
+            if let (Some(min), Some(max), Some(mean)) = (values.min(), values.max(), values.mean())
+            {
+                let quantile = |q| -> Result<f64, PipelineStepError> {
+                    Ok(values
+                        .quantile(q, QuantileMethod::Linear)?
+                        .unwrap_or(f64::NAN))
+                };
+                profile.numeric = Some(NumericSummary {
+                    min,
+                    max,
+                    mean,
+                    std: values.std(1),
+                    p25: quantile(0.25)?,
+                    median: quantile(0.5)?,
+                    p75: quantile(0.75)?,
+                });
+                profile.histogram = Some(histogram(values, min, max, self.bins));
+            }
+        } else {
+            profile.top_values = top_values(column, self.top_k)?;
+        }
+
+        Ok(profile)
+    }
+}
+
+fn histogram(values: &Float64Chunked, min: f64, max: f64, bins: usize) -> Histogram {
+    let bins = if max > min { bins.max(1) } else { 1 };
+    let width = (max - min) / bins as f64;
+    let edges: Vec<f64> = (0..=bins).map(|i| min + width * i as f64).collect();
+    let counts = bin_counts(values, &edges);
+    Histogram { edges, counts }
+}
+
+/// Counts non-null `values` into the bins between `edges`. Values outside the
+/// edges fall into the first or last bin.
+pub(crate) fn bin_counts(values: &Float64Chunked, edges: &[f64]) -> Vec<usize> {
+    let bins = edges.len().saturating_sub(1).max(1);
+    let inner = if edges.len() > 2 {
+        &edges[1..edges.len() - 1]
+    } else {
+        &[]
+    };
+    let mut counts = vec![0; bins];
+    for value in values.into_iter().flatten() {
+        counts[inner.iter().take_while(|edge| value >= **edge).count()] += 1;
+    }
+    counts
+}
+
+/// Occurrences of each non-null value, compared as strings.
+pub(crate) fn value_counts(column: &Column) -> Result<HashMap<String, usize>, PipelineStepError> {
+    let values = column.cast(&DataType::String)?;
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for value in values.str()?.into_iter().flatten() {
+        *counts.entry(value.to_string()).or_default() += 1;
+    }
+    Ok(counts)
+}
+
+/// Most frequent non-null values, ties broken by value.
+fn top_values(column: &Column, k: usize) -> Result<Vec<ValueCount>, PipelineStepError> {
+    let mut counts: Vec<(String, usize)> = value_counts(column)?.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    Ok(counts
+        .into_iter()
+        .take(k)
+        .map(|(value, count)| ValueCount { value, count })
+        .collect())
+}
+
+/// Renders profiles as a self-contained HTML page, one table per frame.
+pub fn to_html(profiles: &[FrameProfile]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Data profile</title>\n\
+         <style>\n\
+         body { font-family: sans-serif; margin: 2em; }\n\
+         table { border-collapse: collapse; margin-bottom: 2em; }\n\
+         th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }\n\
+         th { background: #f0f0f0; }\n\
+         .bar { background: #4a7ebb; height: 10px; display: inline-block; }\n\
+         </style>\n</head>\n<body>\n<h1>Data profile</h1>\n",
+    );
+
+    for profile in profiles {
+        let _ = write!(
+            html,
+            "<h2>{} ({} rows, {} columns)</h2>\n<table>\n\
+             <tr><th>Column</th><th>Type</th><th>Nulls</th><th>Distinct</th>\
+             <th>Summary</th><th>Distribution</th></tr>\n",
+            escape(&profile.name),
+            profile.rows,
+            profile.columns.len()
+        );
+        for column in &profile.columns {
+            let summary = match &column.numeric {
+                Some(n) => format!(
+                    "min {}<br>p25 {}<br>median {}<br>p75 {}<br>max {}<br>mean {}<br>std {}",
+                    fmt_number(n.min),
+                    fmt_number(n.p25),
+                    fmt_number(n.median),
+                    fmt_number(n.p75),
+                    fmt_number(n.max),
+                    fmt_number(n.mean),
+                    n.std.map_or("-".to_string(), fmt_number)
+                ),
+                None => String::new(),
+            };
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{} ({:.1}%)</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&column.name),
+                escape(&column.dtype),
+                column.null_count,
+                column.null_rate * 100.0,
+                column.distinct,
+                summary,
+                distribution(column)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Bar rows for the histogram or top values, scaled to the largest count.
+pub(crate) fn distribution(column: &ColumnProfile) -> String {
+    let rows: Vec<(String, usize)> = match &column.histogram {
+        Some(h) => h
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                (
+                    format!(
+                        "{} – {}",
+                        fmt_number(h.edges[i]),
+                        fmt_number(h.edges[i + 1])
+                    ),
+                    count,
+                )
+            })
+            .collect(),
+        None => column
+            .top_values
+            .iter()
+            .map(|v| (escape(&v.value), v.count))
+            .collect(),
+    };
+    let largest = rows.iter().map(|(_, c)| *c).max().unwrap_or(0).max(1);
+    rows.iter()
+        .map(|(label, count)| {
+            format!(
+                "<span class=\"bar\" style=\"width: {}px\"></span> {} ({})",
+                count * 120 / largest,
+                label,
+                count
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+pub(crate) fn fmt_number(value: f64) -> String {
+    format!("{:.4}", value)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,388 @@
+//! An index of finished runs, so a run can be found by ID, config or input
+//! fingerprint, status, or a file it wrote, without going through the
+//! timestamped run folders one by one.
+//!
+//! Records are rows of a `runs` table in a SQLite file. SQLite's own locking
+//! lets concurrent runs (e.g. backfill partitions) register at once, and each
+//! record is written in one transaction, so a crash never corrupts earlier
+//! ones. Tags, row counts and metrics are stored as JSON text.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Local;
+use rusqlite::types::Type;
+use rusqlite::{Connection, OptionalExtension, Row, params, params_from_iter};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::run_result::{RowCounts, RunResult, RunStatus};
+
+/// Registry used when none is given: next to the run folders.
+pub const DEFAULT_REGISTRY: &str = "data/output/runs.db";
+
+/// One registered run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunRecord {
+    /// The orchestrator's run ID, or else the run folder's name.
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// When the run ended, RFC 3339 in local time.
+    pub finished_at: String,
+    pub status: RunStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_fingerprint: Option<String>,
+    #[serde(default)]
+    pub rows: RowCounts,
+    /// Model evaluation metrics, e.g. `accuracy`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, f64>,
+    pub duration_ms: f64,
+    /// `<stage>: <message>` of a failed run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RunRecord {
+    pub fn from_result(result: &RunResult) -> Self {
+        let folder = result.run_dir.as_deref().and_then(|dir| {
+            Path::new(dir)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        });
+        let finished_at = Local::now();
+        Self {
+            id: result
+                .run_id
+                .clone()
+                .or(folder)
+                .unwrap_or_else(|| finished_at.format("%Y%m%d_%H%M%S").to_string()),
+            name: result.run_name.clone(),
+            tags: result.tags.clone(),
+            finished_at: finished_at.to_rfc3339(),
+            status: result.status,
+            run_dir: result.run_dir.clone(),
+            config_fingerprint: result.config_fingerprint.clone(),
+            input_fingerprint: result.input_fingerprint.clone(),
+            rows: result.rows.clone(),
+            metrics: result.evaluation.clone(),
+            duration_ms: result.stages.iter().map(|s| s.duration_ms).sum(),
+            error: result
+                .error
+                .as_ref()
+                .map(|e| format!("{}: {}", e.stage, e.message)),
+        }
+    }
+}
+
+/// Conditions a listed run meets; unset fields match every run.
+#[derive(Debug, Clone, Default)]
+pub struct RunQuery {
+    pub status: Option<RunStatus>,
+    pub name: Option<String>,
+    /// Tags the run has, with these values.
+    pub tags: BTreeMap<String, String>,
+    /// Prefix of the config fingerprint.
+    pub config_fingerprint: Option<String>,
+    /// Prefix of the input fingerprint.
+    pub input_fingerprint: Option<String>,
+    /// Only the most recent this many runs.
+    pub limit: Option<usize>,
+}
+
+impl RunQuery {
+    /// The `WHERE` clause of everything but `tags`, and its parameters.
+    fn filter(&self) -> (String, Vec<String>) {
+        let mut conditions = vec!["1".to_string()];
+        let mut values = Vec::new();
+        let mut condition = |sql: &str, value: &str| {
+            values.push(value.to_string());
+            conditions.push(sql.replace('?', &format!("?{}", values.len())));
+        };
+        if let Some(status) = self.status {
+            condition("status = ?", status_text(status));
+        }
+        if let Some(name) = &self.name {
+            condition("name = ?", name);
+        }
+        if let Some(prefix) = &self.config_fingerprint {
+            condition("substr(config_fingerprint, 1, length(?)) = ?", prefix);
+        }
+        if let Some(prefix) = &self.input_fingerprint {
+            condition("substr(input_fingerprint, 1, length(?)) = ?", prefix);
+        }
+        (conditions.join(" AND "), values)
+    }
+
+    fn matches_tags(&self, record: &RunRecord) -> bool {
+        self.tags
+            .iter()
+            .all(|(key, value)| record.tags.get(key) == Some(value))
+    }
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    IoError(io::Error),
+    SqliteError(rusqlite::Error),
+    SerdeError(serde_json::Error),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::IoError(e) => write!(f, "{}", e),
+            RegistryError::SqliteError(e) => write!(f, "SQLite error: {}", e),
+            RegistryError::SerdeError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+impl From<io::Error> for RegistryError {
+    fn from(value: io::Error) -> Self {
+        RegistryError::IoError(value)
+    }
+}
+
+impl From<rusqlite::Error> for RegistryError {
+    fn from(value: rusqlite::Error) -> Self {
+        RegistryError::SqliteError(value)
+    }
+}
+
+impl From<serde_json::Error> for RegistryError {
+    fn from(value: serde_json::Error) -> Self {
+        RegistryError::SerdeError(value)
+    }
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    id TEXT NOT NULL,
+    name TEXT,
+    tags TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    run_dir TEXT,
+    config_fingerprint TEXT,
+    input_fingerprint TEXT,
+    rows TEXT NOT NULL,
+    metrics TEXT NOT NULL,
+    duration_ms REAL NOT NULL,
+    error TEXT
+);
+CREATE INDEX IF NOT EXISTS runs_id ON runs (id);
+CREATE INDEX IF NOT EXISTS runs_name ON runs (name);
+";
+
+const COLUMNS: &str = "id, name, tags, finished_at, status, run_dir, config_fingerprint, \
+    input_fingerprint, rows, metrics, duration_ms, error";
+
+/// How long a write waits for another run's to finish before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn status_text(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Running => "running",
+        RunStatus::Success => "success",
+        RunStatus::Failed => "failed",
+    }
+}
+
+/// Column `index` of `row`, stored as JSON text.
+fn json_column<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(index)?;
+    serde_json::from_str(&text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+fn read_record(row: &Row) -> rusqlite::Result<RunRecord> {
+    Ok(RunRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        tags: json_column(row, 2)?,
+        finished_at: row.get(3)?,
+        status: serde_json::from_value(serde_json::Value::String(row.get(4)?))
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, Type::Text, Box::new(e)))?,
+        run_dir: row.get(5)?,
+        config_fingerprint: row.get(6)?,
+        input_fingerprint: row.get(7)?,
+        rows: json_column(row, 8)?,
+        metrics: json_column(row, 9)?,
+        duration_ms: row.get(10)?,
+        error: row.get(11)?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct RunRegistry {
+    path: PathBuf,
+}
+
+impl RunRegistry {
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Connects to the registry, creating the file and table when needed.
+    fn connect(&self) -> Result<Connection, RegistryError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(&self.path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(connection)
+    }
+
+    /// The runs `sql` selects, whose columns are `COLUMNS`; empty when
+    /// nothing was registered.
+    fn select(&self, sql: &str) -> Result<Vec<RunRecord>, RegistryError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let connection = self.connect()?;
+        let mut statement = connection.prepare(sql)?;
+        let records = statement
+            .query_map([], read_record)?
+            .collect::<Result<_, _>>()?;
+        Ok(records)
+    }
+
+    /// Adds `record`, creating the registry when needed.
+    pub fn register(&self, record: &RunRecord) -> Result<(), RegistryError> {
+        self.connect()?.execute(
+            &format!(
+                "INSERT INTO runs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                COLUMNS
+            ),
+            params![
+                record.id,
+                record.name,
+                serde_json::to_string(&record.tags)?,
+                record.finished_at,
+                status_text(record.status),
+                record.run_dir,
+                record.config_fingerprint,
+                record.input_fingerprint,
+                serde_json::to_string(&record.rows)?,
+                serde_json::to_string(&record.metrics)?,
+                record.duration_ms,
+                record.error,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Every registered run, oldest first; empty when nothing was registered.
+    pub fn runs(&self) -> Result<Vec<RunRecord>, RegistryError> {
+        self.select(&format!("SELECT {} FROM runs ORDER BY seq", COLUMNS))
+    }
+
+    /// Runs matching `query`, most recent first.
+    pub fn query(&self, query: &RunQuery) -> Result<Vec<RunRecord>, RegistryError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let connection = self.connect()?;
+        let (filter, values) = query.filter();
+        let mut statement = connection.prepare(&format!(
+            "SELECT {} FROM runs WHERE {} ORDER BY seq DESC",
+            COLUMNS, filter
+        ))?;
+        let records = statement
+            .query_map(params_from_iter(values), read_record)?
+            // Errors are kept, to be returned by `collect`.
+            .filter(|record| {
+                record
+                    .as_ref()
+                    .is_ok_and(|record| query.matches_tags(record))
+                    || record.is_err()
+            })
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect::<Result<_, _>>()?;
+        Ok(records)
+    }
+
+    /// The most recent run with ID or name `id`.
+    pub fn get(&self, id: &str) -> Result<Option<RunRecord>, RegistryError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        Ok(self
+            .connect()?
+            .query_row(
+                &format!(
+                    "SELECT {} FROM runs WHERE id = ?1 OR name = ?1 ORDER BY seq DESC LIMIT 1",
+                    COLUMNS
+                ),
+                [id],
+                read_record,
+            )
+            .optional()?)
+    }
+
+    /// Removes the records of runs whose folder is one of `run_dirs`, e.g.
+    /// once the folders are deleted, returning how many were removed.
+    pub fn forget(&self, run_dirs: &[PathBuf]) -> Result<usize, RegistryError> {
+        if !self.path.exists() {
+            return Ok(0);
+        }
+        let mut connection = self.connect()?;
+        let forgotten: Vec<PathBuf> = run_dirs
+            .iter()
+            .map(std::path::absolute)
+            .collect::<Result<_, _>>()?;
+        // Run folders may be recorded relative or absolute, so they are
+        // compared here rather than in SQL.
+        let transaction = connection.transaction()?;
+        let folders: Vec<(i64, String)> = transaction
+            .prepare("SELECT seq, run_dir FROM runs WHERE run_dir IS NOT NULL")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        let mut removed = 0;
+        for (seq, dir) in folders {
+            if std::path::absolute(&dir).is_ok_and(|dir| forgotten.contains(&dir)) {
+                removed += transaction.execute("DELETE FROM runs WHERE seq = ?1", [seq])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(removed)
+    }
+
+    /// The most recent run whose folder holds `file`, e.g. a `features.csv`.
+    pub fn producing(&self, file: &Path) -> Result<Option<RunRecord>, RegistryError> {
+        let file = fs::canonicalize(file)?;
+        Ok(self
+            .select(&format!(
+                "SELECT {} FROM runs WHERE run_dir IS NOT NULL ORDER BY seq DESC",
+                COLUMNS
+            ))?
+            .into_iter()
+            .find(|record| {
+                record
+                    .run_dir
+                    .as_ref()
+                    .and_then(|dir| fs::canonicalize(dir).ok())
+                    .is_some_and(|dir| file.starts_with(dir))
+            }))
+    }
+}
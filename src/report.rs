@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use polars::prelude::*;
+
+use crate::config::fingerprint::ResolvedConfig;
+use crate::errors::PipelineStepError;
+use crate::manifest::InputFile;
+use crate::pipeline::labels::{LABEL_PREFIX, LabelEncoding};
+use crate::pipeline::quality::FeatureQuality;
+use crate::pipeline::stats::StepStats;
+use crate::profiling::{self, ColumnProfile, ProfilingConfig, escape, fmt_number};
+use crate::run_result::{RowCounts, StageResult, StageStatus};
+
+/// Distinct values up to which an integer label is shown as classes rather
+/// than a histogram.
+const MAX_CLASSES: usize = 50;
+
+/// How a label column's values are spread.
+#[derive(Debug, Clone)]
+pub enum LabelDistribution {
+    /// Row count per class, most frequent first; encoded classes are decoded.
+    Classes {
+        column: String,
+        counts: Vec<(String, usize)>,
+    },
+    /// Histogram and summary of a continuous target.
+    Continuous(ColumnProfile),
+}
+
+/// Class counts or histograms for every column of `labels`, with codes
+/// decoded through `encodings` (keyed by target name).
+pub fn label_distributions(
+    labels: &DataFrame,
+    encodings: &BTreeMap<String, LabelEncoding>,
+) -> Result<Vec<LabelDistribution>, PipelineStepError> {
+    labels
+        .get_columns()
+        .iter()
+        .map(|column| {
+            let discrete =
+                !column.dtype().is_float() && column.drop_nulls().n_unique()? <= MAX_CLASSES;
+            if !discrete {
+                let config = ProfilingConfig { top_k: 0, bins: 20 };
+                return Ok(LabelDistribution::Continuous(
+                    config.profile_column(column)?,
+                ));
+            }
+
+            let encoding = column
+                .name()
+                .strip_prefix(LABEL_PREFIX)
+                .and_then(|target| encodings.get(target));
+            let mut counts: Vec<(String, usize)> = profiling::value_counts(column)?
+                .into_iter()
+                .map(|(value, count)| {
+                    let class = encoding
+                        .and_then(|e| value.parse().ok().and_then(|code| e.decode(code)))
+                        .map_or(value, str::to_string);
+                    (class, count)
+                })
+                .collect();
+            if column.null_count() > 0 {
+                counts.push(("(missing)".to_string(), column.null_count()));
+            }
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            Ok(LabelDistribution::Classes {
+                column: column.name().to_string(),
+                counts,
+            })
+        })
+        .collect()
+}
+
+/// A run summarized as `report.html`, for readers who do not want to open the
+/// JSON artifacts.
+pub struct RunReport<'a> {
+    pub run_id: Option<&'a str>,
+    pub run_name: Option<&'a str>,
+    pub tags: &'a BTreeMap<String, String>,
+    pub fingerprint: &'a str,
+    pub config: &'a ResolvedConfig<'a>,
+    pub inputs: &'a [InputFile],
+    pub rows: &'a RowCounts,
+    pub stages: &'a [StageResult],
+    pub steps: &'a [StepStats],
+    pub label_steps: &'a [StepStats],
+    pub quality: &'a [FeatureQuality],
+    pub labels: &'a [LabelDistribution],
+    pub warnings: &'a [String],
+}
+
+impl RunReport<'_> {
+    /// Self-contained HTML; all styling is inline.
+    pub fn to_html(&self) -> Result<String, serde_json::Error> {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Run report</title>\n\
+             <style>\n\
+             body { font-family: sans-serif; margin: 2em; max-width: 1200px; }\n\
+             table { border-collapse: collapse; margin-bottom: 2em; }\n\
+             th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }\n\
+             th { background: #f0f0f0; }\n\
+             td.num { text-align: right; }\n\
+             tr.flag td { background: #fff3cd; }\n\
+             .bar { background: #4a7ebb; height: 10px; display: inline-block; }\n\
+             pre { background: #f7f7f7; padding: 1em; overflow: auto; }\n\
+             </style>\n</head>\n<body>\n<h1>Run report</h1>\n",
+        );
+
+        self.write_summary(&mut html);
+        self.write_warnings(&mut html);
+        self.write_stages(&mut html);
+        self.write_steps(&mut html);
+        self.write_quality(&mut html);
+        self.write_labels(&mut html);
+
+        let _ = write!(
+            html,
+            "<h2>Configuration</h2>\n<details><summary>Resolved config</summary>\n<pre>{}</pre>\n</details>\n",
+            escape(&self.config.to_json()?)
+        );
+
+        html.push_str("</body>\n</html>\n");
+        Ok(html)
+    }
+
+    fn write_summary(&self, html: &mut String) {
+        html.push_str("<table>\n");
+        if let Some(run_id) = self.run_id {
+            row(html, "Run ID", &escape(run_id));
+        }
+        if let Some(run_name) = self.run_name {
+            row(html, "Run name", &escape(run_name));
+        }
+        if !self.tags.is_empty() {
+            let tags: Vec<String> = self
+                .tags
+                .iter()
+                .map(|(key, value)| format!("<code>{}={}</code>", escape(key), escape(value)))
+                .collect();
+            row(html, "Tags", &tags.join(" "));
+        }
+        row(
+            html,
+            "Config fingerprint",
+            &format!("<code>{}</code>", self.fingerprint),
+        );
+        row(
+            html,
+            "Features config",
+            &escape(&self.config.entrypoint.features),
+        );
+        row(
+            html,
+            "Labels config",
+            &escape(&self.config.entrypoint.labels),
+        );
+        for (frame, count) in [
+            ("Input rows", self.rows.input),
+            ("Feature rows", self.rows.features),
+            ("Label rows", self.rows.labels),
+            ("Train rows", self.rows.train),
+            ("Test rows", self.rows.test),
+        ] {
+            if let Some(count) = count {
+                row(html, frame, &count.to_string());
+            }
+        }
+        html.push_str("</table>\n");
+
+        html.push_str(
+            "<h2>Inputs</h2>\n<table>\n<tr><th>Dataset</th><th>Path</th><th>Size</th><th>Rows</th></tr>\n",
+        );
+        for input in self.inputs {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape(&input.dataset),
+                escape(&input.path),
+                input.size_bytes.map_or("-".to_string(), fmt_bytes),
+                input.rows
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn write_warnings(&self, html: &mut String) {
+        let _ = writeln!(html, "<h2>Warnings ({})</h2>", self.warnings.len());
+        if self.warnings.is_empty() {
+            html.push_str("<p>None.</p>\n");
+            return;
+        }
+        html.push_str("<ul>\n");
+        for warning in self.warnings {
+            let _ = writeln!(html, "<li>{}</li>", escape(warning));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    fn write_stages(&self, html: &mut String) {
+        html.push_str(
+            "<h2>Stages</h2>\n<table>\n<tr><th>Stage</th><th>Status</th><th>Duration</th></tr>\n",
+        );
+        for stage in self.stages {
+            let _ = writeln!(
+                html,
+                "<tr{}><td>{}</td><td>{}</td><td class=\"num\">{}</td></tr>",
+                if stage.status == StageStatus::Failed {
+                    " class=\"flag\""
+                } else {
+                    ""
+                },
+                escape(&stage.name),
+                if stage.status == StageStatus::Failed {
+                    "failed"
+                } else {
+                    "ok"
+                },
+                fmt_ms(stage.duration_ms)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn write_steps(&self, html: &mut String) {
+        for (title, steps) in [
+            ("Feature steps", self.steps),
+            ("Label steps", self.label_steps),
+        ] {
+            if steps.is_empty() {
+                continue;
+            }
+            let _ = write!(
+                html,
+                "<h2>{}</h2>\n<table>\n<tr><th>#</th><th>Function</th><th>Name</th>\
+                 <th>Description</th><th>Duration</th><th>Changes</th></tr>\n",
+                title
+            );
+            for step in steps {
+                let description = if title == "Feature steps" {
+                    self.config
+                        .features
+                        .steps
+                        .get(step.step - 1)
+                        .and_then(|s| s.metadata().description)
+                        .unwrap_or_default()
+                } else {
+                    ""
+                };
+                let _ = writeln!(
+                    html,
+                    "<tr><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                     <td class=\"num\">{}</td><td>{}</td></tr>",
+                    step.step,
+                    step.function,
+                    escape(step.name.as_deref().unwrap_or("")),
+                    escape(description),
+                    step.duration_ms.map_or("-".to_string(), fmt_ms),
+                    escape(&step.summary())
+                );
+            }
+            html.push_str("</table>\n");
+        }
+    }
+
+    fn write_quality(&self, html: &mut String) {
+        html.push_str(
+            "<h2>Feature statistics</h2>\n<table>\n<tr><th>Feature</th><th>Missing</th>\
+             <th>Distinct</th><th>Variance</th><th>Zeros</th><th>Most common value</th>\
+             <th>Flag</th></tr>\n",
+        );
+        for feature in self.quality {
+            let _ = writeln!(
+                html,
+                "<tr{}><td>{}</td><td class=\"num\">{:.1}%</td><td class=\"num\">{}</td>\
+                 <td class=\"num\">{}</td><td class=\"num\">{:.1}%</td><td class=\"num\">{:.1}%</td>\
+                 <td>{}</td></tr>",
+                if feature.degenerate.is_some() {
+                    " class=\"flag\""
+                } else {
+                    ""
+                },
+                escape(&feature.column),
+                feature.null_fraction * 100.0,
+                feature.distinct,
+                feature.variance.map_or("-".to_string(), fmt_number),
+                feature.zero_fraction * 100.0,
+                feature.dominant_fraction * 100.0,
+                feature
+                    .degenerate
+                    .map_or(String::new(), |d| format!("{:?}", d).to_lowercase())
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    fn write_labels(&self, html: &mut String) {
+        html.push_str("<h2>Label distribution</h2>\n");
+        for label in self.labels {
+            match label {
+                LabelDistribution::Classes { column, counts } => {
+                    let total: usize = counts.iter().map(|(_, c)| c).sum();
+                    let max = counts.iter().map(|(_, c)| *c).max().unwrap_or(0).max(1);
+                    let _ = write!(
+                        html,
+                        "<h3>{}</h3>\n<table>\n<tr><th>Class</th><th>Rows</th><th>Share</th><th></th></tr>\n",
+                        escape(column)
+                    );
+                    for (class, count) in counts {
+                        let _ = writeln!(
+                            html,
+                            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.1}%</td>\
+                             <td><span class=\"bar\" style=\"width: {}px\"></span></td></tr>",
+                            escape(class),
+                            count,
+                            *count as f64 / total.max(1) as f64 * 100.0,
+                            count * 200 / max
+                        );
+                    }
+                    html.push_str("</table>\n");
+                }
+                LabelDistribution::Continuous(profile) => {
+                    let _ = write!(
+                        html,
+                        "<h3>{}</h3>\n<p>{} missing of {} rows</p>\n<p>{}</p>\n",
+                        escape(&profile.name),
+                        profile.null_count,
+                        profile.count,
+                        profiling::distribution(profile)
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn row(html: &mut String, label: &str, value: &str) {
+    let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", label, value);
+}
+
+fn fmt_ms(ms: f64) -> String {
+    if ms >= 1000.0 {
+        format!("{:.2} s", ms / 1000.0)
+    } else {
+        format!("{:.1} ms", ms)
+    }
+}
+
+fn fmt_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
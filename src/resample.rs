@@ -0,0 +1,181 @@
+//! Time bucketing of an input dataset: a timestamp column truncated to the
+//! start of its day, week or month, and optionally each entity's rows in a
+//! bucket aggregated into one, e.g. orders into monthly spend per customer.
+//! Applied as the dataset is read, so features and labels both see the
+//! bucketed rows.
+
+use std::collections::BTreeSet;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::PipelineStepError;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Day,
+    /// Weeks start on Monday.
+    Week,
+    Month,
+}
+
+impl Period {
+    /// The Polars duration of one period.
+    fn every(self) -> &'static str {
+        match self {
+            Self::Day => "1d",
+            Self::Week => "1w",
+            Self::Month => "1mo",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResampleAgg {
+    Sum,
+    Mean,
+    Min,
+    Max,
+    /// Non-null values.
+    Count,
+    /// In input order.
+    First,
+    Last,
+}
+
+/// One column of an aggregated bucket.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ResampleAggregation {
+    pub column: String,
+    pub agg: ResampleAgg,
+
+    /// Output column; `column` when omitted, so steps keep reading it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl ResampleAggregation {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.column)
+    }
+
+    fn expr(&self) -> Expr {
+        let column = col(self.column.as_str());
+        match self.agg {
+            ResampleAgg::Sum => column.sum(),
+            ResampleAgg::Mean => column.mean(),
+            ResampleAgg::Min => column.min(),
+            ResampleAgg::Max => column.max(),
+            ResampleAgg::Count => column.count(),
+            ResampleAgg::First => column.first(),
+            ResampleAgg::Last => column.last(),
+        }
+        .alias(self.name())
+    }
+}
+
+/// Buckets rows by the period of a timestamp column.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ResampleConfig {
+    /// Timestamp column, replaced by the first day of each row's period.
+    /// Strings are parsed as ISO 8601 dates or datetimes.
+    pub column: String,
+
+    pub every: Period,
+
+    /// Entity columns buckets are kept apart by, e.g. a customer ID.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub group_by: Vec<String>,
+
+    /// Columns aggregated into one row per entity and period, which drops
+    /// the others; rows are only truncated when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aggregations: Vec<ResampleAggregation>,
+}
+
+impl ResampleConfig {
+    /// Why the config can't be applied, if it can't.
+    pub fn invalid(&self) -> Option<String> {
+        if !self.group_by.is_empty() && self.aggregations.is_empty() {
+            return Some("resample 'group_by' only applies with 'aggregations'".to_string());
+        }
+        let mut names: BTreeSet<&str> = self.group_by.iter().map(String::as_str).collect();
+        names.insert(&self.column);
+        self.aggregations
+            .iter()
+            .find(|aggregation| !names.insert(aggregation.name()))
+            .map(|aggregation| {
+                format!(
+                    "resample output column '{}' is produced twice",
+                    aggregation.name()
+                )
+            })
+    }
+
+    /// Whether rows are aggregated, so there are fewer than were read.
+    pub fn aggregates(&self) -> bool {
+        !self.aggregations.is_empty()
+    }
+
+    /// Columns aggregated rows have: `column`, `group_by` and each
+    /// aggregation's name.
+    pub fn output_columns(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.column.as_str())
+            .chain(self.group_by.iter().map(String::as_str))
+            .chain(self.aggregations.iter().map(|a| a.name()))
+    }
+
+    /// Input columns the resample reads.
+    pub fn input_columns(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.column.as_str())
+            .chain(self.group_by.iter().map(String::as_str))
+            .chain(self.aggregations.iter().map(|a| a.column.as_str()))
+    }
+
+    /// Buckets the rows of `frame`. Aggregated rows come in the order each
+    /// entity and period first appears.
+    pub fn apply(&self, frame: DataFrame) -> Result<DataFrame, PipelineStepError> {
+        let Some(dtype) = frame.schema().get(&self.column).cloned() else {
+            return Err(PipelineStepError::ColumnNotFound {
+                found: self.column.clone(),
+                available: frame
+                    .get_column_names()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            });
+        };
+        let time = match dtype {
+            DataType::String => col(self.column.as_str()).str().to_datetime(
+                Some(TimeUnit::Milliseconds),
+                None,
+                StrptimeOptions::default(),
+                lit("raise"),
+            ),
+            _ => col(self.column.as_str()),
+        };
+        let bucket = time
+            .dt()
+            .truncate(lit(self.every.every()))
+            .cast(DataType::Date)
+            .alias(self.column.as_str());
+        let frame = frame.lazy().with_column(bucket);
+        if !self.aggregates() {
+            return Ok(frame.collect()?);
+        }
+
+        let keys: Vec<Expr> = self
+            .group_by
+            .iter()
+            .map(|c| col(c.as_str()))
+            .chain(std::iter::once(col(self.column.as_str())))
+            .collect();
+        let aggregations: Vec<Expr> = self.aggregations.iter().map(|a| a.expr()).collect();
+        Ok(frame.group_by_stable(keys).agg(aggregations).collect()?)
+    }
+}
@@ -0,0 +1,132 @@
+//! Retention of run folders under `data/output`. Only folders named like a
+//! run folder (`20240122_161429`, or `20240122_161429_<run name>`) are
+//! considered, so incremental and backfill outputs, or anything else kept
+//! there, are never deleted.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, NaiveDateTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Format of run folder names, followed by `_<run name>` for named runs.
+pub const RUN_FOLDER_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Length of a formatted [`RUN_FOLDER_FORMAT`] timestamp.
+const TIMESTAMP_LEN: usize = 15;
+
+/// Which run folders to keep; the rest are deleted. A run is kept when either
+/// rule keeps it.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Keep the most recent this many runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_last: Option<usize>,
+
+    /// Keep runs started less than this many days ago.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_days: Option<u64>,
+}
+
+/// A run folder and when its run started.
+#[derive(Serialize, Debug, Clone)]
+pub struct RunFolder {
+    pub path: PathBuf,
+    pub started: NaiveDateTime,
+}
+
+impl RetentionConfig {
+    /// Run folders under `output_dir` this policy doesn't keep at `now`,
+    /// oldest first. With neither rule set, every run is kept.
+    pub fn expired(
+        &self,
+        output_dir: &Path,
+        now: NaiveDateTime,
+    ) -> Result<Vec<RunFolder>, io::Error> {
+        if self.keep_last.is_none() && self.keep_days.is_none() {
+            return Ok(Vec::new());
+        }
+        let mut folders = run_folders(output_dir)?;
+        // Newest first, so the first `keep_last` are the ones kept.
+        folders.sort_by_key(|folder| std::cmp::Reverse(folder.started));
+        // Past the calendar's range, every run is young enough.
+        let cutoff = self.keep_days.map(|days| {
+            i64::try_from(days)
+                .ok()
+                .and_then(Duration::try_days)
+                .and_then(|age| now.checked_sub_signed(age))
+                .unwrap_or(NaiveDateTime::MIN)
+        });
+
+        let mut expired: Vec<RunFolder> = folders
+            .into_iter()
+            .enumerate()
+            .filter(|(rank, folder)| {
+                let recent = self.keep_last.is_some_and(|n| *rank < n);
+                let young = cutoff.is_some_and(|cutoff| folder.started >= cutoff);
+                !recent && !young
+            })
+            .map(|(_, folder)| folder)
+            .collect();
+        expired.reverse();
+        Ok(expired)
+    }
+}
+
+/// When the run of folder `name` started, if it is named like a run folder:
+/// a timestamp, optionally followed by `_<run name>`.
+pub fn started(name: &str) -> Option<NaiveDateTime> {
+    let (timestamp, rest) = name.split_at_checked(TIMESTAMP_LEN)?;
+    if !(rest.is_empty() || rest.starts_with('_')) {
+        return None;
+    }
+    NaiveDateTime::parse_from_str(timestamp, RUN_FOLDER_FORMAT).ok()
+}
+
+/// Folders directly under `output_dir` named like a run folder.
+pub fn run_folders(output_dir: &Path) -> Result<Vec<RunFolder>, io::Error> {
+    if !output_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut folders = Vec::new();
+    for entry in fs::read_dir(output_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let started = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(started);
+        if let Some(started) = started {
+            folders.push(RunFolder { path, started });
+        }
+    }
+    Ok(folders)
+}
+
+/// Deletes `folders`, returning the bytes freed.
+pub fn delete(folders: &[RunFolder]) -> Result<u64, io::Error> {
+    let mut freed = 0;
+    for folder in folders {
+        freed += size(&folder.path)?;
+        fs::remove_dir_all(&folder.path)?;
+    }
+    Ok(freed)
+}
+
+/// Total size of the files under `path`.
+pub fn size(path: &Path) -> Result<u64, io::Error> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        total += size(&entry?.path())?;
+    }
+    Ok(total)
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::BTreeMap, fmt::Display, fs::File, io::BufWriter, path::Path, time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, info_span};
+
+use crate::pipeline::stats::StepStats;
+
+/// Failure categories reported to orchestrators. Each maps to a stable process exit code.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    Config,
+    Data,
+    Pipeline,
+    Output,
+    Training,
+}
+
+impl FailureKind {
+    /// Exit codes start at 3: 1 is left for unexpected errors and 2 is used by clap for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Config => 3,
+            Self::Data => 4,
+            Self::Pipeline => 5,
+            Self::Output => 6,
+            Self::Training => 7,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Success,
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    Ok,
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StageResult {
+    pub name: String,
+    pub status: StageStatus,
+    pub duration_ms: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ErrorDetails {
+    pub kind: FailureKind,
+    pub stage: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct RowCounts {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub train: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test: Option<usize>,
+}
+
+/// Marker returned by [`RunResult::stage`] once the failure has been recorded in the result.
+#[derive(Debug)]
+pub struct StageFailed;
+
+#[derive(Serialize, Debug)]
+pub struct RunResult {
+    /// ID the orchestrator passed via `--run-id` / `PIPELINE_RUN_ID`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// Name given with `--run-name`, also part of the run folder's name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_name: Option<String>,
+    /// Tags given with `--tag key=value`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    pub status: RunStatus,
+    pub run_dir: Option<String>,
+    /// Hash of the resolved config; see `config.resolved.json` in the run folder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_fingerprint: Option<String>,
+    /// Hash of the path, size and row count of every input dataset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_fingerprint: Option<String>,
+    pub rows: RowCounts,
+    /// Model evaluation on the test rows, e.g. `accuracy`, once it has run.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub evaluation: BTreeMap<String, f64>,
+    pub stages: Vec<StageResult>,
+    /// Per-step timings and shape changes of the feature pipeline, once it has run.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorDetails>,
+}
+
+impl Default for RunResult {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunResult {
+    pub fn new() -> Self {
+        Self {
+            run_id: None,
+            run_name: None,
+            tags: BTreeMap::new(),
+            status: RunStatus::Running,
+            run_dir: None,
+            config_fingerprint: None,
+            input_fingerprint: None,
+            rows: RowCounts::default(),
+            evaluation: BTreeMap::new(),
+            stages: Vec::new(),
+            steps: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Runs one stage inside a `stage` span, recording its status and timing. On
+    /// error the details are stored in `self.error` under `kind` and `StageFailed`
+    /// is returned.
+    pub fn stage<T, E: Display>(
+        &mut self,
+        name: &str,
+        kind: FailureKind,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, StageFailed> {
+        let span = info_span!("stage", stage = name);
+        let _enter = span.enter();
+        let start = Instant::now();
+        let outcome = f();
+        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        match outcome {
+            Ok(value) => {
+                info!(duration_ms, "Stage finished");
+                self.stages.push(StageResult {
+                    name: name.to_string(),
+                    status: StageStatus::Ok,
+                    duration_ms,
+                });
+                Ok(value)
+            }
+            Err(e) => {
+                self.stages.push(StageResult {
+                    name: name.to_string(),
+                    status: StageStatus::Failed,
+                    duration_ms,
+                });
+                self.status = RunStatus::Failed;
+                self.error = Some(ErrorDetails {
+                    kind,
+                    stage: name.to_string(),
+                    message: e.to_string(),
+                });
+                Err(StageFailed)
+            }
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if self.status == RunStatus::Running {
+            self.status = RunStatus::Success;
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match (&self.status, &self.error) {
+            (RunStatus::Failed, Some(error)) => error.kind.exit_code(),
+            (RunStatus::Failed, None) => 1,
+            _ => 0,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<(), std::io::Error> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use std::io::{self, Cursor};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::{get, post};
+use polars::prelude::*;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::errors::PipelineStepError;
+use crate::pipeline::fitted::FittedPipeline;
+
+/// Content type of Arrow IPC streams, accepted and returned by `/transform`.
+pub const ARROW_STREAM: &str = "application/vnd.apache.arrow.stream";
+
+/// Features of the rows in a `/transform` request, in request order. Every
+/// value is cast to a float; `ohe` dummies and `threshold` flags become 0 or 1.
+#[derive(Serialize, Debug)]
+pub struct FeatureVectors {
+    pub columns: Vec<String>,
+    pub features: Vec<Vec<Option<f64>>>,
+}
+
+#[derive(Serialize, Debug)]
+struct ErrorBody {
+    error: String,
+}
+
+#[derive(Debug)]
+enum ServeError {
+    /// The body isn't a frame, e.g. malformed JSON.
+    BadRequest(String),
+    /// The pipeline failed on the rows, e.g. a missing input column.
+    Pipeline(PipelineStepError),
+    Internal(String),
+}
+
+impl IntoResponse for ServeError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            Self::BadRequest(error) => (StatusCode::BAD_REQUEST, error),
+            Self::Pipeline(e) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+            Self::Internal(error) => (StatusCode::INTERNAL_SERVER_ERROR, error),
+        };
+        warn!(status = status.as_u16(), "Transform failed: {}", error);
+        (status, Json(ErrorBody { error })).into_response()
+    }
+}
+
+impl From<PipelineStepError> for ServeError {
+    fn from(value: PipelineStepError) -> Self {
+        Self::Pipeline(value)
+    }
+}
+
+/// Routes:
+///
+/// - `POST /transform`: a JSON array of records, or an Arrow IPC stream with
+///   `Content-Type: application/vnd.apache.arrow.stream`, answered with the
+///   [`FeatureVectors`] or an Arrow stream of the features respectively.
+/// - `GET /health`: `ok` once the pipeline is fitted.
+pub fn router(fitted: FittedPipeline) -> Router {
+    Router::new()
+        .route("/transform", post(transform))
+        .route("/health", get(|| async { "ok" }))
+        .with_state(Arc::new(fitted))
+}
+
+/// Serves [`router`] on `addr` until the process is stopped.
+pub async fn serve(addr: SocketAddr, fitted: FittedPipeline) -> io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!(
+        "Serving {} feature columns on http://{}",
+        fitted.columns().len(),
+        listener.local_addr()?
+    );
+    axum::serve(listener, router(fitted)).await
+}
+
+async fn transform(
+    State(fitted): State<Arc<FittedPipeline>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ServeError> {
+    let arrow = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(ARROW_STREAM));
+    // Polars works on the caller's thread; keep it off the async workers.
+    tokio::task::spawn_blocking(move || apply(&fitted, arrow, &body))
+        .await
+        .map_err(|e| ServeError::Internal(e.to_string()))?
+}
+
+fn apply(fitted: &FittedPipeline, arrow: bool, body: &[u8]) -> Result<Response, ServeError> {
+    let start = Instant::now();
+    let rows = if arrow {
+        IpcStreamReader::new(Cursor::new(body)).finish()
+    } else {
+        JsonReader::new(Cursor::new(body))
+            .with_json_format(JsonFormat::Json)
+            .finish()
+    }
+    .map_err(|e| ServeError::BadRequest(e.to_string()))?;
+
+    let mut features = fitted.transform(&rows)?;
+    info!(
+        rows = rows.height(),
+        duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+        "Transformed request"
+    );
+
+    if arrow {
+        let mut buffer = Vec::new();
+        IpcStreamWriter::new(&mut buffer)
+            .finish(&mut features)
+            .map_err(|e| ServeError::Internal(e.to_string()))?;
+        return Ok(([(header::CONTENT_TYPE, ARROW_STREAM)], buffer).into_response());
+    }
+    Ok(Json(vectors(&features).map_err(PipelineStepError::from)?).into_response())
+}
+
+fn vectors(features: &DataFrame) -> PolarsResult<FeatureVectors> {
+    let mut rows = vec![Vec::with_capacity(features.width()); features.height()];
+    for column in features.get_columns() {
+        let values = column.cast(&DataType::Float64)?;
+        for (row, value) in rows.iter_mut().zip(values.f64()?) {
+            row.push(value);
+        }
+    }
+    Ok(FeatureVectors {
+        columns: features
+            .get_column_names()
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+        features: rows,
+    })
+}
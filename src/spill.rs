@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+use std::fs;
+#[cfg(feature = "native")]
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use polars::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::errors::PipelineStepError;
+
+/// Memory the run's frames may take before inputs are spilled to disk.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryConfig {
+    /// Budget in MiB for the frames held in memory at once.
+    pub budget_mb: u64,
+
+    /// Directory for the spilled Parquet files; the system temp directory
+    /// when omitted. The files are removed when the run ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spill_dir: Option<String>,
+}
+
+/// A frame held in memory, or written to a Parquet file and read back from it
+/// on use.
+#[derive(Debug)]
+pub enum Held {
+    Memory(DataFrame),
+    Spilled(PathBuf),
+}
+
+impl Held {
+    /// The frame, re-scanned from its spill file when it was spilled. Callers
+    /// should drop it once done, so a spilled frame leaves memory again.
+    pub fn frame(&self) -> Result<Cow<'_, DataFrame>, PipelineStepError> {
+        match self {
+            Self::Memory(frame) => Ok(Cow::Borrowed(frame)),
+            Self::Spilled(path) => Ok(Cow::Owned(read_spilled(path)?)),
+        }
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        matches!(self, Self::Spilled(_))
+    }
+}
+
+/// Tracks the estimated size of the frames a run holds against the memory
+/// budget, and spills those that don't fit to a directory of its own, which
+/// is removed when the store is dropped.
+#[derive(Debug)]
+pub struct SpillStore {
+    budget_bytes: u64,
+    held_bytes: u64,
+    dir: PathBuf,
+}
+
+impl SpillStore {
+    pub fn new(config: &MemoryConfig) -> Result<Self, PipelineStepError> {
+        let root = config
+            .spill_dir
+            .as_ref()
+            .map_or_else(std::env::temp_dir, PathBuf::from);
+        let dir = root.join(format!("features-pipeline-spill-{}", process::id()));
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            budget_bytes: config.budget_mb * 1024 * 1024,
+            held_bytes: 0,
+            dir,
+        })
+    }
+
+    /// Counts `frame`, which stays in memory regardless, against the budget.
+    pub fn reserve(&mut self, frame: &DataFrame) {
+        self.held_bytes += frame.estimated_size() as u64;
+    }
+
+    /// Keeps `frame` in memory if it fits in what is left of the budget, and
+    /// otherwise writes it to `<name>.parquet` in the spill directory.
+    pub fn hold(&mut self, name: &str, mut frame: DataFrame) -> Result<Held, PipelineStepError> {
+        let bytes = frame.estimated_size() as u64;
+        if self.held_bytes + bytes <= self.budget_bytes {
+            self.held_bytes += bytes;
+            return Ok(Held::Memory(frame));
+        }
+
+        let path = self.dir.join(format!("{}.parquet", name));
+        write_spilled(&path, &mut frame)?;
+        info!(
+            "Spilled '{}' ({:.1} MiB) to {}; {:.1} of {} MiB budget held",
+            name,
+            bytes as f64 / (1024.0 * 1024.0),
+            path.display(),
+            self.held_bytes as f64 / (1024.0 * 1024.0),
+            self.budget_bytes / (1024 * 1024)
+        );
+        Ok(Held::Spilled(path))
+    }
+}
+
+#[cfg(feature = "native")]
+fn read_spilled(path: &Path) -> Result<DataFrame, PipelineStepError> {
+    let path = PlPath::new(&path.to_string_lossy());
+    Ok(LazyFrame::scan_parquet(path, ScanArgsParquet::default())?.collect()?)
+}
+
+#[cfg(feature = "native")]
+fn write_spilled(path: &Path, frame: &mut DataFrame) -> Result<(), PipelineStepError> {
+    ParquetWriter::new(File::create(path)?).finish(frame)?;
+    Ok(())
+}
+
+/// Spill files are Parquet, which only the `native` feature reads and writes.
+#[cfg(not(feature = "native"))]
+fn read_spilled(path: &Path) -> Result<DataFrame, PipelineStepError> {
+    Err(polars_err!(
+        ComputeError: "cannot read spill file {}: requires the `native` feature",
+        path.display()
+    )
+    .into())
+}
+
+#[cfg(not(feature = "native"))]
+fn write_spilled(path: &Path, _frame: &mut DataFrame) -> Result<(), PipelineStepError> {
+    read_spilled(path).map(|_| ())
+}
+
+impl Drop for SpillStore {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            warn!(
+                "Failed to remove spill directory {}: {}",
+                self.dir.display(),
+                e
+            );
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashMap, env, fmt, sync::Mutex};
+
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry_otlp::{ExporterBuildError, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use tracing::{
+    Event, Level, Span, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Service name reported to the tracing backend unless `OTEL_SERVICE_NAME` is set.
+pub const SERVICE_NAME: &str = "features-pipeline";
+
+/// Batches spans to an OTLP/HTTP collector. `endpoint` is the collector's base
+/// URL, e.g. `http://otel-collector:4318`; `/v1/traces` is appended unless
+/// already present.
+pub fn tracer_provider(endpoint: &str) -> Result<SdkTracerProvider, ExporterBuildError> {
+    let endpoint = endpoint.trim_end_matches('/');
+    let endpoint = if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+    let service_name = env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| SERVICE_NAME.to_string());
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build())
+}
+
+/// Makes `span` a child of the trace context an orchestrator passed in the
+/// `TRACEPARENT` and `TRACESTATE` environment variables (W3C trace context).
+/// Does nothing when `TRACEPARENT` is unset or malformed.
+pub fn adopt_parent_from_env(span: &Span) {
+    let carrier: HashMap<String, String> =
+        [("traceparent", "TRACEPARENT"), ("tracestate", "TRACESTATE")]
+            .into_iter()
+            .filter_map(|(key, var)| Some((key.to_string(), env::var(var).ok()?)))
+            .collect();
+    if carrier.contains_key("traceparent") {
+        let _ = span.set_parent(TraceContextPropagator::new().extract(&carrier));
+    }
+}
+
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Keeps the message of every warning logged during the process, regardless of
+/// the log level, for the run report.
+pub struct WarningCollector;
+
+impl<S: Subscriber> Layer<S> for WarningCollector {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::WARN {
+            return;
+        }
+        let mut message = MessageVisitor(String::new());
+        event.record(&mut message);
+        if let Ok(mut warnings) = WARNINGS.lock() {
+            warnings.push(message.0);
+        }
+    }
+}
+
+/// Warnings collected so far by [`WarningCollector`].
+pub fn warnings() -> Vec<String> {
+    WARNINGS.lock().map(|w| w.clone()).unwrap_or_default()
+}
+
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use std::env;
+
+use rayon::{ThreadPoolBuildError, ThreadPoolBuilder};
+
+/// Caps the threads polars and rayon run on at `max_threads`, so a pipeline
+/// in a CPU-limited container doesn't start one thread per host core.
+///
+/// Both size their global pools once, on first use, so this must be called
+/// at startup, before any data is read and while the process has no other
+/// threads.
+pub fn limit(max_threads: usize) -> Result<(), ThreadPoolBuildError> {
+    // SAFETY: called before any other thread exists that could read the
+    // environment concurrently.
+    unsafe { env::set_var("POLARS_MAX_THREADS", max_threads.to_string()) };
+    ThreadPoolBuilder::new()
+        .num_threads(max_threads)
+        .build_global()
+}
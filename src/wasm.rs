@@ -0,0 +1,154 @@
+//! Browser bindings, built with the `wasm` feature. Configs are passed as text
+//! in the given format (`yaml`, `json` or `toml`) and data as Arrow IPC
+//! streams, so the config editor can validate and preview pipelines without
+//! a server. Nothing here touches the file system; `lookup` tables and
+//! datasets must be inlined by the caller.
+
+use std::io::Cursor;
+
+use polars::prelude::*;
+use polars_arrow::io::ipc::read::{StreamReader, StreamState, read_stream_metadata};
+use polars_arrow::io::ipc::write::{StreamWriter, WriteOptions};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::config::format::ConfigFormat;
+use crate::errors::PipelineStepError;
+use crate::pipeline::features::{FeatureConfig, FeaturePipeline};
+use crate::pipeline::lint::LintIssue;
+
+/// Names the config in error messages, where a loaded one has its path.
+const SOURCE: &str = "<editor>";
+
+#[derive(Serialize, Debug)]
+struct Validation {
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    issues: Vec<LintIssue>,
+}
+
+/// One step of the plan: what it reads and the columns it writes.
+#[derive(Serialize, Debug)]
+struct PlannedStep {
+    /// 1-based position in `steps`.
+    step: usize,
+    function: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    inputs: Vec<String>,
+    /// `ohe` and `multi_hot` outputs depend on the data and are given as
+    /// `<column>__ohe__*` and `<column>__mh__*`.
+    outputs: Vec<String>,
+    requires_fit: bool,
+}
+
+/// Parses and validates a features config, returning JSON
+/// `{ valid, error?, issues }`. `issues` are the [`FeaturePipeline::lint`]
+/// findings of a config that parsed.
+#[wasm_bindgen(js_name = validateConfig)]
+pub fn validate_config(content: &str, format: &str) -> Result<String, JsError> {
+    let validation = match parse(content, format) {
+        Ok(pipeline) => Validation {
+            valid: true,
+            error: None,
+            issues: pipeline.lint(),
+        },
+        Err(e) => Validation {
+            valid: false,
+            error: Some(e.to_string()),
+            issues: Vec::new(),
+        },
+    };
+    Ok(serde_json::to_string(&validation)?)
+}
+
+/// The steps of a features config in run order, as a JSON array.
+#[wasm_bindgen(js_name = explainConfig)]
+pub fn explain_config(content: &str, format: &str) -> Result<String, JsError> {
+    let pipeline = parse(content, format)?;
+    let plan: Vec<PlannedStep> = pipeline
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| PlannedStep {
+            step: i + 1,
+            function: step.function(),
+            name: step.name().map(str::to_string),
+            inputs: step.input_columns().into_iter().map(String::from).collect(),
+            outputs: outputs(step),
+            requires_fit: step.requires_fit(),
+        })
+        .collect();
+    Ok(serde_json::to_string(&plan)?)
+}
+
+/// Applies a features config to the frame in an Arrow IPC stream and returns
+/// the features as another.
+#[wasm_bindgen]
+pub fn transform(content: &str, format: &str, data: &[u8]) -> Result<Vec<u8>, JsError> {
+    let pipeline = parse(content, format)?;
+    let (features, _) = pipeline.apply_lazy(&read_ipc(data)?)?;
+    Ok(write_ipc(&features)?)
+}
+
+fn parse(content: &str, format: &str) -> Result<FeaturePipeline, PipelineStepError> {
+    let format = match format.to_ascii_lowercase().as_str() {
+        "yaml" | "yml" => ConfigFormat::Yaml,
+        "json" => ConfigFormat::Json,
+        "toml" => ConfigFormat::Toml,
+        other => {
+            return Err(polars_err!(
+                InvalidOperation: "unknown config format '{}'; expected yaml, json or toml",
+                other
+            )
+            .into());
+        }
+    };
+    FeaturePipeline::from_content(SOURCE, content, format)
+}
+
+fn outputs(step: &FeatureConfig) -> Vec<String> {
+    match step {
+        FeatureConfig::Ohe { columns, .. } => {
+            columns.iter().map(|c| format!("{}__ohe__*", c)).collect()
+        }
+        FeatureConfig::MultiHot { column, .. } => vec![format!("{}__mh__*", column)],
+        _ => step
+            .name()
+            .map(|name| vec![format!("feature_{}", name)])
+            .unwrap_or_default(),
+    }
+}
+
+fn read_ipc(data: &[u8]) -> PolarsResult<DataFrame> {
+    let mut cursor = Cursor::new(data);
+    let metadata = read_stream_metadata(&mut cursor)?;
+    let mut frame: Option<DataFrame> = None;
+    for state in StreamReader::new(cursor, metadata, None) {
+        let StreamState::Some(batch) = state? else {
+            break;
+        };
+        let batch = DataFrame::from(batch);
+        match frame.as_mut() {
+            Some(frame) => {
+                frame.vstack_mut(&batch)?;
+            }
+            None => frame = Some(batch),
+        }
+    }
+    let mut frame = frame.ok_or_else(|| polars_err!(NoData: "Arrow stream has no batches"))?;
+    frame.rechunk_mut();
+    Ok(frame)
+}
+
+fn write_ipc(frame: &DataFrame) -> PolarsResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    let mut writer = StreamWriter::new(&mut buffer, WriteOptions { compression: None });
+    writer.start(&frame.schema().to_arrow(CompatLevel::newest()), None)?;
+    for batch in frame.iter_chunks(CompatLevel::newest(), true) {
+        writer.write(&batch, None)?;
+    }
+    writer.finish()?;
+    Ok(buffer)
+}